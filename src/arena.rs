@@ -4,9 +4,10 @@
 
 use std::{
     collections::{HashMap, hash_map::Entry},
-    fmt::Debug,
+    fmt::{self, Debug},
     marker::PhantomData,
     ops::{Index, IndexMut},
+    str::FromStr,
 };
 
 use state::{Generator, Rebind, State};
@@ -40,12 +41,61 @@ impl<T> Debug for ID<T> {
     }
 }
 
+/// Prints the ID as `ID(<index>)`, like [`Debug`] does, which can be parsed
+/// back with [`str::parse`].
+impl<T> fmt::Display for ID<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(self, f)
+    }
+}
+
+/// The error returned when parsing an [`ID`] from a string that isn't of the
+/// form `ID(<index>)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ParseIDError;
+
+impl fmt::Display for ParseIDError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("expected an ID of the form `ID(<index>)`")
+    }
+}
+
+impl std::error::Error for ParseIDError {}
+
+/// Parses an ID printed by [`Debug`] or [`fmt::Display`], e.g. `ID(42)`, so
+/// that the IDs found in logs can be turned back into references.
+impl<T> FromStr for ID<T> {
+    type Err = ParseIDError;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let index = string
+            .strip_prefix("ID(")
+            .and_then(|rest| rest.strip_suffix(')'))
+            .ok_or(ParseIDError)?;
+
+        // `u64::from_str` accepts a leading `+`, which the IDs are never
+        // printed with
+        if !index.bytes().all(|byte| byte.is_ascii_digit()) {
+            return Err(ParseIDError);
+        }
+
+        index.parse().map(Self::new).map_err(|_| ParseIDError)
+    }
+}
+
 impl<T> ID<T> {
     /// Creates a new [`ID`] with the given index.
     #[must_use]
     pub const fn new(index: u64) -> Self {
         Self { index, _marker: PhantomData }
     }
+
+    /// Creates the [`ID`] of the given index, the inverse of [`ID::index`].
+    ///
+    /// This is the same as [`ID::new`], spelled out for reconstructing IDs
+    /// from their index, e.g. one read from a log.
+    #[must_use]
+    pub const fn from_index(index: u64) -> Self { Self::new(index) }
 }
 
 impl<T> Clone for ID<T> {
@@ -72,6 +122,27 @@ impl<T> Ord for ID<T> {
     }
 }
 
+/// IDs serialize as their plain index, they're only meaningful to the
+/// [`Arena`] they were issued by.
+#[cfg(feature = "serde")]
+impl<T> serde::Serialize for ID<T> {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.index)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T> serde::Deserialize<'de> for ID<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        <u64 as serde::Deserialize>::deserialize(deserializer).map(Self::new)
+    }
+}
+
 impl<T> std::hash::Hash for ID<T> {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.index.hash(state);
@@ -131,7 +202,20 @@ impl<T, G: State<T>> Arena<T, G> {
     /// Retains only the items in the [`Arena`] that satisfy the given
     /// predicate.
     pub fn retain(&mut self, mut f: impl FnMut(G::ID, &mut T) -> bool) {
-        self.items.retain(|id, item| f(*id, item));
+        let mut removed = Vec::new();
+
+        self.items.retain(|id, item| {
+            let keep = f(*id, item);
+            if !keep {
+                removed.push(*id);
+            }
+
+            keep
+        });
+
+        for id in removed {
+            self.generator.removed(&id, &self.items);
+        }
     }
 
     /// Inserts a new item into the [`Arena`] with explicit ID.
@@ -157,6 +241,24 @@ impl<T, G: State<T>> Arena<T, G> {
         }
     }
 
+    /// Returns the item with the given ID, inserting the one created by
+    /// `create` first if the ID is vacant, like [`Arena::insert_with_id`]
+    /// does.
+    ///
+    /// `create` is only called if the ID is vacant.
+    pub fn get_or_insert_with(
+        &mut self,
+        id: G::ID,
+        create: impl FnOnce() -> T,
+    ) -> &mut T {
+        if let Entry::Vacant(entry) = self.items.entry(id) {
+            entry.insert(create());
+            self.generator.explict_insert_with_id(&id, &self.items);
+        }
+
+        self.items.get_mut(&id).unwrap()
+    }
+
     /// Maps the items in the [`Arena`] to another type using the given
     /// function. The mapped items will have the same IDs as the original
     /// items.
@@ -230,7 +332,65 @@ impl<T, G: State<T>> Arena<T, G> {
 
     /// Removes the item in the [`Arena`] with the given ID and returns it.
     #[must_use]
-    pub fn remove(&mut self, id: G::ID) -> Option<T> { self.items.remove(&id) }
+    pub fn remove(&mut self, id: G::ID) -> Option<T> {
+        let item = self.items.remove(&id)?;
+        self.generator.removed(&id, &self.items);
+
+        Some(item)
+    }
+
+    /// Removes all the items in the [`Arena`].
+    ///
+    /// The state of the ID generator is kept, e.g. [`state::Serial`] carries
+    /// on counting from where it left off, so the IDs of the cleared items are
+    /// never handed out again. [`state::PartialHash`] drops all of its
+    /// tombstones instead, a cleared arena hands out the same IDs as a new
+    /// one.
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.generator.cleared();
+    }
+
+    /// Reserves capacity for at least `additional` more items to be inserted
+    /// without reallocating.
+    pub fn reserve(&mut self, additional: usize) {
+        self.items.reserve(additional);
+    }
+}
+
+impl<T, K: state::Key<T>, V: std::hash::Hasher + Default>
+    Arena<T, state::PartialHash<K, V>>
+{
+    /// Returns the ID of the item whose key, see [`state::Key`], equals the
+    /// given one.
+    ///
+    /// The lookup starts at the ID the key hashes to and moves past the
+    /// colliding items and the tombstones of the removed ones like
+    /// [`state::PartialHash`] does when generating IDs, until the item or a
+    /// vacant ID is found.
+    #[must_use]
+    pub fn find(&self, key: &K::Output) -> Option<ID<T>> {
+        let mut hash = state::hash_key::<T, K, V>(key);
+
+        loop {
+            if !self.generator.is_tombstone(hash) {
+                let item = self.items.get(&ID::new(hash))?;
+
+                if K::key(item) == key {
+                    return Some(ID::new(hash));
+                }
+            }
+
+            hash = hash.wrapping_add(1);
+        }
+    }
+
+    /// Returns the number of tombstones kept by the generator, see
+    /// [`state::PartialHash`].
+    #[cfg(test)]
+    pub(crate) fn tombstone_count(&self) -> usize {
+        self.generator.tombstone_count()
+    }
 }
 
 impl<T, G: State<T>> Index<G::ID> for Arena<T, G> {
@@ -265,3 +425,6 @@ impl<'a, T, G: State<T>> IntoIterator for &'a mut Arena<T, G> {
 
     fn into_iter(self) -> Self::IntoIter { self.items.iter_mut() }
 }
+
+#[cfg(test)]
+mod test;
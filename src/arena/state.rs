@@ -1,6 +1,9 @@
 //! Contains the definition of [`State`]
 
-use std::{collections::HashMap, marker::PhantomData};
+use std::{
+    collections::{HashMap, HashSet},
+    marker::PhantomData,
+};
 
 use crate::arena::ID;
 
@@ -17,6 +20,14 @@ pub trait State<T> {
         id: &Self::ID,
         items: &HashMap<Self::ID, T>,
     );
+
+    /// Got invoked when an item is removed from the [`crate::Arena`], with
+    /// the items left, e.g. to keep its ID from being handed out again.
+    fn removed(&mut self, _id: &Self::ID, _items: &HashMap<Self::ID, T>) {}
+
+    /// Got invoked when all the items are removed from the
+    /// [`crate::Arena`] at once.
+    fn cleared(&mut self) {}
 }
 
 /// A trait for generating unique IDs for items in the [`crate::Arena`].
@@ -78,7 +89,7 @@ impl<T> State<T> for Serial {
         id: &Self::ID,
         _: &HashMap<Self::ID, T>,
     ) {
-        self.0 = std::cmp::max(self.0, id.index);
+        self.0 = std::cmp::max(self.0, id.index + 1);
     }
 }
 
@@ -121,9 +132,7 @@ impl<T: std::hash::Hash, V: std::hash::Hasher + std::default::Default>
     Generator<T> for Hash<V>
 {
     fn next_id(&mut self, items: &HashMap<Self::ID, T>, value: &T) -> Self::ID {
-        let mut hasher = V::default();
-        value.hash(&mut hasher);
-        let mut hash = hasher.finish();
+        let mut hash = hash_of::<V>(value);
 
         // Ensure the hash is unique by checking against existing IDs
         while items.contains_key(&ID::new(hash)) {
@@ -144,39 +153,100 @@ impl<T, U, V> Rebind<T, U> for Hash<V> {
     }
 }
 
-/// Similar to [`Hash`] but onnly hashes the partial contents of the item for
-/// generating hash as the ID.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
-pub struct PartialHash<F, V>(PhantomData<(F, V)>);
+/// Selects the part of an item hashed by [`PartialHash`], e.g. a field
+/// identifying the item.
+pub trait Key<T> {
+    /// The type of the part of the item.
+    type Output: ?Sized + std::hash::Hash + Eq;
+
+    /// Returns the part of the item to hash.
+    fn key(item: &T) -> &Self::Output;
 
-impl<T, F, V> State<T> for PartialHash<F, V> {
+    /// Feeds the part of the item to the hasher, with its
+    /// [`std::hash::Hash`] implementation unless overridden.
+    fn hash(key: &Self::Output, state: &mut impl std::hash::Hasher) {
+        std::hash::Hash::hash(key, state);
+    }
+}
+
+/// Similar to [`Hash`] but onnly hashes the partial contents of the item,
+/// selected by `K`, for generating hash as the ID.
+///
+/// Since equal keys always hash to the same ID, the item of a given key can
+/// be looked up without any additional map, see [`crate::arena::Arena::find`].
+///
+/// The IDs of the removed items are kept as tombstones as long as an item
+/// that collided with a removed one may sit past them: the lookups probe past
+/// the tombstones so that such items are still found. A run of tombstones
+/// followed by a vacant ID is on no item's probe sequence anymore and is
+/// dropped, so the tombstones don't pile up as items come and go, and the
+/// IDs of the removed items are eventually handed out again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartialHash<K, V> {
+    tombstones: HashSet<u64>,
+    _marker: PhantomData<(K, V)>,
+}
+
+impl<K, V> PartialHash<K, V> {
+    /// Checks whether the ID belonged to a removed item.
+    pub(super) fn is_tombstone(&self, index: u64) -> bool {
+        self.tombstones.contains(&index)
+    }
+
+    /// Returns the number of tombstones kept.
+    #[cfg(test)]
+    pub(crate) fn tombstone_count(&self) -> usize { self.tombstones.len() }
+}
+
+impl<K, V> std::default::Default for PartialHash<K, V> {
+    fn default() -> Self {
+        Self { tombstones: HashSet::new(), _marker: PhantomData }
+    }
+}
+
+impl<T, K, V> State<T> for PartialHash<K, V> {
     type ID = ID<T>;
 
     fn explict_insert_with_id(
         &mut self,
-        _: &Self::ID,
+        id: &Self::ID,
         _: &HashMap<Self::ID, T>,
     ) {
+        self.tombstones.remove(&id.index);
+    }
+
+    fn removed(&mut self, id: &Self::ID, items: &HashMap<Self::ID, T>) {
+        self.tombstones.insert(id.index);
+
+        // find the run of tombstones the removed ID belongs to
+        let mut last = id.index;
+        while self.is_tombstone(last.wrapping_add(1)) {
+            last = last.wrapping_add(1);
+        }
+
+        // an item past the run may have probed through it
+        if items.contains_key(&ID::new(last.wrapping_add(1))) {
+            return;
+        }
+
+        let mut tombstone = last;
+        while self.tombstones.remove(&tombstone) {
+            tombstone = tombstone.wrapping_sub(1);
+        }
     }
+
+    fn cleared(&mut self) { self.tombstones.clear(); }
 }
 
-impl<
-    T,
-    R: std::hash::Hash,
-    F: std::default::Default + FnOnce(&T) -> R,
-    V: std::hash::Hasher + std::default::Default,
-> Generator<T> for PartialHash<F, V>
+impl<T, K: Key<T>, V: std::hash::Hasher + std::default::Default> Generator<T>
+    for PartialHash<K, V>
 {
     fn next_id(&mut self, items: &HashMap<Self::ID, T>, value: &T) -> Self::ID {
-        let mapper = F::default();
-        let part = mapper(value);
+        let mut hash = hash_key::<T, K, V>(K::key(value));
 
-        let mut hasher = V::default();
-        part.hash(&mut hasher);
-        let mut hash = hasher.finish();
-
-        // Ensure the hash is unique by checking against existing IDs
-        while items.contains_key(&ID::new(hash)) {
+        // Ensure the hash is unique by checking against existing IDs and the
+        // ones of the removed items
+        while items.contains_key(&ID::new(hash)) || self.is_tombstone(hash) {
             hash = hash.wrapping_add(1);
         }
 
@@ -184,7 +254,7 @@ impl<
     }
 }
 
-impl<T, U, F, V> Rebind<T, U> for PartialHash<F, V> {
+impl<T, U, K, V> Rebind<T, U> for PartialHash<K, V> {
     type Result = Self;
 
     fn rebind(self) -> Self::Result { self }
@@ -193,3 +263,80 @@ impl<T, U, F, V> Rebind<T, U> for PartialHash<F, V> {
         ID::new(id.index)
     }
 }
+
+/// A [`PartialHash`] generator hashing with the [`StableHasher`], so that an
+/// item's key maps to the same ID in every run and on every platform.
+pub type StableHash<K> = PartialHash<K, StableHasher>;
+
+/// A 64-bit FNV-1a hasher, whose output only depends on the values hashed,
+/// unlike [`std::hash::DefaultHasher`] whose algorithm may change between
+/// Rust versions.
+///
+/// Integers are hashed as their little-endian bytes, and `usize` and `isize`
+/// as 64-bit integers, so that the output is the same on every platform.
+/// Note that the output still depends on how the values implement
+/// [`std::hash::Hash`], e.g. a [`crate::symbol::Symbol`] hashes as the index
+/// it was interned at; see [`Key::hash`] to feed the hasher differently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StableHasher(u64);
+
+impl StableHasher {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+}
+
+impl std::default::Default for StableHasher {
+    fn default() -> Self { Self(Self::OFFSET_BASIS) }
+}
+
+impl std::hash::Hasher for StableHasher {
+    fn finish(&self) -> u64 { self.0 }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(Self::PRIME);
+        }
+    }
+
+    fn write_u16(&mut self, value: u16) { self.write(&value.to_le_bytes()); }
+
+    fn write_u32(&mut self, value: u32) { self.write(&value.to_le_bytes()); }
+
+    fn write_u64(&mut self, value: u64) { self.write(&value.to_le_bytes()); }
+
+    fn write_u128(&mut self, value: u128) { self.write(&value.to_le_bytes()); }
+
+    fn write_usize(&mut self, value: usize) { self.write_u64(value as u64); }
+
+    fn write_i16(&mut self, value: i16) { self.write(&value.to_le_bytes()); }
+
+    fn write_i32(&mut self, value: i32) { self.write(&value.to_le_bytes()); }
+
+    fn write_i64(&mut self, value: i64) { self.write(&value.to_le_bytes()); }
+
+    fn write_i128(&mut self, value: i128) { self.write(&value.to_le_bytes()); }
+
+    fn write_isize(&mut self, value: isize) { self.write_i64(value as i64); }
+}
+
+/// Hashes the key with a fresh hasher of type `V`, see [`Key::hash`].
+pub(super) fn hash_key<
+    T,
+    K: Key<T>,
+    V: std::hash::Hasher + std::default::Default,
+>(
+    key: &K::Output,
+) -> u64 {
+    let mut hasher = V::default();
+    K::hash(key, &mut hasher);
+    hasher.finish()
+}
+
+/// Hashes the value with a fresh hasher of type `V`.
+pub(super) fn hash_of<V: std::hash::Hasher + std::default::Default>(
+    value: &(impl std::hash::Hash + ?Sized),
+) -> u64 {
+    let mut hasher = V::default();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
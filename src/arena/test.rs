@@ -0,0 +1,177 @@
+use std::collections::HashSet;
+
+use crate::arena::{Arena, ID, ParseIDError, state};
+
+#[test]
+fn serial_ids_stay_unique_across_clear() {
+    let mut arena = Arena::<&str, state::Serial>::new();
+
+    let before = ["a", "b", "c"].map(|item| arena.insert(item));
+    arena.clear();
+
+    assert!(arena.is_empty());
+    assert!(before.iter().all(|id| arena.get(*id).is_none()));
+
+    let after = ["d", "e"].map(|item| arena.insert(item));
+
+    let ids = before.iter().chain(&after).copied().collect::<HashSet<_>>();
+    assert_eq!(ids.len(), 5);
+    assert_eq!(arena[after[0]], "d");
+}
+
+#[test]
+fn serial_ids_skip_explicitly_inserted_ids() {
+    let mut arena = Arena::<&str, state::Serial>::new();
+
+    arena.insert_with_id(ID::new(3), "explicit").unwrap();
+    arena.reserve(16);
+
+    let generated = arena.insert("generated");
+
+    assert_ne!(generated, ID::new(3));
+    assert_eq!(arena.len(), 2);
+}
+
+#[test]
+fn id_display_parse_round_trip() {
+    for index in [0, 7, 42, u64::MAX] {
+        let id = ID::<()>::from_index(index);
+
+        assert_eq!(id.to_string(), format!("ID({index})"));
+        assert_eq!(id.to_string().parse::<ID<()>>(), Ok(id));
+        assert_eq!(format!("{id:?}").parse::<ID<()>>(), Ok(id));
+    }
+
+    for malformed in ["42", "ID()", "ID(-1)", "ID(+1)", "ID(4 2)", "id(42)"] {
+        assert_eq!(malformed.parse::<ID<()>>(), Err(ParseIDError));
+    }
+}
+
+#[test]
+fn get_or_insert_with_creates_only_when_vacant() {
+    let mut arena = Arena::<String, state::Serial>::new();
+    let mut created = 0;
+
+    let item = arena.get_or_insert_with(ID::new(2), || {
+        created += 1;
+        "first".to_string()
+    });
+    item.push('!');
+
+    let item = arena.get_or_insert_with(ID::new(2), || {
+        created += 1;
+        "second".to_string()
+    });
+
+    assert_eq!(item, "first!");
+    assert_eq!(created, 1);
+    assert_eq!(arena.len(), 1);
+
+    // the explicit ID is skipped by the generated ones
+    let generated =
+        (0..4).map(|_| arena.insert(String::new())).collect::<Vec<_>>();
+    assert!(!generated.contains(&ID::new(2)));
+}
+
+/// Hashes everything to zero, making every key collide.
+#[derive(Default)]
+struct Colliding;
+
+impl std::hash::Hasher for Colliding {
+    fn finish(&self) -> u64 { 0 }
+
+    fn write(&mut self, _: &[u8]) {}
+}
+
+/// Keys the items by themselves.
+struct Whole;
+
+impl state::Key<&'static str> for Whole {
+    type Output = str;
+
+    fn key<'a>(item: &'a &'static str) -> &'a str { item }
+}
+
+#[test]
+fn find_probes_past_removed_items() {
+    let mut arena =
+        Arena::<&'static str, state::PartialHash<Whole, Colliding>>::new();
+
+    let first = arena.insert("first");
+    let second = arena.insert("second");
+    assert_eq!(arena.find("second"), Some(second));
+
+    // the second item sits behind the first one on the probe sequence
+    assert_eq!(arena.remove(first), Some("first"));
+    assert_eq!(arena.find("first"), None);
+    assert_eq!(arena.find("second"), Some(second));
+
+    // the freed ID isn't handed out again
+    let again = arena.insert("first");
+    assert_ne!(again, first);
+    assert_eq!(arena.find("first"), Some(again));
+
+    arena.retain(|_, item| *item != "first");
+    assert_eq!(arena.find("second"), Some(second));
+    assert_eq!(arena.find("first"), None);
+
+    // a cleared arena starts over like a new one
+    arena.clear();
+    assert_eq!(arena.insert("first"), first);
+}
+
+#[test]
+fn tombstones_are_dropped_once_nothing_probes_past_them() {
+    let mut arena =
+        Arena::<&'static str, state::PartialHash<Whole, Colliding>>::new();
+
+    let first = arena.insert("first");
+    let second = arena.insert("second");
+    let third = arena.insert("third");
+
+    // `second` and `third` probed past `first`, then `third` past `second`
+    assert_eq!(arena.remove(first), Some("first"));
+    assert_eq!(arena.remove(second), Some("second"));
+    assert_eq!(arena.generator.tombstone_count(), 2);
+    assert_eq!(arena.find("third"), Some(third));
+
+    // nothing sits past the run of tombstones anymore
+    assert_eq!(arena.remove(third), Some("third"));
+    assert_eq!(arena.generator.tombstone_count(), 0);
+    assert_eq!(arena.insert("first"), first);
+
+    // items coming and going without colliding leave no tombstone behind
+    let mut arena = Arena::<
+        &'static str,
+        state::PartialHash<Whole, std::hash::DefaultHasher>,
+    >::new();
+    let kept = arena.insert("kept");
+
+    for _ in 0..1000 {
+        let id = arena.insert("gone");
+        arena.retain(|item_id, _| item_id != id);
+    }
+
+    assert_eq!(arena.generator.tombstone_count(), 0);
+    assert_eq!(arena.find("kept"), Some(kept));
+}
+
+#[test]
+fn stable_hasher_is_fnv1a() {
+    use std::hash::Hasher;
+
+    let hash = |bytes: &[u8]| {
+        let mut hasher = state::StableHasher::default();
+        hasher.write(bytes);
+        hasher.finish()
+    };
+
+    assert_eq!(hash(b""), 0xcbf2_9ce4_8422_2325);
+    assert_eq!(hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+    assert_eq!(hash(b"foobar"), 0x8594_4171_f739_67e8);
+
+    // integers hash as their little-endian bytes whatever their width
+    let mut hasher = state::StableHasher::default();
+    hasher.write_usize(1);
+    assert_eq!(hasher.finish(), hash(&1u64.to_le_bytes()));
+}
@@ -1,4 +1,7 @@
-use std::collections::HashMap;
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+};
 
 use crate::{
     clause::{Clause, Goal, Predicate},
@@ -10,6 +13,28 @@ impl Goal {
     pub fn canonicalize(&mut self) -> HashMap<usize, usize> {
         self.predicate.canonicalize()
     }
+
+    /// Checks whether canonicalizing the goal would leave it unchanged, that
+    /// is, its variables are numbered from `0` in order of first appearance.
+    #[must_use]
+    pub fn is_canonical(&self) -> bool {
+        let mut next = 0;
+        let mut canonical = true;
+
+        for term in &self.predicate.arguments {
+            term.visit(&mut |term| {
+                if let Term::Variable(id) = term {
+                    match (*id).cmp(&next) {
+                        Ordering::Less => {}
+                        Ordering::Equal => next += 1,
+                        Ordering::Greater => canonical = false,
+                    }
+                }
+            });
+        }
+
+        canonical
+    }
 }
 
 impl Predicate {
@@ -24,7 +49,7 @@ impl Predicate {
         let mut mapping = HashMap::new();
 
         for term in &mut self.arguments {
-            term.canonicalize_internal(&mut counter, &mut mapping);
+            term.canonicalize_with_mapping(&mut counter, &mut mapping);
         }
 
         (counter, mapping)
@@ -36,53 +61,75 @@ impl Term {
         self.canonicalize_with_counter(0)
     }
 
+    /// Renames the variables of the term in order of appearance starting
+    /// from `counter`, returning the counter past the last new variable.
+    ///
+    /// The renaming starts afresh on every call, so canonicalizing two terms
+    /// sharing a variable one after another may rename it differently in
+    /// each of them. Use [`Term::canonicalize_with_mapping`] to rename several
+    /// terms consistently.
     pub fn canonicalize_with_counter(&mut self, mut counter: usize) -> usize {
         let mut mapping = HashMap::new();
-        self.canonicalize_internal(&mut counter, &mut mapping);
+        self.canonicalize_with_mapping(&mut counter, &mut mapping);
 
         counter
     }
 
-    fn canonicalize_internal(
+    /// Renames the variables of the term like
+    /// [`Term::canonicalize_with_counter`] but keeps the renaming in
+    /// `mapping`, from the original to the new variables, so that it carries
+    /// over to the next terms canonicalized with the same `counter` and
+    /// `mapping`.
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    ///
+    /// use slg_prolog_solver::term::Term;
+    ///
+    /// let mut lhs = Term::component("f", [Term::variable(5)]);
+    /// let mut rhs = Term::component("g", [Term::variable(7), Term::variable(5)]);
+    ///
+    /// let (mut counter, mut mapping) = (0, HashMap::new());
+    /// lhs.canonicalize_with_mapping(&mut counter, &mut mapping);
+    /// rhs.canonicalize_with_mapping(&mut counter, &mut mapping);
+    ///
+    /// assert_eq!(lhs.to_string(), "f(?0)");
+    /// assert_eq!(rhs.to_string(), "g(?1, ?0)");
+    /// ```
+    pub fn canonicalize_with_mapping(
         &mut self,
         counter: &mut usize,
         mapping: &mut HashMap<usize, usize>,
     ) {
-        match self {
-            Term::Atom(_) => {}
-            Term::Variable(id) => {
-                if let Some(new_id) = mapping.get(id) {
-                    *id = *new_id;
-                } else {
+        self.visit_mut(&mut |term| {
+            if let Term::Variable(id) = term {
+                *id = *mapping.entry(*id).or_insert_with(|| {
                     let new_id = *counter;
-                    mapping.insert(*id, new_id);
-                    *id = new_id;
                     *counter += 1;
-                }
-            }
-            Term::Compound(_, terms) => {
-                for term in terms {
-                    term.canonicalize_internal(counter, mapping);
-                }
+
+                    new_id
+                });
             }
-        }
+        });
     }
 }
 
 impl Clause {
-    pub fn canonicalize(&mut self) {
+    pub fn canonicalize(&mut self) -> HashMap<usize, usize> {
         let mut counter = 0;
         let mut mapping = HashMap::new();
 
         for term in &mut self.head.arguments {
-            term.canonicalize_internal(&mut counter, &mut mapping);
+            term.canonicalize_with_mapping(&mut counter, &mut mapping);
         }
 
         for goal in &mut self.body {
             for term in &mut goal.predicate.arguments {
-                term.canonicalize_internal(&mut counter, &mut mapping);
+                term.canonicalize_with_mapping(&mut counter, &mut mapping);
             }
         }
+
+        mapping
     }
 
     pub fn canonicalize_with_counter(&mut self, mut counter: usize) -> usize {
@@ -98,61 +145,102 @@ impl Clause {
         mapping: &mut HashMap<usize, usize>,
     ) {
         for term in &mut self.head.arguments {
-            term.canonicalize_internal(counter, mapping);
+            term.canonicalize_with_mapping(counter, mapping);
         }
 
         for goal in &mut self.body {
             for term in &mut goal.predicate.arguments {
-                term.canonicalize_internal(counter, mapping);
+                term.canonicalize_with_mapping(counter, mapping);
             }
         }
     }
 }
 
+impl Substitution {
+    /// Renames the variables other than the `fixed` ones in order of
+    /// appearance, visiting the bindings by ascending variable, starting
+    /// right after the largest `fixed` variable.
+    ///
+    /// Substitutions over the `fixed` variables differing only in the
+    /// numbering of the variables they leave unbound become equal, e.g.
+    /// `{?0 = f(?3)}` and `{?0 = f(?7)}` both become `{?0 = f(?1)}` with `?0`
+    /// fixed.
+    pub fn canonicalize_with_fixed(&mut self, fixed: &HashSet<usize>) {
+        let mut counter = fixed.iter().max().map_or(0, |max| max + 1);
+        let mut mapping =
+            fixed.iter().map(|variable| (*variable, *variable)).collect();
+
+        let mut variables = self.mapping.keys().copied().collect::<Vec<_>>();
+        variables.sort_unstable();
+
+        for variable in variables {
+            self.mapping
+                .get_mut(&variable)
+                .unwrap()
+                .canonicalize_with_mapping(&mut counter, &mut mapping);
+        }
+    }
+}
+
 pub fn reverse_mapping(
     mapping: &HashMap<usize, usize>,
 ) -> HashMap<usize, usize> {
     mapping.iter().map(|(&k, &v)| (v, k)).collect()
 }
 
+/// Translates a substitution over the variables of a canonical goal back to
+/// the variables of the original goal, through the `uncanonicalized_mapping`
+/// from the canonical to the original variables.
+///
+/// The variables bound in the substitution may be nested anywhere in the
+/// arguments of the original goal. Variables missing from the mapping, e.g.
+/// the ones left unbound by a clause in `{?0 = g(?1)}`, are renamed apart
+/// after the largest original variable so that they can't be confused with
+/// any variable of the original goal.
 pub fn uncanonicalize_substitution(
     canonicalized_substitution: &Substitution,
     uncanonicalized_mapping: &HashMap<usize, usize>,
 ) -> Substitution {
-    fn apply_uncanonicalization(
-        term: &mut Term,
-        uncanonicalized_mapping: &HashMap<usize, usize>,
-    ) {
-        match term {
-            Term::Variable(variable) => {
-                if let Some(&uncanonicalized_var) =
-                    uncanonicalized_mapping.get(variable)
-                {
-                    *term = Term::Variable(uncanonicalized_var);
-                }
-            }
-            Term::Compound(_, terms) => {
-                for subterm in terms {
-                    apply_uncanonicalization(subterm, uncanonicalized_mapping);
-                }
-            }
-            _ => {}
-        }
-    }
+    let counter =
+        uncanonicalized_mapping.values().max().map_or(0, |max| max + 1);
+
+    uncanonicalize_substitution_with_counter(
+        canonicalized_substitution,
+        uncanonicalized_mapping,
+        counter,
+    )
+}
+
+/// Translates a substitution like [`uncanonicalize_substitution`] but renames
+/// the variables missing from the mapping starting from `counter`, which must
+/// be past every variable in use wherever the substitution is applied.
+pub fn uncanonicalize_substitution_with_counter(
+    canonicalized_substitution: &Substitution,
+    uncanonicalized_mapping: &HashMap<usize, usize>,
+    mut counter: usize,
+) -> Substitution {
+    let mut mapping = uncanonicalized_mapping.clone();
+
+    // rename in a fixed order so that the fresh variables don't depend on
+    // the iteration order of the substitution
+    let mut variables =
+        canonicalized_substitution.mapping.keys().copied().collect::<Vec<_>>();
+    variables.sort_unstable();
 
     Substitution {
-        mapping: canonicalized_substitution
-            .mapping
-            .iter()
-            .map(|(var, term)| {
-                (uncanonicalized_mapping.get(var).cloned().unwrap_or(*var), {
-                    let mut term = term.clone();
-                    apply_uncanonicalization(
-                        &mut term,
-                        uncanonicalized_mapping,
-                    );
-                    term
-                })
+        mapping: variables
+            .into_iter()
+            .map(|variable| {
+                let mut key = Term::Variable(variable);
+                key.canonicalize_with_mapping(&mut counter, &mut mapping);
+
+                let Term::Variable(key) = key else { unreachable!() };
+
+                let mut term =
+                    canonicalized_substitution.mapping[&variable].clone();
+                term.canonicalize_with_mapping(&mut counter, &mut mapping);
+
+                (key, term)
             })
             .collect(),
     }
@@ -1,19 +1,244 @@
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+    ops::ControlFlow,
+    str::FromStr,
+};
 
-use crate::term::Term;
+use crate::{
+    parser::{self, ParseError},
+    solver::builtin,
+    substitution::Substitution,
+    symbol::Symbol,
+    term::Term,
+};
+
+mod dependency;
+mod stratification;
+
+pub(crate) use dependency::Call;
+pub use dependency::Dependency;
+pub use stratification::Cycle;
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Predicate {
-    pub name: String,
+    pub name: Symbol,
     pub arguments: Vec<Term>,
 }
 
+impl Predicate {
+    /// Creates a predicate with the given name and arguments.
+    ///
+    /// ```
+    /// use slg_prolog_solver::{clause::Predicate, term::Term};
+    ///
+    /// let parent =
+    ///     Predicate::new("parent", [Term::atom("alice"), Term::variable(0)]);
+    ///
+    /// assert_eq!(parent.to_string(), "parent(alice, ?0)");
+    /// ```
+    #[must_use]
+    pub fn new(
+        name: impl Into<Symbol>,
+        arguments: impl IntoIterator<Item = Term>,
+    ) -> Self {
+        Self { name: name.into(), arguments: arguments.into_iter().collect() }
+    }
+
+    /// Returns the name and arity of the predicate.
+    #[must_use]
+    pub fn functor(&self) -> Functor {
+        Functor { name: self.name, arity: self.arguments.len() }
+    }
+
+    /// Checks whether no variable appears more than once in the arguments,
+    /// e.g. `edge(X, Y)` is linear whereas `eq(X, X)` isn't.
+    ///
+    /// When a linear goal is unified with a linear clause head sharing none
+    /// of its variables, no variable occurs twice across the two, so none can
+    /// end up bound to a term containing it. The solver skips the occurs
+    /// check for such unifications only.
+    #[must_use]
+    pub fn is_linear(&self) -> bool {
+        self.is_linear_under(&Substitution::default())
+    }
+
+    /// Same as [`Self::is_linear`] for the predicate the substitution would
+    /// give, without applying it.
+    pub(crate) fn is_linear_under(&self, substitution: &Substitution) -> bool {
+        let mut variables = HashSet::new();
+
+        // the bindings of the substitution are resolved, a variable bound to
+        // a term is replaced by the variables of the term alone
+        let mut visit_unbound = |term: &Term| match term {
+            Term::Variable(variable) if !variables.insert(*variable) => {
+                ControlFlow::Break(())
+            }
+            _ => ControlFlow::Continue(()),
+        };
+
+        self.arguments
+            .iter()
+            .try_for_each(|argument| {
+                argument.try_visit(&mut |term| match term {
+                    Term::Variable(variable) => {
+                        match substitution.mapping.get(variable) {
+                            Some(bound) => bound.try_visit(&mut visit_unbound),
+                            None => visit_unbound(term),
+                        }
+                    }
+                    _ => ControlFlow::Continue(()),
+                })
+            })
+            .is_continue()
+    }
+}
+
+/// Identifies a predicate by its name and arity, e.g. `edge/2`.
+///
+/// Predicates sharing a name but differing in arity, such as `edge/2` and
+/// `edge/3`, are unrelated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Functor {
+    pub name: Symbol,
+    pub arity: usize,
+}
+
+impl Functor {
+    #[must_use]
+    pub fn new(name: impl Into<Symbol>, arity: usize) -> Self {
+        Self { name: name.into(), arity }
+    }
+}
+
+impl<S: Into<Symbol>> From<(S, usize)> for Functor {
+    fn from((name, arity): (S, usize)) -> Self { Self::new(name, arity) }
+}
+
+impl fmt::Display for Functor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.name, self.arity)
+    }
+}
+
+impl fmt::Display for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+
+        // propositions are printed without parentheses
+        if self.arguments.is_empty() {
+            return Ok(());
+        }
+
+        write!(f, "(")?;
+        for (i, arg) in self.arguments.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{arg}")?;
+        }
+        write!(f, ")")
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Goal {
     pub predicate: Predicate,
 }
 
 impl Goal {
+    /// Creates a goal proving the predicate with the given name and
+    /// arguments, see [`Predicate::new`].
+    ///
+    /// ```
+    /// use slg_prolog_solver::{clause::Goal, term::Term};
+    ///
+    /// let goal = Goal::new("ancestor", [Term::variable(0), Term::atom("bob")]);
+    ///
+    /// assert_eq!(goal.to_string(), "ancestor(?0, bob)");
+    /// ```
+    #[must_use]
+    pub fn new(
+        name: impl Into<Symbol>,
+        arguments: impl IntoIterator<Item = Term>,
+    ) -> Self {
+        Self { predicate: Predicate::new(name, arguments) }
+    }
+
+    /// Creates the cut goal `!`.
+    ///
+    /// Once a strand proves every goal preceding the cut in a clause body,
+    /// the cut commits the table to that clause: the alternatives of the
+    /// preceding goals and all the clauses after it are discarded.
+    ///
+    /// Because answers are tabled, the pruning is scoped to the table of the
+    /// call containing the clause. Tables whose clauses contain a cut evaluate
+    /// their clauses one after another instead of interleaving them, except
+    /// that a clause stuck on a recursive dependency lets the next clause
+    /// start before it completes.
+    #[must_use]
+    pub fn cut() -> Self {
+        Self {
+            predicate: Predicate { name: Symbol::CUT, arguments: Vec::new() },
+        }
+    }
+
+    /// Checks whether this goal is the cut goal `!`.
+    #[must_use]
+    pub fn is_cut(&self) -> bool {
+        self.predicate.name == Symbol::CUT
+            && self.predicate.arguments.is_empty()
+    }
+
+    /// Checks whether this goal is the cut goal `!` or a disjunction or
+    /// conjunction containing one.
+    #[must_use]
+    pub fn contains_cut(&self) -> bool {
+        if self.is_cut() {
+            return true;
+        }
+
+        matches!(self.predicate.name, Symbol::DISJUNCTION | Symbol::CONJUNCTION)
+            && self.predicate.arguments.iter().any(|argument| {
+                Goal::from_term(argument)
+                    .is_some_and(|goal| goal.contains_cut())
+            })
+    }
+
+    /// Converts a term into the goal it stands for: an atom `p` stands for
+    /// the proposition `p` and a compound `p(X, Y)` for the goal `p(X, Y)`.
+    ///
+    /// Returns `None` for variables, integers, and strings, which aren't goals.
+    #[must_use]
+    pub fn from_term(term: &Term) -> Option<Self> {
+        let predicate = match term {
+            Term::Atom(name) => {
+                Predicate { name: *name, arguments: Vec::new() }
+            }
+            Term::Compound(name, arguments) => {
+                Predicate { name: *name, arguments: arguments.to_vec() }
+            }
+            Term::Integer(_) | Term::Variable(_) | Term::Str(_) => return None,
+        };
+
+        Some(Self { predicate })
+    }
+
+    /// Converts the goal into a term, the inverse of [`Self::from_term`].
+    #[must_use]
+    pub fn to_term(&self) -> Term {
+        if self.predicate.arguments.is_empty() {
+            Term::Atom(self.predicate.name)
+        } else {
+            Term::Compound(
+                self.predicate.name,
+                self.predicate.arguments.clone().into(),
+            )
+        }
+    }
+
     pub fn max_variable_index(&self) -> Option<usize> {
         self.predicate
             .arguments
@@ -21,44 +246,1252 @@ impl Goal {
             .filter_map(|term| term.max_variable_index())
             .max()
     }
+
+    /// Returns the distinct variables of the goal in order of first
+    /// appearance, i.e. the variables the answers of the goal bind.
+    ///
+    /// ```
+    /// use slg_prolog_solver::parser::parse_goal;
+    ///
+    /// let goal = parse_goal("p(X, f(Y, X), Z)").unwrap();
+    ///
+    /// assert_eq!(goal.query_variables(), [0, 1, 2]);
+    /// ```
+    #[must_use]
+    pub fn query_variables(&self) -> Vec<usize> {
+        let mut variables = Vec::new();
+
+        for term in &self.predicate.arguments {
+            term.visit(&mut |term| {
+                if let Term::Variable(id) = term
+                    && !variables.contains(id)
+                {
+                    variables.push(*id);
+                }
+            });
+        }
+
+        variables
+    }
+}
+
+impl fmt::Display for Goal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.predicate)
+    }
 }
 
 impl Term {
+    /// Returns the largest index of the variables of the term, `None` if it
+    /// has no variable.
     pub fn max_variable_index(&self) -> Option<usize> {
-        match self {
-            Term::Atom(_) => None,
-            Term::Variable(id) => Some(*id),
-            Term::Compound(_, terms) => {
-                terms.iter().filter_map(|term| term.max_variable_index()).max()
+        let mut max_variable_index = None;
+        self.visit(&mut |term| {
+            if let Term::Variable(id) = term {
+                max_variable_index = max_variable_index.max(Some(*id));
             }
-        }
+        });
+
+        max_variable_index
+    }
+
+    /// Like [`Term::max_variable_index`] but the `nth` anonymous variable,
+    /// see [`Term::anonymous`], counts as `nth` rather than its index in the
+    /// anonymous range.
+    ///
+    /// Renaming or shifting a term past the result, see
+    /// [`Term::shift_variables`], keeps both kinds of variables apart without
+    /// moving any named variable into the anonymous range.
+    pub(crate) fn max_variable_offset(&self) -> Option<usize> {
+        let mut max_variable_offset = None;
+        self.visit(&mut |term| {
+            if let Term::Variable(id) = term {
+                let offset = if Term::is_anonymous(*id) {
+                    id - Term::FIRST_ANONYMOUS_VARIABLE
+                } else {
+                    *id
+                };
+
+                max_variable_offset = max_variable_offset.max(Some(offset));
+            }
+        });
+
+        max_variable_offset
     }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Clause {
     pub head: Predicate,
     pub body: Vec<Goal>,
 }
 
+impl Clause {
+    /// Creates a clause with an empty body.
+    ///
+    /// ```
+    /// use slg_prolog_solver::{
+    ///     clause::{Clause, Predicate},
+    ///     term::Term,
+    /// };
+    ///
+    /// let fact = Clause::fact(Predicate::new("parent", [
+    ///     Term::atom("alice"),
+    ///     Term::atom("bob"),
+    /// ]));
+    ///
+    /// assert_eq!(fact.to_string(), "parent(alice, bob).");
+    /// ```
+    #[must_use]
+    pub fn fact(head: Predicate) -> Self { Self { head, body: Vec::new() } }
+
+    /// Creates a clause proving the head whenever all the goals of the body
+    /// are proven.
+    ///
+    /// ```
+    /// use slg_prolog_solver::{
+    ///     clause::{Clause, Goal, Predicate},
+    ///     term::Term,
+    /// };
+    ///
+    /// let (x, y, z) = (Term::variable(0), Term::variable(1), Term::variable(2));
+    ///
+    /// // grandparent(X, Z) :- parent(X, Y), parent(Y, Z).
+    /// let rule =
+    ///     Clause::rule(Predicate::new("grandparent", [x.clone(), z.clone()]), [
+    ///         Goal::new("parent", [x, y.clone()]),
+    ///         Goal::new("parent", [y, z]),
+    ///     ]);
+    ///
+    /// assert_eq!(
+    ///     rule.to_string(),
+    ///     "grandparent(?0, ?2) :- parent(?0, ?1), parent(?1, ?2)."
+    /// );
+    /// ```
+    #[must_use]
+    pub fn rule(head: Predicate, body: impl IntoIterator<Item = Goal>) -> Self {
+        Self { head, body: body.into_iter().collect() }
+    }
+
+    /// Checks whether no variable appears more than once in the head, e.g.
+    /// `edge(X, Y)` is linear whereas `eq(X, X)` isn't, see
+    /// [`Predicate::is_linear`].
+    #[must_use]
+    pub fn has_linear_head(&self) -> bool { self.head.is_linear() }
+
+    /// Counts how many times every variable occurs across the head and the
+    /// body, e.g. `path(X, Z) :- edge(X, Y), path(Y, Z).` has two occurrences
+    /// of each of its variables.
+    #[must_use]
+    pub fn variable_occurrences(&self) -> HashMap<usize, usize> {
+        let mut occurrences = HashMap::new();
+
+        for term in
+            self.head.arguments.iter().chain(
+                self.body.iter().flat_map(|goal| &goal.predicate.arguments),
+            )
+        {
+            count_variables(term, &mut occurrences);
+        }
+
+        occurrences
+    }
+
+    /// Returns the variables occurring exactly once in the clause, sorted,
+    /// which often are misspellings of another variable, e.g. `Y` in
+    /// `grandparent(X, Z) :- parent(X, Y), parent(Yy, Z).`
+    ///
+    /// Anonymous variables, see [`Term::anonymous`], are meant to occur once
+    /// and are never reported.
+    ///
+    /// ```
+    /// use slg_prolog_solver::parser::parse_clause;
+    ///
+    /// let clause =
+    ///     parse_clause("grandparent(X, Z) :- parent(X, Y), parent(Yy, Z).")
+    ///         .unwrap();
+    ///
+    /// // the variables are numbered in order of appearance: X, Z, Y, Yy
+    /// assert_eq!(clause.singletons(), [2, 3]);
+    /// ```
+    #[must_use]
+    pub fn singletons(&self) -> Vec<usize> {
+        let mut singletons = self
+            .variable_occurrences()
+            .into_iter()
+            .filter(|(variable, count)| {
+                *count == 1 && !Term::is_anonymous(*variable)
+            })
+            .map(|(variable, _)| variable)
+            .collect::<Vec<_>>();
+
+        singletons.sort_unstable();
+        singletons
+    }
+
+    /// Reorders the goals of the body so that the most constrained ones are
+    /// proven first, reducing the number of strands the solver explores.
+    ///
+    /// Goals are picked greedily: a goal whose variables are all bound by the
+    /// goals before it comes first since it merely checks the bindings, then
+    /// the goal with the fewest matching clauses in the knowledge base, and
+    /// then the one with the fewest unbound variables. Ties keep the original
+    /// order.
+    ///
+    /// Only runs of ordinary predicate calls are reordered. Cuts and
+    /// built-ins, e.g. `X < Y` or `\+ Goal`, depend on the bindings made
+    /// before them, so they stay in place and no goal moves across them.
+    /// The answers of the clause remain the same, though a non-tabled
+    /// predicate, see [`KnowledgeBase::set_tabled`], may start looping when
+    /// called with fewer bindings.
+    pub fn reorder_body(&mut self, knowledge_base: &KnowledgeBase) {
+        self.reorder_body_by(&|goal| {
+            knowledge_base.matching_clause_count(goal)
+        });
+    }
+
+    /// Same as [`Self::reorder_body`] with `matching` giving the number of
+    /// clauses matching a goal.
+    fn reorder_body_by(&mut self, matching: &impl Fn(&Goal) -> usize) {
+        let mut bound = HashSet::new();
+        let mut body = Vec::with_capacity(self.body.len());
+        let mut run = Vec::new();
+
+        for goal in std::mem::take(&mut self.body) {
+            if goal.contains_cut() || builtin::is_builtin(&goal) {
+                reorder_run(matching, &mut run, &mut bound, &mut body);
+
+                collect_variables(&goal, &mut bound);
+                body.push(goal);
+            } else {
+                run.push(goal);
+            }
+        }
+
+        reorder_run(matching, &mut run, &mut bound, &mut body);
+
+        self.body = body;
+    }
+}
+
+/// Moves the goals of the `run` into the `body` in the order picked by
+/// [`Clause::reorder_body`], `bound` being the variables bound so far and
+/// `matching` giving the number of clauses matching a goal.
+fn reorder_run(
+    matching: &impl Fn(&Goal) -> usize,
+    run: &mut Vec<Goal>,
+    bound: &mut HashSet<usize>,
+    body: &mut Vec<Goal>,
+) {
+    while !run.is_empty() {
+        let cost = |goal: &Goal| {
+            let mut variables = HashSet::new();
+            collect_variables(goal, &mut variables);
+            let unbound = variables.difference(bound).count();
+
+            if unbound == 0 {
+                return (0, 0);
+            }
+
+            (matching(goal), unbound)
+        };
+
+        // `min_by_key` keeps the first of the equally constrained goals
+        let (index, _) =
+            run.iter().enumerate().min_by_key(|(_, goal)| cost(goal)).unwrap();
+
+        let goal = run.remove(index);
+        collect_variables(&goal, bound);
+        body.push(goal);
+    }
+}
+
+/// Adds the variables of the goal to `variables`.
+fn collect_variables(goal: &Goal, variables: &mut HashSet<usize>) {
+    for argument in &goal.predicate.arguments {
+        argument.visit(&mut |term| {
+            if let Term::Variable(variable) = term {
+                variables.insert(*variable);
+            }
+        });
+    }
+}
+
+impl fmt::Display for Clause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.head)?;
+
+        for (i, goal) in self.body.iter().enumerate() {
+            if i == 0 {
+                write!(f, " :- ")?;
+            } else {
+                write!(f, ", ")?;
+            }
+            write!(f, "{goal}")?;
+        }
+
+        write!(f, ".")
+    }
+}
+
+/// A structured clause body allowing disjunctions, e.g. `road(X, Y) ; rail(X,
+/// Y)`, within a single clause.
+///
+/// Use [`BodyElem::into_goals`] to turn it into the body of a [`Clause`]; a
+/// disjunction becomes a single `;` goal whose arguments are the branches
+/// encoded as terms (see [`Goal::to_term`]), and a conjunction nested within a
+/// branch becomes a `,` goal. The solver expands a `;` goal into one strand
+/// per branch when it gets selected.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BodyElem {
+    Goal(Goal),
+
+    /// All the elements must be proven, in order.
+    Conjunction(Vec<BodyElem>),
+
+    /// Any of the elements may be proven, an empty disjunction always fails.
+    Disjunction(Vec<BodyElem>),
+}
+
+impl BodyElem {
+    /// Flattens the body element into the goals of a clause body.
+    #[must_use]
+    pub fn into_goals(self) -> Vec<Goal> {
+        match self {
+            BodyElem::Goal(goal) => vec![goal],
+            BodyElem::Conjunction(elements) => {
+                elements.into_iter().flat_map(BodyElem::into_goals).collect()
+            }
+            BodyElem::Disjunction(elements) => vec![Goal {
+                predicate: Predicate {
+                    name: Symbol::DISJUNCTION,
+                    arguments: elements
+                        .into_iter()
+                        .map(BodyElem::into_term)
+                        .collect(),
+                },
+            }],
+        }
+    }
+
+    fn into_term(self) -> Term {
+        match self {
+            BodyElem::Goal(goal) => goal.to_term(),
+            BodyElem::Conjunction(elements) => Term::Compound(
+                Symbol::CONJUNCTION,
+                elements.into_iter().map(BodyElem::into_term).collect(),
+            ),
+            BodyElem::Disjunction(elements) => Term::Compound(
+                Symbol::DISJUNCTION,
+                elements.into_iter().map(BodyElem::into_term).collect(),
+            ),
+        }
+    }
+}
+
+impl From<Goal> for BodyElem {
+    fn from(goal: Goal) -> Self { BodyElem::Goal(goal) }
+}
+
+/// The key used for bucketing clauses by the top-level shape of their first
+/// head argument.
+///
+/// Two terms with different keys can never unify, which allows the
+/// [`KnowledgeBase`] to skip clauses without attempting unification.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum IndexKey {
+    Atom(Symbol),
+    Integer(i64),
+    Str(String),
+    Compound(Symbol, usize),
+}
+
+impl IndexKey {
+    /// Returns the index key of the given term or `None` if the term is a
+    /// variable, which could unify with anything.
+    fn of(term: &Term) -> Option<Self> {
+        match term {
+            Term::Atom(name) => Some(Self::Atom(*name)),
+            Term::Integer(value) => Some(Self::Integer(*value)),
+            Term::Str(value) => Some(Self::Str(value.clone())),
+            Term::Variable(_) => None,
+            Term::Compound(name, args) => {
+                Some(Self::Compound(*name, args.len()))
+            }
+        }
+    }
+}
+
+/// All the clauses sharing the same predicate name along with the
+/// first-argument index over them.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+struct Procedure {
+    /// The clauses in the order they were added.
+    clauses: Vec<Clause>,
+
+    /// Indices into [`Self::clauses`] bucketed by the [`IndexKey`] of the
+    /// first head argument.
+    clause_indices_by_key: HashMap<IndexKey, Vec<usize>>,
+
+    /// Indices into [`Self::clauses`] whose first head argument is a variable
+    /// or which have no argument at all; these match any call.
+    unindexed_clause_indices: Vec<usize>,
+
+    /// Whether each clause of [`Self::clauses`] has a linear head, see
+    /// [`Clause::has_linear_head`].
+    linear_heads: Vec<bool>,
+
+    /// Indices into [`Self::clauses`] by the canonical form of the clauses,
+    /// see [`Clause::canonicalize`], so that a clause is found up to the
+    /// renaming of its variables without canonicalizing every other one.
+    clause_indices_by_canonical_form: HashMap<Clause, Vec<usize>>,
+}
+
+impl Procedure {
+    fn add_clause(&mut self, clause: Clause) {
+        let linear_head = clause.has_linear_head();
+        self.add_clause_with_linearity(clause, linear_head);
+    }
+
+    /// Adds the clause whose head is already known to be linear or not, see
+    /// [`Clause::has_linear_head`].
+    fn add_clause_with_linearity(&mut self, clause: Clause, linear_head: bool) {
+        let index = self.clauses.len();
+
+        match clause.head.arguments.first().and_then(IndexKey::of) {
+            Some(key) => {
+                self.clause_indices_by_key.entry(key).or_default().push(index);
+            }
+            None => self.unindexed_clause_indices.push(index),
+        }
+
+        self.linear_heads.push(linear_head);
+        self.clauses.push(clause);
+        self.index_canonical_form(index);
+    }
+
+    /// Adds the clause at the given index to the
+    /// [`Self::clause_indices_by_canonical_form`].
+    fn index_canonical_form(&mut self, index: usize) {
+        let mut canonical = self.clauses[index].clone();
+        canonical.canonicalize();
+
+        self.clause_indices_by_canonical_form
+            .entry(canonical)
+            .or_default()
+            .push(index);
+    }
+
+    /// Returns the index of the first clause whose canonical form is the
+    /// given one.
+    fn position(&self, canonical: &Clause) -> Option<usize> {
+        self.clause_indices_by_canonical_form
+            .get(canonical)
+            .and_then(|indices| indices.first().copied())
+    }
+
+    /// Removes the clause at the given index, the indices of the later
+    /// clauses shift down by one.
+    fn remove_clause(&mut self, index: usize) -> Clause {
+        let shift = |indices: &mut Vec<usize>| {
+            indices.retain(|other| *other != index);
+
+            for other in indices.iter_mut().filter(|other| **other > index) {
+                *other -= 1;
+            }
+
+            !indices.is_empty()
+        };
+
+        self.clause_indices_by_key.retain(|_, indices| shift(indices));
+        shift(&mut self.unindexed_clause_indices);
+        self.clause_indices_by_canonical_form
+            .retain(|_, indices| shift(indices));
+
+        self.linear_heads.remove(index);
+        self.clauses.remove(index)
+    }
+
+    /// Rebuilds the [`Self::clause_indices_by_canonical_form`] after the
+    /// clauses have been rewritten.
+    fn reindex_canonical_forms(&mut self) {
+        self.clause_indices_by_canonical_form.clear();
+
+        for index in 0..self.clauses.len() {
+            self.index_canonical_form(index);
+        }
+    }
+
+    /// Returns the indices of the clauses whose first head argument could
+    /// unify with the given term, in the order they were added.
+    fn matching_indices(&self, first_argument: &Term) -> Vec<usize> {
+        let Some(key) = IndexKey::of(first_argument) else {
+            return (0..self.clauses.len()).collect();
+        };
+
+        let indexed =
+            self.clause_indices_by_key.get(&key).map_or(&[][..], Vec::as_slice);
+        let unindexed = self.unindexed_clause_indices.as_slice();
+
+        // both lists are sorted, merge them to preserve the clause order
+        let mut result = Vec::with_capacity(indexed.len() + unindexed.len());
+        let (mut i, mut j) = (0, 0);
+
+        while i < indexed.len() && j < unindexed.len() {
+            if indexed[i] < unindexed[j] {
+                result.push(indexed[i]);
+                i += 1;
+            } else {
+                result.push(unindexed[j]);
+                j += 1;
+            }
+        }
+
+        result.extend_from_slice(&indexed[i..]);
+        result.extend_from_slice(&unindexed[j..]);
+
+        result
+    }
+}
+
+/// The collection of clauses the solver resolves goals against.
+///
+/// With the `serde` feature enabled, a [`KnowledgeBase`] serializes as the
+/// plain list of its clauses, grouped by [`Functor`] in lexicographic order
+/// and otherwise in insertion order. Clauses are stored exactly as they were
+/// added; they're only canonicalized when the solver creates a table, so
+/// deserializing simply re-adds every clause via
+/// [`KnowledgeBase::add_clause`], which also rebuilds the first-argument
+/// index. The tabling mode set by [`KnowledgeBase::set_tabled`], the modes
+/// declared with [`KnowledgeBase::declare_mode`], and the aliases made with
+/// [`KnowledgeBase::alias_predicate`] aren't serialized, every predicate of a
+/// deserialized knowledge base is tabled, unmoded, and unaliased.
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct KnowledgeBase {
-    clauses_by_predicate_name: HashMap<String, Vec<Clause>>,
+    procedures_by_functor: HashMap<Functor, Procedure>,
+
+    /// The predicates resolved depth-first rather than through a table, see
+    /// [`KnowledgeBase::set_tabled`].
+    untabled_functors: HashSet<Functor>,
+
+    /// The argument modes of the predicates, see
+    /// [`KnowledgeBase::declare_mode`].
+    modes_by_functor: HashMap<Functor, Vec<Mode>>,
+
+    /// The predicate names standing for another name, see
+    /// [`KnowledgeBase::alias_predicate`].
+    aliases: HashMap<Symbol, Symbol>,
+}
+
+/// The mode of a predicate argument, see [`KnowledgeBase::declare_mode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Mode {
+    /// `+`, the argument must be ground when the predicate is called.
+    In,
+
+    /// `-`, the argument is produced by the predicate, it may be anything
+    /// when the predicate is called.
+    Out,
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mode::In => f.write_str("+"),
+            Mode::Out => f.write_str("-"),
+        }
+    }
 }
 
 impl KnowledgeBase {
-    /// Returns clauses for a given predicate name
-    pub fn get_clauses(&self, predicate_name: &str) -> Option<&Vec<Clause>> {
-        self.clauses_by_predicate_name.get(predicate_name)
+    /// Returns the clauses of the given predicate, e.g. `("edge", 2)`, or of
+    /// the predicate it's an alias of, see [`Self::alias_predicate`].
+    pub fn get_clauses(
+        &self,
+        functor: impl Into<Functor>,
+    ) -> Option<&Vec<Clause>> {
+        self.procedures_by_functor
+            .get(&self.resolve_alias(functor.into()))
+            .map(|procedure| &procedure.clauses)
+    }
+
+    /// Makes the predicates named `to`, of any arity, resolve against the
+    /// clauses of the predicates named `from`, without copying them, e.g. to
+    /// integrate two ontologies naming the same relation differently.
+    ///
+    /// The clauses of `to` itself, if any, are hidden while it's an alias,
+    /// and the clauses added to `to` afterwards are added to the predicate it
+    /// resolves against, see [`Self::add_clause`]. Aliases chain, aliasing `a`
+    /// to `b` and then `b` to `c` makes `c` resolve against `a` as well,
+    /// but aliasing a name to one resolving back to it would make a cycle
+    /// and is ignored.
+    ///
+    /// ```
+    /// use slg_prolog_solver::{
+    ///     clause::KnowledgeBase, parser::parse_goal, solver::Solver,
+    /// };
+    ///
+    /// let mut kb: KnowledgeBase = "parent(alice, bob).".parse().unwrap();
+    /// kb.alias_predicate("parent", "father_or_mother");
+    ///
+    /// let goal = parse_goal("father_or_mother(alice, X)").unwrap();
+    /// assert_eq!(Solver::new(&kb).solve_all(goal).unwrap().len(), 1);
+    /// ```
+    pub fn alias_predicate(&mut self, from: &str, to: &str) {
+        let (from, to) = (Symbol::new(from), Symbol::new(to));
+
+        if self.resolve_alias(Functor::new(from, 0)).name != to {
+            self.aliases.insert(to, from);
+        }
+    }
+
+    /// Follows the aliases of the predicate name, see
+    /// [`Self::alias_predicate`], returning the predicate whose clauses
+    /// resolve it.
+    pub(crate) fn resolve_alias(&self, mut functor: Functor) -> Functor {
+        while let Some(name) = self.aliases.get(&functor.name) {
+            functor.name = *name;
+        }
+
+        functor
+    }
+
+    /// Returns the name the predicates named `name` resolve against, see
+    /// [`Self::alias_predicate`].
+    pub(crate) fn resolve_alias_name(&self, name: &str) -> Symbol {
+        self.resolve_alias(Functor::new(Symbol::new(name), 0)).name
+    }
+
+    /// Returns every clause, ordered by [`Functor`] and then by their order
+    /// within the predicate.
+    pub fn clauses_iter(&self) -> impl Iterator<Item = &Clause> {
+        let mut procedures =
+            self.procedures_by_functor.iter().collect::<Vec<_>>();
+        procedures.sort_by_key(|(functor, _)| **functor);
+
+        procedures
+            .into_iter()
+            .flat_map(|(_, procedure)| procedure.clauses.iter())
+    }
+
+    /// Returns the distinct names of the predicates in alphabetical order,
+    /// e.g. `edge` only once even if both `edge/2` and `edge/3` are defined.
+    pub fn predicate_names(&self) -> impl Iterator<Item = &str> {
+        let mut names = self
+            .procedures_by_functor
+            .keys()
+            .map(|functor| functor.name.as_str())
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+        names.dedup();
+
+        names.into_iter()
+    }
+
+    /// Returns the total number of clauses across every predicate.
+    pub fn len(&self) -> usize {
+        self.procedures_by_functor
+            .values()
+            .map(|procedure| procedure.clauses.len())
+            .sum()
+    }
+
+    /// Checks whether the knowledge base has no clauses at all.
+    pub fn is_empty(&self) -> bool {
+        self.procedures_by_functor
+            .values()
+            .all(|procedure| procedure.clauses.is_empty())
+    }
+
+    /// Returns the number of clauses of the predicates with the given name,
+    /// whatever their arity, e.g. both `edge/2` and `edge/3` count for
+    /// `edge`, or of the predicates it's an alias of, see
+    /// [`Self::alias_predicate`].
+    pub fn clause_count(&self, predicate_name: &str) -> usize {
+        let name = self.resolve_alias_name(predicate_name);
+
+        self.procedures_by_functor
+            .iter()
+            .filter(|(functor, _)| functor.name == name)
+            .map(|(_, procedure)| procedure.clauses.len())
+            .sum()
+    }
+
+    /// Returns the clauses of the given predicate whose first head argument
+    /// could unify with `first_argument`.
+    ///
+    /// Clauses are bucketed by the atom or functor of their first head
+    /// argument, so only the matching bucket and the clauses having a
+    /// variable as their first argument are yielded. If `first_argument` is a
+    /// variable, every clause of the predicate is yielded.
+    pub fn get_clauses_matching<'s>(
+        &'s self,
+        functor: impl Into<Functor>,
+        first_argument: &Term,
+    ) -> impl Iterator<Item = &'s Clause> + 's {
+        self.procedures_by_functor
+            .get(&self.resolve_alias(functor.into()))
+            .map(|procedure| {
+                (procedure, procedure.matching_indices(first_argument))
+            })
+            .into_iter()
+            .flat_map(|(procedure, indices)| {
+                indices.into_iter().map(move |index| &procedure.clauses[index])
+            })
     }
+
+    /// Returns the clauses of the given predicate, not following aliases,
+    /// whose first head argument could unify with `first_argument`, see
+    /// [`Self::get_clauses_matching`], along with whether their head is
+    /// linear, see [`Clause::has_linear_head`].
+    pub(crate) fn get_candidate_clauses(
+        &self,
+        functor: Functor,
+        first_argument: Option<&Term>,
+    ) -> Vec<(&Clause, bool)> {
+        let Some(procedure) = self.procedures_by_functor.get(&functor) else {
+            return Vec::new();
+        };
+
+        let indices = match first_argument {
+            Some(first_argument) => procedure.matching_indices(first_argument),
+            None => (0..procedure.clauses.len()).collect(),
+        };
+
+        indices
+            .into_iter()
+            .map(|index| {
+                (&procedure.clauses[index], procedure.linear_heads[index])
+            })
+            .collect()
+    }
+
     pub fn new() -> Self {
-        KnowledgeBase { clauses_by_predicate_name: HashMap::new() }
+        KnowledgeBase {
+            procedures_by_functor: HashMap::new(),
+            untabled_functors: HashSet::new(),
+            modes_by_functor: HashMap::new(),
+            aliases: HashMap::new(),
+        }
+    }
+
+    /// Starts building a knowledge base clause by clause, see
+    /// [`KnowledgeBaseBuilder`].
+    #[must_use]
+    pub fn builder() -> KnowledgeBaseBuilder { KnowledgeBaseBuilder::default() }
+
+    /// Sets whether the solver memoizes the answers of the given predicate in
+    /// a table, which is the default.
+    ///
+    /// A non-tabled predicate called from a clause body is resolved
+    /// depth-first in place, like plain SLD resolution: its clauses are
+    /// unfolded into the calling strand instead of going through a table of
+    /// their own, which saves the work list and the answer memo for cheap
+    /// predicates such as facts. Queries and `findall/3` goals on it still
+    /// get a table.
+    ///
+    /// Without a table, nothing detects a variant call, so a non-tabled
+    /// recursive predicate may loop forever, e.g. a left-recursive
+    /// `path(X, Z) :- path(X, Y), edge(Y, Z).`, until the step budget or the
+    /// deadline of the solver stops it: every resolution counts as a step.
+    /// Predicates with a cut in any of their clauses are always tabled since
+    /// the cut needs a table to prune.
+    ///
+    /// Setting whether an alias, see [`Self::alias_predicate`], is tabled sets
+    /// it for the predicate the alias resolves against.
+    pub fn set_tabled(&mut self, functor: impl Into<Functor>, tabled: bool) {
+        let functor = self.resolve_alias(functor.into());
+
+        if tabled {
+            self.untabled_functors.remove(&functor);
+        } else {
+            self.untabled_functors.insert(functor);
+        }
+    }
+
+    /// Checks whether the given predicate is tabled, see
+    /// [`KnowledgeBase::set_tabled`].
+    pub fn is_tabled(&self, functor: impl Into<Functor>) -> bool {
+        !self.untabled_functors.contains(&self.resolve_alias(functor.into()))
+    }
+
+    /// Declares the modes of the arguments of the predicate named `name`,
+    /// whose arity is the number of modes, replacing any previous
+    /// declaration.
+    ///
+    /// The solver checks every call to the predicate against its modes: a
+    /// call leaving a [`Mode::In`] argument non-ground fails with
+    /// [`crate::solver::SolveError::ModeViolation`] before any clause is tried.
+    /// For example, after declaring `parent(+, -)`, the call `parent(X,
+    /// bob)` is rejected whereas `parent(adam, X)` runs as usual. This catches
+    /// the misuse of predicates that only work one way, e.g. ones relying on
+    /// arithmetic comparisons.
+    ///
+    /// The modes of an alias, see [`Self::alias_predicate`], are declared for
+    /// the predicate it resolves against, and calls through any of its aliases
+    /// are checked against them.
+    pub fn declare_mode(
+        &mut self,
+        name: impl Into<Symbol>,
+        modes: impl IntoIterator<Item = Mode>,
+    ) {
+        let modes = modes.into_iter().collect::<Vec<_>>();
+
+        let functor = self.resolve_alias(Functor::new(name, modes.len()));
+
+        self.modes_by_functor.insert(functor, modes);
     }
 
-    pub fn add_clause(&mut self, clause: Clause) {
-        self.clauses_by_predicate_name
-            .entry(clause.head.name.clone())
+    /// Returns the modes declared for the given predicate, see
+    /// [`KnowledgeBase::declare_mode`].
+    pub fn modes(&self, functor: impl Into<Functor>) -> Option<&[Mode]> {
+        self.modes_by_functor
+            .get(&self.resolve_alias(functor.into()))
+            .map(Vec::as_slice)
+    }
+
+    /// Returns the index of the first [`Mode::In`] argument the goal leaves
+    /// non-ground, `None` if the goal respects the modes of its predicate.
+    pub fn mode_violation(&self, goal: &Goal) -> Option<usize> {
+        let modes = self.modes(goal.predicate.functor())?;
+
+        modes.iter().zip(&goal.predicate.arguments).position(
+            |(mode, argument)| *mode == Mode::In && !argument.is_ground(),
+        )
+    }
+
+    /// Reorders the body of every clause, see [`Clause::reorder_body`].
+    pub fn optimize(&mut self) {
+        // reordering doesn't change the clause counts the order is based on,
+        // so they're all counted before rewriting any clause
+        let mut matching = HashMap::new();
+        for clause in self.clauses_iter() {
+            for goal in &clause.body {
+                if !matching.contains_key(goal) {
+                    matching
+                        .insert(goal.clone(), self.matching_clause_count(goal));
+                }
+            }
+        }
+
+        for procedure in self.procedures_by_functor.values_mut() {
+            for clause in &mut procedure.clauses {
+                clause.reorder_body_by(&|goal| matching[goal]);
+            }
+
+            procedure.reindex_canonical_forms();
+        }
+    }
+
+    /// Returns the number of clauses the goal may resolve against, as
+    /// narrowed down by the first-argument index.
+    fn matching_clause_count(&self, goal: &Goal) -> usize {
+        let predicate = &goal.predicate;
+
+        match predicate.arguments.first() {
+            Some(first_argument) => self
+                .get_clauses_matching(predicate.functor(), first_argument)
+                .count(),
+            None => self.get_clauses(predicate.functor()).map_or(0, Vec::len),
+        }
+    }
+
+    /// Adds the clause after the other clauses of its predicate.
+    ///
+    /// A clause whose head names an alias, see [`Self::alias_predicate`], is
+    /// renamed to the predicate the alias resolves against and added to it.
+    pub fn add_clause(&mut self, mut clause: Clause) {
+        clause.head.name = self.resolve_alias(clause.head.functor()).name;
+
+        self.procedures_by_functor
+            .entry(clause.head.functor())
             .or_default()
-            .push(clause);
+            .add_clause(clause);
+    }
+
+    /// Adds a fact of the predicates named `name` for every row of arguments,
+    /// e.g. the tuples of a relation imported from a database.
+    ///
+    /// Unlike calling [`Self::add_clause`] for every fact, the predicate is
+    /// looked up once per arity with room reserved for all of its rows, and
+    /// a ground row is known to make a linear head, see
+    /// [`Clause::has_linear_head`], without collecting its variables; rows
+    /// containing variables are added like any other fact. The facts of an
+    /// alias are added to the predicate it resolves against.
+    ///
+    /// ```
+    /// use slg_prolog_solver::{clause::KnowledgeBase, term::Term};
+    ///
+    /// let mut kb = KnowledgeBase::new();
+    /// kb.add_facts("edge", [vec![Term::atom("a"), Term::atom("b")], vec![
+    ///     Term::atom("b"),
+    ///     Term::atom("c"),
+    /// ]]);
+    ///
+    /// assert_eq!(kb.get_clauses(("edge", 2)).unwrap().len(), 2);
+    /// ```
+    pub fn add_facts(
+        &mut self,
+        name: &str,
+        rows: impl IntoIterator<Item = Vec<Term>>,
+    ) {
+        let name = self.resolve_alias_name(name);
+
+        let mut rows_by_arity = BTreeMap::<usize, Vec<Vec<Term>>>::new();
+        for row in rows {
+            rows_by_arity.entry(row.len()).or_default().push(row);
+        }
+
+        for (arity, rows) in rows_by_arity {
+            let procedure = self
+                .procedures_by_functor
+                .entry(Functor::new(name, arity))
+                .or_default();
+
+            procedure.clauses.reserve(rows.len());
+            procedure.linear_heads.reserve(rows.len());
+
+            for row in rows {
+                let ground = row.iter().all(Term::is_ground);
+                let fact = Clause::fact(Predicate { name, arguments: row });
+
+                if ground {
+                    procedure.add_clause_with_linearity(fact, true);
+                } else {
+                    procedure.add_clause(fact);
+                }
+            }
+        }
+    }
+
+    /// Checks whether the knowledge base already contains the clause, up to
+    /// the renaming of its variables, e.g. `p(X, Y) :- q(Y).` is contained if
+    /// `p(A, B) :- q(B).` has been added.
+    ///
+    /// Both clauses are compared in their canonical form, see
+    /// [`Clause::canonicalize`], a clause of an alias being renamed to the
+    /// predicate the alias resolves against, see [`Self::add_clause`].
+    #[must_use]
+    pub fn contains_clause(&self, clause: &Clause) -> bool {
+        let functor = self.resolve_alias(clause.head.functor());
+        let Some(procedure) = self.procedures_by_functor.get(&functor) else {
+            return false;
+        };
+
+        let mut canonical = clause.clone();
+        canonical.head.name = functor.name;
+        canonical.canonicalize();
+
+        procedure.position(&canonical).is_some()
+    }
+
+    /// Removes the first clause equal to the given one up to the renaming of
+    /// its variables, see [`KnowledgeBase::contains_clause`], returning
+    /// whether one was removed.
+    ///
+    /// Like Prolog's `retract/1` but without unifying: `p(X) :- q(X).` only
+    /// removes a clause of the same shape, not `p(a) :- q(a).`
+    pub fn remove_clause(&mut self, clause: &Clause) -> bool {
+        let functor = self.resolve_alias(clause.head.functor());
+        let Some(procedure) = self.procedures_by_functor.get_mut(&functor)
+        else {
+            return false;
+        };
+
+        let mut canonical = clause.clone();
+        canonical.head.name = functor.name;
+        canonical.canonicalize();
+
+        let Some(index) = procedure.position(&canonical) else {
+            return false;
+        };
+
+        procedure.remove_clause(index);
+        if procedure.clauses.is_empty() {
+            self.procedures_by_functor.remove(&functor);
+        }
+
+        true
+    }
+
+    /// Adds the clause unless the knowledge base already contains it, see
+    /// [`KnowledgeBase::contains_clause`], returning whether it was added.
+    ///
+    /// Loading the same program twice this way leaves the knowledge base as
+    /// if it had been loaded once.
+    pub fn add_clause_unique(&mut self, clause: Clause) -> bool {
+        if self.contains_clause(&clause) {
+            return false;
+        }
+
+        self.add_clause(clause);
+        true
+    }
+
+    /// Statically checks the clauses for common modeling bugs, returning a
+    /// [`Diagnostic`] for every issue found.
+    ///
+    /// Clauses are checked in their canonical form, ordered by [`Functor`] and
+    /// then by their order within the predicate.
+    ///
+    /// Anonymous variables, see [`Term::anonymous`], are never reported since
+    /// they're meant not to be bound.
+    #[must_use]
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+
+        for clause in self.clauses_iter() {
+            // the solver evaluates the built-in instead of looking for clauses
+            if builtin::is_builtin(&Goal { predicate: clause.head.clone() }) {
+                let mut clause = clause.clone();
+                clause.canonicalize();
+
+                diagnostics.push(Diagnostic::BuiltinRedefinition { clause });
+            }
+
+            // facts state that the head holds for any value of its variables
+            if clause.body.is_empty() {
+                continue;
+            }
+
+            let mut head_occurrences = HashMap::new();
+            for term in &clause.head.arguments {
+                count_variables(term, &mut head_occurrences);
+            }
+
+            let mut body_occurrences = HashMap::new();
+            for goal in &clause.body {
+                for term in &goal.predicate.arguments {
+                    count_variables(term, &mut body_occurrences);
+                }
+            }
+
+            let mut unbound = head_occurrences
+                .into_keys()
+                .filter(|variable| {
+                    !body_occurrences.contains_key(variable)
+                        && !Term::is_anonymous(*variable)
+                })
+                .collect::<Vec<_>>();
+
+            // report the variables as they appear in the canonical clause
+            let mut clause = clause.clone();
+            let mapping = clause.canonicalize();
+
+            for variable in &mut unbound {
+                *variable = mapping[variable];
+            }
+            unbound.sort_unstable();
+
+            diagnostics.extend(unbound.into_iter().map(|variable| {
+                Diagnostic::UnboundHeadVariable {
+                    clause: clause.clone(),
+                    variable,
+                }
+            }));
+        }
+
+        diagnostics
+    }
+}
+
+/// Counts the occurrences of every variable in the term.
+fn count_variables(term: &Term, occurrences: &mut HashMap<usize, usize>) {
+    term.visit(&mut |term| {
+        if let Term::Variable(variable) = term {
+            *occurrences.entry(*variable).or_default() += 1;
+        }
+    });
+}
+
+/// An issue found by [`KnowledgeBase::validate`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Diagnostic {
+    /// A variable occurs in the head of a rule but nowhere in its body, e.g.
+    /// `X` in `p(X) :- q(Y).` or in `p(X, X) :- q.`, thus the rule never binds
+    /// it.
+    UnboundHeadVariable {
+        /// The offending clause, canonicalized.
+        clause: Clause,
+
+        /// The unbound variable of the canonicalized clause.
+        variable: usize,
+    },
+
+    /// A clause defines a built-in predicate, e.g. `succ(0, 1).`, which the
+    /// solver evaluates itself without ever looking at the clause.
+    BuiltinRedefinition {
+        /// The offending clause, canonicalized.
+        clause: Clause,
+    },
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnostic::UnboundHeadVariable { clause, variable } => write!(
+                f,
+                "`?{variable}` in the head of `{clause}` never appears in its \
+                 body"
+            ),
+            Diagnostic::BuiltinRedefinition { clause } => write!(
+                f,
+                "`{clause}` defines the built-in `{}` and is never used",
+                clause.head.functor()
+            ),
+        }
+    }
+}
+
+/// Builds a [`KnowledgeBase`] through method chaining, created by
+/// [`KnowledgeBase::builder`].
+///
+/// Every clause goes through [`KnowledgeBase::add_clause`], in the order the
+/// methods are called.
+///
+/// ```
+/// use slg_prolog_solver::{
+///     clause::{Goal, KnowledgeBase, Predicate},
+///     solver::Solver,
+///     term::Term,
+/// };
+///
+/// let (x, y, z) = (Term::variable(0), Term::variable(1), Term::variable(2));
+///
+/// // parent(adam, bob).
+/// // parent(bob, carl).
+/// // grandparent(X, Z) :- parent(X, Y), parent(Y, Z).
+/// let kb = KnowledgeBase::builder()
+///     .fact("parent", [Term::atom("adam"), Term::atom("bob")])
+///     .fact("parent", [Term::atom("bob"), Term::atom("carl")])
+///     .rule(Predicate::new("grandparent", [x.clone(), z.clone()]), [
+///         Goal::new("parent", [x, y.clone()]),
+///         Goal::new("parent", [y, z]),
+///     ])
+///     .build();
+///
+/// let answers = Solver::new(&kb)
+///     .solve_all(Goal::new("grandparent", [
+///         Term::atom("adam"),
+///         Term::variable(0),
+///     ]))
+///     .unwrap();
+///
+/// assert_eq!(answers.len(), 1);
+/// assert_eq!(answers[0].mapping[&0], Term::atom("carl"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct KnowledgeBaseBuilder {
+    knowledge_base: KnowledgeBase,
+}
+
+impl KnowledgeBaseBuilder {
+    /// Adds the fact `name(arguments...)`, see [`Clause::fact`].
+    #[must_use]
+    pub fn fact(
+        self,
+        name: impl Into<Symbol>,
+        arguments: impl IntoIterator<Item = Term>,
+    ) -> Self {
+        self.clause(Clause::fact(Predicate::new(name, arguments)))
+    }
+
+    /// Adds the rule `head :- body`, see [`Clause::rule`].
+    #[must_use]
+    pub fn rule(
+        self,
+        head: Predicate,
+        body: impl IntoIterator<Item = Goal>,
+    ) -> Self {
+        self.clause(Clause::rule(head, body))
+    }
+
+    /// Adds the clause.
+    #[must_use]
+    pub fn clause(mut self, clause: Clause) -> Self {
+        self.knowledge_base.add_clause(clause);
+        self
+    }
+
+    /// Adds the clauses of a program written in Prolog syntax, see
+    /// [`parser::parse_program`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`ParseError`] of a malformed program, none of its clauses
+    /// are added then.
+    pub fn program(mut self, program: &str) -> Result<Self, ParseError> {
+        for clause in parser::parse_program(program)? {
+            self.knowledge_base.add_clause(clause);
+        }
+
+        Ok(self)
+    }
+
+    /// Returns the knowledge base built.
+    #[must_use]
+    pub fn build(self) -> KnowledgeBase { self.knowledge_base }
+}
+
+/// Loads a program written in Prolog syntax, see [`crate::parser`].
+///
+/// ```
+/// use slg_prolog_solver::clause::KnowledgeBase;
+///
+/// let kb: KnowledgeBase = "
+///     % the edges of the graph
+///     edge(a, b).
+///     edge(b, c).
+///
+///     path(X, Y) :- edge(X, Y).
+///     path(X, Y) :- edge(X, Z), path(Z, Y).
+/// "
+/// .parse()
+/// .unwrap();
+///
+/// assert_eq!(kb.get_clauses(("path", 2)).unwrap().len(), 2);
+/// ```
+impl FromStr for KnowledgeBase {
+    type Err = ParseError;
+
+    fn from_str(program: &str) -> Result<Self, Self::Err> {
+        let mut knowledge_base = KnowledgeBase::new();
+
+        for clause in parser::parse_program(program)? {
+            knowledge_base.add_clause(clause);
+        }
+
+        Ok(knowledge_base)
     }
 }
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for KnowledgeBase {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.clauses_iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for KnowledgeBase {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        let clauses =
+            <Vec<Clause> as serde::Deserialize>::deserialize(deserializer)?;
+
+        let mut knowledge_base = Self::new();
+        for clause in clauses {
+            knowledge_base.add_clause(clause);
+        }
+
+        Ok(knowledge_base)
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,216 @@
+//! Contains the predicate-level call graph of a [`KnowledgeBase`], see
+//! [`KnowledgeBase::predicate_dependencies`].
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    ops::ControlFlow,
+};
+
+use crate::{
+    clause::{Functor, KnowledgeBase},
+    solver::builtin,
+    symbol::Symbol,
+    term::Term,
+};
+
+/// A call made by the body of a clause, an edge of the graph returned by
+/// [`KnowledgeBase::predicate_dependencies`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Dependency {
+    /// The predicate called.
+    pub functor: Functor,
+
+    /// Whether the call is made through a negation `\+ Goal`.
+    pub negative: bool,
+}
+
+/// A call made by proving a goal, see [`KnowledgeBase::try_visit_calls`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Call {
+    /// A call to a predicate of the program.
+    Predicate(Dependency),
+
+    /// A goal bound only at runtime, e.g. the goal `G` of
+    /// `findall(X, G, L)`, which may call any predicate.
+    Unknown,
+}
+
+impl KnowledgeBase {
+    /// Returns the predicates every predicate calls in the bodies of its
+    /// clauses, labelled by whether they're called through a negation.
+    ///
+    /// Every predicate having a clause is a key of the graph, the ones only
+    /// having facts map to no dependency. The calls are read off the clause
+    /// bodies, looking into conjunctions, disjunctions, negations, and the
+    /// goals of `findall/3`; the other built-ins, e.g. `X < Y`, aren't
+    /// predicates of the program and are left out. A predicate calling
+    /// another both positively and negatively has both edges, and a call to
+    /// an alias, see [`KnowledgeBase::alias_predicate`], is a call to the
+    /// predicate it resolves against.
+    ///
+    /// ```
+    /// use slg_prolog_solver::clause::{Dependency, Functor, KnowledgeBase};
+    ///
+    /// let kb: KnowledgeBase = "
+    ///     edge(a, b).
+    ///     unreachable(X, Y) :- node(X), node(Y), \\+ path(X, Y).
+    /// "
+    /// .parse()
+    /// .unwrap();
+    ///
+    /// let graph = kb.predicate_dependencies();
+    ///
+    /// assert!(graph[&Functor::new("edge", 2)].is_empty());
+    /// assert!(graph[&Functor::new("unreachable", 2)].contains(&Dependency {
+    ///     functor: Functor::new("path", 2),
+    ///     negative: true,
+    /// }));
+    /// ```
+    #[must_use]
+    pub fn predicate_dependencies(
+        &self,
+    ) -> BTreeMap<Functor, BTreeSet<Dependency>> {
+        let mut graph = BTreeMap::<Functor, BTreeSet<Dependency>>::new();
+
+        for clause in self.clauses_iter() {
+            let callees = graph.entry(clause.head.functor()).or_default();
+
+            for goal in &clause.body {
+                let _ = self.try_visit_calls(&goal.to_term(), &mut |call| {
+                    if let Call::Predicate(dependency) = call {
+                        callees.insert(dependency);
+                    }
+
+                    ControlFlow::<()>::Continue(())
+                });
+            }
+        }
+
+        graph
+    }
+
+    /// Returns the names of the predicates a query on the predicates named
+    /// `from`, of any arity, may end up calling, including themselves,
+    /// following the calls of [`Self::predicate_dependencies`]. A query on an
+    /// alias, see [`Self::alias_predicate`], calls the predicates it resolves
+    /// against.
+    ///
+    /// Predicates called without having any clause are included as well,
+    /// whereas the ones called through a goal bound only at runtime, e.g.
+    /// `findall(X, G, L)` with `G` a variable, can't be followed.
+    #[must_use]
+    pub fn reachable_predicates(&self, from: &str) -> HashSet<String> {
+        let graph = self.predicate_dependencies();
+
+        reachable_functors(&graph, self.resolve_alias_name(from))
+            .into_iter()
+            .map(|functor| functor.name.to_string())
+            .collect()
+    }
+
+    /// Returns the names of the predicates having clauses that a query on
+    /// the predicates named `from` never calls, the complement of
+    /// [`Self::reachable_predicates`].
+    ///
+    /// These can be dropped from the knowledge base without changing the
+    /// answers of such queries, e.g. to prune a large rule library down to
+    /// what a query actually needs. A name is only reported if none of its
+    /// arities is reachable.
+    #[must_use]
+    pub fn unreachable_predicates(&self, from: &str) -> HashSet<String> {
+        let graph = self.predicate_dependencies();
+        let reachable =
+            reachable_functors(&graph, self.resolve_alias_name(from))
+                .into_iter()
+                .map(|functor| functor.name)
+                .collect::<HashSet<_>>();
+
+        graph
+            .into_keys()
+            .filter(|functor| !reachable.contains(&functor.name))
+            .map(|functor| functor.name.to_string())
+            .collect()
+    }
+
+    /// Visits the calls that proving the goal `term` makes, stopping as soon
+    /// as `f` breaks.
+    ///
+    /// The goal is looked into like the clause bodies of
+    /// [`Self::predicate_dependencies`]: through conjunctions, disjunctions,
+    /// negations, and the goals of `findall/3`, leaving the cut and the other
+    /// built-ins out and resolving aliases. The term is traversed with an
+    /// explicit stack, see [`Term::try_visit`].
+    pub(crate) fn try_visit_calls<B>(
+        &self,
+        term: &Term,
+        f: &mut impl FnMut(Call) -> ControlFlow<B>,
+    ) -> ControlFlow<B> {
+        let mut stack = vec![(term, false)];
+
+        while let Some((term, negative)) = stack.pop() {
+            let (name, arguments) = match term {
+                Term::Variable(_) => {
+                    f(Call::Unknown)?;
+                    continue;
+                }
+                Term::Integer(_) | Term::Str(_) => continue,
+                Term::Atom(name) => (*name, &[][..]),
+                Term::Compound(name, arguments) => (*name, &**arguments),
+            };
+
+            match (name, arguments) {
+                (Symbol::CONJUNCTION | Symbol::DISJUNCTION, _) => {
+                    stack.extend(
+                        arguments.iter().rev().map(|goal| (goal, negative)),
+                    );
+                }
+                (Symbol::FINDALL, [_, goal, _]) => stack.push((goal, negative)),
+                (Symbol::NEGATION, [goal]) => stack.push((goal, true)),
+                _ => {
+                    let functor = Functor::new(name, arguments.len());
+
+                    if functor != Functor::new(Symbol::CUT, 0)
+                        && !builtin::is_builtin_functor(functor)
+                    {
+                        f(Call::Predicate(Dependency {
+                            functor: self.resolve_alias(functor),
+                            negative,
+                        }))?;
+                    }
+                }
+            }
+        }
+
+        ControlFlow::Continue(())
+    }
+}
+
+/// Returns the predicates of the `graph` a query on the predicates named
+/// `from` may end up calling, including themselves.
+fn reachable_functors(
+    graph: &BTreeMap<Functor, BTreeSet<Dependency>>,
+    from: Symbol,
+) -> HashSet<Functor> {
+    let mut reachable = HashSet::new();
+    let mut pending = graph
+        .keys()
+        .filter(|functor| functor.name == from)
+        .copied()
+        .collect::<Vec<_>>();
+
+    while let Some(functor) = pending.pop() {
+        if !reachable.insert(functor) {
+            continue;
+        }
+
+        pending.extend(
+            graph
+                .get(&functor)
+                .into_iter()
+                .flatten()
+                .map(|dependency| dependency.functor),
+        );
+    }
+
+    reachable
+}
@@ -0,0 +1,171 @@
+//! Contains the static stratification check of a [`KnowledgeBase`].
+//!
+//! A program is stratified if no predicate depends on itself through a
+//! negation, e.g. `p :- \+ q. q :- p.` isn't. The solver evaluates a negated
+//! goal completely before deciding the negation, which is impossible if the
+//! negated goal waits for the negation in turn; such programs fail with
+//! [`crate::solver::SolveError::UnstratifiedNegation`] when solved.
+//! [`KnowledgeBase::check_stratification`] finds them upfront instead.
+
+use std::{
+    collections::{
+        BTreeMap, BTreeSet, HashMap, HashSet, VecDeque, hash_map::Entry,
+    },
+    fmt,
+};
+
+use crate::clause::{Dependency, Functor, KnowledgeBase};
+
+/// A cycle of predicates depending on each other through a negation, see
+/// [`KnowledgeBase::check_stratification`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Cycle {
+    /// The predicates of the cycle, each one calling the next one and the
+    /// last one calling the first one. The first predicate calls the second
+    /// one through a negation, the other calls may be positive or negative.
+    pub functors: Vec<Functor>,
+}
+
+/// Prints the cycle as the chain of calls, e.g. `p/0 -> \+ q/0 -> p/0`.
+impl fmt::Display for Cycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(first) = self.functors.first() else {
+            return Ok(());
+        };
+
+        write!(f, "{first}")?;
+
+        for (index, functor) in
+            self.functors.iter().chain([first]).enumerate().skip(1)
+        {
+            if index == 1 {
+                write!(f, " -> \\+ {functor}")?;
+            } else {
+                write!(f, " -> {functor}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The calls made by the bodies of the clauses of each predicate, see
+/// [`KnowledgeBase::predicate_dependencies`].
+type DependencyGraph = BTreeMap<Functor, BTreeSet<Dependency>>;
+
+impl KnowledgeBase {
+    /// Checks that no predicate depends on itself through a negation `\+
+    /// Goal`, returning a [`Cycle`] for every group of mutually recursive
+    /// predicates that does.
+    ///
+    /// The dependencies are the ones of
+    /// [`KnowledgeBase::predicate_dependencies`]. A goal that is a variable
+    /// until the program runs can't be followed and is ignored, so a
+    /// program passing the check may still fail with
+    /// [`crate::solver::SolveError::UnstratifiedNegation`] if it calls such
+    /// goals.
+    ///
+    /// ```
+    /// use slg_prolog_solver::clause::KnowledgeBase;
+    ///
+    /// let kb: KnowledgeBase = "
+    ///     win(X) :- move(X, Y), \\+ win(Y).
+    ///     move(a, b).
+    /// "
+    /// .parse()
+    /// .unwrap();
+    ///
+    /// let cycles = kb.check_stratification().unwrap_err();
+    ///
+    /// assert_eq!(cycles.len(), 1);
+    /// assert_eq!(cycles[0].to_string(), "win/1 -> \\+ win/1");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns the cycles found if the program isn't stratified, ordered by
+    /// their first predicate.
+    pub fn check_stratification(&self) -> Result<(), Vec<Cycle>> {
+        let graph = self.predicate_dependencies();
+
+        let mut cycles = Vec::<Cycle>::new();
+        let mut reported = HashSet::new();
+
+        for (caller, callees) in &graph {
+            // the negations within a group of mutually recursive predicates
+            // are reported once
+            if reported.contains(caller) {
+                continue;
+            }
+
+            for callee in callees.iter().filter(|callee| callee.negative) {
+                let Some(path) = shortest_path(&graph, callee.functor, *caller)
+                else {
+                    continue;
+                };
+
+                let mut functors = vec![*caller];
+                functors.extend(path);
+                functors.pop();
+
+                cycles.push(Cycle { functors });
+                reported.extend(
+                    shortest_callers(&graph, *caller).into_keys().filter(
+                        |functor| {
+                            shortest_callers(&graph, *functor)
+                                .contains_key(caller)
+                        },
+                    ),
+                );
+
+                break;
+            }
+        }
+
+        if cycles.is_empty() { Ok(()) } else { Err(cycles) }
+    }
+}
+
+/// Returns the predicates along a shortest chain of calls from `from` to
+/// `to`, both included.
+fn shortest_path(
+    graph: &DependencyGraph,
+    from: Functor,
+    to: Functor,
+) -> Option<Vec<Functor>> {
+    let callers = shortest_callers(graph, from);
+
+    if !callers.contains_key(&to) {
+        return None;
+    }
+
+    let mut path = vec![to];
+    while *path.last().unwrap() != from {
+        path.push(callers[path.last().unwrap()]);
+    }
+
+    path.reverse();
+    Some(path)
+}
+
+/// Searches the chains of calls from `from` breadth-first, returning every
+/// predicate reached, `from` included, mapped to the predicate calling it
+/// along a shortest chain.
+fn shortest_callers(
+    graph: &DependencyGraph,
+    from: Functor,
+) -> HashMap<Functor, Functor> {
+    let mut callers = HashMap::from([(from, from)]);
+    let mut queue = VecDeque::from([from]);
+
+    while let Some(functor) = queue.pop_front() {
+        for callee in graph.get(&functor).into_iter().flatten() {
+            if let Entry::Vacant(entry) = callers.entry(callee.functor) {
+                entry.insert(functor);
+                queue.push_back(callee.functor);
+            }
+        }
+    }
+
+    callers
+}
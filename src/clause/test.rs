@@ -0,0 +1,683 @@
+use std::collections::HashSet;
+
+use crate::{
+    clause::{
+        Clause, Cycle, Diagnostic, Functor, Goal, KnowledgeBase, Predicate,
+    },
+    parser::{parse_clause, parse_goal},
+    solver::{SolveError, Solver},
+    substitution::Substitution,
+    term::Term,
+};
+
+#[test]
+fn first_argument_indexing_with_many_facts() {
+    // edge(n0, n1). edge(n1, n2). ... edge(n4999, n5000).
+    // edge(X, sink).
+    let mut kb = KnowledgeBase::new();
+
+    for i in 0..5000 {
+        kb.add_clause(Clause {
+            head: Predicate {
+                name: "edge".into(),
+                arguments: vec![
+                    Term::atom(format!("n{i}")),
+                    Term::atom(format!("n{}", i + 1)),
+                ],
+            },
+            body: vec![],
+        });
+    }
+
+    kb.add_clause(Clause {
+        head: Predicate {
+            name: "edge".into(),
+            arguments: vec![Term::variable(0), Term::atom("sink")],
+        },
+        body: vec![],
+    });
+
+    // a bound first argument only yields the matching bucket and the clause
+    // with a variable first argument
+    let candidates = kb
+        .get_clauses_matching(("edge", 2), &Term::atom("n42"))
+        .map(|clause| clause.head.arguments[1].clone())
+        .collect::<Vec<_>>();
+
+    assert_eq!(candidates, vec![Term::atom("n43"), Term::atom("sink")]);
+
+    // a compound first argument can't match any atom-keyed bucket
+    assert_eq!(
+        kb.get_clauses_matching(
+            ("edge", 2),
+            &Term::component("n42", [Term::atom("x")])
+        )
+        .count(),
+        1
+    );
+
+    // an unbound first argument yields every clause
+    assert_eq!(
+        kb.get_clauses_matching(("edge", 2), &Term::variable(0)).count(),
+        5001
+    );
+
+    // the solver still finds exactly the expected answers
+    let goal = Goal {
+        predicate: Predicate {
+            name: "edge".into(),
+            arguments: vec![Term::atom("n42"), Term::variable(0)],
+        },
+    };
+
+    let mut solver = Solver::new(&kb);
+    let mut goal_state = solver.create_goal_state(goal);
+
+    let mut solutions = Vec::new();
+    while let Some(solution) = solver.pull_next_goal(&mut goal_state).unwrap() {
+        solutions.push(solution);
+    }
+
+    assert_eq!(solutions.len(), 2);
+
+    for node in ["n43", "sink"] {
+        let expected = Substitution {
+            mapping: [(0, Term::atom(node))].into_iter().collect(),
+        };
+        assert!(solutions.contains(&expected), "Missing edge target: {node}");
+    }
+}
+
+#[test]
+fn display_clauses() {
+    let head = Predicate {
+        name: "grandparent".into(),
+        arguments: vec![Term::variable(0), Term::variable(1)],
+    };
+    let body = vec![
+        Goal {
+            predicate: Predicate {
+                name: "parent".into(),
+                arguments: vec![Term::variable(0), Term::variable(2)],
+            },
+        },
+        Goal {
+            predicate: Predicate {
+                name: "parent".into(),
+                arguments: vec![Term::variable(2), Term::variable(1)],
+            },
+        },
+    ];
+
+    assert_eq!(head.to_string(), "grandparent(?0, ?1)");
+    assert_eq!(body[0].to_string(), "parent(?0, ?2)");
+
+    assert_eq!(
+        Clause { head: head.clone(), body }.to_string(),
+        "grandparent(?0, ?1) :- parent(?0, ?2), parent(?2, ?1)."
+    );
+
+    let fact = Clause {
+        head: Predicate {
+            name: "parent".into(),
+            arguments: vec![
+                Term::atom("alice"),
+                Term::component("pair", [Term::atom("bob"), Term::variable(4)]),
+            ],
+        },
+        body: vec![],
+    };
+    assert_eq!(fact.to_string(), "parent(alice, pair(bob, ?4)).");
+
+    let proposition = Clause {
+        head: Predicate { name: "rain".into(), arguments: vec![] },
+        body: vec![],
+    };
+    assert_eq!(proposition.to_string(), "rain.");
+}
+
+#[test]
+fn load_grandparent_program_from_str() {
+    let kb: KnowledgeBase = "
+        % parent(Parent, Child)
+        parent(alice, bob).
+        parent(bob, carol).
+        parent(bob, dave).
+
+        grandparent(X, Z) :-
+            parent(X, Y),
+            parent(Y, Z).
+    "
+    .parse()
+    .unwrap();
+
+    let mut solver = Solver::new(&kb);
+    let mut goal_state = solver.create_goal_state(Goal::new("grandparent", [
+        Term::atom("alice"),
+        Term::variable(0),
+    ]));
+
+    let mut grandchildren = Vec::new();
+    while let Some(solution) = solver.pull_next_goal(&mut goal_state).unwrap() {
+        grandchildren.push(solution.mapping[&0].to_string());
+    }
+    grandchildren.sort();
+
+    assert_eq!(grandchildren, ["carol", "dave"]);
+}
+
+#[test]
+fn load_program_reports_failing_clause() {
+    let error = "
+        parent(alice, bob).
+        parent(bob, carol).
+        parent(bob carol).
+    "
+    .parse::<KnowledgeBase>()
+    .unwrap_err();
+
+    assert_eq!(error.clause, 3);
+    assert_eq!(error.line, 4);
+}
+
+#[test]
+fn validate_clean_knowledge_base() {
+    let kb: KnowledgeBase = "
+        parent(alice, bob).
+        anything(X).
+        grandparent(X, Z) :- parent(X, Y), parent(Y, Z).
+        same(X, X) :- parent(X, _).
+        older(X, _) :- parent(X, _).
+    "
+    .parse()
+    .unwrap();
+
+    assert_eq!(kb.validate(), []);
+}
+
+#[test]
+fn validate_reports_unbound_head_variables() {
+    let kb: KnowledgeBase = "
+        parent(alice, bob).
+        p(X) :- q(Y).
+        child(C, P, Unused) :- parent(P, C).
+        q(a).
+        r(X, X) :- q.
+    "
+    .parse()
+    .unwrap();
+
+    let diagnostics = kb.validate();
+
+    // `X` occurs twice in the head of `r/2`, yet the body never binds it
+    assert_eq!(
+        diagnostics.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        [
+            "`?2` in the head of `child(?0, ?1, ?2) :- parent(?1, ?0).` never \
+             appears in its body",
+            "`?0` in the head of `p(?0) :- q(?1).` never appears in its body",
+            "`?0` in the head of `r(?0, ?0) :- q.` never appears in its body",
+        ]
+    );
+
+    assert_eq!(diagnostics[1], Diagnostic::UnboundHeadVariable {
+        clause: parse_clause("p(X) :- q(Y).").unwrap(),
+        variable: 0,
+    });
+}
+
+#[test]
+fn validate_reports_builtin_redefinitions() {
+    let kb: KnowledgeBase = "
+        succ(0, 1).
+        var(a).
+        succ(a, b, c).
+    "
+    .parse()
+    .unwrap();
+
+    // `succ/3` isn't a built-in, only `succ/2` is
+    assert_eq!(
+        kb.validate().iter().map(ToString::to_string).collect::<Vec<_>>(),
+        [
+            "`succ(0, 1).` defines the built-in `succ/2` and is never used",
+            "`var(a).` defines the built-in `var/1` and is never used",
+        ]
+    );
+
+    // the solver keeps evaluating the built-in
+    let mut solver = Solver::new(&kb);
+    let mut solve =
+        |goal: &str| solver.solve_all(parse_goal(goal).unwrap()).unwrap().len();
+    assert_eq!(solve("succ(0, 1)"), 1);
+    assert_eq!(solve("var(a)"), 0);
+}
+
+#[test]
+fn predicates_are_keyed_by_name_and_arity() {
+    let kb: KnowledgeBase = "
+        edge(a, b).
+        edge(a, b, 10).
+        edge(b, c).
+        edge(b, c, 20).
+        edge(X, Y, 0) :- edge(X, Y).
+    "
+    .parse()
+    .unwrap();
+
+    assert!(
+        kb.get_clauses(("edge", 2)).unwrap().iter().all(|clause| clause
+            .head
+            .arguments
+            .len()
+            == 2)
+    );
+    assert_eq!(kb.get_clauses(("edge", 3)).unwrap().len(), 3);
+    assert_eq!(kb.get_clauses(("edge", 1)), None);
+    assert_eq!(
+        kb.get_clauses_matching(("edge", 2), &Term::atom("a")).count(),
+        1
+    );
+
+    let mut solver = Solver::new(&kb);
+    let mut goal_state = solver.create_goal_state(Goal::new("edge", [
+        Term::variable(0),
+        Term::variable(1),
+    ]));
+
+    let mut solutions = Vec::new();
+    while let Some(solution) = solver.pull_next_goal(&mut goal_state).unwrap() {
+        solutions.push(solution.to_string());
+    }
+    solutions.sort();
+
+    // only the `edge/2` table has been created
+    assert_eq!(solutions, ["{?0 = a, ?1 = b}", "{?0 = b, ?1 = c}"]);
+    assert_eq!(solver.table_count(), 1);
+}
+
+#[test]
+fn enumerate_every_clause() {
+    let source = "
+        parent(alice, bob).
+        edge(a, b).
+        parent(bob, carol).
+        edge(a, b, 10).
+        grandparent(X, Z) :- parent(X, Y), parent(Y, Z).
+    ";
+    let kb: KnowledgeBase = source.parse().unwrap();
+
+    let clauses = kb.clauses_iter().cloned().collect::<Vec<_>>();
+
+    // ordered by functor, then by their order within the predicate
+    assert_eq!(clauses.iter().map(ToString::to_string).collect::<Vec<_>>(), [
+        "edge(a, b).",
+        "edge(a, b, 10).",
+        "grandparent(?0, ?1) :- parent(?0, ?2), parent(?2, ?1).",
+        "parent(alice, bob).",
+        "parent(bob, carol).",
+    ]);
+
+    let mut reconstructed = KnowledgeBase::new();
+    for clause in clauses {
+        reconstructed.add_clause(clause);
+    }
+    assert_eq!(reconstructed, kb);
+
+    assert_eq!(kb.predicate_names().collect::<Vec<_>>(), [
+        "edge",
+        "grandparent",
+        "parent"
+    ]);
+}
+
+#[test]
+fn count_clauses() {
+    assert!(KnowledgeBase::new().is_empty());
+    assert_eq!(KnowledgeBase::new().len(), 0);
+
+    let kb: KnowledgeBase = "
+        edge(a, b).
+        edge(b, c).
+        edge(a, b, 10).
+        path(X, Y) :- edge(X, Y).
+        path(X, Z) :- edge(X, Y), path(Y, Z).
+        start.
+    "
+    .parse()
+    .unwrap();
+
+    assert!(!kb.is_empty());
+    assert_eq!(kb.len(), 6);
+
+    assert_eq!(kb.clause_count("edge"), 3);
+    assert_eq!(kb.clause_count("path"), 2);
+    assert_eq!(kb.clause_count("start"), 1);
+    assert_eq!(kb.clause_count("missing"), 0);
+}
+
+#[test]
+fn add_clause_unique_deduplicates() {
+    let mut kb = KnowledgeBase::new();
+
+    let fact = parse_clause("edge(a, b).").unwrap();
+    let rule = parse_clause("path(X, Y) :- edge(X, Z), path(Z, Y).").unwrap();
+
+    assert!(!kb.contains_clause(&fact));
+    assert!(kb.add_clause_unique(fact.clone()));
+    assert!(kb.contains_clause(&fact));
+    assert!(!kb.add_clause_unique(fact));
+
+    assert!(kb.add_clause_unique(rule));
+
+    // the same rule with its variables renamed is a duplicate, unlike one
+    // whose variables are related differently
+    let renamed =
+        parse_clause("path(A, B) :- edge(A, C), path(C, B).").unwrap();
+    let different =
+        parse_clause("path(A, B) :- edge(A, C), path(B, C).").unwrap();
+
+    assert!(kb.contains_clause(&renamed));
+    assert!(!kb.add_clause_unique(renamed));
+    assert!(!kb.contains_clause(&different));
+
+    assert!(!kb.contains_clause(&parse_clause("edge(b, a).").unwrap()));
+    assert_eq!(kb.len(), 2);
+
+    // adding a clause regardless of duplicates keeps both copies
+    kb.add_clause(parse_clause("edge(a, b).").unwrap());
+    assert_eq!(kb.clause_count("edge"), 2);
+}
+
+#[test]
+fn remove_clause_keeps_the_indices_of_the_others() {
+    let mut kb: KnowledgeBase = "
+        edge(a, b). edge(X, c). edge(a, d). edge(b, e). edge(a, b).
+        linked(X, Y) :- node(X), node(Y), link(X, Y).
+        node(1). node(2). link(1, 2).
+    "
+    .parse()
+    .unwrap();
+
+    let edges_from = |kb: &KnowledgeBase, from: &str| {
+        kb.get_clauses_matching(("edge", 2), &Term::atom(from))
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+    };
+
+    // the first of the two copies goes, the later clauses move up
+    assert!(kb.remove_clause(&parse_clause("edge(a, b).").unwrap()));
+    assert_eq!(edges_from(&kb, "a"), [
+        "edge(?0, c).",
+        "edge(a, d).",
+        "edge(a, b)."
+    ]);
+    assert_eq!(edges_from(&kb, "b"), ["edge(?0, c).", "edge(b, e)."]);
+
+    assert!(kb.remove_clause(&parse_clause("edge(Y, c).").unwrap()));
+    assert!(kb.remove_clause(&parse_clause("edge(a, b).").unwrap()));
+    assert!(!kb.remove_clause(&parse_clause("edge(a, b).").unwrap()));
+    assert_eq!(edges_from(&kb, "a"), ["edge(a, d)."]);
+    assert!(kb.contains_clause(&parse_clause("edge(b, e).").unwrap()));
+
+    // the reordered body is what the clause is found by afterwards
+    kb.optimize();
+
+    assert!(!kb.contains_clause(
+        &parse_clause("linked(X, Y) :- node(X), node(Y), link(X, Y).").unwrap()
+    ));
+    assert!(kb.remove_clause(
+        &parse_clause("linked(X, Y) :- link(X, Y), node(X), node(Y).").unwrap()
+    ));
+    assert!(kb.get_clauses(("linked", 2)).is_none());
+}
+
+#[test]
+fn reorder_body_by_constraints() {
+    let mut kb: KnowledgeBase = "
+        node(1). node(2). node(3). node(4). node(5). node(6).
+        node(7). node(8). node(9). node(10). node(11). node(12).
+        link(3, 7).
+        linked(X, Y) :- node(X), node(Y), link(X, Y).
+        ordered(X, Y) :- node(X), node(Y), X < Y, link(X, Y), !, node(X).
+    "
+    .parse()
+    .unwrap();
+
+    let solve = |kb: &KnowledgeBase, query: &str| {
+        let mut solver = Solver::new(kb);
+        let answers = solver.solve_all(parse_goal(query).unwrap()).unwrap();
+
+        (answers, solver.steps())
+    };
+
+    let (linked, steps) = solve(&kb, "linked(X, Y)");
+    let (ordered, _) = solve(&kb, "ordered(X, Y)");
+
+    kb.optimize();
+
+    let clauses =
+        kb.clauses_iter().map(ToString::to_string).collect::<Vec<_>>();
+
+    // `link/2` has a single clause, after which `node/1` merely checks the
+    // bindings; nothing moves across the built-in `<` and the cut
+    assert!(clauses.contains(
+        &"linked(?0, ?1) :- link(?0, ?1), node(?0), node(?1).".to_owned()
+    ));
+    assert!(
+        clauses.contains(
+            &"ordered(?0, ?1) :- node(?0), node(?1), <(?0, ?1), link(?0, ?1), \
+              !, node(?0)."
+                .to_owned()
+        )
+    );
+
+    let (optimized_linked, optimized_steps) = solve(&kb, "linked(X, Y)");
+    let (optimized_ordered, _) = solve(&kb, "ordered(X, Y)");
+
+    assert_eq!(optimized_linked, linked);
+    assert_eq!(optimized_ordered, ordered);
+    assert!(optimized_steps < steps, "{optimized_steps} >= {steps}");
+}
+
+#[test]
+fn builder_mixes_programs_and_clauses() {
+    let kb = KnowledgeBase::builder()
+        .program("edge(a, b). path(X, Y) :- edge(X, Y).")
+        .unwrap()
+        .fact("edge", [Term::atom("b"), Term::atom("c")])
+        .clause(parse_clause("path(X, Z) :- edge(X, Y), path(Y, Z).").unwrap())
+        .build();
+
+    assert_eq!(kb.clause_count("edge"), 2);
+    assert_eq!(kb.clause_count("path"), 2);
+
+    let answers =
+        Solver::new(&kb).solve_all(parse_goal("path(a, X)").unwrap()).unwrap();
+    assert_eq!(answers.len(), 2);
+
+    assert!(KnowledgeBase::builder().program("edge(a, b").is_err());
+}
+
+#[test]
+fn detect_linear_heads() {
+    let linear = |source: &str| parse_clause(source).unwrap().has_linear_head();
+
+    assert!(linear("edge(a, b)."));
+    assert!(linear("path(X, Z) :- edge(X, Y), path(Y, Z)."));
+    assert!(linear("wrap(f(X, g(Y)), Z) :- eq(X, X)."));
+
+    assert!(!linear("eq(X, X)."));
+    assert!(!linear("wrap(f(X), g(X))."));
+}
+
+#[test]
+fn family_predicate_dependencies() {
+    let kb: KnowledgeBase = "
+        parent(adam, bob).
+        parent(bob, dan).
+        ancestor(X, Y) :- parent(X, Y).
+        ancestor(X, Y) :- parent(X, Z), ancestor(Z, Y).
+        sibling(X, Y) :- parent(Z, X), parent(Z, Y), X \\= Y.
+        cousin(X, Y) :- parent(A, X), parent(B, Y), sibling(A, B).
+        relative(X, Y) :- ancestor(X, Y).
+        relative(X, Y) :- ancestor(Y, X).
+        relative(X, Y) :- sibling(X, Y).
+        relative(X, Y) :- cousin(X, Y).
+        stranger(X, Y) :- parent(X, _), parent(Y, _), \\+ relative(X, Y).
+    "
+    .parse()
+    .unwrap();
+
+    let graph = kb.predicate_dependencies();
+    let dependencies = |name: &str| {
+        graph[&Functor::new(name, 2)]
+            .iter()
+            .map(|dependency| {
+                let sign = if dependency.negative { "\\+ " } else { "" };
+                format!("{sign}{}", dependency.functor)
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(graph.len(), 6);
+    assert!(dependencies("parent").is_empty());
+    assert_eq!(dependencies("ancestor"), ["ancestor/2", "parent/2"]);
+    // the built-in `\\=/2` isn't a predicate of the program
+    assert_eq!(dependencies("sibling"), ["parent/2"]);
+    assert_eq!(dependencies("relative"), [
+        "ancestor/2",
+        "cousin/2",
+        "sibling/2"
+    ]);
+    assert_eq!(dependencies("stranger"), ["parent/2", "\\+ relative/2"]);
+}
+
+#[test]
+fn report_predicates_unreachable_from_query() {
+    let kb: KnowledgeBase = "
+        edge(a, b).
+        path(X, Y) :- edge(X, Y).
+        path(X, Z) :- edge(X, Y), path(Y, Z).
+        path(X) :- node(X).
+        cyclic :- path(X, X).
+        unused(X) :- helper(X).
+        helper(x).
+    "
+    .parse()
+    .unwrap();
+
+    let names = |names: &[&str]| {
+        names.iter().map(ToString::to_string).collect::<HashSet<_>>()
+    };
+
+    // `node/1` has no clause but is called nonetheless
+    assert_eq!(
+        kb.reachable_predicates("path"),
+        names(&["path", "edge", "node"])
+    );
+    assert_eq!(
+        kb.unreachable_predicates("path"),
+        names(&["cyclic", "unused", "helper"])
+    );
+    assert_eq!(
+        kb.unreachable_predicates("unused"),
+        names(&["cyclic", "path", "edge"])
+    );
+    assert!(kb.reachable_predicates("missing").is_empty());
+
+    // a query on an alias calls the predicates it resolves against
+    let mut kb = kb;
+    kb.alias_predicate("path", "route");
+
+    assert_eq!(
+        kb.reachable_predicates("route"),
+        names(&["path", "edge", "node"])
+    );
+    assert_eq!(
+        kb.unreachable_predicates("route"),
+        names(&["cyclic", "unused", "helper"])
+    );
+}
+
+#[test]
+fn stratified_program_passes() {
+    // negation only refers to the lower stratum of `reachable/2`
+    let kb: KnowledgeBase = r"
+        edge(a, b).
+        edge(b, c).
+        node(a). node(b). node(c). node(d).
+        reachable(X, Y) :- edge(X, Y).
+        reachable(X, Z) :- edge(X, Y), reachable(Y, Z).
+        unreachable(X, Y) :- node(X), node(Y), \+ reachable(X, Y).
+        isolated(X) :- node(X), \+ (edge(X, _) ; edge(_, X)).
+    "
+    .parse()
+    .unwrap();
+
+    assert_eq!(kb.check_stratification(), Ok(()));
+}
+
+#[test]
+fn unstratified_program_reports_cycles() {
+    let kb: KnowledgeBase = r"
+        p :- \+ q.
+        q :- r.
+        r :- p.
+        s :- t, \+ u.
+        u :- findall(X, s, L).
+        v :- \+ v.
+    "
+    .parse()
+    .unwrap();
+
+    let cycles = kb.check_stratification().unwrap_err();
+
+    assert_eq!(cycles, [
+        Cycle {
+            functors: vec![
+                Functor::new("p", 0),
+                Functor::new("q", 0),
+                Functor::new("r", 0),
+            ],
+        },
+        Cycle { functors: vec![Functor::new("s", 0), Functor::new("u", 0)] },
+        Cycle { functors: vec![Functor::new("v", 0)] },
+    ]);
+
+    assert_eq!(cycles[0].to_string(), r"p/0 -> \+ q/0 -> r/0 -> p/0");
+    assert_eq!(cycles[2].to_string(), r"v/0 -> \+ v/0");
+
+    // the runtime check agrees
+    assert_eq!(
+        Solver::new(&kb).solve_all(parse_goal("p").unwrap()),
+        Err(SolveError::UnstratifiedNegation)
+    );
+}
+
+#[test]
+fn count_variable_occurrences_and_singletons() {
+    // X = 0, Y = 1, Z = 2
+    let clean = parse_clause("path(X, Z) :- edge(X, Y), path(Y, Z).").unwrap();
+
+    assert_eq!(
+        clean.variable_occurrences(),
+        [(0, 2), (1, 2), (2, 2)].into_iter().collect()
+    );
+    assert!(clean.singletons().is_empty());
+
+    // X = 0, Y = 1, Z = 2, Zz = 3, the typo leaves Z and Zz alone
+    let typo =
+        parse_clause("sibling(X, Y) :- parent(Z, X), parent(Zz, Y), X \\= Y.")
+            .unwrap();
+
+    assert_eq!(
+        typo.variable_occurrences(),
+        [(0, 3), (1, 3), (2, 1), (3, 1)].into_iter().collect()
+    );
+    assert_eq!(typo.singletons(), [2, 3]);
+
+    // anonymous variables are meant to occur once
+    let anonymous = parse_clause("parent(X) :- parent(X, _).").unwrap();
+    assert!(anonymous.singletons().is_empty());
+}
@@ -1,6 +1,10 @@
 pub mod arena;
 pub mod canonicalize;
 pub mod clause;
+pub mod parser;
+pub mod prelude;
+pub mod repl;
 pub mod solver;
 pub mod substitution;
+pub mod symbol;
 pub mod term;
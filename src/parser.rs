@@ -0,0 +1,771 @@
+//! Contains a parser for programs and goals written in Prolog syntax.
+//!
+//! The parser understands the subset of Prolog the solver supports:
+//!
+//! - atoms, either plain (`parent`), quoted (`'New York'`), or symbolic (`<`)
+//! - integers, e.g. `42` and `-7`
+//! - variables, e.g. `X` and `_Child`; every occurrence of `_` is a distinct
+//!   anonymous variable, see [`Term::anonymous`]
+//! - compound terms, e.g. `f(a, X)`, and lists, e.g. `[a, b | T]`
+//! - the operators `:-`, `;`, `,`, `=`, `\=`, `<`, `>`, `>=`, and `=<`
+//! - line comments starting with `%` and block comments `/* ... */`
+//!
+//! Variables are numbered from zero in the order of their first appearance
+//! within each clause, e.g. `grandparent(X, Z)` becomes `grandparent(?0,
+//! ?1)`. Anonymous variables are numbered separately, in the order of
+//! appearance as well.
+
+use std::{collections::HashMap, fmt, iter::Peekable, str::CharIndices};
+
+use crate::{
+    clause::{Clause, Goal, Predicate},
+    symbol::Symbol,
+    term::Term,
+};
+
+/// The error returned when the source text isn't a valid program or goal.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ParseError {
+    /// The 1-based index of the clause that failed to parse.
+    pub clause: usize,
+
+    /// The 1-based line at which the error was found.
+    pub line: usize,
+
+    /// The 1-based column at which the error was found.
+    pub column: usize,
+
+    /// Describes what went wrong.
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "clause {} at {}:{}: {}",
+            self.clause, self.line, self.column, self.message
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parses a program made of clauses, each terminated by a period.
+///
+/// # Errors
+///
+/// Returns the [`ParseError`] of the first clause that couldn't be parsed.
+pub fn parse_program(source: &str) -> Result<Vec<Clause>, ParseError> {
+    let mut parser = Parser::new(source)?;
+    let mut clauses = Vec::new();
+
+    while parser.current.kind != TokenKind::Eof {
+        clauses.push(parser.parse_clause()?);
+        parser.expect(&TokenKind::End, "`.` at the end of the clause")?;
+
+        parser.clause += 1;
+    }
+
+    Ok(clauses)
+}
+
+/// Parses a single clause, the terminating period is optional.
+///
+/// # Errors
+///
+/// Returns a [`ParseError`] if the source isn't exactly one clause.
+pub fn parse_clause(source: &str) -> Result<Clause, ParseError> {
+    let mut parser = Parser::new(source)?;
+    let clause = parser.parse_clause()?;
+    parser.finish()?;
+
+    Ok(clause)
+}
+
+/// Parses a term, e.g. `point(1, X)` or `[a, b | T]`, the terminating period
+/// is optional.
+///
+/// # Errors
+///
+/// Returns a [`ParseError`] if the source isn't exactly one term.
+pub fn parse_term(source: &str) -> Result<Term, ParseError> {
+    let mut parser = Parser::new(source)?;
+    let term = parser.parse(1200)?;
+    parser.finish()?;
+
+    Ok(term)
+}
+
+/// Parses a goal, e.g. `ancestor(X, bob)`, the terminating period is
+/// optional.
+///
+/// A conjunction or disjunction of goals is parsed as a single `,` or `;`
+/// goal, which the solver proves like a clause body.
+///
+/// # Errors
+///
+/// Returns a [`ParseError`] if the source isn't exactly one goal.
+pub fn parse_goal(source: &str) -> Result<Goal, ParseError> {
+    let mut parser = Parser::new(source)?;
+    let position = parser.current.position;
+
+    let term = parser.parse(1200)?;
+    parser.finish()?;
+
+    Goal::from_term(&term).ok_or_else(|| {
+        parser.error_at(position, format!("`{term}` is not a goal"))
+    })
+}
+
+/// Whether an infix operator associates to the left, the right, or neither.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Associativity {
+    /// `xfx`, neither operand may be an operator of the same priority.
+    None,
+
+    /// `xfy`, e.g. `a, b, c` is `a, (b, c)`.
+    Right,
+}
+
+/// The infix operators along with their priority, a lower priority binds
+/// tighter.
+const INFIX_OPERATORS: &[(&str, u32, Associativity)] = &[
+    (":-", 1200, Associativity::None),
+    (";", 1100, Associativity::Right),
+    (",", 1000, Associativity::Right),
+    ("=", 700, Associativity::None),
+    ("\\=", 700, Associativity::None),
+    ("<", 700, Associativity::None),
+    (">", 700, Associativity::None),
+    (">=", 700, Associativity::None),
+    ("=<", 700, Associativity::None),
+];
+
+/// The prefix operators along with their priority, all of them are `fy`,
+/// i.e. `\+ \+ p` is `\+(\+(p))`.
+const PREFIX_OPERATORS: &[(&str, u32)] = &[("\\+", 900)];
+
+/// The priority of the arguments of compound terms and list items, just
+/// below `,` so that it separates them instead.
+const ARGUMENT_PRIORITY: u32 = 999;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Position {
+    line: usize,
+    column: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TokenKind {
+    Atom(String),
+    Variable(String),
+    Integer(i64),
+    Str(String),
+    OpenParen,
+    CloseParen,
+    OpenBracket,
+    CloseBracket,
+    Bar,
+    Comma,
+
+    /// The period terminating a clause.
+    End,
+    Eof,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenKind::Atom(name) | TokenKind::Variable(name) => {
+                write!(f, "`{name}`")
+            }
+            TokenKind::Integer(value) => write!(f, "`{value}`"),
+            TokenKind::Str(value) => write!(f, "`\"{value}\"`"),
+            TokenKind::OpenParen => write!(f, "`(`"),
+            TokenKind::CloseParen => write!(f, "`)`"),
+            TokenKind::OpenBracket => write!(f, "`[`"),
+            TokenKind::CloseBracket => write!(f, "`]`"),
+            TokenKind::Bar => write!(f, "`|`"),
+            TokenKind::Comma => write!(f, "`,`"),
+            TokenKind::End => write!(f, "`.`"),
+            TokenKind::Eof => write!(f, "the end of the input"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Token {
+    kind: TokenKind,
+    position: Position,
+
+    /// Whether whitespace or a comment precedes the token, which tells the
+    /// compound term `f(a)` apart from the atom `f` followed by `(a)`.
+    spaced: bool,
+}
+
+/// The characters making up symbolic atoms such as `:-` and `>=`.
+fn is_symbol_char(c: char) -> bool { "+-*/\\^<>=~:.?@#&$".contains(c) }
+
+fn is_alphanumeric(c: char) -> bool { c.is_alphanumeric() || c == '_' }
+
+struct Lexer<'a> {
+    source: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+    position: Position,
+
+    /// Whether the previous token may end a term, in which case a `-`
+    /// followed by a digit is an operator rather than a negative integer.
+    after_term: bool,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            chars: source.char_indices().peekable(),
+            position: Position { line: 1, column: 1 },
+            after_term: false,
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> { self.chars.peek().map(|(_, c)| *c) }
+
+    fn peek_second(&self) -> Option<char> {
+        let mut chars = self.chars.clone();
+        chars.next();
+        chars.next().map(|(_, c)| c)
+    }
+
+    fn offset(&mut self) -> usize {
+        self.chars.peek().map_or(self.source.len(), |(offset, _)| *offset)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let (_, c) = self.chars.next()?;
+
+        if c == '\n' {
+            self.position.line += 1;
+            self.position.column = 1;
+        } else {
+            self.position.column += 1;
+        }
+
+        Some(c)
+    }
+
+    /// Skips whitespace and comments, returns whether anything was skipped.
+    fn skip_trivia(&mut self) -> Result<bool, (Position, String)> {
+        let mut skipped = false;
+
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.bump();
+                }
+
+                Some('%') => {
+                    while self.peek().is_some_and(|c| c != '\n') {
+                        self.bump();
+                    }
+                }
+
+                Some('/') if self.peek_second() == Some('*') => {
+                    let start = self.position;
+                    self.bump();
+                    self.bump();
+
+                    loop {
+                        match self.bump() {
+                            Some('*') if self.peek() == Some('/') => {
+                                self.bump();
+                                break;
+                            }
+                            Some(_) => {}
+                            None => {
+                                return Err((
+                                    start,
+                                    "unterminated block comment".to_owned(),
+                                ));
+                            }
+                        }
+                    }
+                }
+
+                _ => return Ok(skipped),
+            }
+
+            skipped = true;
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Token, (Position, String)> {
+        let spaced = self.skip_trivia()?;
+        let position = self.position;
+
+        let kind = self.next_kind(position)?;
+        self.after_term = match &kind {
+            // symbolic atoms are usually operators, e.g. `X >= -7`
+            TokenKind::Atom(name) => !name.chars().all(is_symbol_char),
+
+            kind => matches!(
+                kind,
+                TokenKind::Variable(_)
+                    | TokenKind::Integer(_)
+                    | TokenKind::Str(_)
+                    | TokenKind::CloseParen
+                    | TokenKind::CloseBracket
+            ),
+        };
+
+        Ok(Token { kind, position, spaced })
+    }
+
+    fn next_kind(
+        &mut self,
+        position: Position,
+    ) -> Result<TokenKind, (Position, String)> {
+        let Some(c) = self.peek() else {
+            return Ok(TokenKind::Eof);
+        };
+
+        let punctuation = match c {
+            '(' => Some(TokenKind::OpenParen),
+            ')' => Some(TokenKind::CloseParen),
+            '[' => Some(TokenKind::OpenBracket),
+            ']' => Some(TokenKind::CloseBracket),
+            '|' => Some(TokenKind::Bar),
+            ',' => Some(TokenKind::Comma),
+            '!' | ';' => Some(TokenKind::Atom(c.to_string())),
+            _ => None,
+        };
+
+        if let Some(kind) = punctuation {
+            self.bump();
+            return Ok(kind);
+        }
+
+        // a period followed by whitespace, a comment, or nothing ends the
+        // clause
+        if c == '.'
+            && self
+                .peek_second()
+                .is_none_or(|next| next.is_whitespace() || next == '%')
+        {
+            self.bump();
+            return Ok(TokenKind::End);
+        }
+
+        if c.is_ascii_digit()
+            || (c == '-'
+                && !self.after_term
+                && self.peek_second().is_some_and(|c| c.is_ascii_digit()))
+        {
+            let start = self.offset();
+            self.bump();
+            while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                self.bump();
+            }
+            let end = self.offset();
+
+            return self.source[start..end]
+                .parse()
+                .map(TokenKind::Integer)
+                .map_err(|_| (position, "integer is too large".to_owned()));
+        }
+
+        if c == '\'' || c == '"' {
+            return self.quoted(position, c);
+        }
+
+        let take_while = |lexer: &mut Self, predicate: fn(char) -> bool| {
+            let start = lexer.offset();
+            while lexer.peek().is_some_and(predicate) {
+                lexer.bump();
+            }
+            let end = lexer.offset();
+
+            lexer.source[start..end].to_owned()
+        };
+
+        if c.is_uppercase() || c == '_' {
+            return Ok(TokenKind::Variable(take_while(self, is_alphanumeric)));
+        }
+
+        if c.is_alphabetic() {
+            return Ok(TokenKind::Atom(take_while(self, is_alphanumeric)));
+        }
+
+        if is_symbol_char(c) {
+            return Ok(TokenKind::Atom(take_while(self, is_symbol_char)));
+        }
+
+        Err((position, format!("unexpected character `{c}`")))
+    }
+
+    /// Lexes a single-quoted atom or a double-quoted string, depending on
+    /// the `quote`.
+    fn quoted(
+        &mut self,
+        position: Position,
+        quote: char,
+    ) -> Result<TokenKind, (Position, String)> {
+        self.bump();
+        let mut contents = String::new();
+
+        loop {
+            match self.bump() {
+                // a doubled quote stands for a single one
+                Some(c) if c == quote && self.peek() == Some(quote) => {
+                    self.bump();
+                    contents.push(quote);
+                }
+                Some(c) if c == quote => {
+                    return Ok(if quote == '"' {
+                        TokenKind::Str(contents)
+                    } else {
+                        TokenKind::Atom(contents)
+                    });
+                }
+                Some(c) => contents.push(c),
+                None => {
+                    let what =
+                        if quote == '"' { "string" } else { "quoted atom" };
+
+                    return Err((position, format!("unterminated {what}")));
+                }
+            }
+        }
+    }
+}
+
+struct Parser<'a> {
+    lexer: Lexer<'a>,
+    current: Token,
+
+    /// The 1-based index of the clause being parsed.
+    clause: usize,
+
+    /// The variables of the clause being parsed by name.
+    variables: HashMap<String, usize>,
+    variable_count: usize,
+    anonymous_count: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str) -> Result<Self, ParseError> {
+        let mut lexer = Lexer::new(source);
+        let current =
+            lexer.next_token().map_err(|(position, message)| ParseError {
+                clause: 1,
+                line: position.line,
+                column: position.column,
+                message,
+            })?;
+
+        Ok(Self {
+            lexer,
+            current,
+            clause: 1,
+            variables: HashMap::new(),
+            variable_count: 0,
+            anonymous_count: 0,
+        })
+    }
+
+    fn error_at(&self, position: Position, message: String) -> ParseError {
+        ParseError {
+            clause: self.clause,
+            line: position.line,
+            column: position.column,
+            message,
+        }
+    }
+
+    /// Moves to the next token, returning the current one.
+    fn advance(&mut self) -> Result<Token, ParseError> {
+        let next = self
+            .lexer
+            .next_token()
+            .map_err(|(position, message)| self.error_at(position, message))?;
+
+        Ok(std::mem::replace(&mut self.current, next))
+    }
+
+    fn expect(
+        &mut self,
+        kind: &TokenKind,
+        expected: &str,
+    ) -> Result<Token, ParseError> {
+        if self.current.kind != *kind {
+            return Err(self.unexpected(expected));
+        }
+
+        self.advance()
+    }
+
+    fn unexpected(&self, expected: &str) -> ParseError {
+        self.error_at(
+            self.current.position,
+            format!("expected {expected}, found {}", self.current.kind),
+        )
+    }
+
+    /// Accepts an optional terminating period followed by the end of the
+    /// input.
+    fn finish(&mut self) -> Result<(), ParseError> {
+        if self.current.kind == TokenKind::End {
+            self.advance()?;
+        }
+
+        if self.current.kind != TokenKind::Eof {
+            return Err(self.unexpected("the end of the input"));
+        }
+
+        Ok(())
+    }
+
+    fn parse_clause(&mut self) -> Result<Clause, ParseError> {
+        self.variables.clear();
+        self.variable_count = 0;
+        self.anonymous_count = 0;
+
+        let position = self.current.position;
+        let term = self.parse(1200)?;
+
+        let clause = match term {
+            Term::Compound(name, arguments) if name.as_str() == ":-" => {
+                let [head, body] =
+                    <[Term; 2]>::try_from(Vec::from(arguments)).unwrap();
+
+                let mut goals = Vec::new();
+                self.flatten_body(body, &mut goals, position)?;
+
+                Clause {
+                    head: self.head_predicate(head, position)?,
+                    body: goals,
+                }
+            }
+            head => Clause::fact(self.head_predicate(head, position)?),
+        };
+
+        Ok(clause)
+    }
+
+    fn head_predicate(
+        &self,
+        term: Term,
+        position: Position,
+    ) -> Result<Predicate, ParseError> {
+        match term {
+            Term::Atom(name) => Ok(Predicate::new(name, [])),
+            Term::Compound(name, arguments) => {
+                Ok(Predicate { name, arguments: arguments.into() })
+            }
+            term => Err(self.error_at(
+                position,
+                format!("`{term}` can't be the head of a clause"),
+            )),
+        }
+    }
+
+    fn flatten_body(
+        &self,
+        term: Term,
+        goals: &mut Vec<Goal>,
+        position: Position,
+    ) -> Result<(), ParseError> {
+        match term {
+            Term::Compound(name, arguments) if name == Symbol::CONJUNCTION => {
+                for argument in arguments {
+                    self.flatten_body(argument, goals, position)?;
+                }
+
+                Ok(())
+            }
+            term => {
+                let goal = Goal::from_term(&term).ok_or_else(|| {
+                    self.error_at(position, format!("`{term}` is not a goal"))
+                })?;
+                goals.push(goal);
+
+                Ok(())
+            }
+        }
+    }
+
+    fn infix_operator(&self) -> Option<(&'static str, u32, Associativity)> {
+        let name = match &self.current.kind {
+            TokenKind::Atom(name) => name.as_str(),
+            TokenKind::Comma => ",",
+            _ => return None,
+        };
+
+        INFIX_OPERATORS.iter().find(|(operator, ..)| *operator == name).copied()
+    }
+
+    /// Parses a term whose priority is at most `max_priority`.
+    fn parse(&mut self, max_priority: u32) -> Result<Term, ParseError> {
+        let (mut lhs, mut lhs_priority) = self.parse_prefix(max_priority)?;
+
+        while let Some((name, priority, associativity)) = self.infix_operator()
+        {
+            let (left_max, right_max) = match associativity {
+                Associativity::None => (priority - 1, priority - 1),
+                Associativity::Right => (priority - 1, priority),
+            };
+
+            if priority > max_priority || lhs_priority > left_max {
+                break;
+            }
+
+            self.advance()?;
+            let rhs = self.parse(right_max)?;
+
+            lhs = Term::Compound(Symbol::new(name), vec![lhs, rhs].into());
+            lhs_priority = priority;
+        }
+
+        Ok(lhs)
+    }
+
+    /// Parses a prefix operator applied to its operand, e.g. `\+ p`, or a
+    /// primary term, returning the term along with its priority.
+    ///
+    /// A prefix operator followed by something that can't start its operand,
+    /// e.g. `\+` in `f(\+, a)`, or an opening parenthesis right after it is
+    /// an ordinary atom.
+    fn parse_prefix(
+        &mut self,
+        max_priority: u32,
+    ) -> Result<(Term, u32), ParseError> {
+        let prefix_operator = match &self.current.kind {
+            TokenKind::Atom(name) => PREFIX_OPERATORS
+                .iter()
+                .find(|(operator, _)| operator == name)
+                .copied(),
+            _ => None,
+        };
+
+        let Some((name, priority)) =
+            prefix_operator.filter(|(_, priority)| *priority <= max_priority)
+        else {
+            return Ok((self.parse_primary()?, 0));
+        };
+
+        self.advance()?;
+
+        let operand_follows = match &self.current.kind {
+            TokenKind::OpenParen => self.current.spaced,
+            TokenKind::Comma
+            | TokenKind::CloseParen
+            | TokenKind::CloseBracket
+            | TokenKind::Bar
+            | TokenKind::End
+            | TokenKind::Eof => false,
+            _ => self.infix_operator().is_none(),
+        };
+
+        if !operand_follows {
+            return Ok((self.atom_or_compound(name.to_owned())?, 0));
+        }
+
+        let operand = self.parse(priority)?;
+
+        Ok((Term::component(name, [operand]), priority))
+    }
+
+    fn parse_primary(&mut self) -> Result<Term, ParseError> {
+        let token = self.advance()?;
+
+        match token.kind {
+            TokenKind::Integer(value) => Ok(Term::Integer(value)),
+            TokenKind::Str(value) => Ok(Term::Str(value)),
+
+            TokenKind::Variable(name) => {
+                if name == "_" {
+                    self.anonymous_count += 1;
+                    return Ok(Term::anonymous(self.anonymous_count - 1));
+                }
+
+                let index = if let Some(index) = self.variables.get(&name) {
+                    *index
+                } else {
+                    let index = self.fresh_variable();
+                    self.variables.insert(name, index);
+                    index
+                };
+
+                Ok(Term::Variable(index))
+            }
+
+            TokenKind::Atom(name) => self.atom_or_compound(name),
+
+            TokenKind::OpenParen => {
+                let term = self.parse(1200)?;
+                self.expect(&TokenKind::CloseParen, "`)`")?;
+
+                Ok(term)
+            }
+
+            TokenKind::OpenBracket => {
+                if self.current.kind == TokenKind::CloseBracket {
+                    self.advance()?;
+                    return Ok(Term::Atom(Symbol::NIL));
+                }
+
+                let items = self.parse_arguments()?;
+                let tail = if self.current.kind == TokenKind::Bar {
+                    self.advance()?;
+                    self.parse(ARGUMENT_PRIORITY)?
+                } else {
+                    Term::Atom(Symbol::NIL)
+                };
+                self.expect(&TokenKind::CloseBracket, "`,`, `|`, or `]`")?;
+
+                Ok(Term::list_with_tail(items, tail))
+            }
+
+            kind => Err(self.error_at(
+                token.position,
+                format!("expected a term, found {kind}"),
+            )),
+        }
+    }
+
+    /// Parses the arguments of a compound term if an opening parenthesis
+    /// immediately follows the already consumed name, otherwise, the name is
+    /// an atom.
+    fn atom_or_compound(&mut self, name: String) -> Result<Term, ParseError> {
+        if self.current.kind != TokenKind::OpenParen || self.current.spaced {
+            return Ok(Term::atom(name));
+        }
+
+        self.advance()?;
+        let arguments = self.parse_arguments()?;
+        self.expect(&TokenKind::CloseParen, "`,` or `)`")?;
+
+        Ok(Term::component(name, arguments))
+    }
+
+    /// Parses the comma-separated arguments of a compound term or list.
+    fn parse_arguments(&mut self) -> Result<Vec<Term>, ParseError> {
+        let mut arguments = vec![self.parse(ARGUMENT_PRIORITY)?];
+
+        while self.current.kind == TokenKind::Comma {
+            self.advance()?;
+            arguments.push(self.parse(ARGUMENT_PRIORITY)?);
+        }
+
+        Ok(arguments)
+    }
+
+    fn fresh_variable(&mut self) -> usize {
+        let index = self.variable_count;
+        self.variable_count += 1;
+        index
+    }
+}
+
+#[cfg(test)]
+mod test;
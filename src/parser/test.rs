@@ -0,0 +1,128 @@
+use crate::{
+    clause::{Clause, Goal, Predicate},
+    parser::{parse_clause, parse_goal, parse_program},
+    term::Term,
+};
+
+#[test]
+fn parse_terms() {
+    let clause = parse_clause(
+        "p('New York', -7, [a, B | T], [], f(B, _, _), X) :- !, X >= -7.",
+    )
+    .unwrap();
+
+    assert_eq!(
+        clause.head,
+        Predicate::new("p", [
+            Term::atom("New York"),
+            Term::integer(-7),
+            Term::list_with_tail(
+                [Term::atom("a"), Term::variable(0)],
+                Term::variable(1)
+            ),
+            Term::list([]),
+            Term::component("f", [
+                Term::variable(0),
+                Term::anonymous(0),
+                Term::anonymous(1),
+            ]),
+            Term::variable(2),
+        ])
+    );
+    assert_eq!(clause.body, [
+        Goal::cut(),
+        Goal::new(">=", [Term::variable(2), Term::integer(-7)]),
+    ]);
+}
+
+#[test]
+fn parse_strings() {
+    let clause = parse_clause(r#"p("say ""hi""", 'say', "")."#).unwrap();
+
+    assert_eq!(clause.head.arguments, [
+        Term::string("say \"hi\""),
+        Term::atom("say"),
+        Term::string(""),
+    ]);
+
+    // printed back the way they're written
+    assert_eq!(clause.to_string(), r#"p("say ""hi""", say, "")."#);
+}
+
+#[test]
+fn parse_operators_by_priority() {
+    // `,` binds tighter than `;`, which is right-associative
+    let clause = parse_clause("p :- a, b ; c ; d").unwrap();
+
+    assert_eq!(clause.to_string(), "p :- ;(,(a, b), ;(c, d)).");
+
+    let clause = parse_clause("p :- (a ; b), c").unwrap();
+
+    assert_eq!(clause.to_string(), "p :- ;(a, b), c.");
+
+    // `\+` binds tighter than `,` but looser than `=`
+    let clause = parse_clause("p :- \\+ a, \\+ \\+ X = b, \\+(c)").unwrap();
+
+    assert_eq!(clause.to_string(), "p :- \\+(a), \\+(\\+(=(?0, b))), \\+(c).");
+
+    // without an operand, it's an ordinary atom
+    let clause = parse_clause("p(\\+, a)").unwrap();
+
+    assert_eq!(clause.to_string(), "p(\\+, a).");
+}
+
+#[test]
+fn parse_program_with_comments() {
+    let clauses = parse_program(
+        "
+        % a line comment
+        edge(a, b). /* a block
+                       comment */ edge(b, c).
+        path(X, Y) :- edge(X, Y).
+        ",
+    )
+    .unwrap();
+
+    assert_eq!(clauses.iter().map(ToString::to_string).collect::<Vec<_>>(), [
+        "edge(a, b).",
+        "edge(b, c).",
+        "path(?0, ?1) :- edge(?0, ?1)."
+    ]);
+
+    assert_eq!(
+        clauses[0],
+        Clause::fact(Predicate::new("edge", [
+            Term::atom("a"),
+            Term::atom("b"),
+        ]))
+    );
+}
+
+#[test]
+fn parse_goal_numbers_variables_in_order() {
+    let goal = parse_goal("ancestor(Y, X), X < 3.").unwrap();
+
+    assert_eq!(goal.to_string(), ",(ancestor(?0, ?1), <(?1, 3))");
+}
+
+#[test]
+fn parse_errors() {
+    let error = parse_program("a.\nb :- c.\nd(e :- f.\n").unwrap_err();
+
+    assert_eq!((error.clause, error.line, error.column), (3, 3, 5));
+    assert_eq!(
+        error.to_string(),
+        "clause 3 at 3:5: expected `,` or `)`, found `:-`"
+    );
+
+    // a missing period merges two clauses
+    let error = parse_program("a\nb.").unwrap_err();
+    assert_eq!((error.clause, error.line), (1, 2));
+
+    assert!(parse_program("p('unterminated).").is_err());
+    assert!(parse_program("p(\"unterminated).").is_err());
+    assert!(parse_program("p(99999999999999999999).").is_err());
+    assert!(parse_clause("X :- a.").is_err());
+    assert!(parse_clause("p :- 1.").is_err());
+    assert!(parse_goal("X").is_err());
+}
@@ -0,0 +1,39 @@
+//! Re-exports the types needed to build a knowledge base and query it, so
+//! that a single glob import suffices:
+//!
+//! ```
+//! use slg_prolog_solver::prelude::*;
+//!
+//! let kb = KnowledgeBase::builder()
+//!     .fact("parent", [Term::atom("alice"), Term::atom("bob")])
+//!     .fact("parent", [Term::atom("bob"), Term::atom("carol")])
+//!     .rule(
+//!         Predicate::new("grandparent", [
+//!             Term::variable(0),
+//!             Term::variable(2),
+//!         ]),
+//!         [
+//!             Goal::new("parent", [Term::variable(0), Term::variable(1)]),
+//!             Goal::new("parent", [Term::variable(1), Term::variable(2)]),
+//!         ],
+//!     )
+//!     .build();
+//!
+//! let mut solver = Solver::new(&kb);
+//! let answers: Vec<Substitution> =
+//!     solver.solve_all(parse_goal("grandparent(alice, Z)").unwrap()).unwrap();
+//!
+//! assert_eq!(answers.len(), 1);
+//! assert_eq!(answers[0].mapping[&0], Term::atom("carol"));
+//! ```
+
+pub use crate::{
+    clause::{
+        Clause, Functor, Goal, KnowledgeBase, KnowledgeBaseBuilder, Predicate,
+    },
+    parser::{ParseError, parse_clause, parse_goal, parse_program, parse_term},
+    solver::{GoalState, Solution, SolveError, Solver},
+    substitution::Substitution,
+    symbol::Symbol,
+    term::Term,
+};
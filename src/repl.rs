@@ -0,0 +1,139 @@
+//! Contains [`Session`], a string-driven front end tying the parser, the
+//! clause edits, and the solver together the way an interactive top level
+//! uses them.
+//!
+//! The session doesn't do any IO itself, reading the input and printing the
+//! solutions is left to the caller.
+
+use crate::{
+    clause::KnowledgeBase,
+    parser::{self, ParseError},
+    solver::{GoalState, Solution, SolveError, Solver},
+};
+
+/// An interactive session over a knowledge base, accepting clauses and
+/// queries written in Prolog syntax.
+///
+/// The session keeps a single [`Solver`] owning the knowledge base, so the
+/// tables of a query are reused by the later queries until a clause they
+/// may depend on is asserted or retracted.
+///
+/// ```
+/// use slg_prolog_solver::repl::Session;
+///
+/// let mut session = Session::default();
+/// session.assert("parent(alice, bob).").unwrap();
+/// session.assert("parent(bob, carol).").unwrap();
+/// session.assert("grandparent(X, Z) :- parent(X, Y), parent(Y, Z).").unwrap();
+///
+/// let solutions =
+///     session.query("grandparent(alice, Z)").unwrap().collect::<Vec<_>>();
+///
+/// assert_eq!(solutions.len(), 1);
+/// assert_eq!(solutions[0].get_atom(0), Some("carol"));
+/// ```
+#[derive(Debug)]
+pub struct Session {
+    solver: Solver<'static>,
+}
+
+impl Session {
+    /// Creates a session starting from the clauses of the knowledge base.
+    #[must_use]
+    pub fn new(knowledge_base: KnowledgeBase) -> Self {
+        Self { solver: Solver::owning(knowledge_base) }
+    }
+
+    /// Returns the knowledge base with the clauses asserted so far.
+    #[must_use]
+    pub fn knowledge_base(&self) -> &KnowledgeBase {
+        self.solver.knowledge_base()
+    }
+
+    /// Returns the solver answering the queries, e.g. to bound the answers of
+    /// every table with [`Solver::set_max_answers_per_table`].
+    pub fn solver_mut(&mut self) -> &mut Solver<'static> { &mut self.solver }
+
+    /// Adds the clause, e.g. `parent(alice, bob).`, after every other clause
+    /// of its predicate, see [`Solver::add_clause`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`ParseError`] if the source isn't exactly one clause.
+    pub fn assert(&mut self, clause: &str) -> Result<(), ParseError> {
+        self.solver.add_clause(parser::parse_clause(clause)?);
+
+        Ok(())
+    }
+
+    /// Removes the first clause written like the given one, up to the names
+    /// of its variables, returning whether one was removed, see
+    /// [`Solver::remove_clause`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`ParseError`] if the source isn't exactly one clause.
+    pub fn retract(&mut self, clause: &str) -> Result<bool, ParseError> {
+        Ok(self.solver.remove_clause(&parser::parse_clause(clause)?))
+    }
+
+    /// Starts the query, e.g. `ancestor(alice, X)`, returning an iterator
+    /// over its solutions computed lazily.
+    ///
+    /// The variables of the query are numbered from zero in the order of
+    /// their first appearance, like [`parser::parse_goal`] does, e.g. `X` is
+    /// the variable `0` of the solutions of `ancestor(alice, X)`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`ParseError`] if the source isn't exactly one goal.
+    pub fn query(&mut self, goal: &str) -> Result<Query<'_>, ParseError> {
+        let goal_state =
+            self.solver.create_goal_state(parser::parse_goal(goal)?);
+
+        Ok(Query { solver: &mut self.solver, goal_state, error: None })
+    }
+}
+
+impl Default for Session {
+    fn default() -> Self { Self::new(KnowledgeBase::new()) }
+}
+
+/// The solutions of a query started by [`Session::query`].
+///
+/// The iteration stops at the first [`SolveError`], e.g. once the step
+/// budget of the solver runs out, which [`Query::error`] reports then.
+#[derive(Debug)]
+pub struct Query<'s> {
+    solver: &'s mut Solver<'static>,
+    goal_state: GoalState,
+    error: Option<SolveError>,
+}
+
+impl Query<'_> {
+    /// Returns the error that stopped the iteration, `None` if the query ran
+    /// out of solutions or hasn't stopped yet.
+    #[must_use]
+    pub fn error(&self) -> Option<&SolveError> { self.error.as_ref() }
+}
+
+impl Iterator for Query<'_> {
+    type Item = Solution;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.error.is_some() {
+            return None;
+        }
+
+        match self.solver.next_solution_as(&mut self.goal_state) {
+            Ok(solution) => solution,
+            Err(error) => {
+                self.error = Some(error);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test;
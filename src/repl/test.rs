@@ -0,0 +1,52 @@
+use crate::{repl::Session, solver::Solution};
+
+/// Collects the atoms the variable `0` is bound to, sorted.
+fn atoms(solutions: impl Iterator<Item = Solution>) -> Vec<String> {
+    let mut atoms = solutions
+        .map(|solution| solution.get_atom(0).unwrap().to_owned())
+        .collect::<Vec<_>>();
+    atoms.sort();
+
+    atoms
+}
+
+#[test]
+fn interactive_session() {
+    let mut session = Session::default();
+
+    session.assert("parent(alice, bob).").unwrap();
+    session.assert("parent(bob, carol).").unwrap();
+    session.assert("ancestor(X, Y) :- parent(X, Y).").unwrap();
+    session.assert("ancestor(X, Y) :- parent(X, Z), ancestor(Z, Y).").unwrap();
+
+    assert_eq!(atoms(session.query("ancestor(alice, X)").unwrap()), [
+        "bob", "carol"
+    ]);
+
+    // a new fact reaches the memoized query
+    session.assert("parent(carol, dave).").unwrap();
+    assert_eq!(atoms(session.query("ancestor(alice, X)").unwrap()), [
+        "bob", "carol", "dave"
+    ]);
+
+    // retracting cuts the chain, the variable names don't matter
+    assert!(session.retract("parent(bob, carol)").unwrap());
+    assert!(!session.retract("parent(bob, carol)").unwrap());
+    assert!(session.retract("ancestor(A, B) :- parent(A, B).").unwrap());
+
+    assert!(session.query("ancestor(alice, X)").unwrap().next().is_none());
+
+    session.assert("ancestor(X, Y) :- parent(X, Y).").unwrap();
+    assert_eq!(atoms(session.query("ancestor(alice, X)").unwrap()), ["bob"]);
+    assert_eq!(atoms(session.query("ancestor(X, dave)").unwrap()), ["carol"]);
+
+    // a query can stop halfway
+    let mut query = session.query("parent(X, Y)").unwrap();
+    assert!(query.next().is_some());
+    assert!(query.error().is_none());
+
+    // malformed input is rejected without touching the knowledge base
+    assert!(session.assert("parent(alice").is_err());
+    assert!(session.query("ancestor(X,").is_err());
+    assert_eq!(session.knowledge_base().clauses_iter().count(), 4);
+}
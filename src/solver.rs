@@ -15,78 +15,1148 @@
 //! prove a goal", and "there are subgoals to prove a particular answer", define
 //! the structure of the SLG table.
 
-use std::collections::HashMap;
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    fmt,
+    time::Instant,
+};
 
 use crate::{
     arena::ID,
     canonicalize::{reverse_mapping, uncanonicalize_substitution},
-    clause::{Goal, KnowledgeBase},
+    clause::{Functor, Goal, KnowledgeBase, Predicate},
     solver::{
         stack::Stack,
-        table::{EnsureAnswer, Table, Tables},
+        table::{EnsureAnswer, Error, Tables},
     },
     substitution::Substitution,
+    symbol::Symbol,
+    term::Term,
 };
 
+pub(crate) mod builtin;
+mod dynamic;
+mod explain;
 mod stack;
 mod table;
 
+pub use explain::{ClauseFailure, FailureReason, FailureTrace, ProofTree};
+pub use table::{Strand, Table};
+
+/// The number of strand activations between two checks of the deadline, see
+/// [`Solver::set_deadline`].
+const DEADLINE_CHECK_INTERVAL: usize = 64;
+
 /// A solver is a state-machine allowing the user to query for solutions to a
 /// particular goal
+///
+/// # Cloning
+///
+/// Cloning a solver between two pulls snapshots the whole evaluation: the
+/// tables, their pending strands, the facts asserted, the steps taken, and
+/// the settings are copied, so the clone and the original continue
+/// independently from the same point, e.g. to explore a query in two
+/// directions. The [`GoalState`]s of the original can be cloned along and
+/// used with the clone as well since tables keep their IDs.
+///
+/// The knowledge base is shared when it's borrowed, it can't change while
+/// borrowed. The tracer set by [`Solver::set_tracer`] stays with the original,
+/// the clone starts without one.
+///
+/// A solver is [`Send`] so long as its knowledge base is, and can be moved to
+/// another thread to run a query there.
 #[derive(Debug, Clone)]
 pub struct Solver<'a> {
-    knowledge_base: &'a KnowledgeBase,
+    /// The program, either borrowed or owned by the solver, see
+    /// [`Solver::owning`].
+    knowledge_base: Cow<'a, KnowledgeBase>,
     tables: Tables,
     stack: Stack,
+
+    /// The maximum number of strand activations allowed, `None` if unbounded.
+    max_steps: Option<usize>,
+
+    /// The number of strand activations performed so far.
+    steps: usize,
+
+    /// The instant past which the solver gives up, `None` if unbounded.
+    deadline: Option<Instant>,
+
+    /// The largest term, by [`Term::size`], a binding may be, `None` if
+    /// unbounded.
+    max_term_size: Option<usize>,
+
+    /// The number of answers after which a table stops looking for more,
+    /// `None` if unbounded.
+    max_answers_per_table: Option<usize>,
+
+    /// Whether head unification performs the occurs check.
+    occurs_check: bool,
+
+    /// Whether tables only keep their most general answers.
+    answer_subsumption: bool,
+
+    /// Whether queries yield their answers in ascending order rather than in
+    /// the order they're found.
+    ordered_answers: bool,
+
+    /// The order in which the strands of a table are processed.
+    scheduling_policy: SchedulingPolicy,
+
+    /// The predicates whose facts may be added while solving, see
+    /// [`Solver::declare_dynamic`].
+    dynamic_predicates: HashSet<Functor>,
+
+    /// The facts added by [`Solver::assertz`], tried after the clauses of the
+    /// knowledge base.
+    asserted_facts: KnowledgeBase,
+
+    /// The observer notified of every [`TraceEvent`], if any.
+    tracer: Tracer<'a>,
 }
 
 impl<'a> Solver<'a> {
     /// Creates a new [`Solver`] that will search for solutions to the given
     /// [`Goal`].
     pub fn new(knowledge_base: &'a KnowledgeBase) -> Self {
-        Self { knowledge_base, tables: Tables::new(), stack: Stack::new() }
+        Self::from_cow(Cow::Borrowed(knowledge_base))
+    }
+
+    /// Creates a new [`Solver`] that owns its knowledge base, allowing
+    /// clauses to be added to it later with [`Solver::add_clause`].
+    #[must_use]
+    pub fn owning(knowledge_base: KnowledgeBase) -> Self {
+        Self::from_cow(Cow::Owned(knowledge_base))
+    }
+
+    fn from_cow(knowledge_base: Cow<'a, KnowledgeBase>) -> Self {
+        Self {
+            knowledge_base,
+            tables: Tables::new(),
+            stack: Stack::new(),
+            max_steps: None,
+            steps: 0,
+            deadline: None,
+            max_term_size: None,
+            max_answers_per_table: None,
+            occurs_check: true,
+            answer_subsumption: false,
+            ordered_answers: false,
+            scheduling_policy: SchedulingPolicy::Fifo,
+            dynamic_predicates: HashSet::new(),
+            asserted_facts: KnowledgeBase::new(),
+            tracer: Tracer::default(),
+        }
+    }
+
+    /// Creates a new [`Solver`] that gives up after activating `max_steps`
+    /// strands.
+    ///
+    /// The budget is shared by every query made through this solver. Once it
+    /// is exhausted, [`Solver::pull_next_goal`] reports
+    /// [`SolveError::StepLimitExceeded`] instead of searching any further.
+    pub fn with_max_steps(
+        knowledge_base: &'a KnowledgeBase,
+        max_steps: usize,
+    ) -> Self {
+        Self { max_steps: Some(max_steps), ..Self::new(knowledge_base) }
+    }
+
+    /// Creates a new [`Solver`] that gives up once the `deadline` has passed,
+    /// see [`Solver::set_deadline`].
+    pub fn with_deadline(
+        knowledge_base: &'a KnowledgeBase,
+        deadline: Instant,
+    ) -> Self {
+        Self { deadline: Some(deadline), ..Self::new(knowledge_base) }
+    }
+
+    /// Creates a new [`Solver`] whose tables stop looking for answers once
+    /// they hold `max_answers`, see [`Solver::set_max_answers_per_table`].
+    pub fn with_max_answers_per_table(
+        knowledge_base: &'a KnowledgeBase,
+        max_answers: usize,
+    ) -> Self {
+        Self {
+            max_answers_per_table: Some(max_answers),
+            ..Self::new(knowledge_base)
+        }
+    }
+
+    /// Creates a new [`Solver`] that yields the answers of every query in
+    /// ascending order, see [`Solver::set_ordered_answers`].
+    pub fn with_ordered_answers(knowledge_base: &'a KnowledgeBase) -> Self {
+        Self { ordered_answers: true, ..Self::new(knowledge_base) }
+    }
+
+    /// Creates a new [`Solver`] for the given knowledge base out of an old
+    /// one, reusing the allocations of its tables.
+    ///
+    /// The settings of the old solver carry over, except for the tracer which
+    /// may borrow from a shorter scope. Like [`Solver::reset`], every answer
+    /// memoized and every fact asserted by the old solver is discarded.
+    pub fn new_reusing(
+        solver: Solver<'_>,
+        knowledge_base: &'a KnowledgeBase,
+    ) -> Self {
+        let Solver {
+            knowledge_base: _,
+            mut tables,
+            mut stack,
+            max_steps,
+            steps: _,
+            deadline,
+            max_term_size,
+            max_answers_per_table,
+            occurs_check,
+            answer_subsumption,
+            ordered_answers,
+            scheduling_policy,
+            dynamic_predicates,
+            asserted_facts: _,
+            tracer: _,
+        } = solver;
+
+        tables.clear();
+        stack.clear();
+
+        Self {
+            knowledge_base: Cow::Borrowed(knowledge_base),
+            tables,
+            stack,
+            max_steps,
+            steps: 0,
+            deadline,
+            max_term_size,
+            max_answers_per_table,
+            occurs_check,
+            answer_subsumption,
+            ordered_answers,
+            scheduling_policy,
+            dynamic_predicates,
+            asserted_facts: KnowledgeBase::new(),
+            tracer: Tracer::default(),
+        }
+    }
+
+    /// Discards every table, the facts added by [`Solver::assertz`], and the
+    /// steps taken so far, keeping the allocated capacity and the settings of
+    /// the solver.
+    ///
+    /// This invalidates all the answers memoized so far: the next queries are
+    /// evaluated from scratch, as if made through a new [`Solver`]. Any
+    /// [`GoalState`] created before resetting must not be used anymore.
+    ///
+    /// Useful to run many independent queries in a loop without reallocating
+    /// the tables for every one of them.
+    pub fn reset(&mut self) {
+        self.tables.clear();
+        self.stack.clear();
+        self.asserted_facts = KnowledgeBase::new();
+        self.steps = 0;
+    }
+
+    /// Enables or disables the occurs check when unifying goals with clause
+    /// heads. It's enabled by default.
+    ///
+    /// Disabling it speeds up unification but is unsound for programs that
+    /// unify a variable with a term containing that variable, see
+    /// [`Substitution::unify_terms_unchecked`].
+    pub fn set_occurs_check(&mut self, enabled: bool) {
+        self.occurs_check = enabled;
+    }
+
+    /// Enables or disables answer subsumption. It's disabled by default.
+    ///
+    /// When enabled, an answer that is an instance of an answer already in its
+    /// table (see [`Substitution::is_instance_of`]) is discarded, and answers
+    /// that are instances of a newly found answer are skipped from then on.
+    /// Answers that have already been pulled stay pulled, so a more specific
+    /// answer may still be returned before a more general one is found.
+    pub fn set_answer_subsumption(&mut self, enabled: bool) {
+        self.answer_subsumption = enabled;
+    }
+
+    /// Enables or disables ordered answers. It's disabled by default.
+    ///
+    /// When enabled, [`Solver::pull_next_goal`] yields the answers of a query
+    /// in ascending order, see the [`Ord`] implementations of
+    /// [`Substitution`] and [`Term`], making the output reproducible
+    /// regardless of the order in which the answers are found. The price is
+    /// that the first answer is only returned once the table of the query is
+    /// complete.
+    pub fn set_ordered_answers(&mut self, enabled: bool) {
+        self.ordered_answers = enabled;
+    }
+
+    /// Sets the order in which the strands of a table are processed, it's
+    /// [`SchedulingPolicy::Fifo`] by default.
+    ///
+    /// The strands already pending in the tables are reordered for the new
+    /// policy, so the policy can be switched between two answers.
+    pub fn set_scheduling_policy(&mut self, policy: SchedulingPolicy) {
+        self.scheduling_policy = policy;
+        self.tables.set_scheduling_policy(policy);
+    }
+
+    /// Sets the instant past which [`Solver::pull_next_goal`] gives up with
+    /// [`SolveError::Timeout`], `None` lifts the deadline.
+    ///
+    /// The clock is only read every few strand activations, so the solver
+    /// may overrun the deadline by the time these take. A timed out query can
+    /// be resumed after pushing the deadline back.
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.deadline = deadline;
+    }
+
+    /// Sets the largest term, counted by [`Term::size`], that answers and the
+    /// bindings combined from them may contain, `None` lifts the bound.
+    ///
+    /// Programs building ever larger terms, e.g. `nat(s(X)) :- nat(X).`, have
+    /// infinitely many answers even with tabling. With a bound, the answers
+    /// and the strands exceeding it are dropped and
+    /// [`Solver::pull_next_goal`] reports [`SolveError::TermTooLarge`]. Pulling
+    /// again resumes the query without them, so it eventually runs out of
+    /// answers. Bindings made by unifying with the clause heads aren't checked
+    /// until they reach an answer.
+    pub fn set_max_term_size(&mut self, max_term_size: Option<usize>) {
+        self.max_term_size = max_term_size;
+    }
+
+    /// Sets the number of answers after which a table is considered complete
+    /// and stops looking for more, `None` lifts the bound.
+    ///
+    /// This bounds the memory taken by broad predicates when only the first
+    /// few answers of each subgoal matter. The price is completeness: a
+    /// capped table drops its pending strands, so the answers it never found
+    /// are lost for every query sharing it, including the answers of the
+    /// tables consuming it. Which answers are kept depends on the order they
+    /// are found in, see [`Solver::set_scheduling_policy`].
+    pub fn set_max_answers_per_table(&mut self, max_answers: Option<usize>) {
+        self.max_answers_per_table = max_answers;
+    }
+
+    /// Enables or disables reproducible table IDs. It's disabled by default.
+    ///
+    /// The ID of a table is the hash of its canonical goal, computed with
+    /// [`std::hash::DefaultHasher`] by default, whose algorithm may change
+    /// between Rust versions. Enabling this hashes with the
+    /// [`crate::arena::state::StableHasher`] instead, so that logs and traces
+    /// mentioning table IDs can be compared across runs. The goals are hashed
+    /// by what they read as, with their names spelled out, so a goal keeps
+    /// its ID no matter the order its names were interned in, see
+    /// [`Symbol`].
+    ///
+    /// Changing the setting discards the tables like [`Solver::reset`] does,
+    /// any [`GoalState`] created before must not be used anymore.
+    pub fn set_stable_table_ids(&mut self, enabled: bool) {
+        if self.tables.has_stable_ids() != enabled {
+            self.tables.set_stable_ids(enabled);
+            self.stack.clear();
+        }
+    }
+
+    /// Sets the observer notified of every [`TraceEvent`] happening while
+    /// solving, replacing the previous one.
+    ///
+    /// Events are only constructed when a tracer is set, a solver without one
+    /// doesn't pay for tracing. The tracer isn't cloned along with the
+    /// solver, see [`Solver#cloning`].
+    pub fn set_tracer(&mut self, tracer: impl FnMut(&TraceEvent) + Send + 'a) {
+        self.tracer = Tracer(Some(Box::new(tracer)));
+    }
+
+    /// Notifies the tracer, if any, of the event created by `event`.
+    fn trace(&mut self, event: impl FnOnce(&Self) -> TraceEvent) {
+        // taken out for the duration of the call, `event` reads the solver
+        if let Some(mut tracer) = self.tracer.0.take() {
+            tracer(&event(self));
+            self.tracer.0 = Some(tracer);
+        }
+    }
+
+    /// Consumes a single step from the budget, returns the error to report if
+    /// the budget has been exhausted or the deadline has passed.
+    fn consume_step(&mut self) -> Result<(), Error> {
+        if self.max_steps.is_some_and(|max_steps| self.steps >= max_steps) {
+            return Err(Error::StepLimitExceeded);
+        }
+
+        // reading the clock costs far more than a step, only do it every so
+        // often
+        if self.steps.is_multiple_of(DEADLINE_CHECK_INTERVAL)
+            && self.deadline.is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return Err(Error::Timeout);
+        }
+
+        self.steps += 1;
+        Ok(())
+    }
+}
+
+/// Decides which strand of a table the solver advances next, see
+/// [`Solver::set_scheduling_policy`].
+///
+/// The policy only affects the order in which the answers are found and how
+/// many steps it takes to find the first ones, never the set of answers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum SchedulingPolicy {
+    /// Round-robin: new strands queue up behind the existing ones, so every
+    /// strand gets its turn in a breadth-first manner.
+    #[default]
+    Fifo,
+
+    /// Depth-first: the strands created last are advanced first, which
+    /// usually reaches the first answer in fewer steps for deterministic
+    /// programs. The clauses of a table are still tried in order.
+    Lifo,
+
+    /// Shortest derivation first: the work list is a priority queue advancing
+    /// the strand with the fewest clauses resolved so far, see
+    /// [`Strand::cost`], so the answers with short derivations tend to come
+    /// first, e.g. the nodes fewest hops away in a reachability query.
+    ///
+    /// The answers are still yielded as soon as they're found; together with
+    /// [`Solver::set_ordered_answers`], the answers of a query come in
+    /// ascending order of their derivation length, see
+    /// [`Table::answer_costs`], and then in ascending order.
+    ShortestFirst,
+}
+
+/// The error returned by [`Solver::pull_next_goal`] when the query can't be
+/// answered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SolveError {
+    /// The solver exhausted its step budget given in
+    /// [`Solver::with_max_steps`]. The query can be resumed after raising the
+    /// budget.
+    StepLimitExceeded,
+
+    /// The deadline given in [`Solver::with_deadline`] or
+    /// [`Solver::set_deadline`] has passed. The query can be resumed after
+    /// pushing the deadline back.
+    Timeout,
+
+    /// An answer contained a term larger than the bound given in
+    /// [`Solver::set_max_term_size`] and has been dropped. The query can be
+    /// resumed, it yields the answers within the bound only.
+    TermTooLarge,
+
+    /// A call to the predicate of the given [`Functor`] left the argument at
+    /// the given index non-ground although its mode is
+    /// [`crate::clause::Mode::In`], see
+    /// [`KnowledgeBase::declare_mode`]. The offending call has been dropped,
+    /// the query can be resumed without it, unless the offending call is the
+    /// query itself.
+    ModeViolation(Functor, usize),
+
+    /// The query depends on the negation `\+ Goal` of a goal that in turn
+    /// depends on the goal containing the negation, e.g. `p :- \+ p.`, whose
+    /// truth value can't be decided by evaluating the negated goal first.
+    ///
+    /// The tables involved are left incomplete, [`Solver::reset`] the solver
+    /// before querying it again.
+    UnstratifiedNegation,
+
+    /// The table the goal state reads from has been dropped, e.g. by
+    /// [`Solver::gc`] or by asserting a clause its goal depends on. The query
+    /// can't be resumed, it has to be started again with
+    /// [`Solver::create_goal_state`].
+    StaleGoalState,
+}
+
+impl std::fmt::Display for SolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolveError::StepLimitExceeded => {
+                write!(f, "the solver exceeded its step limit")
+            }
+            SolveError::Timeout => {
+                write!(f, "the solver exceeded its deadline")
+            }
+            SolveError::TermTooLarge => {
+                write!(f, "an answer exceeded the maximum term size")
+            }
+            SolveError::ModeViolation(functor, argument) => {
+                write!(
+                    f,
+                    "argument {} of `{functor}` must be ground when called",
+                    argument + 1
+                )
+            }
+            SolveError::UnstratifiedNegation => {
+                write!(f, "the query uses negation through a recursive cycle")
+            }
+            SolveError::StaleGoalState => {
+                write!(f, "the table of the query has been dropped")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SolveError {}
+
+impl SolveError {
+    /// Translates an internal evaluation error, returns `None` if it means
+    /// that there are no more answers.
+    fn from_error(error: Error) -> Option<Self> {
+        match error {
+            Error::StepLimitExceeded => Some(SolveError::StepLimitExceeded),
+            Error::Timeout => Some(SolveError::Timeout),
+            Error::TermTooLarge => Some(SolveError::TermTooLarge),
+            Error::ModeViolation(functor, argument) => {
+                Some(SolveError::ModeViolation(functor, argument))
+            }
+            Error::NegativeCyclicDependency => {
+                Some(SolveError::UnstratifiedNegation)
+            }
+            Error::NoMoreSolutions | Error::PositiveCyclicDependency(_) => None,
+        }
+    }
+}
+
+/// The error returned by [`Solver::assertz`] when the fact can't be added.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssertError {
+    /// The predicate of the fact hasn't been declared with
+    /// [`Solver::declare_dynamic`].
+    NotDynamic(Functor),
+
+    /// The fact contains variables.
+    NotGround(Predicate),
+}
+
+impl std::fmt::Display for AssertError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AssertError::NotDynamic(functor) => {
+                write!(f, "`{functor}` is not a dynamic predicate")
+            }
+            AssertError::NotGround(fact) => {
+                write!(f, "the fact `{fact}` is not ground")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AssertError {}
+
+/// An event of the SLG evaluation reported to the tracer set with
+/// [`Solver::set_tracer`].
+///
+/// Goals are reported in their canonical form, i.e. the goal of the table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceEvent {
+    /// A table has been created for the goal.
+    NewTable(Goal),
+
+    /// A strand of the goal's table consumed an answer of its selected
+    /// subgoal and forked to continue with the rest of its subgoals.
+    StrandForked(Goal),
+
+    /// A new answer has been added to the goal's table.
+    AnswerFound(Goal, Substitution),
+
+    /// A strand of the goal's table has been delayed because its selected
+    /// subgoal depends on a table still being evaluated, namely the one with
+    /// the given depth-first number.
+    CyclicDependency(Goal, usize),
+}
+
+/// Prints the event in a single line, e.g. `answer over(a, ?0): {?0 = b}`.
+impl fmt::Display for TraceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceEvent::NewTable(goal) => write!(f, "new table {goal}"),
+            TraceEvent::StrandForked(goal) => write!(f, "fork {goal}"),
+            TraceEvent::AnswerFound(goal, answer) => {
+                write!(f, "answer {goal}: {answer}")
+            }
+            TraceEvent::CyclicDependency(goal, depth_first_number) => {
+                write!(f, "delay {goal} on #{depth_first_number}")
+            }
+        }
+    }
+}
+
+/// The observer of [`TraceEvent`]s, if any; a clone of the solver starts
+/// without one.
+#[derive(Default)]
+struct Tracer<'a>(Option<Box<TraceFn<'a>>>);
+
+/// The callback set by [`Solver::set_tracer`].
+type TraceFn<'a> = dyn FnMut(&TraceEvent) + Send + 'a;
+
+impl Clone for Tracer<'_> {
+    fn clone(&self) -> Self { Self(None) }
+}
+
+impl fmt::Debug for Tracer<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Tracer")
     }
 }
 
+/// The progress of a query started by [`Solver::create_goal_state`], its
+/// answers are pulled with [`Solver::pull_next_goal`].
+///
+/// With the `serde` feature enabled, a goal state serializes as a checkpoint
+/// of the enumeration: deserializing it and pulling from it later continues
+/// right after the last answer pulled. The goal state refers to the table of
+/// the query by ID, so it can only be resumed with the same [`Solver`], as
+/// long as the table hasn't been dropped, e.g. by [`Solver::reset`];
+/// resuming it with any other solver may panic or yield wrong answers.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GoalState {
     answer_index: usize,
     table_id: ID<Table>,
+
+    /// The generation of the table, telling it apart from a table created
+    /// again for the same goal once it's been dropped.
+    generation: u64,
     canonical_mapping: HashMap<usize, usize>,
+
+    /// The answers left to yield in descending order, collected once the
+    /// table is complete if the answers are ordered.
+    ordered_answers: Option<Vec<Substitution>>,
+
+    /// The answer computed by [`Solver::peek_next_goal`], yielded by the next
+    /// pull instead of computing a new one.
+    peeked: Option<Substitution>,
+}
+
+impl GoalState {
+    /// Returns the mapping from the variables of the canonical goal, see
+    /// [`Solver::canonical_goal`], to the variables of the query.
+    ///
+    /// The answers stored in the table are expressed in terms of the canonical
+    /// variables, [`GoalState::project`] translates them back to the variables
+    /// of the query with this mapping.
+    ///
+    /// For example, querying `p(?5, a, ?7, ?5)` proves the canonical goal
+    /// `p(?0, a, ?1, ?0)` and the mapping is `{0: 5, 1: 7}`.
+    #[must_use]
+    pub fn canonical_mapping(&self) -> &HashMap<usize, usize> {
+        &self.canonical_mapping
+    }
+
+    /// Translates an answer of the table, expressed in the variables of the
+    /// canonical goal, into the exact variables of the query, which is what
+    /// [`Solver::pull_next_goal`] returns.
+    ///
+    /// Only the bindings of the variables occurring in the query are kept,
+    /// minus the anonymous ones, see [`Term::anonymous`]. For example, the
+    /// answer `{?0 = bob, ?1 = carol}` to the query `parent(?7, ?42)`, whose
+    /// canonical goal is `parent(?0, ?1)`, is projected onto `{?7 = bob, ?42 =
+    /// carol}`.
+    #[must_use]
+    pub fn project(&self, answer: &Substitution) -> Substitution {
+        let mut projected = uncanonicalize_substitution(
+            &Substitution {
+                mapping: answer
+                    .mapping
+                    .iter()
+                    .filter(|(variable, _)| {
+                        self.canonical_mapping.contains_key(variable)
+                    })
+                    .map(|(variable, term)| (*variable, term.clone()))
+                    .collect(),
+            },
+            &self.canonical_mapping,
+        );
+
+        // nobody cares about the bindings of anonymous variables
+        projected.mapping.retain(|variable, _| !Term::is_anonymous(*variable));
+
+        projected
+    }
+}
+
+/// An answer to a query, binding the variables of the query, e.g. `{?0 =
+/// dave}` for the query `grandparent(alice, ?0)`.
+///
+/// This is a thin wrapper around the [`Substitution`] returned by
+/// [`Solver::pull_next_goal`] with accessors for the common cases, see
+/// [`Solver::next_solution_as`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Solution {
+    substitution: Substitution,
+}
+
+impl Solution {
+    /// Returns the term the given query variable is bound to, `None` if the
+    /// variable is unbound or doesn't occur in the query.
+    #[must_use]
+    pub fn get_term(&self, variable: usize) -> Option<&Term> {
+        self.substitution.mapping.get(&variable)
+    }
+
+    /// Returns the name of the atom the given query variable is bound to,
+    /// `None` if it isn't bound to an atom.
+    #[must_use]
+    pub fn get_atom(&self, variable: usize) -> Option<&str> {
+        match self.get_term(variable)? {
+            Term::Atom(name) => Some(name.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Returns the bindings of the query variables.
+    #[must_use]
+    pub fn substitution(&self) -> &Substitution { &self.substitution }
+
+    /// Returns the bindings of the query variables.
+    #[must_use]
+    pub fn into_substitution(self) -> Substitution { self.substitution }
+}
+
+impl From<Substitution> for Solution {
+    fn from(substitution: Substitution) -> Self { Self { substitution } }
+}
+
+impl fmt::Display for Solution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.substitution.fmt(f)
+    }
 }
 
 impl Solver<'_> {
+    /// Returns the knowledge base the solver resolves goals against,
+    /// including the clauses added by [`Solver::add_clause`].
+    #[must_use]
+    pub fn knowledge_base(&self) -> &KnowledgeBase { &self.knowledge_base }
+
+    /// Returns the canonical goal of the table proving the query of the
+    /// `goal_state`, i.e. the query with its variables renamed in order of
+    /// appearance starting from zero.
+    #[must_use]
+    pub fn canonical_goal(&self, goal_state: &GoalState) -> &Goal {
+        self.tables.canonical_goal(goal_state.table_id)
+    }
+
+    /// Returns the table proving the query of the `goal_state`, e.g. to
+    /// inspect its pending strands between two answers.
+    #[must_use]
+    pub fn table(&self, goal_state: &GoalState) -> &Table {
+        self.tables.get(goal_state.table_id)
+    }
+
+    /// Starts a query for the given goal, its answers are then pulled with
+    /// [`Solver::pull_next_goal`].
+    ///
+    /// Tables outlive the queries that created them, every query made through
+    /// the same solver shares them. A goal whose table is already complete is
+    /// answered straight from the stored answers without any further
+    /// resolution, and a partially evaluated table resumes where it left off.
+    /// For example, after enumerating `ancestor(adam, X)`, the query
+    /// `ancestor(bob, X)` for a descendant `bob` of `adam` is answered from the
+    /// table created during the first query.
     pub fn create_goal_state(&mut self, mut goal: Goal) -> GoalState {
         let mapping = goal.canonicalize();
         let mapping = reverse_mapping(&mapping);
 
         let table_id = self.get_table_id(&goal);
+        self.start_table(table_id);
+
+        GoalState {
+            answer_index: 0,
+            table_id,
+            generation: self.tables.generation(table_id),
+            canonical_mapping: mapping,
+            ordered_answers: None,
+            peeked: None,
+        }
+    }
+
+    /// Creates a [`GoalState`] like [`Solver::create_goal_state`] for the goal
+    /// with the bindings of `seed` applied to it first, pinning the variables
+    /// the caller already knows the value of.
+    ///
+    /// The seed is resolved fully before being applied, e.g. `{?0 = ?1, ?1 =
+    /// alice}` binds both `?0` and `?1` to `alice`, and the resulting goal is
+    /// canonicalized as usual, so seeding `grandparent(?0, ?1)` with `{?0 =
+    /// alice}` shares the table of `grandparent(alice, ?1)`. The answers only
+    /// bind the variables left in the seeded goal, the bindings of the seed
+    /// aren't repeated.
+    pub fn create_goal_state_with(
+        &mut self,
+        mut goal: Goal,
+        seed: Substitution,
+    ) -> GoalState {
+        let seed = seed.restrict(&seed.mapping.keys().copied().collect());
+        seed.apply_goal(&mut goal);
+
+        self.create_goal_state(goal)
+    }
+
+    /// Creates a [`GoalState`] like [`Solver::create_goal_state`] for the
+    /// conjunction of the goals, like the top-level query `?- a(X), b(X).`
+    ///
+    /// The goals are solved as the body of an anonymous query clause: they
+    /// share their variables, and each answer binds the variables of every
+    /// goal at once. The query goal is the conjunction `','(a(X), b(X))`, so
+    /// its table is shared with any later query for the same conjunction. An
+    /// empty conjunction succeeds once without binding anything.
+    pub fn create_conjunction_state(&mut self, goals: Vec<Goal>) -> GoalState {
+        let goal =
+            Goal::new(Symbol::CONJUNCTION, goals.iter().map(Goal::to_term));
+
+        self.create_goal_state(goal)
+    }
+
+    /// Checks whether the goal has any answer, stopping the search as soon as
+    /// the first one is found.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SolveError::StepLimitExceeded`] if the step budget given in
+    /// [`Solver::with_max_steps`] ran out before an answer was found, see
+    /// [`SolveError`] for the other errors.
+    pub fn has_solution(&mut self, goal: Goal) -> Result<bool, SolveError> {
+        let mut goal_state = self.create_goal_state(goal);
+
+        Ok(self.pull_next_goal(&mut goal_state)?.is_some())
+    }
+
+    /// Collects every answer of the goal, in the order
+    /// [`Solver::pull_next_goal`] yields them.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error reported by [`Solver::pull_next_goal`], the
+    /// answers collected until then are discarded.
+    pub fn solve_all(
+        &mut self,
+        goal: Goal,
+    ) -> Result<Vec<Substitution>, SolveError> {
+        self.solve_all_limited(goal, usize::MAX)
+    }
+
+    /// Collects the first `limit` answers of the goal, or all of them if
+    /// there are fewer, see [`Solver::solve_all`].
+    ///
+    /// The search stops as soon as `limit` answers are found, which makes
+    /// this usable with goals having infinitely many answers.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error reported by [`Solver::pull_next_goal`], the
+    /// answers collected until then are discarded.
+    pub fn solve_all_limited(
+        &mut self,
+        goal: Goal,
+        limit: usize,
+    ) -> Result<Vec<Substitution>, SolveError> {
+        let mut goal_state = self.create_goal_state(goal);
+
+        let mut answers = Vec::new();
+        while answers.len() < limit {
+            let Some(answer) = self.pull_next_goal(&mut goal_state)? else {
+                break;
+            };
+
+            answers.push(answer);
+        }
+
+        Ok(answers)
+    }
+
+    /// Collects every answer of the goal, grouped by the term the variable
+    /// `key_variable` of the goal is bound to, e.g. grouping the answers of
+    /// `parent(P, C)` by `P` yields the children of every parent.
+    ///
+    /// Within a group, the answers keep the order [`Solver::solve_all`]
+    /// returns them in. An answer leaving the key variable unbound is grouped
+    /// under the [`Term::Variable`] the answer leaves it as.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error reported by [`Solver::pull_next_goal`], the
+    /// answers collected until then are discarded.
+    pub fn solve_grouped(
+        &mut self,
+        goal: Goal,
+        key_variable: usize,
+    ) -> Result<HashMap<Term, Vec<Substitution>>, SolveError> {
+        let mut groups = HashMap::<_, Vec<_>>::new();
+
+        for (key, answer) in
+            self.solve_instantiated(goal, &Term::variable(key_variable))?
+        {
+            groups.entry(key).or_default().push(answer);
+        }
+
+        Ok(groups)
+    }
+
+    /// Collects every answer of the goal as a row of the terms the given
+    /// variables of the goal are bound to, in the order of `variables`, e.g.
+    /// `[bob, carol]` for the answer `{?0 = bob, ?1 = carol}` of `parent(?0,
+    /// ?1)`.
+    ///
+    /// This suits exporting the answers as CSV or displaying them as a
+    /// table. A variable an answer leaves unbound is represented explicitly
+    /// by a [`Term::Variable`], see [`Term::as_variable`], which is the same
+    /// in every cell standing for it.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error reported by [`Solver::pull_next_goal`], the
+    /// rows collected until then are discarded.
+    pub fn solve_table(
+        &mut self,
+        goal: Goal,
+        variables: &[usize],
+    ) -> Result<Vec<Vec<Term>>, SolveError> {
+        let row = Term::component(
+            "row",
+            variables.iter().copied().map(Term::variable),
+        );
 
-        GoalState { answer_index: 0, table_id, canonical_mapping: mapping }
+        Ok(self
+            .solve_instantiated(goal, &row)?
+            .into_iter()
+            .map(|(row, _)| {
+                let Term::Compound(_, cells) = row else {
+                    unreachable!("a compound term is instantiated as such")
+                };
+
+                cells.into()
+            })
+            .collect())
+    }
+
+    /// Collects the `template` instantiated by every answer of the goal, the
+    /// host-side counterpart of `findall(Template, Goal, List)`, e.g. the
+    /// template `pair(C, P)` turns the answers of `parent(P, C)` into the
+    /// swapped pairs.
+    ///
+    /// The terms are materialized eagerly, in the order
+    /// [`Solver::pull_next_goal`] yields the answers. The variables of the
+    /// template are the ones of the goal, those an answer leaves unbound stay
+    /// variables in the projected term.
+    ///
+    /// ```
+    /// use slg_prolog_solver::{
+    ///     clause::KnowledgeBase, parser::parse_goal, solver::Solver, term::Term,
+    /// };
+    ///
+    /// let kb: KnowledgeBase = "parent(alice, bob).".parse().unwrap();
+    /// let mut solver = Solver::new(&kb);
+    ///
+    /// // parent(?0, ?1) projected to pair(?1, ?0)
+    /// let goal = parse_goal("parent(P, C)").unwrap();
+    /// let template =
+    ///     Term::component("pair", [Term::variable(1), Term::variable(0)]);
+    ///
+    /// let pairs = solver.solve_project(goal, template).unwrap();
+    /// assert_eq!(pairs[0].to_string(), "pair(bob, alice)");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error reported by [`Solver::pull_next_goal`], the
+    /// terms collected until then are discarded.
+    pub fn solve_project(
+        &mut self,
+        goal: Goal,
+        template: Term,
+    ) -> Result<Vec<Term>, SolveError> {
+        Ok(self
+            .solve_instantiated(goal, &template)?
+            .into_iter()
+            .map(|(term, _)| term)
+            .collect())
+    }
+
+    /// Collects every answer of the goal along with the `template` it
+    /// instantiates, which [`Solver::solve_project`] and the other
+    /// projections of the answers are built on.
+    fn solve_instantiated(
+        &mut self,
+        goal: Goal,
+        template: &Term,
+    ) -> Result<Vec<(Term, Substitution)>, SolveError> {
+        Ok(self
+            .solve_all(goal)?
+            .into_iter()
+            .map(|answer| {
+                let mut term = template.clone();
+                answer.apply_term(&mut term);
+
+                (term, answer)
+            })
+            .collect())
     }
 
+    /// Pulls the next answer of the goal described by the `goal_state`.
+    ///
+    /// Returns `Ok(None)` if there are no more answers. The bindings of the
+    /// anonymous variables of the query, see [`Term::anonymous`], are left out
+    /// of the answers.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SolveError::StepLimitExceeded`] if the step budget given in
+    /// [`Solver::with_max_steps`] ran out before the next answer was found,
+    /// [`SolveError::Timeout`] if the deadline passed,
+    /// [`SolveError::TermTooLarge`] if an answer exceeded the bound given in
+    /// [`Solver::set_max_term_size`], [`SolveError::ModeViolation`] if the
+    /// query or one of its subgoals doesn't respect the modes of its
+    /// predicate, [`SolveError::UnstratifiedNegation`] if the query negates a
+    /// goal depending on itself, or [`SolveError::StaleGoalState`] if the
+    /// table of the query has been dropped since.
     pub fn pull_next_goal(
         &mut self,
         goal_state: &mut GoalState,
+    ) -> Result<Option<Substitution>, SolveError> {
+        if let Some(answer) = goal_state.peeked.take() {
+            return Ok(Some(answer));
+        }
+
+        if !self.tables.contains(goal_state.table_id, goal_state.generation) {
+            return Err(SolveError::StaleGoalState);
+        }
+
+        // the subgoals are checked as they're selected, the query has to be
+        // checked upfront
+        let query = self.tables.canonical_goal(goal_state.table_id);
+        if let Some(argument) = self.knowledge_base.mode_violation(query) {
+            return Err(SolveError::ModeViolation(
+                query.predicate.functor(),
+                argument,
+            ));
+        }
+
+        if self.ordered_answers {
+            return self.pull_next_ordered_goal(goal_state);
+        }
+
+        loop {
+            // make sure the answer we're interested is present
+            match self
+                .ensure_answer(goal_state.table_id, goal_state.answer_index)
+            {
+                Ok(EnsureAnswer::AnswerAvailable) => {}
+                Err(error) => {
+                    return SolveError::from_error(error).map_or(Ok(None), Err);
+                }
+            }
+
+            // retrieve the answer and increment the counter for the next pull
+            let substitution = self
+                .get_answer(goal_state.table_id, goal_state.answer_index)
+                .unwrap();
+
+            goal_state.answer_index += 1;
+
+            // answers subsumed by a more general one are skipped
+            if let Some(substitution) = substitution {
+                return Ok(Some(goal_state.project(substitution)));
+            }
+        }
+    }
+
+    /// Pulls the next answer of the goal described by the `goal_state` like
+    /// [`Solver::pull_next_goal`], converting it from a [`Solution`].
+    ///
+    /// For example, `next_solution_as::<Solution>` yields the answers through
+    /// the accessors of [`Solution`] rather than as a raw [`Substitution`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`Solver::pull_next_goal`].
+    pub fn next_solution_as<T: From<Solution>>(
+        &mut self,
+        goal_state: &mut GoalState,
+    ) -> Result<Option<T>, SolveError> {
+        Ok(self
+            .pull_next_goal(goal_state)?
+            .map(|substitution| Solution::from(substitution).into()))
+    }
+
+    /// Pulls the next answer like [`Solver::pull_next_goal`], treating any
+    /// [`SolveError`] as if there were no more answers, like
+    /// [`Result::ok`].
+    ///
+    /// Convenient for solvers without any limit on programs without negation,
+    /// which can't fail. Otherwise, an error is indistinguishable from the end
+    /// of the answers; use [`Solver::pull_next_goal`] to tell them apart.
+    pub fn pull_next_goal_ok(
+        &mut self,
+        goal_state: &mut GoalState,
     ) -> Option<Substitution> {
-        // make sure the answer we're interested is present
-        let Ok(EnsureAnswer::AnswerAvailable) =
-            self.ensure_answer(goal_state.table_id, goal_state.answer_index)
-        else {
-            return None;
-        };
-
-        // retrieve the answer and increment the counter for the next pull
-        let substitution = self
-            .get_answer(goal_state.table_id, goal_state.answer_index)
-            .unwrap();
-
-        goal_state.answer_index += 1;
-
-        Some(uncanonicalize_substitution(
-            substitution,
-            &goal_state.canonical_mapping,
-        ))
+        self.pull_next_goal(goal_state).ok().flatten()
+    }
+
+    /// Computes the next answer of the goal like [`Solver::pull_next_goal`]
+    /// without consuming it: the next pull returns the same answer.
+    ///
+    /// Peeking again before pulling returns the same answer as well, the
+    /// answer is only computed once and yielded once.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`Solver::pull_next_goal`], nothing is buffered
+    /// then.
+    pub fn peek_next_goal<'g>(
+        &mut self,
+        goal_state: &'g mut GoalState,
+    ) -> Result<Option<&'g Substitution>, SolveError> {
+        if goal_state.peeked.is_none() {
+            goal_state.peeked = self.pull_next_goal(goal_state)?;
+        }
+
+        Ok(goal_state.peeked.as_ref())
+    }
+
+    /// Pulls the next answer in ascending order, evaluating the table of the
+    /// query completely first.
+    fn pull_next_ordered_goal(
+        &mut self,
+        goal_state: &mut GoalState,
+    ) -> Result<Option<Substitution>, SolveError> {
+        if goal_state.ordered_answers.is_none() {
+            // resumes where it left off if the step budget ran out before
+            loop {
+                match self
+                    .ensure_answer(goal_state.table_id, goal_state.answer_index)
+                {
+                    Ok(EnsureAnswer::AnswerAvailable) => {
+                        goal_state.answer_index += 1;
+                    }
+                    Err(error) => match SolveError::from_error(error) {
+                        Some(error) => return Err(error),
+                        None => break,
+                    },
+                }
+            }
+
+            // the shortest derivations come first if the strands are
+            // scheduled by their length
+            let by_cost =
+                self.scheduling_policy == SchedulingPolicy::ShortestFirst;
+            let costs = self.tables.get(goal_state.table_id).answer_costs();
+
+            let mut answers = (0..goal_state.answer_index)
+                .filter_map(|index| {
+                    let answer =
+                        self.get_answer(goal_state.table_id, index).unwrap()?;
+
+                    Some((by_cost.then_some(costs[index]), answer))
+                })
+                .map(|(cost, answer)| (cost, goal_state.project(answer)))
+                .collect::<Vec<_>>();
+            answers.sort_unstable_by(|lhs, rhs| rhs.cmp(lhs));
+
+            goal_state.ordered_answers =
+                Some(answers.into_iter().map(|(_, answer)| answer).collect());
+        }
+
+        Ok(goal_state.ordered_answers.as_mut().and_then(Vec::pop))
     }
 }
 
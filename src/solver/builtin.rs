@@ -0,0 +1,350 @@
+//! Contains the evaluation of the built-in predicates.
+//!
+//! Built-in goals aren't resolved against the [`crate::clause::KnowledgeBase`].
+//! Instead, they're evaluated right away whenever a strand selects them, and
+//! the strand either continues with the resulting substitution or is dropped.
+//!
+//! The following built-ins are supported:
+//!
+//! - `</2`, `>/2`, `>=/2`, and `=</2` compare two integers. They fail if either
+//!   argument isn't an integer, including unbound variables.
+//! - `X = Y` unifies its arguments, binding their variables exactly like
+//!   unifying a goal with a clause head would, whereas `X \= Y` succeeds
+//!   without binding anything if they don't unify.
+//! - `findall(Template, Goal, List)` unifies `List` with the instances of
+//!   `Template` for every answer of `Goal`. Unlike the other built-ins, it
+//!   needs the solver to drive `Goal`, see [`as_findall`].
+//! - `\+ Goal` succeeds if `Goal` has no answer, see [`as_negation`].
+//! - `between(Low, High, X)` enumerates the integers from `Low` to `High`, see
+//!   [`as_between`].
+//! - `copy_term(Source, Copy)` unifies `Copy` with `Source` whose variables are
+//!   renamed to fresh ones, see [`expand_copy_term`].
+//! - `length(List, N)` unifies `N` with the length of `List`, or generates the
+//!   lists of length `N`, see [`as_length`].
+//! - `succ(X, Y)` holds if `Y` is `X + 1` for a non-negative integer `X`,
+//!   computing either argument from the other. It fails if neither argument is
+//!   an integer, and `succ(X, 0)` fails since `X` can't be negative.
+//! - `ground(X)` succeeds if `X` contains no variable and `var(X)` if `X` is an
+//!   unbound variable, both under the bindings of the strand at the time
+//!   they're selected. They never bind anything.
+//! - `;(A, B, ...)` and `,(A, B, ...)` prove any, respectively all, of their
+//!   arguments, see [`expand_control`].
+
+use std::collections::HashMap;
+
+use crate::{
+    clause::{Functor, Goal},
+    substitution::Substitution,
+    symbol::Symbol,
+    term::Term,
+};
+
+/// Checks whether the goal refers to a built-in predicate.
+pub(crate) fn is_builtin(goal: &Goal) -> bool {
+    is_builtin_functor(goal.predicate.functor())
+}
+
+/// Checks whether the predicate is a built-in, see [`is_builtin`].
+pub(crate) fn is_builtin_functor(functor: Functor) -> bool {
+    let Functor { name, arity } = functor;
+
+    match name {
+        Symbol::LESS
+        | Symbol::GREATER
+        | Symbol::GREATER_OR_EQUAL
+        | Symbol::LESS_OR_EQUAL
+        | Symbol::UNIFY
+        | Symbol::NOT_UNIFIABLE
+        | Symbol::COPY_TERM
+        | Symbol::LENGTH
+        | Symbol::SUCC => arity == 2,
+
+        Symbol::NEGATION | Symbol::GROUND | Symbol::VAR => arity == 1,
+        Symbol::FINDALL | Symbol::BETWEEN => arity == 3,
+
+        Symbol::DISJUNCTION | Symbol::CONJUNCTION => true,
+
+        _ => false,
+    }
+}
+
+/// Evaluates the built-in goal, the `substitution` must have already been
+/// applied to the goal. `occurs_check` tells whether unifications perform the
+/// occurs check.
+///
+/// Returns the substitution to continue with, or `None` if the goal failed.
+///
+/// # Panics
+///
+/// Panics if the goal isn't a built-in, see [`is_builtin`].
+pub(super) fn evaluate(
+    goal: &Goal,
+    substitution: Substitution,
+    occurs_check: bool,
+) -> Option<Substitution> {
+    let arguments = goal.predicate.arguments.as_slice();
+
+    let unify = |substitution: Substitution| {
+        let [lhs, rhs] = arguments else {
+            return None;
+        };
+
+        if occurs_check {
+            substitution.unify_terms(lhs, rhs)
+        } else {
+            substitution.unify_terms_unchecked(lhs, rhs)
+        }
+    };
+
+    let succeeded = match goal.predicate.name {
+        Symbol::UNIFY => return unify(substitution),
+        Symbol::SUCC => {
+            let [x, y] = arguments else {
+                return None;
+            };
+
+            // compute whichever argument is missing, then check it
+            let (argument, value) = match (x, y) {
+                (Term::Integer(x), _) if *x >= 0 => (y, x.checked_add(1)?),
+                (_, Term::Integer(y)) if *y > 0 => (x, y - 1),
+                _ => return None,
+            };
+
+            return substitution.unify_terms(argument, &Term::Integer(value));
+        }
+        Symbol::NOT_UNIFIABLE => unify(Substitution::default()).is_none(),
+
+        Symbol::GROUND => arguments.iter().all(Term::is_ground),
+        Symbol::VAR => matches!(arguments, [Term::Variable(_)]),
+
+        Symbol::LESS => compare(arguments, |lhs, rhs| lhs < rhs),
+        Symbol::GREATER => compare(arguments, |lhs, rhs| lhs > rhs),
+        Symbol::GREATER_OR_EQUAL => compare(arguments, |lhs, rhs| lhs >= rhs),
+        Symbol::LESS_OR_EQUAL => compare(arguments, |lhs, rhs| lhs <= rhs),
+
+        name => panic!("`{name}` is not a built-in predicate"),
+    };
+
+    succeeded.then_some(substitution)
+}
+
+/// The arguments of a `findall/3` goal.
+pub(super) struct Findall {
+    /// The term instantiated for every answer.
+    pub template: Term,
+
+    /// The goal whose answers are collected, `None` if the given term can't
+    /// be a goal, which fails the `findall/3`.
+    ///
+    /// An atom `p` stands for the proposition `p` and a compound `p(X, Y)`
+    /// for the goal `p(X, Y)`.
+    pub goal: Option<Goal>,
+
+    /// The term unified with the list of instances.
+    pub result: Term,
+}
+
+/// Destructures a `findall/3` goal, returns `None` if the goal isn't one.
+pub(super) fn as_findall(goal: &Goal) -> Option<Findall> {
+    let [template, inner, result] = goal.predicate.arguments.as_slice() else {
+        return None;
+    };
+
+    if goal.predicate.name != Symbol::FINDALL {
+        return None;
+    }
+
+    Some(Findall {
+        template: template.clone(),
+        goal: Goal::from_term(inner),
+        result: result.clone(),
+    })
+}
+
+/// Destructures a `\+ Goal` goal, returns `None` if the goal isn't one.
+///
+/// Otherwise, returns the negated goal, or `Some(None)` if the given term
+/// can't be a goal, which fails the negation. The negated goal is evaluated
+/// completely before the negation succeeds, therefore, a goal depending on
+/// the table containing the negation can't be negated, see
+/// [`crate::solver::SolveError::UnstratifiedNegation`].
+pub(super) fn as_negation(goal: &Goal) -> Option<Option<Goal>> {
+    let [inner] = goal.predicate.arguments.as_slice() else {
+        return None;
+    };
+
+    if goal.predicate.name != Symbol::NEGATION {
+        return None;
+    }
+
+    Some(Goal::from_term(inner))
+}
+
+/// The arguments of a `between/3` goal whose bounds are integers.
+pub(super) struct Between {
+    /// The smallest integer of the range.
+    pub low: i64,
+
+    /// The largest integer of the range, the range is empty if it's smaller
+    /// than [`Self::low`].
+    pub high: i64,
+
+    /// The term unified with each integer of the range in turn.
+    pub value: Term,
+}
+
+/// Destructures a `between(Low, High, X)` goal, returns `None` if the goal
+/// isn't one.
+///
+/// Otherwise, returns the range, or `Some(None)` if either bound isn't an
+/// integer, which fails the goal. Unlike the other built-ins, `between/3`
+/// may succeed more than once: the solver binds `X` to `Low` and defers the
+/// rest of the range as `between(Low + 1, High, X)`, so the integers are only
+/// generated as the answers are pulled.
+pub(super) fn as_between(goal: &Goal) -> Option<Option<Between>> {
+    let [low, high, value] = goal.predicate.arguments.as_slice() else {
+        return None;
+    };
+
+    if goal.predicate.name != Symbol::BETWEEN {
+        return None;
+    }
+
+    let (Term::Integer(low), Term::Integer(high)) = (low, high) else {
+        return Some(None);
+    };
+
+    Some(Some(Between { low: *low, high: *high, value: value.clone() }))
+}
+
+/// The arguments of a `length/2` goal whose first argument is a list.
+pub(super) struct Length {
+    /// The number of items the list starts with.
+    pub items: usize,
+
+    /// The variable ending a partial list, e.g. `T` in `[a, b|T]`, `None`
+    /// for a proper list.
+    pub tail: Option<usize>,
+
+    /// The term unified with the length of the list.
+    pub length: Term,
+}
+
+/// Destructures a `length(List, N)` goal, returns `None` if the goal isn't
+/// one.
+///
+/// Otherwise, returns the shape of the list, or `Some(None)` if `List` is
+/// neither a proper nor a partial list, or `N` is neither an integer nor a
+/// variable, which fails the goal. A proper list has a single length,
+/// whereas a partial list can be closed at any length: the solver binds its
+/// tail to the lists of fresh variables making up `N` items, or, if `N` is
+/// unbound, closes the list right away and defers growing it by one item
+/// like `between/3` does, so that the lengths are only generated as the
+/// answers are pulled.
+pub(super) fn as_length(goal: &Goal) -> Option<Option<Length>> {
+    let [list, length] = goal.predicate.arguments.as_slice() else {
+        return None;
+    };
+
+    if goal.predicate.name != Symbol::LENGTH {
+        return None;
+    }
+
+    if !matches!(length, Term::Integer(_) | Term::Variable(_)) {
+        return Some(None);
+    }
+
+    let mut items = 0;
+    let mut list = list;
+
+    let tail = loop {
+        match list {
+            Term::Atom(Symbol::NIL) => break None,
+            Term::Variable(variable) => break Some(*variable),
+            Term::Compound(Symbol::CONS, arguments) if arguments.len() == 2 => {
+                items += 1;
+                list = &arguments[1];
+            }
+            _ => return Some(None),
+        }
+    };
+
+    Some(Some(Length { items, tail, length: length.clone() }))
+}
+
+/// Expands a `copy_term(Source, Copy)` goal into the unification `Copy =
+/// Source'`, `Source'` being `Source` with its variables consistently renamed
+/// to fresh ones starting from `counter`; returns `None` if the goal isn't
+/// one.
+///
+/// `counter` must be past every variable in use, so that the copy shares no
+/// variable with the source, e.g. `copy_term(f(X, Y, X), C)` binds `C` to
+/// `f(A, B, A)` for fresh `A` and `B`.
+pub(super) fn expand_copy_term(
+    goal: &Goal,
+    mut counter: usize,
+) -> Option<Goal> {
+    let [source, copy] = goal.predicate.arguments.as_slice() else {
+        return None;
+    };
+
+    if goal.predicate.name != Symbol::COPY_TERM {
+        return None;
+    }
+
+    let mut renamed = source.clone();
+    renamed.canonicalize_with_mapping(&mut counter, &mut HashMap::new());
+
+    Some(Goal::new(Symbol::UNIFY, [copy.clone(), renamed]))
+}
+
+/// Expands a disjunction `;(A, B, ...)` or a conjunction `,(A, B, ...)` into
+/// the alternative sequences of goals it stands for, returns `None` if the
+/// goal is neither.
+///
+/// A disjunction yields a sequence per branch whereas a conjunction yields a
+/// single sequence. Branches containing a term that isn't a goal are dropped
+/// since they can never succeed.
+pub(super) fn expand_control(goal: &Goal) -> Option<Vec<Vec<Goal>>> {
+    let arguments = &goal.predicate.arguments;
+
+    match goal.predicate.name {
+        Symbol::DISJUNCTION => {
+            Some(arguments.iter().filter_map(conjuncts).collect())
+        }
+        Symbol::CONJUNCTION => Some(
+            conjuncts(&Term::Compound(
+                Symbol::CONJUNCTION,
+                arguments.clone().into(),
+            ))
+            .into_iter()
+            .collect(),
+        ),
+        _ => None,
+    }
+}
+
+/// Flattens a term standing for a conjunction of goals, returns `None` if any
+/// of the conjuncts isn't a goal.
+fn conjuncts(term: &Term) -> Option<Vec<Goal>> {
+    match term {
+        Term::Compound(name, arguments) if *name == Symbol::CONJUNCTION => {
+            let mut goals = Vec::new();
+
+            for argument in arguments {
+                goals.extend(conjuncts(argument)?);
+            }
+
+            Some(goals)
+        }
+        term => Goal::from_term(term).map(|goal| vec![goal]),
+    }
+}
+
+/// Compares two integer arguments, non-integer arguments fail the comparison.
+fn compare(arguments: &[Term], comparison: impl Fn(i64, i64) -> bool) -> bool {
+    match arguments {
+        [Term::Integer(lhs), Term::Integer(rhs)] => comparison(*lhs, *rhs),
+        _ => false,
+    }
+}
@@ -0,0 +1,180 @@
+//! Contains the support for dynamic predicates, whose facts may be added
+//! while solving, and for clauses added to or removed from the knowledge base
+//! of a solver.
+//!
+//! Changing the clauses of a predicate changes the answers of every goal that
+//! may call it, so the tables of these goals are removed and re-evaluated
+//! from scratch the next time they're queried. Clauses can only be changed
+//! between calls to [`Solver::pull_next_goal`], when no table is being
+//! evaluated.
+//!
+//! Dependencies aren't recorded while solving. Instead, the predicates that
+//! may depend on the changed one are found from the program itself: a
+//! predicate depends on every predicate called in the body of its clauses,
+//! transitively, see `Solver::dependents`. A table is invalidated if its goal
+//! calls any of them. This over-approximates the tables actually affected,
+//! e.g. `ancestor(dan, ?0)` is invalidated by any new `parent/2` fact, but
+//! never misses one.
+
+use std::{collections::HashSet, ops::ControlFlow};
+
+use crate::{
+    clause::{Call, Clause, Functor, KnowledgeBase, Predicate},
+    solver::{AssertError, Solver},
+    term::Term,
+};
+
+impl Solver<'_> {
+    /// Declares the predicate, e.g. `("parent", 2)`, as dynamic, allowing
+    /// facts to be added to it with [`Solver::assertz`]. Declaring an alias,
+    /// see [`KnowledgeBase::alias_predicate`], declares the predicate it
+    /// resolves against.
+    pub fn declare_dynamic(&mut self, functor: impl Into<Functor>) {
+        let functor = self.knowledge_base.resolve_alias(functor.into());
+        self.dynamic_predicates.insert(functor);
+    }
+
+    /// Adds the ground fact after every other clause of its predicate, like
+    /// Prolog's `assertz/1`.
+    ///
+    /// The tables of every goal that may call the predicate, directly or
+    /// not, are invalidated: their memoized answers are discarded, and the
+    /// [`GoalState`]s created for them must not be used anymore. Querying the
+    /// goals again re-evaluates them with the new fact. A fact of an alias,
+    /// see [`KnowledgeBase::alias_predicate`], is added to the predicate the
+    /// alias resolves against.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AssertError::NotDynamic`] if the predicate hasn't been
+    /// declared with [`Solver::declare_dynamic`], and
+    /// [`AssertError::NotGround`] if the fact contains variables.
+    ///
+    /// [`GoalState`]: crate::solver::GoalState
+    pub fn assertz(&mut self, mut fact: Predicate) -> Result<(), AssertError> {
+        let functor = self.knowledge_base.resolve_alias(fact.functor());
+        fact.name = functor.name;
+
+        if !self.dynamic_predicates.contains(&functor) {
+            return Err(AssertError::NotDynamic(functor));
+        }
+
+        if !fact.arguments.iter().all(Term::is_ground) {
+            return Err(AssertError::NotGround(fact));
+        }
+
+        let dependents = self.dependents(functor);
+        let knowledge_base = &self.knowledge_base;
+        self.tables.remove_where(|goal| {
+            calls_any(knowledge_base, &goal.to_term(), &dependents)
+        });
+
+        self.asserted_facts.add_clause(Clause::fact(fact));
+
+        Ok(())
+    }
+
+    /// Adds the clause after every other clause of its predicate in the
+    /// knowledge base of the solver.
+    ///
+    /// Like [`Solver::assertz`], the tables of every goal that may call the
+    /// predicate, directly or not, are invalidated, and the [`GoalState`]s
+    /// created for them must not be used anymore. Unlike it, the clause may
+    /// be a rule and its predicate needn't be dynamic.
+    ///
+    /// A solver created by [`Solver::owning`] adds the clause to its own
+    /// knowledge base. Any other solver first clones the knowledge base it
+    /// borrows, which is left untouched.
+    ///
+    /// [`GoalState`]: crate::solver::GoalState
+    pub fn add_clause(&mut self, clause: Clause) {
+        let functor = self.knowledge_base.resolve_alias(clause.head.functor());
+
+        self.knowledge_base.to_mut().add_clause(clause);
+
+        let dependents = self.dependents(functor);
+        let knowledge_base = &self.knowledge_base;
+        self.tables.remove_where(|goal| {
+            calls_any(knowledge_base, &goal.to_term(), &dependents)
+        });
+    }
+
+    /// Removes the clause from the knowledge base of the solver, see
+    /// [`KnowledgeBase::remove_clause`], returning whether it was found.
+    ///
+    /// Like [`Solver::add_clause`], the tables of every goal that may call the
+    /// predicate are invalidated, and a solver borrowing its knowledge base
+    /// first clones it. The facts added by [`Solver::assertz`] aren't
+    /// removed.
+    pub fn remove_clause(&mut self, clause: &Clause) -> bool {
+        if !self.knowledge_base.contains_clause(clause) {
+            return false;
+        }
+
+        self.knowledge_base.to_mut().remove_clause(clause);
+
+        let dependents = self.dependents(
+            self.knowledge_base.resolve_alias(clause.head.functor()),
+        );
+        let knowledge_base = &self.knowledge_base;
+        self.tables.remove_where(|goal| {
+            calls_any(knowledge_base, &goal.to_term(), &dependents)
+        });
+
+        true
+    }
+
+    /// Returns the predicates whose answers may depend on the given one,
+    /// including itself.
+    fn dependents(&self, functor: Functor) -> HashSet<Functor> {
+        let mut dependents = HashSet::from([functor]);
+
+        // the asserted facts have no body, only the program can add more
+        loop {
+            let mut changed = false;
+
+            for clause in self.knowledge_base.clauses_iter() {
+                let head = clause.head.functor();
+
+                if !dependents.contains(&head)
+                    && clause.body.iter().any(|goal| {
+                        calls_any(
+                            &self.knowledge_base,
+                            &goal.to_term(),
+                            &dependents,
+                        )
+                    })
+                {
+                    dependents.insert(head);
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                return dependents;
+            }
+        }
+    }
+}
+
+/// Checks whether proving the goal `term` may call any of the predicates,
+/// see [`KnowledgeBase::try_visit_calls`].
+///
+/// A variable may stand for any goal once bound, e.g. the goal of a
+/// `findall/3`, so it's assumed to call every predicate.
+fn calls_any(
+    knowledge_base: &KnowledgeBase,
+    term: &Term,
+    functors: &HashSet<Functor>,
+) -> bool {
+    knowledge_base
+        .try_visit_calls(term, &mut |call| match call {
+            Call::Predicate(dependency)
+                if !functors.contains(&dependency.functor) =>
+            {
+                ControlFlow::Continue(())
+            }
+            Call::Predicate(_) | Call::Unknown => ControlFlow::Break(()),
+        })
+        .is_break()
+}
@@ -0,0 +1,782 @@
+//! Contains the reconstruction of the proof of an answer, see
+//! [`Solver::explain`], and of the reasons a goal has no answer, see
+//! [`Solver::explain_failure`].
+//!
+//! Tables only memoize the answers of their goals, not how they were derived.
+//! Rather than recording the clause and the subgoal answers behind every
+//! answer while solving, which every query would pay for, the proof is
+//! rebuilt on demand: the answer is applied to the goal, and a clause whose
+//! body goals all have answers agreeing with it is searched for, recursively.
+//! The answers of the body goals come from the tables, so they're cheap to
+//! look up once the query has been solved. Failures are traced the same way,
+//! looking for the first goal of every clause body past which the body has
+//! no answer.
+
+use std::{collections::HashMap, fmt};
+
+use crate::{
+    canonicalize::uncanonicalize_substitution_with_counter,
+    clause::{Clause, Goal},
+    solver::{SolveError, Solver, builtin, table::first_fresh_variable},
+    substitution::Substitution,
+    symbol::Symbol,
+};
+
+/// The derivation of an answer, see [`Solver::explain`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProofTree {
+    /// The goal proven, with the answer applied to it.
+    pub goal: Goal,
+
+    /// The clause resolving the goal as it's written in the knowledge base,
+    /// `None` for the built-ins and the cut, which aren't resolved against
+    /// any clause.
+    pub clause: Option<Clause>,
+
+    /// The proofs of the goals of the clause body, in order. A conjunction,
+    /// or a disjunction, is replaced by the goals of its branch proven.
+    pub children: Vec<ProofTree>,
+}
+
+/// Prints the proven goals, one per line, each one indented below the goal
+/// it helps proving.
+impl fmt::Display for ProofTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut pending = vec![(self, 0)];
+
+        while let Some((tree, depth)) = pending.pop() {
+            writeln!(f, "{:indent$}{}", "", tree.goal, indent = depth * 2)?;
+
+            pending.extend(
+                tree.children.iter().rev().map(|child| (child, depth + 1)),
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// The reason a goal has no answer, see [`Solver::explain_failure`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailureTrace {
+    /// The goal without any answer.
+    pub goal: Goal,
+
+    /// Why the goal has no answer.
+    pub reason: FailureReason,
+}
+
+/// Why a goal has no answer, see [`FailureTrace`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FailureReason {
+    /// No clause head unifies with the goal.
+    NoMatchingClause,
+
+    /// The goal is a built-in that fails, e.g. `1 > 2`.
+    Builtin,
+
+    /// The goal is being traced further up, i.e. it could only be proven
+    /// through itself.
+    Cyclic,
+
+    /// Every clause whose head unifies with the goal fails somewhere in its
+    /// body. The clauses that only fail because a cut discards them aren't
+    /// listed.
+    Clauses(Vec<ClauseFailure>),
+}
+
+/// Where the body of a clause fails, see [`FailureReason::Clauses`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClauseFailure {
+    /// The clause as it's written in the knowledge base.
+    pub clause: Clause,
+
+    /// The index of the first goal of the body such that the goals up to it
+    /// have no answer together.
+    pub subgoal_index: usize,
+
+    /// Why the goal at [`Self::subgoal_index`], bound by the clause head, has
+    /// no answer, `None` if it has answers but none agreeing with the goals
+    /// before it, or if it's a built-in preceded by other goals.
+    pub cause: Option<Box<FailureTrace>>,
+}
+
+/// Prints the failed goals, one per line, the clauses failing below the goal
+/// they fail to prove and the causes of their failure further below.
+impl fmt::Display for FailureTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut pending = vec![(Line::Goal(self), 0)];
+
+        while let Some((line, depth)) = pending.pop() {
+            write!(f, "{:indent$}", "", indent = depth * 2)?;
+
+            match line {
+                Line::Goal(trace) => {
+                    write!(f, "{}: ", trace.goal)?;
+
+                    let failures = match &trace.reason {
+                        FailureReason::NoMatchingClause => {
+                            writeln!(f, "no clause head unifies")?;
+                            continue;
+                        }
+                        FailureReason::Builtin => {
+                            writeln!(f, "the built-in fails")?;
+                            continue;
+                        }
+                        FailureReason::Cyclic => {
+                            writeln!(f, "only provable through itself")?;
+                            continue;
+                        }
+                        FailureReason::Clauses(failures) => failures,
+                    };
+
+                    writeln!(f, "every matching clause fails")?;
+
+                    pending.extend(
+                        failures
+                            .iter()
+                            .rev()
+                            .map(|failure| (Line::Clause(failure), depth + 1)),
+                    );
+                }
+
+                Line::Clause(failure) => {
+                    write!(
+                        f,
+                        "`{}` fails at `{}`",
+                        failure.clause,
+                        failure.clause.body[failure.subgoal_index],
+                    )?;
+
+                    match &failure.cause {
+                        Some(cause) => {
+                            writeln!(f)?;
+                            pending.push((Line::Goal(cause), depth + 1));
+                        }
+                        None => writeln!(f, " given the goals before it")?,
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Drops the causes with an explicit stack, a long chain of goals failing
+/// through one another doesn't grow the native stack.
+impl Drop for FailureTrace {
+    fn drop(&mut self) {
+        let mut pending = Vec::new();
+        self.take_causes(&mut pending);
+
+        while let Some(mut cause) = pending.pop() {
+            cause.take_causes(&mut pending);
+        }
+    }
+}
+
+impl FailureTrace {
+    /// Moves the causes of the failures of the clauses to `pending`.
+    fn take_causes(&mut self, pending: &mut Vec<FailureTrace>) {
+        if let FailureReason::Clauses(failures) = &mut self.reason {
+            pending.extend(
+                failures
+                    .iter_mut()
+                    .filter_map(|failure| failure.cause.take())
+                    .map(|cause| *cause),
+            );
+        }
+    }
+}
+
+/// A line of the [`FailureTrace`] being printed.
+enum Line<'a> {
+    Goal(&'a FailureTrace),
+    Clause(&'a ClauseFailure),
+}
+
+impl Solver<'_> {
+    /// Reconstructs a proof of the `answer` to the `goal`, as returned by
+    /// [`Solver::pull_next_goal`], naming the clause resolving every subgoal.
+    ///
+    /// The clauses are tried in order along with the answers of their body
+    /// goals, the first combination proving the answer is returned. A goal
+    /// isn't explained by itself further down its own proof, so the proof of
+    /// a recursive predicate is always finite. Cuts are read as `true`, which
+    /// may pick a clause the cut actually discarded, and the goals of
+    /// conjunctions and disjunctions are proven in their place.
+    ///
+    /// Returns `None` if the `answer` isn't an answer to the `goal`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`Solver::pull_next_goal`] raised while solving
+    /// the subgoals.
+    pub fn explain(
+        &mut self,
+        mut goal: Goal,
+        answer: &Substitution,
+    ) -> Result<Option<ProofTree>, SolveError> {
+        answer
+            .restrict(&answer.mapping.keys().copied().collect())
+            .apply_goal(&mut goal);
+
+        self.prove(goal)
+    }
+
+    /// Traces why the goal has no answer, naming for every clause whose head
+    /// unifies with the goal the first goal of its body past which the body
+    /// has no answer, and why that goal has none, recursively.
+    ///
+    /// The trace is partial: a goal having answers on its own, but none
+    /// agreeing with the bindings of the goals before it, isn't traced any
+    /// further, see [`ClauseFailure::cause`].
+    ///
+    /// Returns `None` if the goal has an answer.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`Solver::pull_next_goal`] raised while solving
+    /// the goal and its subgoals.
+    pub fn explain_failure(
+        &mut self,
+        goal: Goal,
+    ) -> Result<Option<FailureTrace>, SolveError> {
+        if self.has_solution(goal.clone())? {
+            return Ok(None);
+        }
+
+        self.trace_failure(goal).map(Some)
+    }
+
+    /// Traces why the goal, known to have no answer, fails.
+    ///
+    /// Like [`Solver::prove`], the trace runs on a stack of [`Trace`]s rather
+    /// than recursively.
+    fn trace_failure(
+        &mut self,
+        goal: Goal,
+    ) -> Result<FailureTrace, SolveError> {
+        // the canonical goals being traced, one per trace on the stack
+        let mut path = Vec::new();
+        let mut traces = Vec::new();
+
+        let mut returned = match self.open_trace(goal, &mut path) {
+            Ok(trace) => {
+                traces.push(trace);
+                None
+            }
+            Err(trace) => return Ok(trace),
+        };
+
+        while let Some(trace) = traces.last_mut() {
+            match self.advance_trace(trace, returned.take())? {
+                TraceStep::Trace(goal) => {
+                    match self.open_trace(goal, &mut path) {
+                        Ok(trace) => traces.push(trace),
+                        Err(trace) => returned = Some(trace),
+                    }
+                }
+                TraceStep::Return(trace) => {
+                    traces.pop();
+                    path.pop();
+
+                    if traces.is_empty() {
+                        return Ok(trace);
+                    }
+
+                    returned = Some(trace);
+                }
+            }
+        }
+
+        unreachable!("the last trace returns")
+    }
+
+    /// Starts tracing the failure of the goal through its clauses, or returns
+    /// the trace right away for the cut, the built-ins, and the goals being
+    /// traced further up.
+    fn open_trace(
+        &self,
+        goal: Goal,
+        path: &mut Vec<Goal>,
+    ) -> Result<Trace, FailureTrace> {
+        if builtin::is_builtin(&goal) || goal.is_cut() {
+            return Err(FailureTrace { goal, reason: FailureReason::Builtin });
+        }
+
+        let mut canonical = goal.clone();
+        canonical.canonicalize();
+
+        if path.contains(&canonical) {
+            return Err(FailureTrace { goal, reason: FailureReason::Cyclic });
+        }
+
+        path.push(canonical);
+
+        let clauses = self
+            .candidate_clauses(&goal.predicate)
+            .into_iter()
+            .map(|(clause, _)| clause.clone())
+            .collect::<Vec<_>>();
+
+        Ok(Trace {
+            goal,
+            clauses: clauses.into_iter(),
+            matched: false,
+            failures: Vec::new(),
+            waiting: None,
+        })
+    }
+
+    /// Carries the trace on until it needs the trace of another goal or
+    /// finishes, `returned` being the trace of the goal it asked for last.
+    fn advance_trace(
+        &mut self,
+        trace: &mut Trace,
+        returned: Option<FailureTrace>,
+    ) -> Result<TraceStep, SolveError> {
+        if let Some(cause) = returned {
+            let (clause, subgoal_index) = trace.waiting.take().unwrap();
+
+            trace.failures.push(ClauseFailure {
+                clause,
+                subgoal_index,
+                cause: Some(Box::new(cause)),
+            });
+        }
+
+        for clause in trace.clauses.by_ref() {
+            let Some((mut body, substitution, _)) =
+                self.resolve(&trace.goal, &clause)
+            else {
+                continue;
+            };
+
+            trace.matched = true;
+
+            let substitution = substitution
+                .restrict(&substitution.mapping.keys().copied().collect());
+            for goal in &mut body {
+                substitution.apply_goal(goal);
+            }
+
+            // the shortest prefix of the body without any answer
+            let mut failing = None;
+            for index in 0..body.len() {
+                let prefix = Goal::new(
+                    Symbol::CONJUNCTION,
+                    body[..=index].iter().map(Goal::to_term),
+                );
+
+                if !self.has_solution(prefix)? {
+                    failing = Some(index);
+                    break;
+                }
+            }
+
+            // the clause only fails through a cut
+            let Some(subgoal_index) = failing else {
+                continue;
+            };
+
+            let subgoal = body.swap_remove(subgoal_index);
+            // a built-in usually needs the bindings of the goals before it,
+            // e.g. `A > B`, it fails on its own for lack of them
+            if (subgoal_index > 0 && builtin::is_builtin(&subgoal))
+                || self.has_solution(subgoal.clone())?
+            {
+                trace.failures.push(ClauseFailure {
+                    clause,
+                    subgoal_index,
+                    cause: None,
+                });
+            } else {
+                trace.waiting = Some((clause, subgoal_index));
+
+                return Ok(TraceStep::Trace(subgoal));
+            }
+        }
+
+        let reason = if trace.matched {
+            FailureReason::Clauses(std::mem::take(&mut trace.failures))
+        } else {
+            FailureReason::NoMatchingClause
+        };
+
+        Ok(TraceStep::Return(FailureTrace { goal: trace.goal.clone(), reason }))
+    }
+
+    /// Searches a proof of the instantiated goal.
+    ///
+    /// The search runs on a stack of [`Proof`]s rather than recursively, a
+    /// long chain of goals proven through one another doesn't grow the native
+    /// stack.
+    fn prove(&mut self, goal: Goal) -> Result<Option<ProofTree>, SolveError> {
+        // the canonical goals being proven, one per proof on the stack
+        let mut path = Vec::new();
+        let mut proofs = Vec::new();
+
+        let mut returned = match self.open_proof(goal, &mut path)? {
+            Ok(proof) => {
+                proofs.push(proof);
+                None
+            }
+            Err(tree) => return Ok(tree),
+        };
+
+        while let Some(proof) = proofs.last_mut() {
+            match self.advance_proof(proof, returned.take())? {
+                Advance::Prove(goal) => {
+                    match self.open_proof(goal, &mut path)? {
+                        Ok(proof) => proofs.push(proof),
+                        Err(tree) => returned = Some(tree),
+                    }
+                }
+                Advance::Return(tree) => {
+                    proofs.pop();
+                    path.pop();
+
+                    if proofs.is_empty() {
+                        return Ok(tree);
+                    }
+
+                    returned = Some(tree);
+                }
+            }
+        }
+
+        unreachable!("the last proof returns")
+    }
+
+    /// Starts the proof of the instantiated goal by its clauses, or returns
+    /// the outcome right away for the cut, the built-ins, and the goals being
+    /// proven further up.
+    fn open_proof(
+        &mut self,
+        goal: Goal,
+        path: &mut Vec<Goal>,
+    ) -> Result<Result<Proof, Option<ProofTree>>, SolveError> {
+        if goal.is_cut() {
+            return Ok(Err(Some(ProofTree::leaf(goal))));
+        }
+
+        if builtin::is_builtin(&goal) {
+            let proven = self.has_solution(goal.clone())?;
+
+            return Ok(Err(proven.then(|| ProofTree::leaf(goal))));
+        }
+
+        let mut canonical = goal.clone();
+        canonical.canonicalize();
+
+        if path.contains(&canonical) {
+            return Ok(Err(None));
+        }
+
+        path.push(canonical);
+
+        let clauses = self
+            .candidate_clauses(&goal.predicate)
+            .into_iter()
+            .map(|(clause, _)| clause.clone())
+            .collect::<Vec<_>>();
+
+        Ok(Ok(Proof { goal, clauses: clauses.into_iter(), body: None }))
+    }
+
+    /// Carries the proof on until it needs the proof of another goal or
+    /// finishes, `returned` being the proof of the goal it asked for last.
+    fn advance_proof(
+        &mut self,
+        proof: &mut Proof,
+        returned: Option<Option<ProofTree>>,
+    ) -> Result<Advance, SolveError> {
+        // the goal asked for is proven, the goals after it are next;
+        // otherwise the next choice is tried below
+        if let Some(Some(tree)) = returned {
+            let body = proof.body.as_mut().unwrap();
+            let level = body.levels.last_mut().unwrap();
+            level.tree = Some(tree);
+
+            let goals = level.rest.clone();
+            let substitution = level.bound.clone();
+            if let Some(tree) = self.descend(proof, goals, substitution)? {
+                return Ok(Advance::Return(Some(tree)));
+            }
+        }
+
+        loop {
+            let Some(body) = &mut proof.body else {
+                let Some(clause) = proof.clauses.next() else {
+                    return Ok(Advance::Return(None));
+                };
+
+                let Some((goals, substitution, counter)) =
+                    self.resolve(&proof.goal, &clause)
+                else {
+                    continue;
+                };
+
+                proof.body = Some(Body { clause, levels: Vec::new(), counter });
+
+                if let Some(tree) = self.descend(proof, goals, substitution)? {
+                    return Ok(Advance::Return(Some(tree)));
+                }
+
+                continue;
+            };
+
+            let Some(level) = body.levels.last_mut() else {
+                proof.body = None;
+                continue;
+            };
+
+            let Some(choice) = level.choices.next() else {
+                body.levels.pop();
+                continue;
+            };
+
+            let (tree, goals) = match choice {
+                Choice::Cut => (
+                    Some(ProofTree::leaf(level.goal.clone())),
+                    level.rest.clone(),
+                ),
+                Choice::Branch(mut goals) => {
+                    goals.extend(level.rest.iter().cloned());
+                    (None, goals)
+                }
+                Choice::Answer(answer) => {
+                    // the variables the answer leaves unbound are renamed
+                    // apart
+                    let answer = uncanonicalize_substitution_with_counter(
+                        &answer,
+                        &level.canonical_mapping,
+                        body.counter,
+                    );
+                    body.counter =
+                        body.counter.max(first_fresh_variable(&answer, []));
+
+                    let mut instance = level.goal.clone();
+                    answer.apply_goal(&mut instance);
+
+                    level.tree = None;
+                    level.bound =
+                        level.substitution.clone().compose_unchecked(answer);
+
+                    return Ok(Advance::Prove(instance));
+                }
+            };
+
+            level.tree = tree;
+            let substitution = level.substitution.clone();
+            if let Some(tree) = self.descend(proof, goals, substitution)? {
+                return Ok(Advance::Return(Some(tree)));
+            }
+        }
+    }
+
+    /// Renames the clause apart from the goal and unifies their heads,
+    /// returns the body goals, the unifier, and a variable past every one in
+    /// use, or `None` if the heads don't unify.
+    fn resolve(
+        &self,
+        goal: &Goal,
+        clause: &Clause,
+    ) -> Option<(Vec<Goal>, Substitution, usize)> {
+        let mut renamed = clause.clone();
+        renamed.canonicalize_with_counter(first_fresh_variable(
+            &Substitution::default(),
+            &goal.predicate.arguments,
+        ));
+        renamed.head.name = goal.predicate.name;
+
+        let substitution = if self.occurs_check {
+            Substitution::default()
+                .unify_predicate(&goal.predicate, &renamed.head)
+        } else {
+            Substitution::default()
+                .unify_predicate_unchecked(&goal.predicate, &renamed.head)
+        }?;
+
+        let counter = first_fresh_variable(
+            &substitution,
+            renamed
+                .body
+                .iter()
+                .chain([goal])
+                .flat_map(|goal| &goal.predicate.arguments),
+        );
+
+        Some((renamed.body, substitution, counter))
+    }
+
+    /// Moves on to the first of the remaining body `goals`, listing the ways
+    /// it can be proven given the bindings of the goals before it, returns
+    /// the finished proof if no goal remains.
+    fn descend(
+        &mut self,
+        proof: &mut Proof,
+        goals: Vec<Goal>,
+        substitution: Substitution,
+    ) -> Result<Option<ProofTree>, SolveError> {
+        let body = proof.body.as_mut().unwrap();
+
+        let mut goals = goals.into_iter();
+        let Some(mut goal) = goals.next() else {
+            // the proof is done, the levels aren't needed anymore
+            let children =
+                body.levels.iter_mut().filter_map(|level| level.tree.take());
+
+            return Ok(Some(ProofTree {
+                goal: proof.goal.clone(),
+                clause: Some(body.clause.clone()),
+                children: children.collect(),
+            }));
+        };
+
+        substitution
+            .restrict(&substitution.mapping.keys().copied().collect())
+            .apply_goal(&mut goal);
+
+        // the control constructs are proven in place, tabling them on their
+        // own would cut the choices of their own table only
+        let mut canonical_mapping = Default::default();
+        let choices = if goal.is_cut() {
+            vec![Choice::Cut]
+        } else if let Some(branches) = builtin::expand_control(&goal) {
+            branches.into_iter().map(Choice::Branch).collect()
+        } else {
+            let mut goal_state = self.create_goal_state(goal.clone());
+            while self.pull_next_goal(&mut goal_state)?.is_some() {}
+
+            canonical_mapping = goal_state.canonical_mapping.clone();
+            self.table(&goal_state)
+                .answers()
+                .iter()
+                .cloned()
+                .map(Choice::Answer)
+                .collect()
+        };
+
+        body.levels.push(Level {
+            goal,
+            rest: goals.collect(),
+            bound: substitution.clone(),
+            substitution,
+            canonical_mapping,
+            choices: choices.into_iter(),
+            tree: None,
+        });
+
+        Ok(None)
+    }
+}
+
+impl ProofTree {
+    /// The proof of a goal not resolved against any clause.
+    fn leaf(goal: Goal) -> Self {
+        Self { goal, clause: None, children: Vec::new() }
+    }
+}
+
+/// What [`Solver::advance_proof`] needs next.
+enum Advance {
+    /// The proof of the instantiated goal, `None` if it has none.
+    Prove(Goal),
+
+    /// Nothing, the proof is done, `None` if the goal has no proof.
+    Return(Option<ProofTree>),
+}
+
+/// The search of a proof of a goal, trying its clauses in order.
+struct Proof {
+    /// The goal to prove, instantiated.
+    goal: Goal,
+
+    /// The clauses not tried yet.
+    clauses: std::vec::IntoIter<Clause>,
+
+    /// The body of the clause being tried, if any.
+    body: Option<Body>,
+}
+
+/// The search of a proof of the body goals of a clause.
+struct Body {
+    /// The clause as it's written in the knowledge base.
+    clause: Clause,
+
+    /// The body goals proven so far, along with the one being proven.
+    levels: Vec<Level>,
+
+    /// A variable past every one in use.
+    counter: usize,
+}
+
+/// A body goal and the ways it can be proven, tried one after another.
+struct Level {
+    /// The goal with the bindings of the goals before it applied.
+    goal: Goal,
+
+    /// The body goals after it.
+    rest: Vec<Goal>,
+
+    /// The bindings of the goals before it.
+    substitution: Substitution,
+
+    /// The bindings of the goals up to it, given the current choice.
+    bound: Substitution,
+
+    /// The mapping of the goal to its canonical form, the answers are
+    /// expressed in.
+    canonical_mapping: HashMap<usize, usize>,
+
+    /// The choices not tried yet.
+    choices: std::vec::IntoIter<Choice>,
+
+    /// The proof of the goal by the current choice, `None` for a branch of a
+    /// control construct whose goals are proven as body goals of their own.
+    tree: Option<ProofTree>,
+}
+
+/// A way a body goal can be proven.
+enum Choice {
+    /// The goal is a cut, read as `true`.
+    Cut,
+
+    /// The goal is a conjunction or a disjunction, proven by the goals of
+    /// this branch.
+    Branch(Vec<Goal>),
+
+    /// An answer of the goal's table, in canonical form.
+    Answer(Substitution),
+}
+
+/// What [`Solver::advance_trace`] needs next.
+enum TraceStep {
+    /// The trace of the failure of the instantiated goal.
+    Trace(Goal),
+
+    /// Nothing, the trace is done.
+    Return(FailureTrace),
+}
+
+/// The trace of the failure of a goal, going through its clauses in order.
+struct Trace {
+    /// The goal without any answer, instantiated.
+    goal: Goal,
+
+    /// The clauses not tried yet.
+    clauses: std::vec::IntoIter<Clause>,
+
+    /// Whether the head of any clause tried so far unifies with the goal.
+    matched: bool,
+
+    /// The clauses tried so far failing somewhere in their body.
+    failures: Vec<ClauseFailure>,
+
+    /// The clause whose failing body goal is being traced, along with the
+    /// index of the goal.
+    waiting: Option<(Clause, usize)>,
+}
@@ -1,20 +1,25 @@
-use std::ops::Index;
+use std::{collections::HashMap, ops::Index};
 
 use crate::{arena::ID, solver::table::Table};
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct Stack {
     stack: Vec<Entry>,
+
+    /// The position of every table on the stack, so that checking whether a
+    /// table is active doesn't depend on the depth of the stack.
+    positions: HashMap<ID<Table>, usize>,
     counter: usize,
 }
 
 impl Stack {
     pub fn is_active(&self, table: ID<Table>) -> Option<usize> {
-        self.stack.iter().position(|entry| entry.table == table)
+        self.positions.get(&table).copied()
     }
 
     pub fn push(&mut self, table: ID<Table>) -> usize {
         let len = self.stack.len();
+        self.positions.insert(table, len);
         self.stack.push(Entry {
             table,
             depth_first_number: {
@@ -27,7 +32,38 @@ impl Stack {
         len
     }
 
-    pub fn pop(&mut self) -> Option<Entry> { self.stack.pop() }
+    pub fn pop(&mut self) -> Option<Entry> {
+        let entry = self.stack.pop()?;
+        self.positions.remove(&entry.table);
+
+        Some(entry)
+    }
+
+    pub fn len(&self) -> usize { self.stack.len() }
+
+    /// Returns the position of the entry with the given depth-first number,
+    /// if it's still on the stack.
+    ///
+    /// Depth-first numbers are handed out in push order, so they increase
+    /// from the bottom to the top of the stack and can be binary searched.
+    pub fn position_by_dfn(&self, dfn: DepthFirstNumber) -> Option<usize> {
+        self.stack
+            .binary_search_by_key(&dfn, |entry| entry.depth_first_number)
+            .ok()
+    }
+
+    /// Returns the entry with the given depth-first number, if it's still on
+    /// the stack, see [`Self::position_by_dfn`].
+    pub fn entry_by_dfn(&self, dfn: DepthFirstNumber) -> Option<&Entry> {
+        self.position_by_dfn(dfn).map(|position| &self.stack[position])
+    }
+
+    /// Removes all the entries, keeping the allocated capacity.
+    pub fn clear(&mut self) {
+        self.stack.clear();
+        self.positions.clear();
+        self.counter = 0;
+    }
 }
 
 impl Index<usize> for Stack {
@@ -39,7 +75,9 @@ impl Index<usize> for Stack {
 }
 
 impl Stack {
-    pub fn new() -> Self { Self { stack: Vec::new(), counter: 0 } }
+    pub fn new() -> Self {
+        Self { stack: Vec::new(), positions: HashMap::new(), counter: 0 }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -54,3 +92,6 @@ pub struct DepthFirstNumber(pub usize);
 impl DepthFirstNumber {
     pub const MAX: Self = Self(usize::MAX);
 }
+
+#[cfg(test)]
+mod test;
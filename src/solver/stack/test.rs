@@ -0,0 +1,34 @@
+use crate::{
+    arena::ID,
+    solver::stack::{DepthFirstNumber, Stack},
+};
+
+#[test]
+fn find_entries_by_depth_first_number() {
+    let mut stack = Stack::new();
+    let tables = [3, 1, 4, 5].map(ID::new);
+
+    for table in tables {
+        stack.push(table);
+    }
+
+    for (position, table) in tables.into_iter().enumerate() {
+        let dfn = DepthFirstNumber(position);
+
+        assert_eq!(stack.position_by_dfn(dfn), Some(position));
+        assert_eq!(stack.entry_by_dfn(dfn).unwrap().table, table);
+    }
+
+    // popped entries aren't found anymore, and their numbers aren't reused
+    stack.pop();
+    stack.pop();
+    let pushed = stack.push(ID::new(9));
+
+    assert_eq!(stack.entry_by_dfn(DepthFirstNumber(3)), None);
+    assert_eq!(stack.position_by_dfn(DepthFirstNumber(4)), Some(pushed));
+    assert_eq!(
+        stack.entry_by_dfn(DepthFirstNumber(4)).unwrap().table,
+        ID::new(9)
+    );
+    assert_eq!(stack.position_by_dfn(DepthFirstNumber(5)), None);
+}
@@ -1,25 +1,290 @@
-use std::collections::{HashMap, VecDeque};
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashSet, VecDeque},
+    hash::{DefaultHasher, Hasher},
+    ops::{Index, IndexMut},
+};
 
 use crate::{
     arena::{Arena, ID, state},
-    canonicalize::{reverse_mapping, uncanonicalize_substitution},
-    clause::{Goal, KnowledgeBase},
-    solver::{GoalState, Solver, stack::DepthFirstNumber},
+    canonicalize::{reverse_mapping, uncanonicalize_substitution_with_counter},
+    clause::{Clause, Functor, Goal, Predicate},
+    solver::{
+        GoalState, SchedulingPolicy, Solver, TraceEvent, builtin,
+        stack::DepthFirstNumber,
+    },
     substitution::Substitution,
+    symbol::Symbol,
+    term::Term,
 };
 
 /// Manages the SLG tables for the solver.
 ///
 /// Maps between [`Goal`] to the [`ID<Table>`].
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct Tables {
-    tables: Arena<Table, state::Default>,
-    table_ids_by_goal: HashMap<Goal, ID<Table>>,
+    /// The ID of a table is derived from the hash of its canonical goal, so
+    /// looking up the table of a goal doesn't need a separate map.
+    tables: TableArena,
+
+    /// The number of tables created so far, the generation of the next one,
+    /// see [`Table::generation`].
+    created: u64,
+}
+
+/// The arena of the [`Tables`], hashing the canonical goals into IDs either
+/// with the [`DefaultHasher`] or with the [`state::StableHasher`], see
+/// [`Solver::set_stable_table_ids`].
+#[derive(Debug, Clone)]
+enum TableArena {
+    Default(Arena<Table, state::PartialHash<CanonicalGoal, DefaultHasher>>),
+    Stable(Arena<Table, state::StableHash<StableCanonicalGoal>>),
+}
+
+impl Default for TableArena {
+    fn default() -> Self { Self::Default(Arena::new()) }
+}
+
+impl TableArena {
+    fn insert(&mut self, table: Table) -> ID<Table> {
+        match self {
+            Self::Default(arena) => arena.insert(table),
+            Self::Stable(arena) => arena.insert(table),
+        }
+    }
+
+    fn find(&self, canonicalized_goal: &Goal) -> Option<ID<Table>> {
+        match self {
+            Self::Default(arena) => arena.find(canonicalized_goal),
+            Self::Stable(arena) => arena.find(canonicalized_goal),
+        }
+    }
+
+    fn get(&self, table_id: ID<Table>) -> Option<&Table> {
+        match self {
+            Self::Default(arena) => arena.get(table_id),
+            Self::Stable(arena) => arena.get(table_id),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Self::Default(arena) => arena.len(),
+            Self::Stable(arena) => arena.len(),
+        }
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = (ID<Table>, &Table)> + '_> {
+        match self {
+            Self::Default(arena) => Box::new(arena.iter()),
+            Self::Stable(arena) => Box::new(arena.iter()),
+        }
+    }
+
+    fn items_mut(&mut self) -> Box<dyn Iterator<Item = &mut Table> + '_> {
+        match self {
+            Self::Default(arena) => Box::new(arena.items_mut()),
+            Self::Stable(arena) => Box::new(arena.items_mut()),
+        }
+    }
+
+    fn retain(&mut self, f: impl FnMut(ID<Table>, &mut Table) -> bool) {
+        match self {
+            Self::Default(arena) => arena.retain(f),
+            Self::Stable(arena) => arena.retain(f),
+        }
+    }
+
+    fn clear(&mut self) {
+        match self {
+            Self::Default(arena) => arena.clear(),
+            Self::Stable(arena) => arena.clear(),
+        }
+    }
+
+    #[cfg(test)]
+    fn tombstone_count(&self) -> usize {
+        match self {
+            Self::Default(arena) => arena.tombstone_count(),
+            Self::Stable(arena) => arena.tombstone_count(),
+        }
+    }
+
+    #[cfg(test)]
+    fn remove(&mut self, table_id: ID<Table>) -> Option<Table> {
+        match self {
+            Self::Default(arena) => arena.remove(table_id),
+            Self::Stable(arena) => arena.remove(table_id),
+        }
+    }
+}
+
+impl Index<ID<Table>> for TableArena {
+    type Output = Table;
+
+    fn index(&self, table_id: ID<Table>) -> &Table {
+        match self {
+            Self::Default(arena) => &arena[table_id],
+            Self::Stable(arena) => &arena[table_id],
+        }
+    }
+}
+
+impl IndexMut<ID<Table>> for TableArena {
+    fn index_mut(&mut self, table_id: ID<Table>) -> &mut Table {
+        match self {
+            Self::Default(arena) => &mut arena[table_id],
+            Self::Stable(arena) => &mut arena[table_id],
+        }
+    }
+}
+
+/// Selects the canonical goal of a [`Table`] as the part hashed into its ID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct CanonicalGoal;
+
+impl state::Key<Table> for CanonicalGoal {
+    type Output = Goal;
+
+    fn key(table: &Table) -> &Goal { &table.canonicalized_goal }
+}
+
+/// Selects the canonical goal of a [`Table`] like [`CanonicalGoal`], but
+/// hashes it by its contents alone, see [`hash_goal_stably`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct StableCanonicalGoal;
+
+impl state::Key<Table> for StableCanonicalGoal {
+    type Output = Goal;
+
+    fn key(table: &Table) -> &Goal { &table.canonicalized_goal }
+
+    fn hash(goal: &Goal, state: &mut impl Hasher) {
+        hash_goal_stably(goal, state);
+    }
+}
+
+/// Feeds the goal to the hasher in an encoding only depending on what the
+/// goal reads as, unlike its derived [`std::hash::Hash`] implementation:
+/// symbols are fed by name rather than by the index they were interned at,
+/// and every term by a tag of its own followed by its contents.
+fn hash_goal_stably(goal: &Goal, state: &mut impl Hasher) {
+    fn write_symbol(state: &mut impl Hasher, symbol: Symbol) {
+        let name = symbol.as_str();
+        state.write_usize(name.len());
+        state.write(name.as_bytes());
+    }
+
+    write_symbol(state, goal.predicate.name);
+    state.write_usize(goal.predicate.arguments.len());
+
+    let mut stack = goal.predicate.arguments.iter().rev().collect::<Vec<_>>();
+
+    while let Some(term) = stack.pop() {
+        match term {
+            Term::Atom(name) => {
+                state.write_u8(0);
+                write_symbol(state, *name);
+            }
+            Term::Integer(value) => {
+                state.write_u8(1);
+                state.write_i64(*value);
+            }
+            Term::Variable(variable) => {
+                state.write_u8(2);
+                state.write_usize(*variable);
+            }
+            Term::Compound(name, arguments) => {
+                state.write_u8(3);
+                write_symbol(state, *name);
+                state.write_usize(arguments.len());
+                stack.extend(arguments.iter().rev());
+            }
+            Term::Str(string) => {
+                state.write_u8(4);
+                state.write_usize(string.len());
+                state.write(string.as_bytes());
+            }
+        }
+    }
 }
 
 impl Tables {
-    pub fn new() -> Self {
-        Self { tables: Arena::new(), table_ids_by_goal: HashMap::new() }
+    pub fn new() -> Self { Self { tables: TableArena::default(), created: 0 } }
+
+    /// Discards the tables and hashes the IDs of the new ones with the
+    /// [`state::StableHasher`] if enabled, see
+    /// [`Solver::set_stable_table_ids`].
+    ///
+    /// The generations carry on, a [`GoalState`] created before can't read
+    /// from a new table sharing the ID of its own.
+    pub fn set_stable_ids(&mut self, enabled: bool) {
+        self.tables = if enabled {
+            TableArena::Stable(Arena::new())
+        } else {
+            TableArena::default()
+        };
+    }
+
+    /// Checks whether the IDs are hashed by the [`state::StableHasher`].
+    pub fn has_stable_ids(&self) -> bool {
+        matches!(self.tables, TableArena::Stable(_))
+    }
+
+    /// Removes all the tables, keeping the allocated capacity.
+    pub fn clear(&mut self) { self.tables.clear(); }
+
+    /// Returns the ID of the table of the canonical goal, if any.
+    pub fn find(&self, canonicalized_goal: &Goal) -> Option<ID<Table>> {
+        self.tables.find(canonicalized_goal)
+    }
+
+    /// Adds the table, returning its ID, and makes it the latest generation.
+    fn insert(&mut self, mut table: Table) -> ID<Table> {
+        table.generation = self.created;
+        self.created += 1;
+
+        self.tables.insert(table)
+    }
+
+    /// Removes the tables whose canonical goal satisfies `remove`.
+    ///
+    /// The table created again for one of their goals may get the same ID,
+    /// see [`state::PartialHash`], but a later generation, see
+    /// [`Table::generation`].
+    pub fn remove_where(&mut self, mut remove: impl FnMut(&Goal) -> bool) {
+        self.tables.retain(|_, table| !remove(&table.canonicalized_goal));
+    }
+
+    /// Reorders the strands of every table for the given policy, see
+    /// [`Solver::set_scheduling_policy`].
+    pub fn set_scheduling_policy(&mut self, policy: SchedulingPolicy) {
+        for table in self.tables.items_mut() {
+            table.work_list.set_scheduling_policy(policy);
+        }
+    }
+
+    /// Returns the canonical goal the table was created for.
+    pub fn canonical_goal(&self, table_id: ID<Table>) -> &Goal {
+        &self.tables[table_id].canonicalized_goal
+    }
+
+    /// Returns the table of the given ID.
+    pub fn get(&self, table_id: ID<Table>) -> &Table { &self.tables[table_id] }
+
+    /// Returns the generation of the table of the given ID, see
+    /// [`Table::generation`].
+    pub fn generation(&self, table_id: ID<Table>) -> u64 {
+        self.tables[table_id].generation
+    }
+
+    /// Checks whether the table of the given ID is still the one of the
+    /// given generation, i.e. it hasn't been removed, see [`Solver::gc`],
+    /// nor created again since.
+    pub fn contains(&self, table_id: ID<Table>, generation: u64) -> bool {
+        self.tables
+            .get(table_id)
+            .is_some_and(|table| table.generation == generation)
     }
 }
 
@@ -33,6 +298,10 @@ pub(super) enum Error {
     NoMoreSolutions,
     PositiveCyclicDependency(DepthFirstNumber),
     NegativeCyclicDependency,
+    StepLimitExceeded,
+    Timeout,
+    TermTooLarge,
+    ModeViolation(Functor, usize),
 }
 
 #[derive(Debug)]
@@ -40,208 +309,563 @@ enum PullAnswerFromStrand {
     Stale,
     NewAnswer,
     Progress,
+
+    /// A substitution exceeded the maximum term size and has been dropped,
+    /// see [`Solver::set_max_term_size`].
+    TermTooLarge,
+
+    /// A subgoal left the argument at the given index non-ground against the
+    /// modes of its predicate and has been dropped, see
+    /// [`crate::clause::KnowledgeBase::declare_mode`].
+    ModeViolation(Functor, usize),
+
+    /// The step budget ran out or the deadline passed while resolving a
+    /// non-tabled goal, the alternatives left have been set aside in
+    /// [`Table::interrupted`] to resume from.
+    Interrupted(Error),
+}
+
+impl PullAnswerFromStrand {
+    /// Returns the error to report if something has been dropped.
+    fn rejection(&self) -> Option<Error> {
+        match self {
+            PullAnswerFromStrand::TermTooLarge => Some(Error::TermTooLarge),
+            PullAnswerFromStrand::ModeViolation(functor, argument) => {
+                Some(Error::ModeViolation(*functor, *argument))
+            }
+            PullAnswerFromStrand::Interrupted(error) => Some(*error),
+            PullAnswerFromStrand::Stale
+            | PullAnswerFromStrand::NewAnswer
+            | PullAnswerFromStrand::Progress => None,
+        }
+    }
 }
 
 impl Solver<'_> {
     /// Gets an ID to the table for the given goal.
-    pub(super) fn get_table_id(
-        &mut self,
-        canonicalized_goal: &Goal,
-    ) -> ID<Table> {
-        if let Some(table_id) =
-            self.tables.table_ids_by_goal.get(canonicalized_goal)
-        {
-            return *table_id;
+    ///
+    /// Tables are keyed by the canonical form of their goal, the goal is
+    /// canonicalized first unless it already is, so that alpha-equivalent
+    /// goals always share a table.
+    pub(super) fn get_table_id(&mut self, goal: &Goal) -> ID<Table> {
+        if !goal.is_canonical() {
+            let mut canonicalized_goal = goal.clone();
+            canonicalized_goal.canonicalize();
+
+            return self.get_table_id(&canonicalized_goal);
         }
 
-        let id = ID::new(self.tables.table_ids_by_goal.len() as u64);
-        self.tables.table_ids_by_goal.insert(canonicalized_goal.clone(), id);
+        let canonicalized_goal = goal;
+
+        if let Some(table_id) = self.tables.find(canonicalized_goal) {
+            return table_id;
+        }
 
-        let new_table =
-            self.create_table(self.knowledge_base, canonicalized_goal);
+        // the alternatives are only scheduled once the table is evaluated,
+        // see `Self::start_table`, otherwise creating the table of a subgoal
+        // would create the tables of its own subgoals by a nested call
+        let id = self
+            .tables
+            .insert(Table::new(canonicalized_goal, self.scheduling_policy));
 
-        self.tables.tables.insert_with_id(id, new_table).unwrap();
+        self.trace(|_| TraceEvent::NewTable(canonicalized_goal.clone()));
 
         id
     }
 
+    /// Schedules the alternatives of the table unless it's been done
+    /// already.
+    ///
+    /// The tables of the subgoals selected by the new strands are created
+    /// without being started, so a chain of tables is started one frame at a
+    /// time by [`Self::ensure_answer`] rather than by nested calls.
+    pub(super) fn start_table(&mut self, table_id: ID<Table>) {
+        let table = &mut self.tables.tables[table_id];
+
+        if table.started {
+            return;
+        }
+
+        table.started = true;
+        let canonicalized_goal = table.canonicalized_goal.clone();
+
+        self.create_table(table_id, &canonicalized_goal);
+    }
+
+    /// Returns the number of tables created so far.
+    pub fn table_count(&self) -> usize { self.tables.tables.len() }
+
+    /// Returns the number of strand activations performed so far, the unit
+    /// of the budget given in [`Solver::with_max_steps`].
+    pub fn steps(&self) -> usize { self.steps }
+
+    /// Returns the number of answers accumulated so far in the table of the
+    /// given goal, or `None` if no table has been created for it.
+    ///
+    /// The goal doesn't need to be canonicalized; `p(?3, a)` and `p(?0, a)`
+    /// refer to the same table.
+    pub fn answers_for(&self, goal: &Goal) -> Option<usize> {
+        let mut goal = goal.clone();
+        goal.canonicalize();
+
+        let table = &self.tables.tables[self.tables.find(&goal)?];
+
+        Some(table.answers.len() - table.subsumed_answers.len())
+    }
+
+    /// Returns the number of tables currently being evaluated.
+    ///
+    /// This is always zero between calls to [`Solver::pull_next_goal`].
+    pub fn stack_depth(&self) -> usize { self.stack.len() }
+
+    /// Drops the complete tables, the ones having found all their answers,
+    /// that none of the `live` goal states reads from, directly or through a
+    /// table still being evaluated, returning the number of tables dropped.
+    ///
+    /// The tables are memoized for the lifetime of the solver otherwise,
+    /// this reclaims their memory between queries at the cost of evaluating
+    /// the goals dropped again the next time they're queried.
+    ///
+    /// A [`GoalState`] left out of `live` whose table has been dropped can't
+    /// read from the table created again for its goal, if any, even though
+    /// both tables may share their ID: pulling from it fails with
+    /// [`crate::solver::SolveError::StaleGoalState`], and the query has to be
+    /// started again.
+    ///
+    /// Must be called between calls to [`Solver::pull_next_goal`]; tables
+    /// still being evaluated are always kept.
+    pub fn gc<'g>(
+        &mut self,
+        live: impl IntoIterator<Item = &'g GoalState>,
+    ) -> usize {
+        let mut reachable = HashSet::new();
+        let mut pending = live
+            .into_iter()
+            .map(|goal_state| goal_state.table_id)
+            .chain(
+                self.tables
+                    .tables
+                    .iter()
+                    .filter(|(_, table)| !table.is_complete())
+                    .map(|(table_id, _)| table_id),
+            )
+            .collect::<Vec<_>>();
+
+        // a complete table reads from no other table anymore, only the
+        // strands of the incomplete ones do
+        while let Some(table_id) = pending.pop() {
+            if !reachable.insert(table_id) {
+                continue;
+            }
+
+            if let Some(table) = self.tables.tables.get(table_id) {
+                pending.extend(
+                    table
+                        .work_list
+                        .iter()
+                        .map(|strand| strand.selected_subgoal_state.table_id),
+                );
+            }
+        }
+
+        let count = self.tables.tables.len();
+        self.tables.tables.retain(|table_id, _| reachable.contains(&table_id));
+
+        count - self.tables.tables.len()
+    }
+
     pub(super) fn get_answer(
         &self,
         table_id: ID<Table>,
         answer_index: usize,
-    ) -> Option<&Substitution> {
-        self.tables
-            .tables
-            .get(table_id)
-            .and_then(|table| table.answers.get(answer_index))
+    ) -> Option<Option<&Substitution>> {
+        self.tables.tables.get(table_id).and_then(|table| {
+            let answer = table.answers.get(answer_index)?;
+
+            Some(
+                (!table.subsumed_answers.contains(&answer_index))
+                    .then_some(answer),
+            )
+        })
     }
 
+    /// Makes sure the answer at `answer_index` of the table is available,
+    /// evaluating the table and the tables it depends on as needed.
+    ///
+    /// The evaluation is naturally recursive: pulling an answer from a strand
+    /// requires an answer of the table of its selected subgoal first. Instead
+    /// of recursing on the native call stack, the tables being evaluated are
+    /// kept as [`Frame`]s on the heap, so that long chains of dependent
+    /// tables can't overflow the call stack.
     pub(super) fn ensure_answer(
         &mut self,
         table_id: ID<Table>,
         answer_index: usize,
     ) -> Result<EnsureAnswer, Error> {
+        self.start_table(table_id);
+
+        if let Some(result) = self.check_answer(table_id, answer_index) {
+            return result;
+        }
+
+        let mut frames = vec![self.push_frame(table_id)];
+        let mut callee_result = None;
+
+        loop {
+            let frame = frames.last_mut().unwrap();
+
+            let advance = match callee_result.take() {
+                // resume the strand that waited for the frame just returned
+                Some(result) => {
+                    let strand = frame.waiting_strand.take().unwrap();
+                    let result = self.try_pull_next_answer_from_strand(
+                        frame.table_id,
+                        strand,
+                        result,
+                    );
+
+                    self.handle_strand_result(frame, result)
+                }
+
+                None => self.pull_next_answer(frame),
+            };
+
+            match advance {
+                Advance::Continue => {}
+
+                Advance::Call(table_id) => {
+                    // the table is started by its first call, the answers of
+                    // its facts may already be all the caller needs
+                    let started = self.tables.tables[table_id].started;
+                    self.start_table(table_id);
+
+                    if !started
+                        && !self.tables.tables[table_id].answers.is_empty()
+                    {
+                        callee_result = Some(Ok(EnsureAnswer::AnswerAvailable));
+                    } else {
+                        frames.push(self.push_frame(table_id));
+                    }
+                }
+
+                Advance::Return(result) => {
+                    self.stack.pop();
+                    frames.pop();
+
+                    let result = result.map(|()| EnsureAnswer::AnswerAvailable);
+
+                    if frames.is_empty() {
+                        return result;
+                    }
+
+                    callee_result = Some(result);
+                }
+            }
+        }
+    }
+
+    /// Checks whether the answer at `answer_index` of the table can be
+    /// settled without evaluating the table, returns `None` if the table has
+    /// to be evaluated.
+    fn check_answer(
+        &self,
+        table_id: ID<Table>,
+        answer_index: usize,
+    ) -> Option<Result<EnsureAnswer, Error>> {
         let table = self.tables.tables.get(table_id).unwrap();
 
         // if the table already has answers (memoized), return it immediately
         if answer_index < table.answers.len() {
-            // if the answer is already available, return it
-            return Ok(EnsureAnswer::AnswerAvailable);
+            return Some(Ok(EnsureAnswer::AnswerAvailable));
         }
 
         // if reaches here, it means the answer is not yet available, we need
         // to process a new strand.
         assert!(table.answers.len() == answer_index);
 
-        if let Some(counter) = self.stack.is_active(table_id) {
-            // if the table is already active, we cannot process it again
-            return Err(Error::PositiveCyclicDependency(
+        // if the table is already active, we cannot process it again
+        self.stack.is_active(table_id).map(|counter| {
+            Err(Error::PositiveCyclicDependency(
                 self.stack[counter].depth_first_number,
-            ));
+            ))
+        })
+    }
+
+    /// Checks whether the table holds as many answers as allowed, see
+    /// [`Solver::set_max_answers_per_table`].
+    fn is_table_full(&self, table_id: ID<Table>) -> bool {
+        self.max_answers_per_table.is_some_and(|max_answers| {
+            self.tables.tables[table_id].answers.len() >= max_answers
+        })
+    }
+
+    fn push_frame(&mut self, table_id: ID<Table>) -> Frame {
+        Frame {
+            table_id,
+            stack_index: self.stack.push(table_id),
+            cyclic_counter: DepthFirstNumber::MAX,
+            delayed_strands: Vec::new(),
+            waiting_strand: None,
         }
+    }
 
-        let stack_index = self.stack.push(table_id);
+    /// Pulls the next strand from the work list of the frame's table towards
+    /// a new answer in the [`Table::answers`] list.
+    fn pull_next_answer(&mut self, frame: &mut Frame) -> Advance {
+        let table_id = frame.table_id;
 
-        // pull the next answer from the strand
-        let result = self.pull_next_answer(table_id, stack_index);
+        // report what has been dropped while creating the table
+        if let Some(error) = self.tables.tables[table_id].rejection.take() {
+            return Advance::Return(Err(error));
+        }
 
-        self.stack.pop();
+        // the table holds as many answers as allowed, it's complete
+        if self.is_table_full(table_id) {
+            let table = &mut self.tables.tables[table_id];
+            table.work_list.clear();
+            table.deferred_alternatives.clear();
+            table.interrupted.clear();
+            frame.delayed_strands.clear();
 
-        result.map(|()| EnsureAnswer::AnswerAvailable)
-    }
+            return Advance::Return(Err(Error::NoMoreSolutions));
+        }
 
-    /// Pulls out a new answer from the strand to the [`Table::answers`] list.
-    fn pull_next_answer(
-        &mut self,
-        table_id: ID<Table>,
-        stack_index: usize,
-    ) -> Result<(), Error> {
-        let mut cyclic_counter = DepthFirstNumber::MAX;
-        let mut delayed_strands = Vec::new();
+        // finish resolving the non-tabled goals interrupted by the limits
+        if !self.tables.tables[table_id].interrupted.is_empty() {
+            let pending =
+                std::mem::take(&mut self.tables.tables[table_id].interrupted);
+            let result = self.schedule_pending(table_id, pending);
 
-        loop {
-            match self.tables.tables[table_id].work_list.pop_front() {
-                Some(strand) => {
-                    let result =
-                        self.try_pull_next_answer_from_strand(table_id, strand);
-
-                    match result {
-                        // new answer has been created, stop now enough progress
-                        // has been made
-                        Ok(PullAnswerFromStrand::NewAnswer) => {
-                            // push the cyclic dependency found back for further
-                            // processing
-                            self.tables.tables[table_id]
-                                .work_list
-                                .extend(delayed_strands);
+            return self.handle_strand_result(frame, Ok(result));
+        }
 
-                            return Ok(());
-                        }
+        let Some(strand) = self.tables.tables[table_id].work_list.pop_front()
+        else {
+            // the table evaluates its clauses in order, move on to the next
+            // one
+            if let Some(alternative) =
+                self.tables.tables[table_id].deferred_alternatives.pop_front()
+            {
+                let result = self.schedule(table_id, alternative);
 
-                        // continue processing the next strand
-                        Ok(PullAnswerFromStrand::Stale)
-                        | Ok(PullAnswerFromStrand::Progress) => {
-                            continue;
-                        }
+                return self.handle_strand_result(frame, Ok(result));
+            }
 
-                        Err((Error::NegativeCyclicDependency, _)) => {
-                            return Err(Error::NegativeCyclicDependency);
-                        }
+            // no more strand to produce answer, no more new answers
+            if frame.delayed_strands.is_empty() {
+                return Advance::Return(Err(Error::NoMoreSolutions));
+            }
 
-                        Err((
-                            Error::PositiveCyclicDependency(counter),
-                            strand,
-                        )) => {
-                            delayed_strands.push(strand);
-                            cyclic_counter = counter.min(cyclic_counter);
-                        }
+            return Advance::Return(Err(self.cyclic(
+                std::mem::take(&mut frame.delayed_strands),
+                frame.cyclic_counter,
+                frame.stack_index,
+            )));
+        };
 
-                        Err((Error::NoMoreSolutions, _)) => {
-                            // this strand can't produce any more answers,
-                            // continue
-                            continue;
-                        }
-                    }
-                }
+        // the strand has been discarded by a cut
+        if self.tables.tables[table_id].is_pruned(&strand) {
+            return Advance::Continue;
+        }
 
-                // no more strand to produce answer, no more new answers
-                None => {
-                    if delayed_strands.is_empty() {
-                        return Err(Error::NoMoreSolutions);
-                    }
+        if let Err(error) = self.consume_step() {
+            // put everything back so the table stays intact
+            self.tables.tables[table_id].work_list.push_front(strand);
+            self.requeue_strands(
+                table_id,
+                std::mem::take(&mut frame.delayed_strands),
+            );
 
-                    return Err(self.cyclic(
-                        delayed_strands,
-                        cyclic_counter,
-                        stack_index,
-                    ));
-                }
+            return Advance::Return(Err(error));
+        }
+
+        let subgoal_state = &strand.selected_subgoal_state;
+
+        match self
+            .check_answer(subgoal_state.table_id, subgoal_state.answer_index)
+        {
+            Some(result) => {
+                let result = self
+                    .try_pull_next_answer_from_strand(table_id, strand, result);
+
+                self.handle_strand_result(frame, result)
+            }
+
+            // the table of the selected subgoal has to be evaluated first
+            None => {
+                let callee = subgoal_state.table_id;
+                frame.waiting_strand = Some(strand);
+
+                Advance::Call(callee)
+            }
+        }
+    }
+
+    /// Decides how the evaluation of the frame's table continues after
+    /// pulling from one of its strands.
+    #[allow(clippy::result_large_err)]
+    fn handle_strand_result(
+        &mut self,
+        frame: &mut Frame,
+        result: Result<PullAnswerFromStrand, (Error, Strand)>,
+    ) -> Advance {
+        let table_id = frame.table_id;
+
+        match result {
+            // new answer has been created, stop now enough progress has been
+            // made
+            Ok(PullAnswerFromStrand::NewAnswer) => {
+                // push the cyclic dependency found back for further
+                // processing
+                self.requeue_strands(
+                    table_id,
+                    std::mem::take(&mut frame.delayed_strands),
+                );
+
+                Advance::Return(Ok(()))
+            }
+
+            // continue processing the next strand
+            Ok(
+                PullAnswerFromStrand::Stale | PullAnswerFromStrand::Progress,
+            ) => Advance::Continue,
+
+            // the offending substitution or subgoal is gone, or the limits
+            // stopped a non-tabled goal, the strands can be resumed
+            Ok(
+                rejected @ (PullAnswerFromStrand::TermTooLarge
+                | PullAnswerFromStrand::ModeViolation(..)
+                | PullAnswerFromStrand::Interrupted(..)),
+            ) => {
+                self.requeue_strands(
+                    table_id,
+                    std::mem::take(&mut frame.delayed_strands),
+                );
+
+                Advance::Return(Err(rejected.rejection().unwrap()))
+            }
+
+            Err((Error::NegativeCyclicDependency, _)) => {
+                Advance::Return(Err(Error::NegativeCyclicDependency))
+            }
+
+            Err((
+                error @ (Error::StepLimitExceeded
+                | Error::Timeout
+                | Error::TermTooLarge
+                | Error::ModeViolation(..)),
+                strand,
+            )) => {
+                self.tables.tables[table_id].work_list.push_front(strand);
+                self.requeue_strands(
+                    table_id,
+                    std::mem::take(&mut frame.delayed_strands),
+                );
+
+                Advance::Return(Err(error))
             }
+
+            Err((Error::PositiveCyclicDependency(counter), strand)) => {
+                self.trace(|solver| {
+                    TraceEvent::CyclicDependency(
+                        solver.tables.tables[table_id]
+                            .canonicalized_goal
+                            .clone(),
+                        counter.0,
+                    )
+                });
+
+                frame.delayed_strands.push(strand);
+                frame.cyclic_counter = counter.min(frame.cyclic_counter);
+
+                Advance::Continue
+            }
+
+            // this strand can't produce any more answers, continue
+            Err((Error::NoMoreSolutions, _)) => Advance::Continue,
         }
     }
 
+    /// Resolves the cycle the delayed strands of the table at `stack_index`
+    /// are part of, `cyclic_counter` being the depth-first number of the
+    /// oldest table of the cycle, the leader.
+    ///
+    /// The leader completes the cycle, whereas any other table hands its
+    /// delayed strands over to the leader by reporting the cycle further.
     fn cyclic(
         &mut self,
         cylic_strands: Vec<Strand>,
         cyclic_counter: DepthFirstNumber,
         stack_index: usize,
     ) -> Error {
-        let current_table = self.stack[stack_index].table;
-        let current_dfn = self.stack[stack_index].depth_first_number;
+        let current = self.stack[stack_index];
 
-        match current_dfn.cmp(&cyclic_counter) {
-            std::cmp::Ordering::Less => {
+        match current.depth_first_number.cmp(&cyclic_counter) {
+            Ordering::Less => {
                 // negative cyclic dependency
                 Error::NegativeCyclicDependency
             }
 
-            std::cmp::Ordering::Equal => {
-                self.clear_strands_after_cycle(current_table, cylic_strands);
+            Ordering::Equal => {
+                self.clear_strands_after_cycle(current.table, cylic_strands);
 
                 Error::NoMoreSolutions
             }
 
-            std::cmp::Ordering::Greater => {
-                self.tables.tables[current_table]
-                    .work_list
-                    .extend(cylic_strands);
+            // the leader is further down the stack, the cycle is reported up
+            // to it
+            Ordering::Greater => {
+                let leader = *self.stack.entry_by_dfn(cyclic_counter).expect(
+                    "the leader of the cycle must still be on the stack",
+                );
+
+                self.requeue_strands(current.table, cylic_strands);
 
-                Error::PositiveCyclicDependency(cyclic_counter)
+                Error::PositiveCyclicDependency(leader.depth_first_number)
             }
         }
     }
 
+    /// Drops the delayed strands of a completed cycle along with the strands
+    /// of every table they depend on.
     fn clear_strands_after_cycle(
         &mut self,
         table_id: ID<Table>,
-        strands: impl IntoIterator<Item = Strand>,
+        strands: Vec<Strand>,
     ) {
         assert!(self.tables.tables[table_id].work_list.is_empty());
 
-        for strand in strands {
+        let mut pending = strands;
+        while let Some(strand) = pending.pop() {
             let selected_strand_table_id =
                 strand.selected_subgoal_state.table_id;
 
-            let strands = std::mem::take(
-                &mut self.tables.tables[selected_strand_table_id].work_list,
+            pending.extend(
+                self.tables.tables[selected_strand_table_id].work_list.drain(),
             );
-
-            self.clear_strands_after_cycle(selected_strand_table_id, strands);
         }
     }
 
+    /// Continues the strand now that the answer of its selected subgoal has
+    /// been ensured, `ensured` being the outcome of ensuring it.
     #[allow(clippy::result_large_err)]
     fn try_pull_next_answer_from_strand(
         &mut self,
         table_id: ID<Table>,
         mut selected_strand: Strand,
+        ensured: Result<EnsureAnswer, Error>,
     ) -> Result<PullAnswerFromStrand, (Error, Strand)> {
-        match self.ensure_answer(
-            selected_strand.selected_subgoal_state.table_id,
-            selected_strand.selected_subgoal_state.answer_index,
-        ) {
+        match ensured {
             Ok(EnsureAnswer::AnswerAvailable) => {}
 
+            // the negated goal depends on a table being evaluated, which in
+            // turn waits for the negation to complete
+            Err(Error::PositiveCyclicDependency(_))
+                if selected_strand.negated =>
+            {
+                return Err((Error::NegativeCyclicDependency, selected_strand));
+            }
+
             Err(Error::PositiveCyclicDependency(counter)) => {
                 // propagate the cyclic dependency error
                 return Err((
@@ -255,80 +879,752 @@ impl Solver<'_> {
                 return Err((Error::NegativeCyclicDependency, selected_strand));
             }
 
+            Err(
+                error @ (Error::StepLimitExceeded
+                | Error::Timeout
+                | Error::TermTooLarge
+                | Error::ModeViolation(..)),
+            ) => {
+                // give the strand back so that it can be resumed
+                return Err((error, selected_strand));
+            }
+
             // if the answer is not available, this strand will be dropped,
             // e.g. removed from the table
             Err(Error::NoMoreSolutions) => {
+                // unless it's collecting the answers for `findall/3`, which
+                // is now complete
+                if let Some(collector) = selected_strand.collector.take() {
+                    return Ok(self.finish_collecting(
+                        table_id,
+                        selected_strand,
+                        collector,
+                    ));
+                }
+
+                // or proving a negation, which now succeeds
+                if selected_strand.negated {
+                    return Ok(self.schedule(table_id, Alternative {
+                        clause_index: selected_strand.clause_index,
+                        cuts_passed: selected_strand.cuts_passed,
+                        substitution: selected_strand.substitution,
+                        rest_subgoals: selected_strand.rest_subgoals,
+                        cost: selected_strand.cost,
+                    }));
+                }
+
                 return Ok(PullAnswerFromStrand::Stale);
             }
-        };
+        }
+
+        // the negated goal has an answer, the negation fails
+        if selected_strand.negated {
+            return Ok(PullAnswerFromStrand::Stale);
+        }
 
         // if reaches here, it means that the answer at the
         // `selected_strand.selected_subgoal_state` exists
 
-        let pulled_answer = self.tables.tables
-            [selected_strand.selected_subgoal_state.table_id]
-            .answers[selected_strand.selected_subgoal_state.answer_index]
-            .clone();
+        // skip the answers subsumed by a more general one
+        if self.tables.tables[selected_strand.selected_subgoal_state.table_id]
+            .subsumed_answers
+            .contains(&selected_strand.selected_subgoal_state.answer_index)
+        {
+            selected_strand.selected_subgoal_state.answer_index += 1;
+            self.push_strand(table_id, selected_strand);
+
+            return Ok(PullAnswerFromStrand::Progress);
+        }
 
-        let uncanonicalized_substitution = uncanonicalize_substitution(
-            &pulled_answer,
-            &selected_strand.selected_subgoal_state.canonical_mapping,
+        let subgoal_table = &self.tables.tables
+            [selected_strand.selected_subgoal_state.table_id];
+        let answer_index = selected_strand.selected_subgoal_state.answer_index;
+        let pulled_answer = subgoal_table.answers[answer_index].clone();
+        let pulled_cost = subgoal_table.answer_costs[answer_index];
+
+        // the variables the answer leaves unbound are renamed apart from every
+        // variable of the strand
+        let counter = first_fresh_variable(
+            &selected_strand.substitution,
+            selected_strand
+                .rest_subgoals
+                .iter()
+                .chain([&selected_strand.selected_subgoal])
+                .flat_map(|goal| &goal.predicate.arguments)
+                .chain(selected_strand.collector.iter().flat_map(
+                    |collector| [&collector.template, &collector.result],
+                )),
         );
+        let uncanonicalized_substitution =
+            uncanonicalize_substitution_with_counter(
+                &pulled_answer,
+                &selected_strand.selected_subgoal_state.canonical_mapping,
+                counter,
+            );
+
+        // a `findall/3` strand records the instance of the template and moves
+        // on to the next answer instead of forking
+        if let Some(collector) = &mut selected_strand.collector {
+            if let Some(substitution) = self.compose(
+                selected_strand.substitution.clone(),
+                uncanonicalized_substitution,
+            ) {
+                let mut instance = collector.template.clone();
+                substitution.apply_term(&mut instance);
+                collector.instances.push(instance);
+            }
+
+            selected_strand.selected_subgoal_state.answer_index += 1;
+            self.push_strand(table_id, selected_strand);
+
+            return Ok(PullAnswerFromStrand::Progress);
+        }
 
         // here, we'll "fork" the strand, the current "selected_strand" will
         // pursue the next answer of the current selected subgoal, whereas the
-        // `next_strand` will drop the current selected subgoal and pull a new
-        // subgoal to prove from the work list.
+        // `forked` alternative will drop the current selected subgoal and
+        // pull a new subgoal to prove from the rest of the subgoals.
         selected_strand.selected_subgoal_state.answer_index += 1;
 
-        // no more subgoal left to prove, push to the answer list.
-        if selected_strand.rest_subgoals.is_empty() {
-            let table = &mut self.tables.tables[table_id];
-
-            let mut answer = selected_strand.substitution.clone();
-            answer.compose(uncanonicalized_substitution);
+        // the answer can't be combined with the bindings of the strand
+        let Some(substitution) = self.compose(
+            selected_strand.substitution.clone(),
+            uncanonicalized_substitution,
+        ) else {
+            self.push_strand(table_id, selected_strand);
 
-            let added = table.insert_answer(answer);
-            table.work_list.push_back(selected_strand);
+            return Ok(PullAnswerFromStrand::Progress);
+        };
 
-            // New answers have been added, report back to the caller.
-            Ok(if added {
-                PullAnswerFromStrand::NewAnswer
-            } else {
-                PullAnswerFromStrand::Progress
-            })
-        } else {
-            let mut forked = selected_strand.clone();
+        if self.exceeds_max_term_size(&substitution) {
+            self.push_strand(table_id, selected_strand);
 
-            // compose a new substitution
-            forked.substitution.compose(uncanonicalized_substitution);
+            return Ok(PullAnswerFromStrand::TermTooLarge);
+        }
 
-            // pop the subgoal list
-            forked.selected_subgoal = forked.rest_subgoals.pop_front().unwrap();
+        let forked = Alternative {
+            clause_index: selected_strand.clause_index,
+            cuts_passed: selected_strand.cuts_passed,
+            substitution,
+            rest_subgoals: selected_strand.rest_subgoals.clone(),
+            cost: selected_strand.cost + pulled_cost,
+        };
 
-            // apply the substitution
-            forked
-                .substitution
-                .apply_predicate(&mut forked.selected_subgoal.predicate);
+        self.trace(|solver| {
+            TraceEvent::StrandForked(
+                solver.tables.tables[table_id].canonicalized_goal.clone(),
+            )
+        });
 
-            // canonicalize the new subgoal
-            let mapping = forked.selected_subgoal.canonicalize();
-            let mapping = reverse_mapping(&mapping);
+        // make sure a new forked strand is processed first.
+        let result = match self.scheduling_policy {
+            SchedulingPolicy::Fifo | SchedulingPolicy::ShortestFirst => {
+                let result = self.schedule(table_id, forked);
+                self.push_strand(table_id, selected_strand);
 
-            forked.selected_subgoal_state = GoalState {
-                answer_index: 0,
-                table_id: self.get_table_id(&forked.selected_subgoal),
-                canonical_mapping: mapping,
+                result
+            }
+            SchedulingPolicy::Lifo => {
+                self.push_strand(table_id, selected_strand);
+                self.schedule(table_id, forked)
+            }
+        };
+
+        Ok(result)
+    }
+
+    /// Adds the strand to the work list of the table, at the end or at the
+    /// front depending on the [`SchedulingPolicy`].
+    fn push_strand(&mut self, table_id: ID<Table>, strand: Strand) {
+        let work_list = &mut self.tables.tables[table_id].work_list;
+
+        match self.scheduling_policy {
+            // the work list of `ShortestFirst` is ordered by cost, the strand
+            // goes behind the ones of the same cost
+            SchedulingPolicy::Fifo | SchedulingPolicy::ShortestFirst => {
+                work_list.push_back(strand);
+            }
+            SchedulingPolicy::Lifo => work_list.push_front(strand),
+        }
+    }
+
+    /// Puts the strands delayed by a cycle back at the end of the work list
+    /// of the table, or in order of cost for
+    /// [`SchedulingPolicy::ShortestFirst`].
+    fn requeue_strands(&mut self, table_id: ID<Table>, strands: Vec<Strand>) {
+        self.tables.tables[table_id].work_list.extend(strands);
+    }
+
+    /// Composes the answer of a subgoal into the substitution of the strand,
+    /// performing the occurs check if enabled.
+    fn compose(
+        &self,
+        substitution: Substitution,
+        answer: Substitution,
+    ) -> Option<Substitution> {
+        if self.occurs_check {
+            substitution.compose(answer)
+        } else {
+            Some(substitution.compose_unchecked(answer))
+        }
+    }
+
+    /// Checks the goal against the modes of its predicate, returns the outcome
+    /// rejecting it if it leaves an input argument non-ground, see
+    /// [`crate::clause::KnowledgeBase::declare_mode`].
+    fn check_mode(&self, goal: &Goal) -> Option<PullAnswerFromStrand> {
+        self.knowledge_base.mode_violation(goal).map(|argument| {
+            PullAnswerFromStrand::ModeViolation(
+                goal.predicate.functor(),
+                argument,
+            )
+        })
+    }
+
+    /// Checks whether any binding of the substitution is larger than the
+    /// maximum term size, see [`Solver::set_max_term_size`].
+    fn exceeds_max_term_size(&self, substitution: &Substitution) -> bool {
+        self.max_term_size
+            .is_some_and(|max_term_size| exceeds(substitution, max_term_size))
+    }
+
+    /// Unifies the result of a `findall/3` with the collected instances and
+    /// continues with the rest of the strand's subgoals.
+    fn finish_collecting(
+        &mut self,
+        table_id: ID<Table>,
+        strand: Strand,
+        collector: Collector,
+    ) -> PullAnswerFromStrand {
+        let list = Term::list(collector.instances);
+
+        let Some(substitution) =
+            strand.substitution.unify_terms(&collector.result, &list)
+        else {
+            return PullAnswerFromStrand::Stale;
+        };
+
+        self.schedule(table_id, Alternative {
+            clause_index: strand.clause_index,
+            cuts_passed: strand.cuts_passed,
+            substitution,
+            rest_subgoals: strand.rest_subgoals,
+            cost: strand.cost,
+        })
+    }
+
+    /// Continues proving the rest of a clause body.
+    ///
+    /// Runs the cuts at the front of the body, then either records an answer
+    /// if the body is exhausted or pushes a new strand selecting the next
+    /// subgoal to the work list of the table.
+    ///
+    /// Returns [`PullAnswerFromStrand::NewAnswer`] if a new answer has been
+    /// added to the table, [`PullAnswerFromStrand::Stale`] if a built-in goal
+    /// failed, [`PullAnswerFromStrand::TermTooLarge`] if an answer has been
+    /// rejected for its size, [`PullAnswerFromStrand::ModeViolation`] if a
+    /// subgoal has been rejected for its modes,
+    /// [`PullAnswerFromStrand::Interrupted`] if the limits of the solver
+    /// stopped the resolution of a non-tabled goal, otherwise
+    /// [`PullAnswerFromStrand::Progress`].
+    fn schedule(
+        &mut self,
+        table_id: ID<Table>,
+        alternative: Alternative,
+    ) -> PullAnswerFromStrand {
+        self.schedule_pending(table_id, vec![alternative])
+    }
+
+    /// Continues proving the alternatives like [`Self::schedule`], the last
+    /// one first.
+    fn schedule_pending(
+        &mut self,
+        table_id: ID<Table>,
+        mut pending: Vec<Alternative>,
+    ) -> PullAnswerFromStrand {
+        // disjunctions and non-tabled goals fork the alternative, the forks
+        // are kept on an explicit stack so that deep non-tabled recursion
+        // doesn't overflow the call stack
+        let mut result = PullAnswerFromStrand::Stale;
+
+        while let Some(alternative) = pending.pop() {
+            let outcome =
+                self.schedule_alternative(table_id, alternative, &mut pending);
+
+            // the alternatives left are resumed the next time the table is
+            // evaluated, on top of the ones interrupted before
+            if let PullAnswerFromStrand::Interrupted(error) = outcome {
+                self.tables.tables[table_id].interrupted.append(&mut pending);
+
+                return PullAnswerFromStrand::Interrupted(error);
+            }
+
+            result = match (result, outcome) {
+                (
+                    rejected @ (PullAnswerFromStrand::TermTooLarge
+                    | PullAnswerFromStrand::ModeViolation(..)
+                    | PullAnswerFromStrand::Interrupted(..)),
+                    _,
+                )
+                | (
+                    _,
+                    rejected @ (PullAnswerFromStrand::TermTooLarge
+                    | PullAnswerFromStrand::ModeViolation(..)
+                    | PullAnswerFromStrand::Interrupted(..)),
+                ) => rejected,
+                (PullAnswerFromStrand::NewAnswer, _)
+                | (_, PullAnswerFromStrand::NewAnswer) => {
+                    PullAnswerFromStrand::NewAnswer
+                }
+                (PullAnswerFromStrand::Progress, _)
+                | (_, PullAnswerFromStrand::Progress) => {
+                    PullAnswerFromStrand::Progress
+                }
+                (PullAnswerFromStrand::Stale, PullAnswerFromStrand::Stale) => {
+                    PullAnswerFromStrand::Stale
+                }
             };
+        }
 
-            // push the forked strand and the parent strand to the work lit
-            let table = &mut self.tables.tables[table_id];
+        result
+    }
+
+    /// Advances a single alternative for [`Self::schedule`], the alternatives
+    /// it forks into are pushed onto `pending` in reverse order so that
+    /// they're scheduled in order.
+    fn schedule_alternative(
+        &mut self,
+        table_id: ID<Table>,
+        mut alternative: Alternative,
+        pending: &mut Vec<Alternative>,
+    ) -> PullAnswerFromStrand {
+        loop {
+            // no more subgoal left to prove, push to the answer list.
+            let Some(mut selected_subgoal) =
+                alternative.rest_subgoals.pop_front()
+            else {
+                // the answers past the limit are dropped, the table is
+                // completed the next time it's evaluated
+                if self.is_table_full(table_id) {
+                    return PullAnswerFromStrand::Stale;
+                }
+
+                let added = self.tables.tables[table_id].insert_answer(
+                    alternative.substitution,
+                    alternative.cost,
+                    self.answer_subsumption,
+                    self.max_term_size,
+                );
+
+                // New answers have been added, report back to the caller.
+                return match added {
+                    Ok(true) => {
+                        self.trace(|solver| {
+                            let table = &solver.tables.tables[table_id];
+
+                            TraceEvent::AnswerFound(
+                                table.canonicalized_goal.clone(),
+                                table.answers.last().unwrap().clone(),
+                            )
+                        });
+
+                        PullAnswerFromStrand::NewAnswer
+                    }
+                    Ok(false) => PullAnswerFromStrand::Progress,
+                    Err(_) => PullAnswerFromStrand::TermTooLarge,
+                };
+            };
+
+            if selected_subgoal.is_cut() {
+                alternative.cuts_passed += 1;
+                self.tables.tables[table_id]
+                    .cut(alternative.clause_index, alternative.cuts_passed);
+
+                continue;
+            }
+
+            alternative.substitution.apply_goal(&mut selected_subgoal);
+
+            if let Some(rejected) = self.check_mode(&selected_subgoal) {
+                return rejected;
+            }
 
-            // make sure a new forked strand is processed first.
-            table.work_list.push_back(forked);
-            table.work_list.push_back(selected_strand);
+            // disjunctions fork an alternative per branch, all sharing the
+            // clause index so that a cut in one branch prunes the others
+            if let Some(branches) = builtin::expand_control(&selected_subgoal) {
+                for branch in branches.into_iter().rev() {
+                    let mut rest_subgoals = VecDeque::from(branch);
+                    rest_subgoals
+                        .extend(alternative.rest_subgoals.iter().cloned());
+
+                    pending.push(Alternative {
+                        clause_index: alternative.clause_index,
+                        cuts_passed: alternative.cuts_passed,
+                        substitution: alternative.substitution.clone(),
+                        rest_subgoals,
+                        cost: alternative.cost,
+                    });
+                }
+
+                return PullAnswerFromStrand::Stale;
+            }
+
+            // `findall/3` gets a strand collecting every answer of its goal
+            if let Some(findall) = builtin::as_findall(&selected_subgoal) {
+                let Some(goal) = findall.goal else {
+                    return PullAnswerFromStrand::Stale;
+                };
+
+                if let Some(rejected) = self.check_mode(&goal) {
+                    return rejected;
+                }
+
+                let strand = self.new_strand(
+                    alternative,
+                    goal,
+                    Some(Collector {
+                        template: findall.template,
+                        result: findall.result,
+                        instances: Vec::new(),
+                    }),
+                );
+                self.push_strand(table_id, strand);
+
+                return PullAnswerFromStrand::Progress;
+            }
+
+            // `\+/1` gets a strand checking that its goal has no answer
+            if let Some(negated) = builtin::as_negation(&selected_subgoal) {
+                let Some(goal) = negated else {
+                    return PullAnswerFromStrand::Stale;
+                };
+
+                if let Some(rejected) = self.check_mode(&goal) {
+                    return rejected;
+                }
+
+                let mut strand = self.new_strand(alternative, goal, None);
+                strand.negated = true;
+                self.push_strand(table_id, strand);
+
+                return PullAnswerFromStrand::Progress;
+            }
+
+            // `copy_term/2` boils down to unifying with a renamed source
+            if let Some(unification) = builtin::expand_copy_term(
+                &selected_subgoal,
+                first_fresh_variable(
+                    &alternative.substitution,
+                    alternative
+                        .rest_subgoals
+                        .iter()
+                        .chain([&selected_subgoal])
+                        .flat_map(|goal| &goal.predicate.arguments),
+                ),
+            ) {
+                selected_subgoal = unification;
+            }
+
+            // `between/3` binds its first integer right away and leaves the
+            // rest of the range for later
+            if let Some(range) = builtin::as_between(&selected_subgoal) {
+                let Some(range) = range.filter(|range| range.low <= range.high)
+                else {
+                    return PullAnswerFromStrand::Stale;
+                };
+
+                if let Term::Integer(value) = range.value {
+                    if !(range.low..=range.high).contains(&value) {
+                        return PullAnswerFromStrand::Stale;
+                    }
+
+                    continue;
+                }
 
-            Ok(PullAnswerFromStrand::Progress)
+                if range.low < range.high {
+                    let mut rest_subgoals = alternative.rest_subgoals.clone();
+                    rest_subgoals.push_front(Goal::new(Symbol::BETWEEN, [
+                        Term::integer(range.low + 1),
+                        Term::integer(range.high),
+                        range.value.clone(),
+                    ]));
+
+                    // deferred alternatives only start once the work list
+                    // runs dry, keeping the enumeration lazy
+                    self.tables.tables[table_id]
+                        .deferred_alternatives
+                        .push_front(Alternative {
+                            clause_index: alternative.clause_index,
+                            cuts_passed: alternative.cuts_passed,
+                            substitution: alternative.substitution.clone(),
+                            rest_subgoals,
+                            cost: alternative.cost,
+                        });
+                }
+
+                let unified = alternative
+                    .substitution
+                    .unify_terms(&range.value, &Term::integer(range.low));
+
+                match unified {
+                    Some(substitution) => {
+                        alternative.substitution = substitution;
+                        continue;
+                    }
+
+                    None => return PullAnswerFromStrand::Stale,
+                }
+            }
+
+            // `length/2` measures a list, or closes a partial list at the given
+            // length or at every length in turn
+            if let Some(length) = builtin::as_length(&selected_subgoal) {
+                let Some(length) = length else {
+                    return PullAnswerFromStrand::Stale;
+                };
+
+                let counter = first_fresh_variable(
+                    &alternative.substitution,
+                    alternative
+                        .rest_subgoals
+                        .iter()
+                        .chain([&selected_subgoal])
+                        .flat_map(|goal| &goal.predicate.arguments),
+                );
+                let items = Term::integer(
+                    i64::try_from(length.items).unwrap_or(i64::MAX),
+                );
+
+                let unified = match (length.tail, &length.length) {
+                    (None, _) => alternative
+                        .substitution
+                        .unify_terms(&length.length, &items),
+
+                    (Some(tail), Term::Integer(target)) => {
+                        let Some(missing) =
+                            usize::try_from(*target).ok().and_then(|target| {
+                                target.checked_sub(length.items)
+                            })
+                        else {
+                            return PullAnswerFromStrand::Stale;
+                        };
+
+                        alternative.substitution.unify_terms(
+                            &Term::Variable(tail),
+                            &Term::list(
+                                (counter..counter + missing)
+                                    .map(Term::Variable),
+                            ),
+                        )
+                    }
+
+                    (Some(tail), _) => {
+                        // the longer lists wait until the work list runs dry,
+                        // keeping the enumeration lazy
+                        let grown =
+                            alternative.substitution.clone().unify_terms(
+                                &Term::Variable(tail),
+                                &Term::list_with_tail(
+                                    [Term::Variable(counter)],
+                                    Term::Variable(counter + 1),
+                                ),
+                            );
+
+                        if let Some(substitution) = grown {
+                            let mut rest_subgoals =
+                                alternative.rest_subgoals.clone();
+                            rest_subgoals.push_front(selected_subgoal.clone());
+
+                            self.tables.tables[table_id]
+                                .deferred_alternatives
+                                .push_front(Alternative {
+                                    clause_index: alternative.clause_index,
+                                    cuts_passed: alternative.cuts_passed,
+                                    substitution,
+                                    rest_subgoals,
+                                    cost: alternative.cost,
+                                });
+                        }
+
+                        alternative
+                            .substitution
+                            .unify_terms(
+                                &Term::Variable(tail),
+                                &Term::Atom(Symbol::NIL),
+                            )
+                            .and_then(|substitution| {
+                                substitution.unify_terms(&length.length, &items)
+                            })
+                    }
+                };
+
+                match unified {
+                    Some(substitution) => {
+                        alternative.substitution = substitution;
+                        continue;
+                    }
+
+                    None => return PullAnswerFromStrand::Stale,
+                }
+            }
+
+            // built-ins are evaluated right away, without a table, so a
+            // failing guard drops the alternative before any table is created
+            // for the subgoals after it
+            if builtin::is_builtin(&selected_subgoal) {
+                match builtin::evaluate(
+                    &selected_subgoal,
+                    alternative.substitution,
+                    self.occurs_check,
+                ) {
+                    Some(substitution) => {
+                        alternative.substitution = substitution;
+                        continue;
+                    }
+
+                    None => return PullAnswerFromStrand::Stale,
+                }
+            }
+
+            // non-tabled goals are resolved in place, SLD-style; every
+            // resolution is a step so that the limits of the solver stop a
+            // non-tabled goal that loops
+            if let Some(resolvents) =
+                self.unfold(&alternative, &selected_subgoal)
+            {
+                if let Err(error) = self.consume_step() {
+                    alternative.rest_subgoals.push_front(selected_subgoal);
+                    pending.push(alternative);
+
+                    return PullAnswerFromStrand::Interrupted(error);
+                }
+
+                pending.extend(resolvents.into_iter().rev());
+
+                return PullAnswerFromStrand::Stale;
+            }
+
+            let strand = self.new_strand(alternative, selected_subgoal, None);
+            self.push_strand(table_id, strand);
+
+            return PullAnswerFromStrand::Progress;
+        }
+    }
+
+    /// Resolves the selected subgoal of the alternative against the clauses
+    /// of its predicate, returning an alternative per matching clause with
+    /// the clause body in front of the rest of the subgoals.
+    ///
+    /// Returns `None` if the subgoal must go through a table instead, i.e.
+    /// its predicate is tabled or has a clause containing a cut, see
+    /// [`crate::clause::KnowledgeBase::set_tabled`].
+    fn unfold(
+        &self,
+        alternative: &Alternative,
+        selected_subgoal: &Goal,
+    ) -> Option<Vec<Alternative>> {
+        let predicate = &selected_subgoal.predicate;
+
+        if self.knowledge_base.is_tabled(predicate.functor()) {
+            return None;
+        }
+
+        let clauses = self.candidate_clauses(predicate);
+
+        if clauses
+            .iter()
+            .any(|(clause, _)| clause.body.iter().any(Goal::contains_cut))
+        {
+            return None;
+        }
+
+        // rename the clauses apart from every variable of the alternative
+        let counter = first_fresh_variable(
+            &alternative.substitution,
+            alternative
+                .rest_subgoals
+                .iter()
+                .chain([selected_subgoal])
+                .flat_map(|goal| &goal.predicate.arguments),
+        );
+
+        // the occurs check is only skipped when both the head and the subgoal
+        // are linear, the subgoal as seen through the bindings of the
+        // alternative, see `Predicate::is_linear`
+        let linear_goal = self.occurs_check
+            && predicate.is_linear_under(&alternative.substitution);
+
+        let mut resolvents = Vec::new();
+
+        for (clause, linear_head) in clauses {
+            let mut clause = clause.clone();
+            clause.canonicalize_with_counter(counter);
+            clause.head.name = predicate.name;
+
+            // the renamed clause shares no variable with the subgoal, so
+            // unifying it can't be cyclic if both sides are linear
+            let unified = if self.occurs_check && !(linear_head && linear_goal)
+            {
+                alternative
+                    .substitution
+                    .clone()
+                    .unify_predicate(predicate, &clause.head)
+            } else {
+                alternative
+                    .substitution
+                    .clone()
+                    .unify_predicate_unchecked(predicate, &clause.head)
+            };
+
+            let Some(substitution) = unified else {
+                continue;
+            };
+
+            let mut rest_subgoals = VecDeque::from(clause.body);
+            rest_subgoals.extend(alternative.rest_subgoals.iter().cloned());
+
+            resolvents.push(Alternative {
+                clause_index: alternative.clause_index,
+                cuts_passed: alternative.cuts_passed,
+                substitution,
+                rest_subgoals,
+                cost: alternative.cost + 1,
+            });
+        }
+
+        Some(resolvents)
+    }
+
+    /// Creates a strand selecting the given subgoal, the substitution of the
+    /// alternative must have already been applied to it.
+    fn new_strand(
+        &mut self,
+        alternative: Alternative,
+        mut selected_subgoal: Goal,
+        collector: Option<Collector>,
+    ) -> Strand {
+        // canonicalize the new subgoal
+        let mapping = selected_subgoal.canonicalize();
+        let mapping = reverse_mapping(&mapping);
+        let table_id = self.get_table_id(&selected_subgoal);
+
+        Strand {
+            selected_subgoal_state: GoalState {
+                answer_index: 0,
+                table_id,
+                generation: self.tables.generation(table_id),
+                canonical_mapping: mapping,
+                ordered_answers: None,
+                peeked: None,
+            },
+            rest_subgoals: alternative.rest_subgoals,
+            selected_subgoal,
+            substitution: alternative.substitution,
+            clause_index: alternative.clause_index,
+            cuts_passed: alternative.cuts_passed,
+            collector,
+            negated: false,
+            cost: alternative.cost,
         }
     }
 }
@@ -336,8 +1632,11 @@ impl Solver<'_> {
 /// Represents a "goal to prove" aspect of the SLG solver.
 ///
 /// Table contains multiple [`Strand`]s each of which represents a possible
-/// way to prove the goal. These strands are stored in a [`VecDeque`] which
-/// will be processed in a round-robin fashion.
+/// way to prove the goal. These strands wait in a work list, advanced in the
+/// order of the [`SchedulingPolicy`] set by [`Solver::set_scheduling_policy`]:
+/// a queue taken from the front, round-robin or depth-first, or a binary
+/// heap taking the strand of the lowest [`Strand::cost`] first under
+/// [`SchedulingPolicy::ShortestFirst`].
 ///
 /// After processing a strand, it will yield a new answer and possibly create
 /// new more strands to explore.
@@ -350,107 +1649,630 @@ pub struct Table {
     ///
     /// If a [`Self::work_list`] is empty, it means there are no more possible
     /// answers to create.
-    work_list: VecDeque<Strand>,
+    work_list: WorkList,
 
     /// The list of answers that have been found so far.
     answers: Vec<Substitution>,
 
+    /// The length of the shortest derivation found for each of the
+    /// [`Self::answers`], at the same index.
+    answer_costs: Vec<usize>,
+
+    /// The indices of the [`Self::answers`] made redundant by a more general
+    /// answer found later on, consumers skip over them.
+    ///
+    /// The answers themselves are kept so that the indices held by consumers
+    /// remain valid.
+    subsumed_answers: HashSet<usize>,
+
     /// The canonicalized goal being proven.
     canonicalized_goal: Goal,
 
-    /// The maximum variable index found in the [`Self::canonicalized_goal`]
-    max_inference_variable_index: Option<usize>,
+    /// The variables of the [`Self::canonicalized_goal`], the only ones the
+    /// answers keep.
+    query_variables: HashSet<usize>,
+
+    /// The clauses that haven't been started yet.
+    ///
+    /// This is only used by tables having a clause with a cut, which try
+    /// their clauses one after another; the next clause starts once the
+    /// [`Self::work_list`] runs dry. The rest of the range of a `between/3`
+    /// goal waits here as well, in front of the later clauses.
+    deferred_alternatives: VecDeque<Alternative>,
+
+    /// The most recent cut performed in this table, if any.
+    cut: Option<Cut>,
+
+    /// The error to report for an alternative dropped while the table was
+    /// being created, the next time the table is evaluated.
+    rejection: Option<Error>,
+
+    /// The alternatives set aside when the step budget ran out or the
+    /// deadline passed while resolving a non-tabled goal, resumed before any
+    /// other strand the next time the table is evaluated. The last one is
+    /// resumed first.
+    interrupted: Vec<Alternative>,
+
+    /// Whether the alternatives of the table have been scheduled, see
+    /// [`Solver::start_table`].
+    started: bool,
+
+    /// The number of tables created before this one, telling it apart from
+    /// the tables created for the same goal before it's been removed or after
+    /// it, which may share its ID.
+    generation: u64,
+}
+
+/// Describes which strands of a [`Table`] a cut has discarded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Cut {
+    /// The index of the clause containing the cut.
+    clause_index: usize,
+
+    /// The number of cuts in the clause body up to and including this cut.
+    cuts_passed: usize,
 }
 
 impl Table {
-    pub fn insert_answer(&mut self, mut answer: Substitution) -> bool {
-        let answer_to_add =
-            if let Some(max_index) = self.max_inference_variable_index {
-                // if the answer has inference variables, we need to filter them
-                // out to avoid storing unnecessary data
-                answer.mapping.retain(|k, _| *k <= max_index);
-                answer
-            } else {
-                Substitution::default()
-            };
+    /// Returns the canonical goal the table proves.
+    #[must_use]
+    pub fn canonical_goal(&self) -> &Goal { &self.canonicalized_goal }
+
+    /// Returns the answers found so far, in the order they were found.
+    ///
+    /// With answer subsumption, see [`Solver::set_answer_subsumption`], the
+    /// answers made redundant by a more general one are still listed here.
+    #[must_use]
+    pub fn answers(&self) -> &[Substitution] { &self.answers }
+
+    /// Returns the derivation length of each of the [`Self::answers`], at the
+    /// same index: the number of clauses resolved to prove it, the built-ins
+    /// and the negated goals counting for nothing.
+    ///
+    /// An answer derived again through a shorter derivation keeps the
+    /// shorter length. Only the derivations explored before the answer is
+    /// consumed count, so the lengths are the shortest ones when the strands
+    /// are advanced by [`SchedulingPolicy::ShortestFirst`].
+    #[must_use]
+    pub fn answer_costs(&self) -> &[usize] { &self.answer_costs }
+
+    /// Checks whether the table has found all of its answers, having neither
+    /// a strand nor a clause left to evaluate.
+    fn is_complete(&self) -> bool {
+        self.started
+            && self.work_list.is_empty()
+            && self.deferred_alternatives.is_empty()
+            && self.interrupted.is_empty()
+            && self.rejection.is_none()
+    }
+
+    /// Returns the number of strands waiting to be processed, the table has
+    /// no more answers to find once it reaches zero.
+    #[must_use]
+    pub fn work_list_len(&self) -> usize { self.work_list.len() }
 
-        // check if the answer is already present
-        if self.answers.contains(&answer_to_add) {
-            return false;
+    /// Returns the strands waiting to be processed, the next one first.
+    pub fn strands(&self) -> impl Iterator<Item = &Strand> {
+        self.work_list.iter()
+    }
+
+    /// Creates an empty table for the given canonicalized goal, its
+    /// alternatives are scheduled afterwards by [`Solver::start_table`] in
+    /// the order of the `policy`.
+    fn new(canonicalized_goal: &Goal, policy: SchedulingPolicy) -> Self {
+        Self {
+            work_list: WorkList::new(policy),
+            answers: Vec::new(),
+            answer_costs: Vec::new(),
+            subsumed_answers: HashSet::new(),
+            canonicalized_goal: canonicalized_goal.clone(),
+            query_variables: canonicalized_goal
+                .query_variables()
+                .into_iter()
+                .collect(),
+            deferred_alternatives: VecDeque::new(),
+            cut: None,
+            rejection: None,
+            interrupted: Vec::new(),
+            started: false,
+            generation: 0,
+        }
+    }
+
+    /// Commits the table to the given clause.
+    ///
+    /// Discards the clauses after it and the strands of the clause that
+    /// haven't gone past this cut yet, i.e. the alternatives of the goals
+    /// preceding the cut.
+    fn cut(&mut self, clause_index: usize, cuts_passed: usize) {
+        self.cut = Some(Cut { clause_index, cuts_passed });
+        self.deferred_alternatives.clear();
+
+        let mut work_list = std::mem::take(&mut self.work_list);
+        work_list.retain(|x| !self.is_pruned(x));
+        self.work_list = work_list;
+
+        let interrupted = std::mem::take(&mut self.interrupted);
+        self.interrupted = interrupted
+            .into_iter()
+            .filter(|x| !self.prunes(x.clause_index, x.cuts_passed))
+            .collect();
+    }
+
+    /// Checks whether the strand has been discarded by a cut.
+    fn is_pruned(&self, strand: &Strand) -> bool {
+        self.prunes(strand.clause_index, strand.cuts_passed)
+    }
+
+    /// Checks whether the cut of the table discards the strands of the given
+    /// clause having gone past `cuts_passed` cuts.
+    fn prunes(&self, clause_index: usize, cuts_passed: usize) -> bool {
+        self.cut.is_some_and(|cut| {
+            clause_index > cut.clause_index
+                || (clause_index == cut.clause_index
+                    && cuts_passed < cut.cuts_passed)
+        })
+    }
+
+    /// Records a new answer derived in `cost` clause resolutions, returns
+    /// `false` if it doesn't contribute anything new to the table.
+    ///
+    /// With `subsumption`, an answer that is an instance of an existing one
+    /// is discarded and the existing answers that are instances of it are
+    /// marked as subsumed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::TermTooLarge`], discarding the answer, if any of its
+    /// bindings is larger than `max_term_size`.
+    pub(super) fn insert_answer(
+        &mut self,
+        answer: Substitution,
+        cost: usize,
+        subsumption: bool,
+        max_term_size: Option<usize>,
+    ) -> Result<bool, Error> {
+        // only the variables of the goal are of interest, the ones introduced
+        // by the clauses are filtered out to avoid storing unnecessary data
+        let mut answer_to_add = answer.restrict(&self.query_variables);
+
+        // the variables left unbound are numbered by the clause the answer
+        // comes from, renumber them so that alpha-equivalent answers collapse
+        answer_to_add.canonicalize_with_fixed(&self.query_variables);
+
+        if max_term_size
+            .is_some_and(|max_term_size| exceeds(&answer_to_add, max_term_size))
+        {
+            return Err(Error::TermTooLarge);
+        }
+
+        // check if the answer is already present, keeping the shorter
+        // derivation
+        if let Some(index) =
+            self.answers.iter().position(|answer| *answer == answer_to_add)
+        {
+            let existing = &mut self.answer_costs[index];
+            *existing = cost.min(*existing);
+
+            return Ok(false);
+        }
+
+        if subsumption {
+            let live_answers =
+                self.answers.iter().enumerate().filter(|(index, _)| {
+                    !self.subsumed_answers.contains(index)
+                });
+
+            let mut subsumed = Vec::new();
+            for (index, existing) in live_answers {
+                if answer_to_add.is_instance_of(existing) {
+                    return Ok(false);
+                }
+
+                if existing.is_instance_of(&answer_to_add) {
+                    subsumed.push(index);
+                }
+            }
+
+            self.subsumed_answers.extend(subsumed);
         }
 
         self.answers.push(answer_to_add);
-        true
+        self.answer_costs.push(cost);
+        Ok(true)
     }
 }
 
 impl Solver<'_> {
-    fn create_table(
-        &mut self,
-        knowledge_base: &KnowledgeBase,
-        canonicalized_goal: &Goal,
-    ) -> Table {
-        // create a new table by looking at the matching clauses
-        let clauses =
-            knowledge_base.get_clauses(&canonicalized_goal.predicate.name);
-
-        let mut answers = Vec::new();
-        let mut strands = VecDeque::new();
-        let max_inference_variable_index =
-            canonicalized_goal.max_variable_index();
+    /// Returns the clauses that could resolve the predicate, following its
+    /// aliases, along with whether their head is linear. The facts asserted
+    /// while solving come after the clauses of the program.
+    ///
+    /// The clauses of an alias are named after the predicate it resolves
+    /// against, their head is renamed to the predicate before unifying.
+    pub(super) fn candidate_clauses(
+        &self,
+        predicate: &Predicate,
+    ) -> Vec<(&Clause, bool)> {
+        let functor = self.knowledge_base.resolve_alias(predicate.functor());
+        let first_argument = predicate.arguments.first();
+
+        let mut clauses =
+            self.knowledge_base.get_candidate_clauses(functor, first_argument);
+        clauses.extend(
+            self.asserted_facts.get_candidate_clauses(functor, first_argument),
+        );
+
+        clauses
+    }
+
+    /// Schedules the alternatives of the freshly inserted table.
+    fn create_table(&mut self, table_id: ID<Table>, canonicalized_goal: &Goal) {
+        // find the alternatives by looking at the matching clauses, the facts
+        // asserted while solving come after the clauses of the program; a
+        // built-in goal has a single alternative evaluating the goal itself
+        // instead, the clauses defining it are never used, like within rule
+        // bodies, see `crate::clause::Diagnostic::BuiltinRedefinition`
+        let predicate = &canonicalized_goal.predicate;
+        let is_builtin = builtin::is_builtin(canonicalized_goal);
+        let clauses = if is_builtin {
+            Vec::new()
+        } else {
+            self.candidate_clauses(predicate)
+        };
+
+        let max_inference_variable_index = predicate
+            .arguments
+            .iter()
+            .filter_map(Term::max_variable_offset)
+            .max();
+        let linear_goal = predicate.is_linear();
+        let mut alternatives = VecDeque::new();
+
+        if is_builtin {
+            alternatives.push_back(Alternative {
+                clause_index: 0,
+                cuts_passed: 0,
+                substitution: Substitution::default(),
+                rest_subgoals: [canonicalized_goal.clone()].into(),
+                cost: 0,
+            });
+        }
 
         // find the applicable clause to create a new stand.
-        for clause in clauses.into_iter().flatten() {
+        for (clause_index, (clause, linear_head)) in
+            clauses.into_iter().enumerate()
+        {
             // check if the clause is applicable
 
             let mut clause = clause.clone();
             clause.canonicalize_with_counter(
                 max_inference_variable_index.map_or(0, |x| x + 1),
             );
+            clause.head.name = predicate.name;
+
+            // the renamed clause shares no variable with the goal, so
+            // unifying it can't be cyclic if both sides are linear, see
+            // `Predicate::is_linear`
+            let unified = if self.occurs_check && !(linear_head && linear_goal)
+            {
+                Substitution::default().unify_predicate(
+                    &canonicalized_goal.predicate,
+                    &clause.head,
+                )
+            } else {
+                Substitution::default().unify_predicate_unchecked(
+                    &canonicalized_goal.predicate,
+                    &clause.head,
+                )
+            };
 
-            let Some(substitution) = Substitution::default()
-                .unify_predicate(&canonicalized_goal.predicate, &clause.head)
-            else {
+            let Some(substitution) = unified else {
                 continue;
             };
 
-            if clause.body.is_empty() {
-                answers.push(substitution);
-            } else {
-                // select the first subgoal as the selected subgoal right away
-                let mut selected_subgoal = clause.body[0].clone();
-
-                substitution.apply_predicate(&mut selected_subgoal.predicate);
-                let mapping = selected_subgoal.canonicalize();
-                let mapping = reverse_mapping(&mapping);
-
-                // push a new strand
-                strands.push_back(Strand {
-                    selected_subgoal_state: GoalState {
-                        answer_index: 0,
-                        table_id: self.get_table_id(&selected_subgoal),
-                        canonical_mapping: mapping,
-                    },
-
-                    rest_subgoals: clause.body[1..].to_vec().into(),
-                    selected_subgoal,
-                    substitution,
-                });
-            }
+            alternatives.push_back(Alternative {
+                clause_index,
+                cuts_passed: 0,
+                substitution,
+                rest_subgoals: clause.body.into(),
+                cost: 1,
+            });
         }
 
-        Table {
-            work_list: strands,
-            answers,
-            canonicalized_goal: canonicalized_goal.clone(),
-            max_inference_variable_index: canonicalized_goal
-                .max_variable_index(),
+        // a cut only makes sense if the clauses are tried in order
+        let sequential = alternatives.iter().any(|alternative| {
+            alternative.rest_subgoals.iter().any(Goal::contains_cut)
+        });
+
+        let mut rejection = None;
+
+        if sequential {
+            if let Some(first) = alternatives.pop_front() {
+                self.tables.tables[table_id].deferred_alternatives =
+                    alternatives;
+                rejection = self.schedule(table_id, first).rejection();
+            }
+        } else {
+            // facts are recorded as answers right away, rules start a new
+            // strand selecting their first subgoal
+            for alternative in alternatives {
+                if self.is_table_full(table_id) {
+                    break;
+                }
+
+                let result = self.schedule(table_id, alternative);
+                rejection = rejection.or(result.rejection());
+            }
+
+            // the strands of the first clauses have been pushed below the
+            // ones of the later clauses, restore the clause order
+            if self.scheduling_policy == SchedulingPolicy::Lifo {
+                self.tables.tables[table_id].work_list.reverse();
+            }
         }
+
+        self.tables.tables[table_id].rejection = rejection;
     }
 }
 
+/// A table being evaluated by [`Solver::ensure_answer`].
+struct Frame {
+    table_id: ID<Table>,
+
+    /// The index of the table in the [`Solver`]'s stack.
+    stack_index: usize,
+
+    /// The lowest depth-first number among the tables the delayed strands
+    /// are waiting on.
+    cyclic_counter: DepthFirstNumber,
+
+    /// The strands waiting on a table being evaluated further down the
+    /// stack.
+    delayed_strands: Vec<Strand>,
+
+    /// The strand waiting for the frame above this one to return.
+    waiting_strand: Option<Strand>,
+}
+
+/// Describes how [`Solver::ensure_answer`] proceeds after advancing a
+/// [`Frame`].
+enum Advance {
+    /// Keep evaluating the frame.
+    Continue,
+
+    /// Evaluate the given table first, then resume the waiting strand of the
+    /// frame.
+    Call(ID<Table>),
+
+    /// The frame has finished, either with a new answer or an error.
+    Return(Result<(), Error>),
+}
+
+/// A clause instance whose remaining body hasn't been turned into a
+/// [`Strand`] yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Alternative {
+    /// The index of the clause among the candidates of the table.
+    clause_index: usize,
+
+    /// The number of cuts in the clause body already gone past.
+    cuts_passed: usize,
+
+    /// The substitution built so far.
+    substitution: Substitution,
+
+    /// The subgoals left to prove.
+    rest_subgoals: VecDeque<Goal>,
+
+    /// The length of the derivation so far, see [`Strand::cost`].
+    cost: usize,
+}
+
 /// Represents a "way to prove the goal".
 ///
+/// The strands of a [`Table`] waiting to be processed, in the order the
+/// [`SchedulingPolicy`] advances them.
+#[derive(Debug, Clone)]
+enum WorkList {
+    /// The strands of [`SchedulingPolicy::Fifo`] and
+    /// [`SchedulingPolicy::Lifo`], the next one first.
+    Queue(VecDeque<Strand>),
+
+    /// The strands of [`SchedulingPolicy::ShortestFirst`], the cheapest one
+    /// on top, see [`Strand::cost`].
+    Prioritized {
+        heap: BinaryHeap<Prioritized>,
+
+        /// The sequence of the next strand pushed at the back, counting up.
+        back: isize,
+
+        /// The sequence of the last strand put back at the front, counting
+        /// down.
+        front: isize,
+    },
+}
+
+/// A strand of a [`WorkList::Prioritized`], the strands of the same cost are
+/// advanced in the order of their sequence.
+#[derive(Debug, Clone)]
+struct Prioritized {
+    strand: Strand,
+    sequence: isize,
+}
+
+impl PartialEq for Prioritized {
+    fn eq(&self, other: &Self) -> bool { self.cmp(other) == Ordering::Equal }
+}
+
+impl Eq for Prioritized {}
+
+impl PartialOrd for Prioritized {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The cheapest strand, and the first one pushed among those of the same
+/// cost, is the greatest, i.e. the top of the max-heap.
+impl Ord for Prioritized {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .strand
+            .cost
+            .cmp(&self.strand.cost)
+            .then(other.sequence.cmp(&self.sequence))
+    }
+}
+
+/// Two work lists are equal if they advance the same strands in the same
+/// order.
+impl PartialEq for WorkList {
+    fn eq(&self, other: &Self) -> bool { self.iter().eq(other.iter()) }
+}
+
+impl Eq for WorkList {}
+
+impl Default for WorkList {
+    fn default() -> Self { Self::Queue(VecDeque::new()) }
+}
+
+impl WorkList {
+    fn new(policy: SchedulingPolicy) -> Self {
+        match policy {
+            SchedulingPolicy::Fifo | SchedulingPolicy::Lifo => Self::default(),
+            SchedulingPolicy::ShortestFirst => {
+                Self::Prioritized { heap: BinaryHeap::new(), back: 0, front: 0 }
+            }
+        }
+    }
+
+    /// Rebuilds the work list for the policy if it orders the strands
+    /// differently, keeping the order they were to be advanced in among the
+    /// strands of the same cost.
+    fn set_scheduling_policy(&mut self, policy: SchedulingPolicy) {
+        let prioritized = policy == SchedulingPolicy::ShortestFirst;
+
+        if prioritized != matches!(self, Self::Prioritized { .. }) {
+            let strands = self.drain();
+
+            *self = Self::new(policy);
+            self.extend(strands);
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Self::Queue(strands) => strands.len(),
+            Self::Prioritized { heap, .. } => heap.len(),
+        }
+    }
+
+    fn is_empty(&self) -> bool { self.len() == 0 }
+
+    fn clear(&mut self) {
+        match self {
+            Self::Queue(strands) => strands.clear(),
+            Self::Prioritized { heap, .. } => heap.clear(),
+        }
+    }
+
+    /// Removes the next strand to advance.
+    fn pop_front(&mut self) -> Option<Strand> {
+        match self {
+            Self::Queue(strands) => strands.pop_front(),
+            Self::Prioritized { heap, .. } => {
+                heap.pop().map(|prioritized| prioritized.strand)
+            }
+        }
+    }
+
+    /// Adds the strand at the end of the queue, or behind the strands of the
+    /// same cost.
+    fn push_back(&mut self, strand: Strand) {
+        match self {
+            Self::Queue(strands) => strands.push_back(strand),
+            Self::Prioritized { heap, back, .. } => {
+                heap.push(Prioritized { strand, sequence: *back });
+                *back += 1;
+            }
+        }
+    }
+
+    /// Adds the strand at the front of the queue, or in front of the strands
+    /// of the same cost.
+    fn push_front(&mut self, strand: Strand) {
+        match self {
+            Self::Queue(strands) => strands.push_front(strand),
+            Self::Prioritized { heap, front, .. } => {
+                *front -= 1;
+                heap.push(Prioritized { strand, sequence: *front });
+            }
+        }
+    }
+
+    /// Reverses the order of the queue, the order of the strands of
+    /// [`SchedulingPolicy::ShortestFirst`] only depends on their cost and
+    /// sequence.
+    fn reverse(&mut self) {
+        if let Self::Queue(strands) = self {
+            strands.make_contiguous().reverse();
+        }
+    }
+
+    fn retain(&mut self, mut f: impl FnMut(&Strand) -> bool) {
+        match self {
+            Self::Queue(strands) => strands.retain(|strand| f(strand)),
+            Self::Prioritized { heap, .. } => {
+                heap.retain(|prioritized| f(&prioritized.strand));
+            }
+        }
+    }
+
+    /// Returns the strands, the next one first.
+    fn iter(&self) -> impl Iterator<Item = &Strand> {
+        let strands: Vec<_> = match self {
+            Self::Queue(strands) => strands.iter().collect(),
+            Self::Prioritized { heap, .. } => {
+                let mut prioritized = heap.iter().collect::<Vec<_>>();
+                prioritized.sort_unstable_by(|lhs, rhs| rhs.cmp(lhs));
+
+                prioritized
+                    .into_iter()
+                    .map(|prioritized| &prioritized.strand)
+                    .collect()
+            }
+        };
+
+        strands.into_iter()
+    }
+
+    /// Removes every strand, the next one first, keeping the work list
+    /// ordered for the same policy.
+    fn drain(&mut self) -> Vec<Strand> {
+        match self {
+            Self::Queue(strands) => strands.drain(..).collect(),
+            Self::Prioritized { heap, .. } => {
+                let mut strands = std::mem::take(heap).into_sorted_vec();
+                strands.reverse();
+
+                strands
+                    .into_iter()
+                    .map(|prioritized| prioritized.strand)
+                    .collect()
+            }
+        }
+    }
+}
+
+impl Extend<Strand> for WorkList {
+    fn extend<I: IntoIterator<Item = Strand>>(&mut self, strands: I) {
+        for strand in strands {
+            self.push_back(strand);
+        }
+    }
+}
+
 /// A strand consists of a series of subgoals that need to be proven in order
 /// to establish the validity of the original goal.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -468,4 +2290,95 @@ pub struct Strand {
     /// Describes how to pull out the answer from the
     /// [`Self::selected_subgoal`]
     selected_subgoal_state: GoalState,
+
+    /// The index of the clause this strand originates from among the
+    /// candidates of the table.
+    clause_index: usize,
+
+    /// The number of cuts in the clause body this strand has gone past.
+    cuts_passed: usize,
+
+    /// Set if the strand collects every answer of the
+    /// [`Self::selected_subgoal`] for `findall/3` instead of forking on each
+    /// of them.
+    collector: Option<Collector>,
+
+    /// Set if the strand proves `\+ Goal`, the [`Self::selected_subgoal`]
+    /// being `Goal`; the strand is dropped on its first answer and continues
+    /// once it runs out of answers instead.
+    negated: bool,
+
+    /// The length of the derivation so far, see [`Self::cost`].
+    cost: usize,
+}
+
+impl Strand {
+    /// Returns the subgoal whose answers the strand is consuming, with the
+    /// [`Self::substitution`] applied and then canonicalized, i.e. the goal of
+    /// the table the answers come from.
+    #[must_use]
+    pub fn selected_subgoal(&self) -> &Goal { &self.selected_subgoal }
+
+    /// Returns the subgoals left to prove after the
+    /// [`Self::selected_subgoal`], in order.
+    #[must_use]
+    pub fn rest_subgoals(&self) -> &VecDeque<Goal> { &self.rest_subgoals }
+
+    /// Returns the substitution built so far, over the variables of the
+    /// canonical goal of the table and of the clause the strand originates
+    /// from.
+    #[must_use]
+    pub fn substitution(&self) -> &Substitution { &self.substitution }
+
+    /// Returns the length of the derivation so far: the number of clauses
+    /// resolved to reach the strand, including the ones behind the answers
+    /// it has consumed, see [`Table::answer_costs`].
+    #[must_use]
+    pub fn cost(&self) -> usize { self.cost }
+}
+
+/// The state of a strand evaluating `findall(Template, Goal, List)`.
+///
+/// Every answer of the goal is materialized into [`Self::instances`] before
+/// the strand can continue, so `findall/3` over a goal with many answers
+/// holds all of them in memory at once. A goal that never completes, e.g.
+/// one depending on the table containing the `findall/3` itself, makes the
+/// `findall/3` fail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Collector {
+    /// The term instantiated for every answer.
+    template: Term,
+
+    /// The term unified with the list of instances once the goal completes.
+    result: Term,
+
+    /// The instances of the template collected so far.
+    instances: Vec<Term>,
 }
+
+/// Checks whether any binding of the substitution is larger than
+/// `max_term_size`, see [`Term::size`].
+fn exceeds(substitution: &Substitution, max_term_size: usize) -> bool {
+    substitution.mapping.values().any(|term| term.size() > max_term_size)
+}
+
+/// Returns the first variable past every variable of the substitution and the
+/// terms, from which new variables can be introduced without clashing.
+pub(super) fn first_fresh_variable<'t>(
+    substitution: &Substitution,
+    terms: impl IntoIterator<Item = &'t Term>,
+) -> usize {
+    substitution
+        .mapping
+        .iter()
+        .flat_map(|(variable, term)| {
+            [Some(*variable), term.max_variable_offset()]
+        })
+        .chain(terms.into_iter().map(Term::max_variable_offset))
+        .flatten()
+        .max()
+        .map_or(0, |max| max + 1)
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,289 @@
+use std::collections::HashSet;
+
+use crate::{
+    arena::ID,
+    clause::{Clause, Goal, KnowledgeBase, Predicate},
+    parser::parse_goal,
+    solver::{SchedulingPolicy, Solver, table::Table},
+    substitution::Substitution,
+    symbol::Symbol,
+    term::Term,
+};
+
+fn path_knowledge_base() -> KnowledgeBase {
+    // edge(a, b).
+    // edge(b, c).
+    // edge(c, d).
+    //
+    // path(X, Y) :- edge(X, Y).
+    // path(X, Y) :- edge(X, Z), path(Z, Y).
+    let mut kb = KnowledgeBase::new();
+
+    for (from, to) in [("a", "b"), ("b", "c"), ("c", "d")] {
+        kb.add_clause(Clause {
+            head: Predicate {
+                name: "edge".into(),
+                arguments: vec![Term::atom(from), Term::atom(to)],
+            },
+            body: vec![],
+        });
+    }
+
+    let edge = |from: usize, to: usize| Goal {
+        predicate: Predicate {
+            name: "edge".into(),
+            arguments: vec![Term::variable(from), Term::variable(to)],
+        },
+    };
+    let path = |from: usize, to: usize| Predicate {
+        name: "path".into(),
+        arguments: vec![Term::variable(from), Term::variable(to)],
+    };
+
+    kb.add_clause(Clause { head: path(0, 1), body: vec![edge(0, 1)] });
+    kb.add_clause(Clause {
+        head: path(0, 1),
+        body: vec![edge(0, 2), Goal { predicate: path(2, 1) }],
+    });
+
+    kb
+}
+
+fn solve_path_from(solver: &mut Solver, from: &str) {
+    let mut goal_state = solver.create_goal_state(Goal {
+        predicate: Predicate {
+            name: "path".into(),
+            arguments: vec![Term::atom(from), Term::variable(0)],
+        },
+    });
+
+    while solver.pull_next_goal(&mut goal_state).unwrap().is_some() {}
+}
+
+#[test]
+fn removed_table_ids_are_reused_by_a_later_generation() {
+    let kb = path_knowledge_base();
+    let mut solver = Solver::new(&kb);
+
+    solve_path_from(&mut solver, "c");
+
+    let ids_before = table_ids(&solver);
+    assert!(ids_before.len() > 1);
+
+    // reclaim the table of the first query as a garbage collector would
+    let goal = Goal {
+        predicate: Predicate {
+            name: "path".into(),
+            arguments: vec![Term::atom("c"), Term::variable(0)],
+        },
+    };
+    let removed = solver.tables.find(&goal).unwrap();
+    let generation = solver.tables.generation(removed);
+    let _ = solver.tables.tables.remove(removed).unwrap();
+
+    solve_path_from(&mut solver, "a");
+
+    // `path(b, ?0)` needs `path(c, ?0)` again, whose table is created again
+    // with the ID freed above, but as a later generation
+    let recreated = solver.tables.find(&goal).unwrap();
+
+    assert_eq!(recreated, removed);
+    assert!(solver.tables.generation(recreated) > generation);
+    assert!(!solver.tables.contains(recreated, generation));
+    assert_eq!(solver.tables.tables.tombstone_count(), 0);
+
+    // every table is found by its goal, no two of them share an ID
+    for (id, table) in solver.tables.tables.iter() {
+        assert_eq!(solver.tables.find(&table.canonicalized_goal), Some(id));
+    }
+}
+
+#[test]
+fn invalidated_tables_leave_no_tombstone_behind() {
+    let mut solver = Solver::owning(path_knowledge_base());
+    solver.declare_dynamic(("edge", 2));
+
+    let goal = parse_goal("path(a, X)").unwrap();
+    let mut previous = None;
+
+    for index in 0..100 {
+        let mut goal_state = solver.create_goal_state(goal.clone());
+
+        let mut count = 0;
+        while solver.pull_next_goal(&mut goal_state).unwrap().is_some() {
+            count += 1;
+        }
+        assert_eq!(count, 3 + index);
+
+        // every assertion drops the tables of `path/2`, the goal state of
+        // the previous round reads from a table created again since
+        if let Some(mut previous) = previous.replace(goal_state) {
+            assert_eq!(
+                solver.pull_next_goal(&mut previous),
+                Err(crate::solver::SolveError::StaleGoalState)
+            );
+        }
+
+        let fact = parse_goal(&format!("edge(d, n{index})")).unwrap();
+        solver.assertz(fact.predicate).unwrap();
+    }
+
+    assert_eq!(solver.tables.tables.tombstone_count(), 0);
+}
+
+fn table_ids(solver: &Solver) -> HashSet<ID<Table>> {
+    solver.tables.tables.iter().map(|(id, _)| id).collect()
+}
+
+#[test]
+fn identical_canonical_goals_share_table_id() {
+    let kb = path_knowledge_base();
+    let mut solver = Solver::new(&kb);
+
+    let path_from_a = |variable| Goal {
+        predicate: Predicate {
+            name: "path".into(),
+            arguments: vec![Term::atom("a"), Term::variable(variable)],
+        },
+    };
+
+    // both canonicalize to `path(a, ?0)`
+    let first = solver.create_goal_state(path_from_a(3));
+    let second = solver.create_goal_state(path_from_a(0));
+
+    assert_eq!(first.table_id, second.table_id);
+    assert_eq!(solver.tables.find(&path_from_a(0)), Some(first.table_id));
+}
+
+#[test]
+fn alpha_equivalent_goals_share_table_id() {
+    let kb = path_knowledge_base();
+    let mut solver = Solver::new(&kb);
+
+    let path = |from, to| Goal {
+        predicate: Predicate {
+            name: "path".into(),
+            arguments: vec![Term::variable(from), Term::variable(to)],
+        },
+    };
+
+    // neither goal is canonical, both stand for `path(?0, ?1)`
+    let first = solver.get_table_id(&path(3, 7));
+    let second = solver.get_table_id(&path(5, 2));
+
+    assert_eq!(first, second);
+    assert_eq!(solver.tables.find(&path(0, 1)), Some(first));
+
+    // `path(?0, ?0)` isn't alpha-equivalent to the goals above
+    assert_ne!(solver.get_table_id(&path(4, 4)), first);
+}
+
+#[test]
+fn answers_keep_sparse_query_variables() {
+    // p(?7, f(?2, ?7), ?42)
+    let goal = Goal::new("p", [
+        Term::variable(7),
+        Term::component("f", [Term::variable(2), Term::variable(7)]),
+        Term::variable(42),
+    ]);
+
+    assert_eq!(goal.query_variables(), [7, 2, 42]);
+
+    let mut table = Table::new(&goal, SchedulingPolicy::Fifo);
+
+    // ?3 is introduced by a clause, it's below the largest query variable
+    // but isn't part of the query
+    let mut answer = Substitution::default();
+    answer.mapping.insert(7, Term::atom("a"));
+    answer.mapping.insert(2, Term::component("g", [Term::variable(3)]));
+    answer.mapping.insert(3, Term::atom("b"));
+    answer.mapping.insert(42, Term::atom("c"));
+
+    assert_eq!(table.insert_answer(answer, 1, false, None), Ok(true));
+
+    let mut variables =
+        table.answers[0].mapping.keys().copied().collect::<Vec<_>>();
+    variables.sort_unstable();
+
+    assert_eq!(variables, [2, 7, 42]);
+    assert_eq!(table.answers[0].mapping[&2].to_string(), "g(b)");
+}
+
+#[test]
+fn alpha_equivalent_answers_are_stored_once() {
+    // the variable left unbound is numbered differently by each clause
+    let kb = "
+        p(g(A)).
+        p(W) :- W = g(V).
+    "
+    .parse::<KnowledgeBase>()
+    .unwrap();
+    let mut solver = Solver::new(&kb);
+
+    let answers = solver.solve_all(parse_goal("p(X)").unwrap()).unwrap();
+    assert_eq!(answers.len(), 1);
+
+    let goal_state = solver.create_goal_state(parse_goal("p(X)").unwrap());
+    assert_eq!(solver.table(&goal_state).answers().len(), 1);
+
+    // inserted directly, the renumbered variable is the same for both
+    let mut table =
+        Table::new(&parse_goal("p(X)").unwrap(), SchedulingPolicy::Fifo);
+
+    for residual in [3, 8] {
+        let mut answer = Substitution::default();
+        answer
+            .mapping
+            .insert(0, Term::component("g", [Term::variable(residual)]));
+
+        assert_eq!(
+            table.insert_answer(answer, 1, false, None),
+            Ok(residual == 3)
+        );
+    }
+
+    assert_eq!(table.answers[0].mapping[&0].to_string(), "g(?1)");
+}
+
+#[test]
+fn stable_table_ids_are_reproducible() {
+    let kb = KnowledgeBase::new();
+    let mut solver = Solver::new(&kb);
+    solver.set_stable_table_ids(true);
+
+    // the FNV-1a hash of the name, the arguments count, and the tagged
+    // arguments `?0` and `2`
+    let goal_state =
+        solver.create_goal_state(parse_goal("length(L, 2)").unwrap());
+
+    assert_eq!(goal_state.table_id.index(), 6_292_839_628_153_347_090);
+
+    // the same goal maps to the same ID in another solver
+    let mut other = Solver::new(&kb);
+    other.set_stable_table_ids(true);
+
+    let other_state =
+        other.create_goal_state(parse_goal("length(X, 2)").unwrap());
+    assert_eq!(other_state.table_id, goal_state.table_id);
+
+    // switching back discards the tables
+    solver.set_stable_table_ids(false);
+    assert_eq!(solver.table_count(), 0);
+}
+
+#[test]
+fn stable_table_ids_ignore_the_interning_order() {
+    let kb = KnowledgeBase::new();
+    let mut solver = Solver::new(&kb);
+    solver.set_stable_table_ids(true);
+
+    // interned in the reverse order of their appearance in the goal, the
+    // atoms hash by their names regardless
+    let _ = Symbol::new("stable_id_b");
+    let _ = Symbol::new("stable_id_a");
+
+    let goal_state = solver
+        .create_goal_state(parse_goal("p(stable_id_a, stable_id_b)").unwrap());
+
+    assert_eq!(goal_state.table_id.index(), 5_794_045_085_642_268_531);
+}
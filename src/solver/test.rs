@@ -1,7 +1,17 @@
 // Basic tests for the SLG solver
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
 use crate::{
-    clause::{Clause, Goal, KnowledgeBase, Predicate},
-    solver::Solver,
+    canonicalize::uncanonicalize_substitution,
+    clause::{BodyElem, Clause, Functor, Goal, KnowledgeBase, Mode, Predicate},
+    parser::{parse_clause, parse_goal, parse_program},
+    solver::{
+        AssertError, FailureReason, SchedulingPolicy, Solution, SolveError,
+        Solver, TraceEvent,
+    },
     substitution::Substitution,
     term::Term,
 };
@@ -11,7 +21,7 @@ fn simple_fact() {
     // fact: parent(alice, bob).
     let clause = Clause {
         head: Predicate {
-            name: "parent".to_string(),
+            name: "parent".into(),
             arguments: vec![Term::atom("alice"), Term::atom("bob")],
         },
         body: vec![],
@@ -21,7 +31,7 @@ fn simple_fact() {
 
     let goal = Goal {
         predicate: Predicate {
-            name: "parent".to_string(),
+            name: "parent".into(),
             arguments: vec![Term::atom("alice"), Term::atom("bob")],
         },
     };
@@ -29,13 +39,13 @@ fn simple_fact() {
     let mut solver = Solver::new(&kb);
     let mut goal_state = solver.create_goal_state(goal);
 
-    let solution = solver.pull_next_goal(&mut goal_state).unwrap();
+    let solution = solver.pull_next_goal(&mut goal_state).unwrap().unwrap();
 
     // no inference variable in the query, therefore, the mapping should be
     // empty
     assert!(solution.mapping.is_empty());
 
-    assert!(solver.pull_next_goal(&mut goal_state).is_none());
+    assert!(solver.pull_next_goal(&mut goal_state).unwrap().is_none());
 }
 
 #[test]
@@ -43,19 +53,19 @@ fn simple_rule() {
     // rule: grandparent(X, Y) :- parent(X, Z), parent(Z, Y).
     let clause = Clause {
         head: Predicate {
-            name: "grandparent".to_string(),
+            name: "grandparent".into(),
             arguments: vec![Term::variable(0), Term::variable(1)],
         },
         body: vec![
             Goal {
                 predicate: Predicate {
-                    name: "parent".to_string(),
+                    name: "parent".into(),
                     arguments: vec![Term::variable(0), Term::variable(2)],
                 },
             },
             Goal {
                 predicate: Predicate {
-                    name: "parent".to_string(),
+                    name: "parent".into(),
                     arguments: vec![Term::variable(2), Term::variable(1)],
                 },
             },
@@ -64,14 +74,14 @@ fn simple_rule() {
 
     let fact1 = Clause {
         head: Predicate {
-            name: "parent".to_string(),
+            name: "parent".into(),
             arguments: vec![Term::atom("alice"), Term::atom("bob")],
         },
         body: vec![],
     };
     let fact2 = Clause {
         head: Predicate {
-            name: "parent".to_string(),
+            name: "parent".into(),
             arguments: vec![Term::atom("bob"), Term::atom("carol")],
         },
         body: vec![],
@@ -84,20 +94,20 @@ fn simple_rule() {
 
     let goal = Goal {
         predicate: Predicate {
-            name: "grandparent".to_string(),
+            name: "grandparent".into(),
             arguments: vec![Term::atom("alice"), Term::atom("carol")],
         },
     };
     let mut solver = Solver::new(&kb);
     let mut goal_state = solver.create_goal_state(goal);
 
-    let solution = solver.pull_next_goal(&mut goal_state).unwrap();
+    let solution = solver.pull_next_goal(&mut goal_state).unwrap().unwrap();
 
     // no inference variable in the query, therefore, the mapping should be
     // empty
     assert!(dbg!(solution.mapping).is_empty());
 
-    assert!(solver.pull_next_goal(&mut goal_state).is_none());
+    assert!(solver.pull_next_goal(&mut goal_state).unwrap().is_none());
 }
 
 #[test]
@@ -109,7 +119,7 @@ fn enumerate_single_solution() {
 
     let fact1 = Clause {
         head: Predicate {
-            name: "parent".to_string(),
+            name: "parent".into(),
             arguments: vec![Term::atom("alice"), Term::atom("bob")],
         },
         body: vec![],
@@ -117,7 +127,7 @@ fn enumerate_single_solution() {
 
     let fact2 = Clause {
         head: Predicate {
-            name: "parent".to_string(),
+            name: "parent".into(),
             arguments: vec![Term::atom("bob"), Term::atom("dave")],
         },
         body: vec![],
@@ -125,19 +135,19 @@ fn enumerate_single_solution() {
 
     let grandparent_rule = Clause {
         head: Predicate {
-            name: "grandparent".to_string(),
+            name: "grandparent".into(),
             arguments: vec![Term::variable(0), Term::variable(1)],
         },
         body: vec![
             Goal {
                 predicate: Predicate {
-                    name: "parent".to_string(),
+                    name: "parent".into(),
                     arguments: vec![Term::variable(0), Term::variable(2)],
                 },
             },
             Goal {
                 predicate: Predicate {
-                    name: "parent".to_string(),
+                    name: "parent".into(),
                     arguments: vec![Term::variable(2), Term::variable(1)],
                 },
             },
@@ -151,7 +161,7 @@ fn enumerate_single_solution() {
 
     let query = Goal {
         predicate: Predicate {
-            name: "grandparent".to_string(),
+            name: "grandparent".into(),
             arguments: vec![Term::atom("alice"), Term::variable(0)],
         },
     };
@@ -159,9 +169,10 @@ fn enumerate_single_solution() {
     let mut solver = Solver::new(&kb);
     let mut goal_state = solver.create_goal_state(query);
 
-    let queried_solution = solver.pull_next_goal(&mut goal_state).unwrap();
+    let queried_solution =
+        solver.pull_next_goal(&mut goal_state).unwrap().unwrap();
 
-    assert!(solver.pull_next_goal(&mut goal_state).is_none());
+    assert!(solver.pull_next_goal(&mut goal_state).unwrap().is_none());
 
     assert_eq!(queried_solution.mapping.len(), 1);
     assert_eq!(queried_solution.mapping.get(&0), Some(&Term::atom("dave")));
@@ -175,14 +186,14 @@ fn enumerate_multiple_solution() {
 
     let fact1 = Clause {
         head: Predicate {
-            name: "parent".to_string(),
+            name: "parent".into(),
             arguments: vec![Term::atom("alice"), Term::atom("dave")],
         },
         body: vec![],
     };
     let fact2 = Clause {
         head: Predicate {
-            name: "parent".to_string(),
+            name: "parent".into(),
             arguments: vec![Term::atom("bob"), Term::atom("carol")],
         },
         body: vec![],
@@ -194,7 +205,7 @@ fn enumerate_multiple_solution() {
 
     let query = Goal {
         predicate: Predicate {
-            name: "parent".to_string(),
+            name: "parent".into(),
             arguments: vec![Term::variable(0), Term::variable(1)],
         },
     };
@@ -202,10 +213,12 @@ fn enumerate_multiple_solution() {
     let mut solver = Solver::new(&kb);
     let mut goal_state = solver.create_goal_state(query);
 
-    let queried_solution_1 = solver.pull_next_goal(&mut goal_state).unwrap();
-    let queried_solution_2 = solver.pull_next_goal(&mut goal_state).unwrap();
+    let queried_solution_1 =
+        solver.pull_next_goal(&mut goal_state).unwrap().unwrap();
+    let queried_solution_2 =
+        solver.pull_next_goal(&mut goal_state).unwrap().unwrap();
 
-    assert!(solver.pull_next_goal(&mut goal_state).is_none());
+    assert!(solver.pull_next_goal(&mut goal_state).unwrap().is_none());
 
     let expecteds = [
         Substitution {
@@ -232,21 +245,21 @@ fn enumerate_multiple_nested_solution() {
     // parent(dave, carol).
     let fact1 = Clause {
         head: Predicate {
-            name: "parent".to_string(),
+            name: "parent".into(),
             arguments: vec![Term::atom("bob"), Term::atom("alice")],
         },
         body: vec![],
     };
     let fact2 = Clause {
         head: Predicate {
-            name: "parent".to_string(),
+            name: "parent".into(),
             arguments: vec![Term::atom("alice"), Term::atom("dave")],
         },
         body: vec![],
     };
     let fact3 = Clause {
         head: Predicate {
-            name: "parent".to_string(),
+            name: "parent".into(),
             arguments: vec![Term::atom("dave"), Term::atom("carol")],
         },
         body: vec![],
@@ -255,19 +268,19 @@ fn enumerate_multiple_nested_solution() {
     // rule: grandparent(X, Y) :- parent(X, Z), parent(Z, Y).
     let grandparent_rule = Clause {
         head: Predicate {
-            name: "grandparent".to_string(),
+            name: "grandparent".into(),
             arguments: vec![Term::variable(0), Term::variable(1)],
         },
         body: vec![
             Goal {
                 predicate: Predicate {
-                    name: "parent".to_string(),
+                    name: "parent".into(),
                     arguments: vec![Term::variable(0), Term::variable(2)],
                 },
             },
             Goal {
                 predicate: Predicate {
-                    name: "parent".to_string(),
+                    name: "parent".into(),
                     arguments: vec![Term::variable(2), Term::variable(1)],
                 },
             },
@@ -277,19 +290,19 @@ fn enumerate_multiple_nested_solution() {
     // rule: great_grandparent(X, Y) :- parent(X, Z), grandparent(Z, Y)
     let great_grandparent_rule = Clause {
         head: Predicate {
-            name: "great_grandparent".to_string(),
+            name: "great_grandparent".into(),
             arguments: vec![Term::variable(0), Term::variable(1)],
         },
         body: vec![
             Goal {
                 predicate: Predicate {
-                    name: "parent".to_string(),
+                    name: "parent".into(),
                     arguments: vec![Term::variable(0), Term::variable(2)],
                 },
             },
             Goal {
                 predicate: Predicate {
-                    name: "grandparent".to_string(),
+                    name: "grandparent".into(),
                     arguments: vec![Term::variable(2), Term::variable(1)],
                 },
             },
@@ -306,7 +319,7 @@ fn enumerate_multiple_nested_solution() {
     // Test grandparent(?0, ?1) - should return 2 solutions
     let grandparent_query = Goal {
         predicate: Predicate {
-            name: "grandparent".to_string(),
+            name: "grandparent".into(),
             arguments: vec![Term::variable(0), Term::variable(1)],
         },
     };
@@ -314,10 +327,10 @@ fn enumerate_multiple_nested_solution() {
     let mut solver = Solver::new(&kb);
     let mut goal_state = solver.create_goal_state(grandparent_query);
 
-    let solution1 = solver.pull_next_goal(&mut goal_state).unwrap();
-    let solution2 = solver.pull_next_goal(&mut goal_state).unwrap();
+    let solution1 = solver.pull_next_goal(&mut goal_state).unwrap().unwrap();
+    let solution2 = solver.pull_next_goal(&mut goal_state).unwrap().unwrap();
 
-    assert_eq!(solver.pull_next_goal(&mut goal_state), None);
+    assert_eq!(solver.pull_next_goal(&mut goal_state).unwrap(), None);
 
     let expected_grandparent_solutions = [
         Substitution {
@@ -338,16 +351,16 @@ fn enumerate_multiple_nested_solution() {
     // Test great_grandparent(?0, ?1) - should return 1 solution
     let great_grandparent_query = Goal {
         predicate: Predicate {
-            name: "great_grandparent".to_string(),
+            name: "great_grandparent".into(),
             arguments: vec![Term::variable(0), Term::variable(1)],
         },
     };
 
     let mut goal_state = solver.create_goal_state(great_grandparent_query);
 
-    let solution = solver.pull_next_goal(&mut goal_state).unwrap();
+    let solution = solver.pull_next_goal(&mut goal_state).unwrap().unwrap();
 
-    assert!(solver.pull_next_goal(&mut goal_state).is_none());
+    assert!(solver.pull_next_goal(&mut goal_state).unwrap().is_none());
 
     let expected_great_grandparent_solution = Substitution {
         mapping: [(0, Term::atom("bob")), (1, Term::atom("carol"))]
@@ -363,7 +376,7 @@ fn no_solution() {
     // fact: parent(alice, bob).
     let clause = Clause {
         head: Predicate {
-            name: "parent".to_string(),
+            name: "parent".into(),
             arguments: vec![Term::atom("alice"), Term::atom("bob")],
         },
         body: vec![],
@@ -373,14 +386,14 @@ fn no_solution() {
 
     let goal = Goal {
         predicate: Predicate {
-            name: "parent".to_string(),
+            name: "parent".into(),
             arguments: vec![Term::atom("bob"), Term::atom("alice")],
         },
     };
     let mut solver = Solver::new(&kb);
     let mut goal_state = solver.create_goal_state(goal);
 
-    let solution = solver.pull_next_goal(&mut goal_state);
+    let solution = solver.pull_next_goal(&mut goal_state).unwrap();
 
     assert!(solution.is_none());
 }
@@ -390,21 +403,21 @@ fn recursive_query() {
     // Create facts
     let fact1 = Clause {
         head: Predicate {
-            name: "over".to_string(),
+            name: "over".into(),
             arguments: vec![Term::atom("a"), Term::atom("b")],
         },
         body: vec![],
     };
     let fact2 = Clause {
         head: Predicate {
-            name: "over".to_string(),
+            name: "over".into(),
             arguments: vec![Term::atom("b"), Term::atom("c")],
         },
         body: vec![],
     };
     let fact3 = Clause {
         head: Predicate {
-            name: "over".to_string(),
+            name: "over".into(),
             arguments: vec![Term::atom("c"), Term::atom("d")],
         },
         body: vec![],
@@ -413,19 +426,19 @@ fn recursive_query() {
     // Create transitive rule: over(?0, ?1) :- over(?0, ?2), over(?2, ?1).
     let transitive_rule = Clause {
         head: Predicate {
-            name: "over".to_string(),
+            name: "over".into(),
             arguments: vec![Term::variable(0), Term::variable(1)],
         },
         body: vec![
             Goal {
                 predicate: Predicate {
-                    name: "over".to_string(),
+                    name: "over".into(),
                     arguments: vec![Term::variable(0), Term::variable(2)],
                 },
             },
             Goal {
                 predicate: Predicate {
-                    name: "over".to_string(),
+                    name: "over".into(),
                     arguments: vec![Term::variable(2), Term::variable(1)],
                 },
             },
@@ -440,7 +453,7 @@ fn recursive_query() {
 
     let query = Goal {
         predicate: Predicate {
-            name: "over".to_string(),
+            name: "over".into(),
             arguments: vec![Term::atom("a"), Term::atom("d")],
         },
     };
@@ -449,9 +462,9 @@ fn recursive_query() {
     let mut goal_state = solver.create_goal_state(query);
 
     // Collect all solutions
-    let solution1 = solver.pull_next_goal(&mut goal_state).unwrap();
+    let solution1 = solver.pull_next_goal(&mut goal_state).unwrap().unwrap();
 
-    assert_eq!(solver.pull_next_goal(&mut goal_state), None);
+    assert_eq!(solver.pull_next_goal(&mut goal_state).unwrap(), None);
 
     assert!(solution1.mapping.is_empty());
 }
@@ -461,21 +474,21 @@ fn enumerate_recursive_query() {
     // Create facts
     let fact1 = Clause {
         head: Predicate {
-            name: "over".to_string(),
+            name: "over".into(),
             arguments: vec![Term::atom("a"), Term::atom("b")],
         },
         body: vec![],
     };
     let fact2 = Clause {
         head: Predicate {
-            name: "over".to_string(),
+            name: "over".into(),
             arguments: vec![Term::atom("b"), Term::atom("c")],
         },
         body: vec![],
     };
     let fact3 = Clause {
         head: Predicate {
-            name: "over".to_string(),
+            name: "over".into(),
             arguments: vec![Term::atom("c"), Term::atom("d")],
         },
         body: vec![],
@@ -484,19 +497,19 @@ fn enumerate_recursive_query() {
     // Create transitive rule: over(?0, ?1) :- over(?0, ?2), over(?2, ?1).
     let transitive_rule = Clause {
         head: Predicate {
-            name: "over".to_string(),
+            name: "over".into(),
             arguments: vec![Term::variable(0), Term::variable(1)],
         },
         body: vec![
             Goal {
                 predicate: Predicate {
-                    name: "over".to_string(),
+                    name: "over".into(),
                     arguments: vec![Term::variable(0), Term::variable(2)],
                 },
             },
             Goal {
                 predicate: Predicate {
-                    name: "over".to_string(),
+                    name: "over".into(),
                     arguments: vec![Term::variable(2), Term::variable(1)],
                 },
             },
@@ -512,19 +525,13 @@ fn enumerate_recursive_query() {
     // Query: over(a, ?0) - should return solutions where ?0 = b, c, d
     let query = Goal {
         predicate: Predicate {
-            name: "over".to_string(),
+            name: "over".into(),
             arguments: vec![Term::atom("a"), Term::variable(0)],
         },
     };
 
     let mut solver = Solver::new(&kb);
-    let mut goal_state = solver.create_goal_state(query);
-
-    // Collect all solutions
-    let mut solutions = Vec::new();
-    while let Some(solution) = solver.pull_next_goal(&mut goal_state) {
-        solutions.push(solution);
-    }
+    let solutions = solver.solve_all(query).unwrap();
 
     // Should have 3 solutions: ?0 = b, c, d
     assert_eq!(solutions.len(), 3);
@@ -550,67 +557,38 @@ fn mutual_recursion_odd_even() {
     // Facts:
     // even(0).
     // Rules:
-    // odd(X) :- even(Y), succ(Y, X).
-    // even(X) :- odd(Y), succ(Y, X).
-    // succ(0, 1).
-    // succ(1, 2).
-    // succ(2, 3).
-    // succ(3, 4).
+    // odd(X) :- even(Y), Y < 4, succ(Y, X).
+    // even(X) :- odd(Y), Y < 4, succ(Y, X).
 
     let even_fact = Clause {
         head: Predicate {
-            name: "even".to_string(),
-            arguments: vec![Term::atom("0")],
+            name: "even".into(),
+            arguments: vec![Term::integer(0)],
         },
         body: vec![],
     };
 
-    let succ_facts = vec![
-        Clause {
-            head: Predicate {
-                name: "succ".to_string(),
-                arguments: vec![Term::atom("0"), Term::atom("1")],
-            },
-            body: vec![],
-        },
-        Clause {
-            head: Predicate {
-                name: "succ".to_string(),
-                arguments: vec![Term::atom("1"), Term::atom("2")],
-            },
-            body: vec![],
-        },
-        Clause {
-            head: Predicate {
-                name: "succ".to_string(),
-                arguments: vec![Term::atom("2"), Term::atom("3")],
-            },
-            body: vec![],
-        },
-        Clause {
-            head: Predicate {
-                name: "succ".to_string(),
-                arguments: vec![Term::atom("3"), Term::atom("4")],
-            },
-            body: vec![],
-        },
-    ];
-
     let odd_rule = Clause {
         head: Predicate {
-            name: "odd".to_string(),
+            name: "odd".into(),
             arguments: vec![Term::variable(0)],
         },
         body: vec![
             Goal {
                 predicate: Predicate {
-                    name: "even".to_string(),
+                    name: "even".into(),
                     arguments: vec![Term::variable(1)],
                 },
             },
             Goal {
                 predicate: Predicate {
-                    name: "succ".to_string(),
+                    name: "<".into(),
+                    arguments: vec![Term::variable(1), Term::integer(4)],
+                },
+            },
+            Goal {
+                predicate: Predicate {
+                    name: "succ".into(),
                     arguments: vec![Term::variable(1), Term::variable(0)],
                 },
             },
@@ -619,19 +597,25 @@ fn mutual_recursion_odd_even() {
 
     let even_rule = Clause {
         head: Predicate {
-            name: "even".to_string(),
+            name: "even".into(),
             arguments: vec![Term::variable(0)],
         },
         body: vec![
             Goal {
                 predicate: Predicate {
-                    name: "odd".to_string(),
+                    name: "odd".into(),
                     arguments: vec![Term::variable(1)],
                 },
             },
             Goal {
                 predicate: Predicate {
-                    name: "succ".to_string(),
+                    name: "<".into(),
+                    arguments: vec![Term::variable(1), Term::integer(4)],
+                },
+            },
+            Goal {
+                predicate: Predicate {
+                    name: "succ".into(),
                     arguments: vec![Term::variable(1), Term::variable(0)],
                 },
             },
@@ -640,32 +624,24 @@ fn mutual_recursion_odd_even() {
 
     let mut kb = KnowledgeBase::new();
     kb.add_clause(even_fact);
-    for fact in succ_facts {
-        kb.add_clause(fact);
-    }
     kb.add_clause(odd_rule);
     kb.add_clause(even_rule);
 
     // Test odd(?0) - should return solutions for 1, 3
     let odd_query = Goal {
         predicate: Predicate {
-            name: "odd".to_string(),
+            name: "odd".into(),
             arguments: vec![Term::variable(0)],
         },
     };
 
     let mut solver = Solver::new(&kb);
-    let mut goal_state = solver.create_goal_state(odd_query);
-
-    let mut odd_solutions = Vec::new();
-    while let Some(solution) = solver.pull_next_goal(&mut goal_state) {
-        odd_solutions.push(solution);
-    }
+    let odd_solutions = solver.solve_all(odd_query).unwrap();
 
     assert_eq!(odd_solutions.len(), 2);
     let expected_odd = [
-        Substitution { mapping: [(0, Term::atom("1"))].into_iter().collect() },
-        Substitution { mapping: [(0, Term::atom("3"))].into_iter().collect() },
+        Substitution { mapping: [(0, Term::integer(1))].into_iter().collect() },
+        Substitution { mapping: [(0, Term::integer(3))].into_iter().collect() },
     ];
 
     for expected in &expected_odd {
@@ -678,23 +654,18 @@ fn mutual_recursion_odd_even() {
     // Test even(?0) - should return solutions for 0, 2, 4
     let even_query = Goal {
         predicate: Predicate {
-            name: "even".to_string(),
+            name: "even".into(),
             arguments: vec![Term::variable(0)],
         },
     };
 
-    let mut goal_state = solver.create_goal_state(even_query);
-
-    let mut even_solutions = Vec::new();
-    while let Some(solution) = solver.pull_next_goal(&mut goal_state) {
-        even_solutions.push(solution);
-    }
+    let even_solutions = solver.solve_all(even_query).unwrap();
 
     assert_eq!(even_solutions.len(), 3);
     let expected_even = [
-        Substitution { mapping: [(0, Term::atom("0"))].into_iter().collect() },
-        Substitution { mapping: [(0, Term::atom("2"))].into_iter().collect() },
-        Substitution { mapping: [(0, Term::atom("4"))].into_iter().collect() },
+        Substitution { mapping: [(0, Term::integer(0))].into_iter().collect() },
+        Substitution { mapping: [(0, Term::integer(2))].into_iter().collect() },
+        Substitution { mapping: [(0, Term::integer(4))].into_iter().collect() },
     ];
 
     for expected in &expected_even {
@@ -705,9 +676,8 @@ fn mutual_recursion_odd_even() {
     }
 }
 
-#[test]
-fn graph_reachability_with_multiple_edge_types() {
-    // Test recursive reachability in a graph with multiple edge types
+/// Builds the multi edge type graph program used by several tests.
+fn graph_reachability_knowledge_base() -> KnowledgeBase {
     // Facts for different edge types:
     // road(a, b).
     // road(b, c).
@@ -724,14 +694,14 @@ fn graph_reachability_with_multiple_edge_types() {
     let road_facts = vec![
         Clause {
             head: Predicate {
-                name: "road".to_string(),
+                name: "road".into(),
                 arguments: vec![Term::atom("a"), Term::atom("b")],
             },
             body: vec![],
         },
         Clause {
             head: Predicate {
-                name: "road".to_string(),
+                name: "road".into(),
                 arguments: vec![Term::atom("b"), Term::atom("c")],
             },
             body: vec![],
@@ -741,14 +711,14 @@ fn graph_reachability_with_multiple_edge_types() {
     let rail_facts = vec![
         Clause {
             head: Predicate {
-                name: "rail".to_string(),
+                name: "rail".into(),
                 arguments: vec![Term::atom("c"), Term::atom("d")],
             },
             body: vec![],
         },
         Clause {
             head: Predicate {
-                name: "rail".to_string(),
+                name: "rail".into(),
                 arguments: vec![Term::atom("d"), Term::atom("e")],
             },
             body: vec![],
@@ -757,7 +727,7 @@ fn graph_reachability_with_multiple_edge_types() {
 
     let boat_fact = Clause {
         head: Predicate {
-            name: "boat".to_string(),
+            name: "boat".into(),
             arguments: vec![Term::atom("e"), Term::atom("f")],
         },
         body: vec![],
@@ -766,36 +736,36 @@ fn graph_reachability_with_multiple_edge_types() {
     let connected_rules = vec![
         Clause {
             head: Predicate {
-                name: "connected".to_string(),
+                name: "connected".into(),
                 arguments: vec![Term::variable(0), Term::variable(1)],
             },
             body: vec![Goal {
                 predicate: Predicate {
-                    name: "road".to_string(),
+                    name: "road".into(),
                     arguments: vec![Term::variable(0), Term::variable(1)],
                 },
             }],
         },
         Clause {
             head: Predicate {
-                name: "connected".to_string(),
+                name: "connected".into(),
                 arguments: vec![Term::variable(0), Term::variable(1)],
             },
             body: vec![Goal {
                 predicate: Predicate {
-                    name: "rail".to_string(),
+                    name: "rail".into(),
                     arguments: vec![Term::variable(0), Term::variable(1)],
                 },
             }],
         },
         Clause {
             head: Predicate {
-                name: "connected".to_string(),
+                name: "connected".into(),
                 arguments: vec![Term::variable(0), Term::variable(1)],
             },
             body: vec![Goal {
                 predicate: Predicate {
-                    name: "boat".to_string(),
+                    name: "boat".into(),
                     arguments: vec![Term::variable(0), Term::variable(1)],
                 },
             }],
@@ -804,12 +774,12 @@ fn graph_reachability_with_multiple_edge_types() {
 
     let reachable_base = Clause {
         head: Predicate {
-            name: "reachable".to_string(),
+            name: "reachable".into(),
             arguments: vec![Term::variable(0), Term::variable(1)],
         },
         body: vec![Goal {
             predicate: Predicate {
-                name: "connected".to_string(),
+                name: "connected".into(),
                 arguments: vec![Term::variable(0), Term::variable(1)],
             },
         }],
@@ -817,19 +787,19 @@ fn graph_reachability_with_multiple_edge_types() {
 
     let reachable_recursive = Clause {
         head: Predicate {
-            name: "reachable".to_string(),
+            name: "reachable".into(),
             arguments: vec![Term::variable(0), Term::variable(1)],
         },
         body: vec![
             Goal {
                 predicate: Predicate {
-                    name: "connected".to_string(),
+                    name: "connected".into(),
                     arguments: vec![Term::variable(0), Term::variable(2)],
                 },
             },
             Goal {
                 predicate: Predicate {
-                    name: "reachable".to_string(),
+                    name: "reachable".into(),
                     arguments: vec![Term::variable(2), Term::variable(1)],
                 },
             },
@@ -850,10 +820,18 @@ fn graph_reachability_with_multiple_edge_types() {
     kb.add_clause(reachable_base);
     kb.add_clause(reachable_recursive);
 
+    kb
+}
+
+#[test]
+fn graph_reachability_with_multiple_edge_types() {
+    // Test recursive reachability in a graph with multiple edge types
+    let kb = graph_reachability_knowledge_base();
+
     // Test reachable(a, ?0) - should find all nodes reachable from 'a'
     let query = Goal {
         predicate: Predicate {
-            name: "reachable".to_string(),
+            name: "reachable".into(),
             arguments: vec![Term::atom("a"), Term::variable(0)],
         },
     };
@@ -862,7 +840,7 @@ fn graph_reachability_with_multiple_edge_types() {
     let mut goal_state = solver.create_goal_state(query);
 
     let mut solutions = Vec::new();
-    while let Some(solution) = solver.pull_next_goal(&mut goal_state) {
+    while let Some(solution) = solver.pull_next_goal(&mut goal_state).unwrap() {
         solutions.push(solution);
     }
 
@@ -881,9 +859,8 @@ fn graph_reachability_with_multiple_edge_types() {
     }
 }
 
-#[test]
-fn family_relationships_complex_recursion() {
-    // Test complex family relationships with multiple recursive predicates
+/// Builds the family relationships program used by several tests.
+fn family_knowledge_base() -> KnowledgeBase {
     // Facts:
     // parent(adam, bob).
     // parent(adam, carol).
@@ -905,42 +882,42 @@ fn family_relationships_complex_recursion() {
     let parent_facts = vec![
         Clause {
             head: Predicate {
-                name: "parent".to_string(),
+                name: "parent".into(),
                 arguments: vec![Term::atom("adam"), Term::atom("bob")],
             },
             body: vec![],
         },
         Clause {
             head: Predicate {
-                name: "parent".to_string(),
+                name: "parent".into(),
                 arguments: vec![Term::atom("adam"), Term::atom("carol")],
             },
             body: vec![],
         },
         Clause {
             head: Predicate {
-                name: "parent".to_string(),
+                name: "parent".into(),
                 arguments: vec![Term::atom("bob"), Term::atom("dan")],
             },
             body: vec![],
         },
         Clause {
             head: Predicate {
-                name: "parent".to_string(),
+                name: "parent".into(),
                 arguments: vec![Term::atom("bob"), Term::atom("eve")],
             },
             body: vec![],
         },
         Clause {
             head: Predicate {
-                name: "parent".to_string(),
+                name: "parent".into(),
                 arguments: vec![Term::atom("carol"), Term::atom("frank")],
             },
             body: vec![],
         },
         Clause {
             head: Predicate {
-                name: "parent".to_string(),
+                name: "parent".into(),
                 arguments: vec![Term::atom("dan"), Term::atom("grace")],
             },
             body: vec![],
@@ -949,12 +926,12 @@ fn family_relationships_complex_recursion() {
 
     let ancestor_base = Clause {
         head: Predicate {
-            name: "ancestor".to_string(),
+            name: "ancestor".into(),
             arguments: vec![Term::variable(0), Term::variable(1)],
         },
         body: vec![Goal {
             predicate: Predicate {
-                name: "parent".to_string(),
+                name: "parent".into(),
                 arguments: vec![Term::variable(0), Term::variable(1)],
             },
         }],
@@ -962,19 +939,19 @@ fn family_relationships_complex_recursion() {
 
     let ancestor_recursive = Clause {
         head: Predicate {
-            name: "ancestor".to_string(),
+            name: "ancestor".into(),
             arguments: vec![Term::variable(0), Term::variable(1)],
         },
         body: vec![
             Goal {
                 predicate: Predicate {
-                    name: "parent".to_string(),
+                    name: "parent".into(),
                     arguments: vec![Term::variable(0), Term::variable(2)],
                 },
             },
             Goal {
                 predicate: Predicate {
-                    name: "ancestor".to_string(),
+                    name: "ancestor".into(),
                     arguments: vec![Term::variable(2), Term::variable(1)],
                 },
             },
@@ -983,19 +960,19 @@ fn family_relationships_complex_recursion() {
 
     let sibling_rule = Clause {
         head: Predicate {
-            name: "sibling".to_string(),
+            name: "sibling".into(),
             arguments: vec![Term::variable(0), Term::variable(1)],
         },
         body: vec![
             Goal {
                 predicate: Predicate {
-                    name: "parent".to_string(),
+                    name: "parent".into(),
                     arguments: vec![Term::variable(2), Term::variable(0)],
                 },
             },
             Goal {
                 predicate: Predicate {
-                    name: "parent".to_string(),
+                    name: "parent".into(),
                     arguments: vec![Term::variable(2), Term::variable(1)],
                 },
             },
@@ -1006,25 +983,25 @@ fn family_relationships_complex_recursion() {
 
     let cousin_rule = Clause {
         head: Predicate {
-            name: "cousin".to_string(),
+            name: "cousin".into(),
             arguments: vec![Term::variable(0), Term::variable(1)],
         },
         body: vec![
             Goal {
                 predicate: Predicate {
-                    name: "parent".to_string(),
+                    name: "parent".into(),
                     arguments: vec![Term::variable(2), Term::variable(0)],
                 },
             },
             Goal {
                 predicate: Predicate {
-                    name: "parent".to_string(),
+                    name: "parent".into(),
                     arguments: vec![Term::variable(3), Term::variable(1)],
                 },
             },
             Goal {
                 predicate: Predicate {
-                    name: "sibling".to_string(),
+                    name: "sibling".into(),
                     arguments: vec![Term::variable(2), Term::variable(3)],
                 },
             },
@@ -1034,48 +1011,48 @@ fn family_relationships_complex_recursion() {
     let relative_rules = vec![
         Clause {
             head: Predicate {
-                name: "relative".to_string(),
+                name: "relative".into(),
                 arguments: vec![Term::variable(0), Term::variable(1)],
             },
             body: vec![Goal {
                 predicate: Predicate {
-                    name: "ancestor".to_string(),
+                    name: "ancestor".into(),
                     arguments: vec![Term::variable(0), Term::variable(1)],
                 },
             }],
         },
         Clause {
             head: Predicate {
-                name: "relative".to_string(),
+                name: "relative".into(),
                 arguments: vec![Term::variable(0), Term::variable(1)],
             },
             body: vec![Goal {
                 predicate: Predicate {
-                    name: "ancestor".to_string(),
+                    name: "ancestor".into(),
                     arguments: vec![Term::variable(1), Term::variable(0)],
                 },
             }],
         },
         Clause {
             head: Predicate {
-                name: "relative".to_string(),
+                name: "relative".into(),
                 arguments: vec![Term::variable(0), Term::variable(1)],
             },
             body: vec![Goal {
                 predicate: Predicate {
-                    name: "sibling".to_string(),
+                    name: "sibling".into(),
                     arguments: vec![Term::variable(0), Term::variable(1)],
                 },
             }],
         },
         Clause {
             head: Predicate {
-                name: "relative".to_string(),
+                name: "relative".into(),
                 arguments: vec![Term::variable(0), Term::variable(1)],
             },
             body: vec![Goal {
                 predicate: Predicate {
-                    name: "cousin".to_string(),
+                    name: "cousin".into(),
                     arguments: vec![Term::variable(0), Term::variable(1)],
                 },
             }],
@@ -1094,10 +1071,18 @@ fn family_relationships_complex_recursion() {
         kb.add_clause(rule);
     }
 
+    kb
+}
+
+#[test]
+fn family_relationships_complex_recursion() {
+    // Test complex family relationships with multiple recursive predicates
+    let kb = family_knowledge_base();
+
     // Test ancestor(adam, ?0) - should find all descendants of adam
     let ancestor_query = Goal {
         predicate: Predicate {
-            name: "ancestor".to_string(),
+            name: "ancestor".into(),
             arguments: vec![Term::atom("adam"), Term::variable(0)],
         },
     };
@@ -1106,7 +1091,7 @@ fn family_relationships_complex_recursion() {
     let mut goal_state = solver.create_goal_state(ancestor_query);
 
     let mut ancestor_solutions = Vec::new();
-    while let Some(solution) = solver.pull_next_goal(&mut goal_state) {
+    while let Some(solution) = solver.pull_next_goal(&mut goal_state).unwrap() {
         ancestor_solutions.push(solution);
     }
 
@@ -1127,7 +1112,7 @@ fn family_relationships_complex_recursion() {
     // Test cousin(dan, ?0) - should find cousins of dan
     let cousin_query = Goal {
         predicate: Predicate {
-            name: "cousin".to_string(),
+            name: "cousin".into(),
             arguments: vec![Term::atom("dan"), Term::variable(0)],
         },
     };
@@ -1135,7 +1120,7 @@ fn family_relationships_complex_recursion() {
     let mut goal_state = solver.create_goal_state(cousin_query);
 
     let mut cousin_solutions = Vec::new();
-    while let Some(solution) = solver.pull_next_goal(&mut goal_state) {
+    while let Some(solution) = solver.pull_next_goal(&mut goal_state).unwrap() {
         cousin_solutions.push(solution);
     }
 
@@ -1168,28 +1153,28 @@ fn circular_dependency_with_multiple_predicates() {
     let depend_facts = vec![
         Clause {
             head: Predicate {
-                name: "depends".to_string(),
+                name: "depends".into(),
                 arguments: vec![Term::atom("a"), Term::atom("b")],
             },
             body: vec![],
         },
         Clause {
             head: Predicate {
-                name: "depends".to_string(),
+                name: "depends".into(),
                 arguments: vec![Term::atom("b"), Term::atom("c")],
             },
             body: vec![],
         },
         Clause {
             head: Predicate {
-                name: "depends".to_string(),
+                name: "depends".into(),
                 arguments: vec![Term::atom("c"), Term::atom("a")],
             },
             body: vec![],
         },
         Clause {
             head: Predicate {
-                name: "depends".to_string(),
+                name: "depends".into(),
                 arguments: vec![Term::atom("d"), Term::atom("e")],
             },
             body: vec![],
@@ -1198,12 +1183,12 @@ fn circular_dependency_with_multiple_predicates() {
 
     let indirect_depends_base = Clause {
         head: Predicate {
-            name: "indirect_depends".to_string(),
+            name: "indirect_depends".into(),
             arguments: vec![Term::variable(0), Term::variable(1)],
         },
         body: vec![Goal {
             predicate: Predicate {
-                name: "depends".to_string(),
+                name: "depends".into(),
                 arguments: vec![Term::variable(0), Term::variable(1)],
             },
         }],
@@ -1211,19 +1196,19 @@ fn circular_dependency_with_multiple_predicates() {
 
     let indirect_depends_recursive = Clause {
         head: Predicate {
-            name: "indirect_depends".to_string(),
+            name: "indirect_depends".into(),
             arguments: vec![Term::variable(0), Term::variable(1)],
         },
         body: vec![
             Goal {
                 predicate: Predicate {
-                    name: "depends".to_string(),
+                    name: "depends".into(),
                     arguments: vec![Term::variable(0), Term::variable(2)],
                 },
             },
             Goal {
                 predicate: Predicate {
-                    name: "indirect_depends".to_string(),
+                    name: "indirect_depends".into(),
                     arguments: vec![Term::variable(2), Term::variable(1)],
                 },
             },
@@ -1233,24 +1218,24 @@ fn circular_dependency_with_multiple_predicates() {
     let related_rules = vec![
         Clause {
             head: Predicate {
-                name: "related".to_string(),
+                name: "related".into(),
                 arguments: vec![Term::variable(0), Term::variable(1)],
             },
             body: vec![Goal {
                 predicate: Predicate {
-                    name: "indirect_depends".to_string(),
+                    name: "indirect_depends".into(),
                     arguments: vec![Term::variable(0), Term::variable(1)],
                 },
             }],
         },
         Clause {
             head: Predicate {
-                name: "related".to_string(),
+                name: "related".into(),
                 arguments: vec![Term::variable(0), Term::variable(1)],
             },
             body: vec![Goal {
                 predicate: Predicate {
-                    name: "indirect_depends".to_string(),
+                    name: "indirect_depends".into(),
                     arguments: vec![Term::variable(1), Term::variable(0)],
                 },
             }],
@@ -1270,7 +1255,7 @@ fn circular_dependency_with_multiple_predicates() {
     // Test indirect_depends(a, ?0) - should handle the cycle properly
     let query = Goal {
         predicate: Predicate {
-            name: "indirect_depends".to_string(),
+            name: "indirect_depends".into(),
             arguments: vec![Term::atom("a"), Term::variable(0)],
         },
     };
@@ -1279,7 +1264,7 @@ fn circular_dependency_with_multiple_predicates() {
     let mut goal_state = solver.create_goal_state(query);
 
     let mut solutions = Vec::new();
-    while let Some(solution) = solver.pull_next_goal(&mut goal_state) {
+    while let Some(solution) = solver.pull_next_goal(&mut goal_state).unwrap() {
         solutions.push(solution);
     }
 
@@ -1302,7 +1287,7 @@ fn circular_dependency_with_multiple_predicates() {
     // Test that 'd' only depends on 'e' (no cycle)
     let query_d = Goal {
         predicate: Predicate {
-            name: "indirect_depends".to_string(),
+            name: "indirect_depends".into(),
             arguments: vec![Term::atom("d"), Term::variable(0)],
         },
     };
@@ -1310,7 +1295,8 @@ fn circular_dependency_with_multiple_predicates() {
     let mut goal_state_d = solver.create_goal_state(query_d);
 
     let mut solutions_d = Vec::new();
-    while let Some(solution) = solver.pull_next_goal(&mut goal_state_d) {
+    while let Some(solution) = solver.pull_next_goal(&mut goal_state_d).unwrap()
+    {
         solutions_d.push(solution);
     }
 
@@ -1364,7 +1350,7 @@ fn deep_recursive_chain_with_branching() {
     .into_iter()
     .map(|(from, to)| Clause {
         head: Predicate {
-            name: "connects".to_string(),
+            name: "connects".into(),
             arguments: vec![Term::atom(from), Term::atom(to)],
         },
         body: vec![],
@@ -1373,12 +1359,12 @@ fn deep_recursive_chain_with_branching() {
 
     let path_base = Clause {
         head: Predicate {
-            name: "path".to_string(),
+            name: "path".into(),
             arguments: vec![Term::variable(0), Term::variable(1)],
         },
         body: vec![Goal {
             predicate: Predicate {
-                name: "connects".to_string(),
+                name: "connects".into(),
                 arguments: vec![Term::variable(0), Term::variable(1)],
             },
         }],
@@ -1386,19 +1372,19 @@ fn deep_recursive_chain_with_branching() {
 
     let path_recursive = Clause {
         head: Predicate {
-            name: "path".to_string(),
+            name: "path".into(),
             arguments: vec![Term::variable(0), Term::variable(1)],
         },
         body: vec![
             Goal {
                 predicate: Predicate {
-                    name: "connects".to_string(),
+                    name: "connects".into(),
                     arguments: vec![Term::variable(0), Term::variable(2)],
                 },
             },
             Goal {
                 predicate: Predicate {
-                    name: "path".to_string(),
+                    name: "path".into(),
                     arguments: vec![Term::variable(2), Term::variable(1)],
                 },
             },
@@ -1407,12 +1393,12 @@ fn deep_recursive_chain_with_branching() {
 
     let reachable_from_root = Clause {
         head: Predicate {
-            name: "reachable_from_root".to_string(),
+            name: "reachable_from_root".into(),
             arguments: vec![Term::variable(0)],
         },
         body: vec![Goal {
             predicate: Predicate {
-                name: "path".to_string(),
+                name: "path".into(),
                 arguments: vec![Term::atom("root"), Term::variable(0)],
             },
         }],
@@ -1420,19 +1406,19 @@ fn deep_recursive_chain_with_branching() {
 
     let depth_2_rule = Clause {
         head: Predicate {
-            name: "depth_2".to_string(),
+            name: "depth_2".into(),
             arguments: vec![Term::variable(0)],
         },
         body: vec![
             Goal {
                 predicate: Predicate {
-                    name: "connects".to_string(),
+                    name: "connects".into(),
                     arguments: vec![Term::variable(1), Term::variable(0)],
                 },
             },
             Goal {
                 predicate: Predicate {
-                    name: "connects".to_string(),
+                    name: "connects".into(),
                     arguments: vec![Term::atom("root"), Term::variable(1)],
                 },
             },
@@ -1441,19 +1427,19 @@ fn deep_recursive_chain_with_branching() {
 
     let depth_3_rule = Clause {
         head: Predicate {
-            name: "depth_3".to_string(),
+            name: "depth_3".into(),
             arguments: vec![Term::variable(0)],
         },
         body: vec![
             Goal {
                 predicate: Predicate {
-                    name: "connects".to_string(),
+                    name: "connects".into(),
                     arguments: vec![Term::variable(1), Term::variable(0)],
                 },
             },
             Goal {
                 predicate: Predicate {
-                    name: "depth_2".to_string(),
+                    name: "depth_2".into(),
                     arguments: vec![Term::variable(1)],
                 },
             },
@@ -1473,7 +1459,7 @@ fn deep_recursive_chain_with_branching() {
     // Test reachable_from_root(?0) - should find all nodes reachable from root
     let reachable_query = Goal {
         predicate: Predicate {
-            name: "reachable_from_root".to_string(),
+            name: "reachable_from_root".into(),
             arguments: vec![Term::variable(0)],
         },
     };
@@ -1482,7 +1468,7 @@ fn deep_recursive_chain_with_branching() {
     let mut goal_state = solver.create_goal_state(reachable_query);
 
     let mut reachable_solutions = Vec::new();
-    while let Some(solution) = solver.pull_next_goal(&mut goal_state) {
+    while let Some(solution) = solver.pull_next_goal(&mut goal_state).unwrap() {
         reachable_solutions.push(solution);
     }
 
@@ -1506,7 +1492,7 @@ fn deep_recursive_chain_with_branching() {
     // Test depth_2(?0) - should find b1, b2, b3
     let depth_2_query = Goal {
         predicate: Predicate {
-            name: "depth_2".to_string(),
+            name: "depth_2".into(),
             arguments: vec![Term::variable(0)],
         },
     };
@@ -1514,7 +1500,7 @@ fn deep_recursive_chain_with_branching() {
     let mut goal_state = solver.create_goal_state(depth_2_query);
 
     let mut depth_2_solutions = Vec::new();
-    while let Some(solution) = solver.pull_next_goal(&mut goal_state) {
+    while let Some(solution) = solver.pull_next_goal(&mut goal_state).unwrap() {
         depth_2_solutions.push(solution);
     }
 
@@ -1534,7 +1520,7 @@ fn deep_recursive_chain_with_branching() {
     // Test depth_3(?0) - should find c1, c2, c3
     let depth_3_query = Goal {
         predicate: Predicate {
-            name: "depth_3".to_string(),
+            name: "depth_3".into(),
             arguments: vec![Term::variable(0)],
         },
     };
@@ -1542,7 +1528,7 @@ fn deep_recursive_chain_with_branching() {
     let mut goal_state = solver.create_goal_state(depth_3_query);
 
     let mut depth_3_solutions = Vec::new();
-    while let Some(solution) = solver.pull_next_goal(&mut goal_state) {
+    while let Some(solution) = solver.pull_next_goal(&mut goal_state).unwrap() {
         depth_3_solutions.push(solution);
     }
 
@@ -1559,3 +1545,3072 @@ fn deep_recursive_chain_with_branching() {
         );
     }
 }
+
+#[test]
+fn step_limit_stops_non_terminating_generator() {
+    // nat(zero).
+    // nat(s(X)) :- nat(X).
+    let base = Clause {
+        head: Predicate {
+            name: "nat".into(),
+            arguments: vec![Term::atom("zero")],
+        },
+        body: vec![],
+    };
+    let step = Clause {
+        head: Predicate {
+            name: "nat".into(),
+            arguments: vec![Term::component("s", [Term::variable(0)])],
+        },
+        body: vec![Goal {
+            predicate: Predicate {
+                name: "nat".into(),
+                arguments: vec![Term::variable(0)],
+            },
+        }],
+    };
+
+    let mut kb = KnowledgeBase::new();
+    kb.add_clause(base);
+    kb.add_clause(step);
+
+    let goal = Goal {
+        predicate: Predicate {
+            name: "nat".into(),
+            arguments: vec![Term::variable(0)],
+        },
+    };
+
+    let mut solver = Solver::with_max_steps(&kb, 100);
+    let mut goal_state = solver.create_goal_state(goal);
+
+    // `nat(?0)` has infinitely many answers, enumerating them all only stops
+    // because of the step limit
+    let mut solutions = Vec::new();
+    let error = loop {
+        match solver.pull_next_goal(&mut goal_state) {
+            Ok(Some(solution)) => solutions.push(solution),
+            Ok(None) => panic!("`nat(?0)` should never run out of answers"),
+            Err(error) => break error,
+        }
+    };
+
+    assert_eq!(error, SolveError::StepLimitExceeded);
+    assert!(!solutions.is_empty());
+    assert!(solutions.contains(&Substitution {
+        mapping: [(0, Term::atom("zero"))].into_iter().collect(),
+    }));
+
+    // the budget stays exhausted
+    assert_eq!(
+        solver.pull_next_goal(&mut goal_state),
+        Err(SolveError::StepLimitExceeded)
+    );
+}
+
+#[test]
+fn deadline_stops_infinite_enumeration() {
+    let program = "
+        nat(zero).
+        nat(s(X)) :- nat(X).
+    ";
+
+    let mut kb = KnowledgeBase::new();
+    for clause in parse_program(program).unwrap() {
+        kb.add_clause(clause);
+    }
+
+    let start = Instant::now();
+    let mut solver =
+        Solver::with_deadline(&kb, start + Duration::from_millis(20));
+
+    // `nat(?0)` has infinitely many answers, collecting them all only stops
+    // because of the deadline
+    assert_eq!(
+        solver.solve_all(parse_goal("nat(X)").unwrap()),
+        Err(SolveError::Timeout)
+    );
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    // pushing the deadline back resumes the enumeration
+    let mut goal_state =
+        solver.create_goal_state(parse_goal("nat(s(s(X)))").unwrap());
+    solver.set_deadline(None);
+
+    assert!(solver.pull_next_goal(&mut goal_state).unwrap().is_some());
+
+    // a query interrupted by the deadline yields its remaining answers, and
+    // only those, once the deadline is raised
+    let mut program = "path(X, Y) :- edge(X, Y).
+        path(X, Y) :- edge(X, Z), path(Z, Y).\n"
+        .to_owned();
+    for i in 0..40 {
+        program.push_str(&format!("edge(n{i}, n{}).\n", i + 1));
+    }
+    let kb = program.parse::<KnowledgeBase>().unwrap();
+    let goal = parse_goal("path(X, Y)").unwrap();
+
+    let mut expected = Solver::new(&kb).solve_all(goal.clone()).unwrap();
+    expected.sort();
+    assert_eq!(expected.len(), 41 * 40 / 2);
+
+    let mut solver = Solver::new(&kb);
+    let mut goal_state = solver.create_goal_state(goal);
+    let mut answers =
+        vec![solver.pull_next_goal(&mut goal_state).unwrap().unwrap()];
+
+    solver.set_deadline(Some(Instant::now()));
+    loop {
+        match solver.pull_next_goal(&mut goal_state) {
+            Ok(answer) => answers.push(answer.unwrap()),
+            Err(error) => {
+                assert_eq!(error, SolveError::Timeout);
+                break;
+            }
+        }
+    }
+    assert!(answers.len() < expected.len());
+
+    solver.set_deadline(Some(Instant::now() + Duration::from_secs(60)));
+    while let Some(answer) = solver.pull_next_goal(&mut goal_state).unwrap() {
+        answers.push(answer);
+    }
+
+    answers.sort();
+    assert_eq!(answers, expected);
+}
+
+#[test]
+fn max_term_size_bounds_infinite_enumeration() {
+    let program = "
+        nat(zero).
+        nat(s(X)) :- nat(X).
+    ";
+
+    let mut kb = KnowledgeBase::new();
+    for clause in parse_program(program).unwrap() {
+        kb.add_clause(clause);
+    }
+
+    let mut solver = Solver::new(&kb);
+    solver.set_max_term_size(Some(10));
+
+    assert_eq!(
+        solver.solve_all(parse_goal("nat(X)").unwrap()),
+        Err(SolveError::TermTooLarge)
+    );
+
+    // resuming skips the answers exceeding the bound, `s(...s(zero)...)`
+    // nested nine times is the largest one within it
+    solver.reset();
+    let mut goal_state =
+        solver.create_goal_state(parse_goal("nat(X)").unwrap());
+
+    let mut answers = Vec::new();
+    let mut rejections = 0;
+    loop {
+        match solver.pull_next_goal(&mut goal_state) {
+            Ok(Some(answer)) => answers.push(answer.mapping[&0].size()),
+            Ok(None) => break,
+            Err(SolveError::TermTooLarge) => rejections += 1,
+            Err(error) => panic!("unexpected error: {error}"),
+        }
+    }
+    answers.sort_unstable();
+
+    assert!(rejections > 0);
+    assert_eq!(answers, (1..=10).collect::<Vec<_>>());
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn serde_round_trip_family_relationships() {
+    let kb = family_knowledge_base();
+
+    let json = serde_json::to_string(&kb).unwrap();
+    let deserialized: KnowledgeBase = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(deserialized, kb);
+
+    // the representation is stable
+    assert_eq!(serde_json::to_string(&deserialized).unwrap(), json);
+
+    for name in ["ancestor", "cousin", "relative"] {
+        let query = Goal {
+            predicate: Predicate {
+                name: name.into(),
+                arguments: vec![Term::variable(0), Term::variable(1)],
+            },
+        };
+
+        let mut solutions = Vec::new();
+        for kb in [&kb, &deserialized] {
+            let mut solver = Solver::new(kb);
+            let mut goal_state = solver.create_goal_state(query.clone());
+
+            let mut answers = Vec::new();
+            while let Some(solution) =
+                solver.pull_next_goal(&mut goal_state).unwrap()
+            {
+                answers.push(solution);
+            }
+
+            answers.sort_by_key(ToString::to_string);
+            solutions.push(answers);
+        }
+
+        assert!(!solutions[0].is_empty());
+        assert_eq!(solutions[0], solutions[1], "Mismatch for {name}");
+    }
+}
+
+#[test]
+fn occurs_check_toggle() {
+    // self_loop(X, X).
+    let clause = Clause {
+        head: Predicate {
+            name: "self_loop".into(),
+            arguments: vec![Term::variable(0), Term::variable(0)],
+        },
+        body: vec![],
+    };
+
+    let mut kb = KnowledgeBase::new();
+    kb.add_clause(clause);
+
+    // ?- self_loop(Y, f(Y)).
+    let goal = Goal {
+        predicate: Predicate {
+            name: "self_loop".into(),
+            arguments: vec![
+                Term::variable(0),
+                Term::component("f", [Term::variable(0)]),
+            ],
+        },
+    };
+
+    let mut solver = Solver::new(&kb);
+    let mut goal_state = solver.create_goal_state(goal.clone());
+
+    assert!(solver.pull_next_goal(&mut goal_state).unwrap().is_none());
+
+    let mut solver = Solver::new(&kb);
+    solver.set_occurs_check(false);
+    let mut goal_state = solver.create_goal_state(goal);
+
+    assert!(solver.pull_next_goal(&mut goal_state).unwrap().is_some());
+}
+
+#[test]
+fn conjunctive_query_yields_grandparent_chains() {
+    let kb = family_knowledge_base();
+    let mut solver = Solver::new(&kb);
+
+    // ?- parent(X, Y), parent(Y, Z).
+    let mut goal_state = solver.create_conjunction_state(vec![
+        parse_goal("parent(X, Y)").unwrap(),
+        Goal::new("parent", [Term::variable(1), Term::variable(2)]),
+    ]);
+
+    let mut chains = Vec::new();
+    while let Some(answer) = solver.pull_next_goal(&mut goal_state).unwrap() {
+        chains.push(
+            (0..3)
+                .map(|variable| answer.mapping[&variable].to_string())
+                .collect::<Vec<_>>()
+                .join(" -> "),
+        );
+    }
+    chains.sort();
+
+    assert_eq!(chains, [
+        "adam -> bob -> dan",
+        "adam -> bob -> eve",
+        "adam -> carol -> frank",
+        "bob -> dan -> grace",
+    ]);
+
+    let mut empty = solver.create_conjunction_state(Vec::new());
+    assert_eq!(
+        solver.pull_next_goal(&mut empty).unwrap(),
+        Some(Substitution::default())
+    );
+    assert_eq!(solver.pull_next_goal(&mut empty).unwrap(), None);
+}
+
+#[test]
+fn conjunction_of_goals_with_shifted_variables() {
+    let kb = family_knowledge_base();
+    let mut solver = Solver::new(&kb);
+
+    let mut count_answers = |goals: Vec<Goal>| {
+        let mut goal_state = solver.create_conjunction_state(goals);
+        let mut count = 0;
+        while solver.pull_next_goal(&mut goal_state).unwrap().is_some() {
+            count += 1;
+        }
+
+        count
+    };
+
+    // both goals are `parent(?0, ?1)`, built independently of each other
+    let first = parse_goal("parent(X, Y)").unwrap();
+    let second = parse_goal("parent(X, Y)").unwrap();
+
+    // conjoined as they are, the goals share their variables
+    assert_eq!(count_answers(vec![first.clone(), second.clone()]), 6);
+
+    let by = first.max_variable_index().unwrap() + 1;
+    let second = Goal::new(
+        second.predicate.name,
+        second
+            .predicate
+            .arguments
+            .into_iter()
+            .map(|argument| argument.shift_variables(by)),
+    );
+    assert_eq!(second.to_string(), "parent(?2, ?3)");
+
+    assert_eq!(count_answers(vec![first, second]), 6 * 6);
+}
+
+#[test]
+fn conjunction_of_goals_with_shifted_anonymous_variables() {
+    let kb = "p(a). q(b).".parse::<KnowledgeBase>().unwrap();
+    let mut solver = Solver::new(&kb);
+
+    let mut has_answer = |goals: Vec<Goal>| {
+        let mut goal_state = solver.create_conjunction_state(goals);
+        solver.pull_next_goal(&mut goal_state).unwrap().is_some()
+    };
+
+    let first = parse_goal("p(_)").unwrap();
+    let second = parse_goal("q(_)").unwrap();
+
+    // built separately, both goals use the first anonymous variable
+    assert!(!has_answer(vec![first.clone(), second.clone()]));
+
+    let by = first.predicate.arguments[0].max_variable_offset().unwrap() + 1;
+    let second = Goal::new(
+        second.predicate.name,
+        second
+            .predicate
+            .arguments
+            .into_iter()
+            .map(|argument| argument.shift_variables(by)),
+    );
+
+    assert!(has_answer(vec![first, second]));
+}
+
+#[test]
+fn linear_heads_skip_the_occurs_check() {
+    let kb = "
+        pair(X, Y).
+        eq(X, X).
+    "
+    .parse::<KnowledgeBase>()
+    .unwrap();
+
+    let solve = |kb: &KnowledgeBase, goal: &str, occurs_check: bool| {
+        let mut solver = Solver::new(kb);
+        solver.set_occurs_check(occurs_check);
+        solver.solve_all(parse_goal(goal).unwrap()).unwrap()
+    };
+
+    // the head of `pair/2` is linear, binding `Y` to `f(Y)` never happens
+    // whether the occurs check is enabled or not
+    assert_eq!(
+        solve(&kb, "pair(Y, f(Y))", true),
+        solve(&kb, "pair(Y, f(Y))", false)
+    );
+    assert_eq!(solve(&kb, "pair(Y, f(Y))", true).len(), 1);
+
+    // the head of `eq/2` isn't linear, it still goes through the occurs check
+    assert!(solve(&kb, "eq(Y, f(Y))", true).is_empty());
+    assert_eq!(solve(&kb, "eq(Y, f(Y))", false).len(), 1);
+
+    let family = family_knowledge_base();
+    for goal in ["ancestor(adam, X)", "cousin(X, Y)", "relative(X, Y)"] {
+        assert_eq!(solve(&family, goal, true), solve(&family, goal, false));
+    }
+}
+
+#[test]
+fn repeated_variables_keep_the_occurs_check() {
+    let mut kb = "
+        p(f(X), X).
+        pair(X, Y).
+        same(X, X).
+        q(A) :- p(A, A).
+        r :- same(A, B), pair(A, f(B)).
+    "
+    .parse::<KnowledgeBase>()
+    .unwrap();
+
+    let solve = |kb: &KnowledgeBase, goal: &str| {
+        let mut solver = Solver::new(kb);
+        solver.set_occurs_check(true);
+        solver.solve_all(parse_goal(goal).unwrap()).unwrap()
+    };
+
+    for tabled in [true, false] {
+        kb.set_tabled(("p", 2), tabled);
+        kb.set_tabled(("pair", 2), tabled);
+
+        // `p(A, A)` repeats `A`, unifying it binds `X` to `f(X)`
+        assert!(solve(&kb, "p(A, A)").is_empty());
+        assert!(solve(&kb, "q(A)").is_empty());
+        assert_eq!(solve(&kb, "p(A, B)").len(), 1);
+
+        // `pair(A, f(B))` is linear, but not once `A` is bound to `B`; the
+        // unification goes through the occurs check and still succeeds
+        assert_eq!(solve(&kb, "pair(A, f(A))").len(), 1);
+        assert_eq!(solve(&kb, "r").len(), 1);
+    }
+}
+
+#[test]
+fn capped_tables_stop_after_max_answers() {
+    let mut program = String::new();
+    for n in 0..50 {
+        program.push_str(&format!("number({n}).\n"));
+    }
+    program.push_str("pair(X, Y) :- number(X), number(Y).");
+
+    let kb = program.parse::<KnowledgeBase>().unwrap();
+
+    let mut solver = Solver::with_max_answers_per_table(&kb, 3);
+    let mut goal_state =
+        solver.create_goal_state(parse_goal("number(X)").unwrap());
+
+    let mut answers = Vec::new();
+    while let Some(answer) = solver.pull_next_goal(&mut goal_state).unwrap() {
+        answers.push(answer.mapping[&0].clone());
+    }
+
+    assert_eq!(answers, [Term::from(0), Term::from(1), Term::from(2)]);
+
+    let table = solver.table(&goal_state);
+    assert_eq!(table.answers().len(), 3);
+    assert_eq!(table.work_list_len(), 0);
+
+    // the consumers only see the answers of the capped table
+    let pairs = solver.solve_all(parse_goal("pair(X, Y)").unwrap()).unwrap();
+    assert_eq!(pairs.len(), 3);
+    assert_eq!(solver.answers_for(&parse_goal("number(X)").unwrap()), Some(3));
+
+    let mut solver = Solver::new(&kb);
+    solver.set_max_answers_per_table(Some(3));
+    solver.set_max_answers_per_table(None);
+    assert_eq!(
+        solver.solve_all(parse_goal("pair(X, Y)").unwrap()).unwrap().len(),
+        50 * 50
+    );
+}
+
+#[test]
+fn propositional_facts_and_rules() {
+    let kb = "
+        rain.
+        wet :- rain.
+        slippery :- wet, cold.
+        p :- q.
+        q :- p.
+    "
+    .parse::<KnowledgeBase>()
+    .unwrap();
+
+    let proposition = |name: &str| Goal {
+        predicate: Predicate { name: name.into(), arguments: vec![] },
+    };
+
+    let mut canonical = proposition("wet");
+    assert!(canonical.canonicalize().is_empty());
+    assert_eq!(canonical.max_variable_index(), None);
+    assert_eq!(
+        Substitution::default().unify_predicate(
+            &canonical.predicate,
+            &proposition("wet").predicate
+        ),
+        Some(Substitution::default())
+    );
+
+    let mut solver = Solver::new(&kb);
+
+    // a proposition has a single answer binding nothing
+    for name in ["rain", "wet"] {
+        let mut goal_state = solver.create_goal_state(proposition(name));
+
+        assert_eq!(
+            solver.pull_next_goal(&mut goal_state).unwrap(),
+            Some(Substitution::default())
+        );
+        assert_eq!(solver.pull_next_goal(&mut goal_state).unwrap(), None);
+        assert_eq!(solver.table(&goal_state).answers(), [
+            Substitution::default()
+        ]);
+    }
+
+    // `cold` has no clause and the cycle between `p` and `q` has no base
+    // case
+    for name in ["slippery", "p", "q"] {
+        assert!(solver.solve_all(proposition(name)).unwrap().is_empty());
+    }
+}
+
+#[test]
+fn explain_grandparent_answer() {
+    let kb = "
+        parent(alice, bob).
+        parent(bob, carol).
+        parent(bob, dave).
+        grandparent(X, Z) :- parent(X, Y), parent(Y, Z).
+    "
+    .parse::<KnowledgeBase>()
+    .unwrap();
+
+    let mut solver = Solver::new(&kb);
+    let goal = parse_goal("grandparent(alice, X)").unwrap();
+    let answer = solver.solve_all(goal.clone()).unwrap().remove(0);
+
+    let proof = solver.explain(goal.clone(), &answer).unwrap().unwrap();
+
+    assert_eq!(proof.goal.to_string(), "grandparent(alice, carol)");
+    assert_eq!(
+        proof.clause.as_ref(),
+        kb.get_clauses(("grandparent", 2)).unwrap().first()
+    );
+    assert_eq!(
+        proof
+            .children
+            .iter()
+            .map(|child| child.clause.as_ref().unwrap().to_string())
+            .collect::<Vec<_>>(),
+        ["parent(alice, bob).", "parent(bob, carol)."]
+    );
+    assert!(proof.children.iter().all(|child| child.children.is_empty()));
+
+    // `bob` isn't a grandchild of `alice`
+    let mut not_an_answer = Substitution::default();
+    not_an_answer.mapping.insert(0, Term::atom("bob"));
+    assert_eq!(solver.explain(goal, &not_an_answer).unwrap(), None);
+}
+
+#[test]
+fn explain_failures() {
+    let kb = "
+        parent(alice, bob).
+        parent(bob, carol).
+        grandparent(X, Z) :- parent(X, Y), parent(Y, Z).
+        older(X, Y) :- age(X, A), age(Y, B), A > B.
+        age(alice, 70).
+        age(bob, 40).
+    "
+    .parse::<KnowledgeBase>()
+    .unwrap();
+
+    let mut solver = Solver::new(&kb);
+    let mut explain =
+        |goal: &str| solver.explain_failure(parse_goal(goal).unwrap()).unwrap();
+
+    assert_eq!(explain("parent(alice, X)"), None);
+
+    let trace = explain("parent(bob, alice)").unwrap();
+    assert_eq!(trace.goal.to_string(), "parent(bob, alice)");
+    assert_eq!(trace.reason, FailureReason::NoMatchingClause);
+
+    // the second goal, bound by the clause head alone, is `parent(Y, dave)`
+    // which has no answer at all
+    let trace = explain("grandparent(alice, dave)").unwrap();
+    let FailureReason::Clauses(failures) = &trace.reason else {
+        panic!("the clause head unifies");
+    };
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].subgoal_index, 1);
+    let cause = failures[0].cause.as_ref().unwrap();
+    assert_eq!(cause.goal.predicate.name, "parent");
+    assert!(cause.goal.predicate.arguments[0].as_variable().is_some());
+    assert_eq!(cause.goal.predicate.arguments[1], Term::atom("dave"));
+    assert_eq!(cause.reason, FailureReason::NoMatchingClause);
+
+    assert_eq!(
+        explain("older(bob, alice)").unwrap().to_string(),
+        "older(bob, alice): every matching clause fails
+  `older(?0, ?1) :- age(?0, ?2), age(?1, ?3), >(?2, ?3).` fails at `>(?2, ?3)` \
+         given the goals before it
+"
+    );
+    assert_eq!(
+        explain("grandparent(carol, X)").unwrap().to_string(),
+        "grandparent(carol, ?0): every matching clause fails
+  `grandparent(?0, ?1) :- parent(?0, ?2), parent(?2, ?1).` fails at \
+         `parent(?0, ?2)`
+    parent(carol, ?3): no clause head unifies
+"
+    );
+}
+
+#[test]
+fn explain_answer_through_control_constructs() {
+    let kb = "
+        q(a).
+        q(b).
+        r(b).
+        w(X) :- q(X), !.
+        v(X) :- (r(X) ; q(X)), X = a.
+    "
+    .parse::<KnowledgeBase>()
+    .unwrap();
+
+    let mut solver = Solver::new(&kb);
+    let mut explain = |goal: &str| {
+        solver
+            .explain(parse_goal(goal).unwrap(), &Substitution::default())
+            .unwrap()
+            .map(|proof| proof.to_string())
+    };
+
+    // the cut isn't tabled apart from the clause it belongs to
+    assert_eq!(explain("w(a)").unwrap(), "w(a)\n  q(a)\n  !\n");
+
+    // the first branch of the disjunction doesn't agree with `X = a`
+    assert_eq!(explain("v(a)").unwrap(), "v(a)\n  q(a)\n  =(a, a)\n");
+    assert_eq!(explain("v(b)"), None);
+}
+
+#[test]
+fn explain_long_chain_does_not_overflow_stack() {
+    const LENGTH: usize = 10_000;
+
+    let mut program = String::new();
+    for i in 0..LENGTH {
+        program.push_str(&format!("p{i}(X) :- p{}(X).\n", i + 1));
+    }
+    program.push_str(&format!("p{LENGTH}(a).\n"));
+
+    let kb = program.parse::<KnowledgeBase>().unwrap();
+    let mut solver = Solver::new(&kb);
+
+    let proof = solver
+        .explain(parse_goal("p0(a)").unwrap(), &Substitution::default())
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(proof.to_string().lines().count(), LENGTH + 1);
+}
+
+#[test]
+fn explain_failure_of_long_chain_does_not_overflow_stack() {
+    const LENGTH: usize = 5_000;
+
+    let mut program = String::new();
+    for i in 0..LENGTH {
+        program.push_str(&format!("p{i}(X) :- p{}(X).\n", i + 1));
+    }
+    program.push_str(&format!("p{LENGTH}(b).\n"));
+
+    let kb = program.parse::<KnowledgeBase>().unwrap();
+    let mut solver = Solver::new(&kb);
+
+    let trace =
+        solver.explain_failure(parse_goal("p0(a)").unwrap()).unwrap().unwrap();
+
+    // a goal line and a clause line per link, the last goal has no clause
+    assert_eq!(trace.to_string().lines().count(), 2 * LENGTH + 1);
+}
+
+#[test]
+fn explain_recursive_answer() {
+    let kb = family_knowledge_base();
+    let mut solver = Solver::new(&kb);
+
+    let goal = parse_goal("ancestor(adam, grace)").unwrap();
+    let proof =
+        solver.explain(goal, &Substitution::default()).unwrap().unwrap();
+
+    assert_eq!(
+        proof.to_string(),
+        "ancestor(adam, grace)
+  parent(adam, bob)
+  ancestor(bob, grace)
+    parent(bob, dan)
+    ancestor(dan, grace)
+      parent(dan, grace)
+"
+    );
+}
+
+#[test]
+fn cloned_solver_continues_independently() {
+    let kb = family_knowledge_base();
+    let mut solver = Solver::new(&kb);
+
+    let mut goal_state =
+        solver.create_goal_state(parse_goal("ancestor(adam, X)").unwrap());
+    let first = solver.pull_next_goal(&mut goal_state).unwrap().unwrap();
+
+    let mut cloned_solver = solver.clone();
+    let mut cloned_goal_state = goal_state.clone();
+
+    // interleave the pulls so that any state shared between the solvers
+    // would show up
+    let mut original = Vec::new();
+    let mut cloned = Vec::new();
+
+    loop {
+        let next = solver.pull_next_goal(&mut goal_state).unwrap();
+        let cloned_next =
+            cloned_solver.pull_next_goal(&mut cloned_goal_state).unwrap();
+
+        if next.is_none() && cloned_next.is_none() {
+            break;
+        }
+
+        original.extend(next);
+        cloned.extend(cloned_next);
+
+        // the clone makes progress of its own
+        cloned_solver.solve_all(parse_goal("cousin(X, Y)").unwrap()).unwrap();
+    }
+
+    assert_eq!(original, cloned);
+    assert_eq!(original.len(), 5);
+    assert!(!original.contains(&first));
+
+    assert!(cloned_solver.table_count() > solver.table_count());
+    assert!(cloned_solver.steps() > solver.steps());
+}
+
+#[test]
+fn cloned_solver_keeps_tracers_apart() {
+    let kb = family_knowledge_base();
+    let (original_sender, original_events) = std::sync::mpsc::channel();
+    let (cloned_sender, cloned_events) = std::sync::mpsc::channel();
+
+    let mut solver = Solver::new(&kb);
+    solver.set_tracer(move |event: &TraceEvent| {
+        original_sender.send(event.to_string()).unwrap();
+    });
+
+    let mut cloned_solver = solver.clone();
+    cloned_solver.set_tracer(move |event: &TraceEvent| {
+        cloned_sender.send(event.to_string()).unwrap();
+    });
+
+    solver.solve_all(parse_goal("ancestor(adam, X)").unwrap()).unwrap();
+    cloned_solver.solve_all(parse_goal("sibling(X, Y)").unwrap()).unwrap();
+
+    drop(solver);
+    drop(cloned_solver);
+
+    let original = original_events.iter().collect::<Vec<_>>();
+    let cloned = cloned_events.iter().collect::<Vec<_>>();
+
+    assert_eq!(original.first().unwrap(), "new table ancestor(adam, ?0)");
+    assert_eq!(cloned.first().unwrap(), "new table sibling(?0, ?1)");
+
+    // each tracer only observes the solver it's set on
+    assert!(original.iter().all(|event| !event.contains("sibling")));
+    assert!(cloned.iter().all(|event| !event.contains("ancestor")));
+}
+
+#[test]
+fn table_statistics_after_graph_reachability() {
+    let kb = graph_reachability_knowledge_base();
+
+    let query = Goal {
+        predicate: Predicate {
+            name: "reachable".into(),
+            arguments: vec![Term::atom("a"), Term::variable(0)],
+        },
+    };
+
+    let mut solver = Solver::new(&kb);
+    assert_eq!(solver.table_count(), 0);
+    assert_eq!(solver.answers_for(&query), None);
+
+    let mut goal_state = solver.create_goal_state(query.clone());
+    while solver.pull_next_goal(&mut goal_state).unwrap().is_some() {}
+
+    assert_eq!(solver.stack_depth(), 0);
+
+    // a reachable and a connected table per node, each of the connected
+    // tables has its own road, rail, and boat table
+    assert_eq!(solver.table_count(), 6 * 5);
+
+    let reachable = |from: &str, variable: usize| Goal {
+        predicate: Predicate {
+            name: "reachable".into(),
+            arguments: vec![Term::atom(from), Term::variable(variable)],
+        },
+    };
+    let connected = |from: &str| Goal {
+        predicate: Predicate {
+            name: "connected".into(),
+            arguments: vec![Term::atom(from), Term::variable(0)],
+        },
+    };
+
+    assert_eq!(solver.answers_for(&query), Some(5));
+    assert_eq!(solver.answers_for(&reachable("c", 0)), Some(3));
+
+    // the goal is canonicalized before looking up the table
+    assert_eq!(solver.answers_for(&reachable("e", 7)), Some(1));
+    assert_eq!(solver.answers_for(&reachable("f", 0)), Some(0));
+
+    for from in ["a", "b", "c", "d", "e"] {
+        assert_eq!(solver.answers_for(&connected(from)), Some(1));
+    }
+    assert_eq!(solver.answers_for(&connected("f")), Some(0));
+}
+
+#[test]
+fn cut_commits_to_first_matching_clause() {
+    // geq(b, a).
+    // geq(c, a).
+    // geq(c, b).
+    // max(X, Y, X) :- geq(X, Y), !.
+    // max(X, Y, Y).
+    let mut kb = KnowledgeBase::new();
+
+    for (greater, lesser) in [("b", "a"), ("c", "a"), ("c", "b")] {
+        kb.add_clause(Clause {
+            head: Predicate {
+                name: "geq".into(),
+                arguments: vec![Term::atom(greater), Term::atom(lesser)],
+            },
+            body: vec![],
+        });
+    }
+
+    kb.add_clause(Clause {
+        head: Predicate {
+            name: "max".into(),
+            arguments: vec![
+                Term::variable(0),
+                Term::variable(1),
+                Term::variable(0),
+            ],
+        },
+        body: vec![
+            Goal {
+                predicate: Predicate {
+                    name: "geq".into(),
+                    arguments: vec![Term::variable(0), Term::variable(1)],
+                },
+            },
+            Goal::cut(),
+        ],
+    });
+    kb.add_clause(Clause {
+        head: Predicate {
+            name: "max".into(),
+            arguments: vec![
+                Term::variable(0),
+                Term::variable(1),
+                Term::variable(1),
+            ],
+        },
+        body: vec![],
+    });
+
+    let max = |x: &str, y: &str| Goal {
+        predicate: Predicate {
+            name: "max".into(),
+            arguments: vec![Term::atom(x), Term::atom(y), Term::variable(0)],
+        },
+    };
+
+    let mut solver = Solver::new(&kb);
+
+    // the guard succeeds, the cut discards the second clause
+    let mut goal_state = solver.create_goal_state(max("c", "a"));
+
+    assert_eq!(
+        solver.pull_next_goal(&mut goal_state).unwrap(),
+        Some(Substitution {
+            mapping: [(0, Term::atom("c"))].into_iter().collect(),
+        })
+    );
+    assert_eq!(solver.pull_next_goal(&mut goal_state).unwrap(), None);
+
+    // the guard fails, the second clause applies
+    let mut goal_state = solver.create_goal_state(max("a", "c"));
+
+    assert_eq!(
+        solver.pull_next_goal(&mut goal_state).unwrap(),
+        Some(Substitution {
+            mapping: [(0, Term::atom("c"))].into_iter().collect(),
+        })
+    );
+    assert_eq!(solver.pull_next_goal(&mut goal_state).unwrap(), None);
+}
+
+#[test]
+fn cut_prunes_alternatives_of_preceding_goals() {
+    // parent(adam, bob).
+    // parent(adam, carol).
+    // first_child(X, Y) :- parent(X, Y), !.
+    let mut kb = KnowledgeBase::new();
+
+    for child in ["bob", "carol"] {
+        kb.add_clause(Clause {
+            head: Predicate {
+                name: "parent".into(),
+                arguments: vec![Term::atom("adam"), Term::atom(child)],
+            },
+            body: vec![],
+        });
+    }
+
+    kb.add_clause(Clause {
+        head: Predicate {
+            name: "first_child".into(),
+            arguments: vec![Term::variable(0), Term::variable(1)],
+        },
+        body: vec![
+            Goal {
+                predicate: Predicate {
+                    name: "parent".into(),
+                    arguments: vec![Term::variable(0), Term::variable(1)],
+                },
+            },
+            Goal::cut(),
+        ],
+    });
+
+    let goal = Goal {
+        predicate: Predicate {
+            name: "first_child".into(),
+            arguments: vec![Term::atom("adam"), Term::variable(0)],
+        },
+    };
+
+    let mut solver = Solver::new(&kb);
+    let mut goal_state = solver.create_goal_state(goal);
+
+    assert_eq!(
+        solver.pull_next_goal(&mut goal_state).unwrap(),
+        Some(Substitution {
+            mapping: [(0, Term::atom("bob"))].into_iter().collect(),
+        })
+    );
+    assert_eq!(solver.pull_next_goal(&mut goal_state).unwrap(), None);
+}
+
+#[test]
+fn integer_comparisons() {
+    let kb = KnowledgeBase::new();
+    let mut solver = Solver::new(&kb);
+
+    let mut holds = |name: &str, lhs: Term, rhs: Term| {
+        let goal = Goal {
+            predicate: Predicate {
+                name: name.into(),
+                arguments: vec![lhs, rhs],
+            },
+        };
+        let mut goal_state = solver.create_goal_state(goal);
+
+        solver.pull_next_goal(&mut goal_state).unwrap().is_some()
+    };
+
+    assert!(holds("<", Term::integer(2), Term::integer(3)));
+    assert!(!holds("<", Term::integer(3), Term::integer(2)));
+    assert!(!holds("<", Term::integer(3), Term::integer(3)));
+
+    assert!(holds(">", Term::integer(3), Term::integer(2)));
+    assert!(!holds(">", Term::integer(2), Term::integer(3)));
+
+    assert!(holds(">=", Term::integer(3), Term::integer(3)));
+    assert!(!holds(">=", Term::integer(2), Term::integer(3)));
+
+    assert!(holds("=<", Term::integer(-1), Term::integer(0)));
+    assert!(!holds("=<", Term::integer(1), Term::integer(0)));
+
+    // unbound and non-numeric arguments fail rather than panic
+    assert!(!holds("<", Term::variable(0), Term::integer(3)));
+    assert!(!holds("<", Term::atom("two"), Term::integer(3)));
+}
+
+#[test]
+fn max_with_comparison_and_cut() {
+    // max(X, Y, X) :- >=(X, Y), !.
+    // max(X, Y, Y).
+    let mut kb = KnowledgeBase::new();
+
+    kb.add_clause(Clause {
+        head: Predicate {
+            name: "max".into(),
+            arguments: vec![
+                Term::variable(0),
+                Term::variable(1),
+                Term::variable(0),
+            ],
+        },
+        body: vec![
+            Goal {
+                predicate: Predicate {
+                    name: ">=".into(),
+                    arguments: vec![Term::variable(0), Term::variable(1)],
+                },
+            },
+            Goal::cut(),
+        ],
+    });
+    kb.add_clause(Clause {
+        head: Predicate {
+            name: "max".into(),
+            arguments: vec![
+                Term::variable(0),
+                Term::variable(1),
+                Term::variable(1),
+            ],
+        },
+        body: vec![],
+    });
+
+    let mut solver = Solver::new(&kb);
+
+    for (x, y, expected) in [(7, 3, 7), (3, 7, 7), (5, 5, 5)] {
+        let goal = Goal {
+            predicate: Predicate {
+                name: "max".into(),
+                arguments: vec![
+                    Term::integer(x),
+                    Term::integer(y),
+                    Term::variable(0),
+                ],
+            },
+        };
+        let mut goal_state = solver.create_goal_state(goal);
+
+        assert_eq!(
+            solver.pull_next_goal(&mut goal_state).unwrap(),
+            Some(Substitution {
+                mapping: [(0, Term::integer(expected))].into_iter().collect(),
+            })
+        );
+        assert_eq!(solver.pull_next_goal(&mut goal_state).unwrap(), None);
+    }
+}
+
+#[test]
+fn findall_collects_answers_into_list() {
+    // parent(alice, bob).
+    // parent(alice, carol).
+    // parent(bob, dave).
+    // children(P, L) :- findall(C, parent(P, C), L).
+    let mut kb = KnowledgeBase::new();
+
+    for (parent, child) in
+        [("alice", "bob"), ("alice", "carol"), ("bob", "dave")]
+    {
+        kb.add_clause(Clause {
+            head: Predicate {
+                name: "parent".into(),
+                arguments: vec![Term::atom(parent), Term::atom(child)],
+            },
+            body: vec![],
+        });
+    }
+
+    kb.add_clause(Clause {
+        head: Predicate {
+            name: "children".into(),
+            arguments: vec![Term::variable(0), Term::variable(1)],
+        },
+        body: vec![Goal {
+            predicate: Predicate {
+                name: "findall".into(),
+                arguments: vec![
+                    Term::variable(2),
+                    Term::component("parent", [
+                        Term::variable(0),
+                        Term::variable(2),
+                    ]),
+                    Term::variable(1),
+                ],
+            },
+        }],
+    });
+
+    let mut solver = Solver::new(&kb);
+
+    // ?- findall(X, parent(alice, X), L).
+    let goal = Goal {
+        predicate: Predicate {
+            name: "findall".into(),
+            arguments: vec![
+                Term::variable(0),
+                Term::component("parent", [
+                    Term::atom("alice"),
+                    Term::variable(0),
+                ]),
+                Term::variable(1),
+            ],
+        },
+    };
+    let mut goal_state = solver.create_goal_state(goal);
+
+    let solution = solver.pull_next_goal(&mut goal_state).unwrap().unwrap();
+    let list = Term::list([Term::atom("bob"), Term::atom("carol")]);
+
+    assert_eq!(list.to_string(), "[bob, carol]");
+    assert_eq!(solution, Substitution {
+        mapping: [(1, list)].into_iter().collect(),
+    });
+    assert_eq!(solver.pull_next_goal(&mut goal_state).unwrap(), None);
+
+    // through a rule, including the empty case
+    for (parent, children) in
+        [("bob", Term::list([Term::atom("dave")])), ("dave", Term::list([]))]
+    {
+        let goal = Goal {
+            predicate: Predicate {
+                name: "children".into(),
+                arguments: vec![Term::atom(parent), Term::variable(0)],
+            },
+        };
+        let mut goal_state = solver.create_goal_state(goal);
+
+        assert_eq!(
+            solver.pull_next_goal(&mut goal_state).unwrap(),
+            Some(Substitution {
+                mapping: [(0, children)].into_iter().collect(),
+            })
+        );
+        assert_eq!(solver.pull_next_goal(&mut goal_state).unwrap(), None);
+    }
+}
+
+#[test]
+fn disjunction_matches_separate_clauses() {
+    let separate = graph_reachability_knowledge_base();
+
+    // connected(X, Y) :- road(X, Y) ; rail(X, Y) ; boat(X, Y).
+    let mut disjunctive = KnowledgeBase::new();
+    for name in ["road", "rail", "boat", "reachable"] {
+        for clause in separate.get_clauses((name, 2)).unwrap() {
+            disjunctive.add_clause(clause.clone());
+        }
+    }
+
+    let edge = |name: &str| {
+        BodyElem::Goal(Goal {
+            predicate: Predicate {
+                name: name.into(),
+                arguments: vec![Term::variable(0), Term::variable(1)],
+            },
+        })
+    };
+    disjunctive.add_clause(Clause {
+        head: Predicate {
+            name: "connected".into(),
+            arguments: vec![Term::variable(0), Term::variable(1)],
+        },
+        body: BodyElem::Disjunction(vec![
+            edge("road"),
+            edge("rail"),
+            edge("boat"),
+        ])
+        .into_goals(),
+    });
+
+    assert_eq!(disjunctive.get_clauses(("connected", 2)).unwrap().len(), 1);
+
+    for name in ["connected", "reachable"] {
+        let query = Goal {
+            predicate: Predicate {
+                name: name.into(),
+                arguments: vec![Term::variable(0), Term::variable(1)],
+            },
+        };
+
+        let mut solutions = Vec::new();
+        for kb in [&separate, &disjunctive] {
+            let mut solver = Solver::new(kb);
+            let mut goal_state = solver.create_goal_state(query.clone());
+
+            let mut answers = Vec::new();
+            while let Some(solution) =
+                solver.pull_next_goal(&mut goal_state).unwrap()
+            {
+                answers.push(solution);
+            }
+
+            answers.sort_by_key(ToString::to_string);
+            solutions.push(answers);
+        }
+
+        assert!(!solutions[0].is_empty());
+        assert_eq!(solutions[0], solutions[1], "Mismatch for {name}");
+    }
+}
+
+#[test]
+fn answer_subsumption_keeps_most_general_answer() {
+    // general(X).      specific(a).
+    // general(a).      specific(X).
+    let mut kb = KnowledgeBase::new();
+    kb.add_clause(Clause::fact(Predicate::new("general", [Term::variable(0)])));
+    kb.add_clause(Clause::fact(Predicate::new("general", [Term::atom("a")])));
+    kb.add_clause(Clause::fact(Predicate::new("specific", [Term::atom("a")])));
+    kb.add_clause(Clause::fact(Predicate::new("specific", [Term::variable(
+        0,
+    )])));
+
+    for name in ["general", "specific"] {
+        let query = Goal::new(name, [Term::variable(0)]);
+
+        for (subsumption, expected) in [(false, 2), (true, 1)] {
+            let mut solver = Solver::new(&kb);
+            solver.set_answer_subsumption(subsumption);
+
+            let mut goal_state = solver.create_goal_state(query.clone());
+
+            let mut solutions = Vec::new();
+            while let Some(solution) =
+                solver.pull_next_goal(&mut goal_state).unwrap()
+            {
+                solutions.push(solution);
+            }
+
+            assert_eq!(solutions.len(), expected, "Mismatch for {name}");
+            // the most general answer leaves `?0` unbound
+            assert!(solutions.iter().any(|solution| matches!(
+                solution.mapping.get(&0),
+                None | Some(Term::Variable(_))
+            )));
+            assert_eq!(solver.answers_for(&query), Some(expected));
+        }
+    }
+}
+
+#[test]
+fn tracer_records_evaluation_of_transitive_query() {
+    // over(a, b).  over(b, c).
+    // over(X, Y) :- over(X, Z), over(Z, Y).
+    let over = |from: Term, to: Term| Predicate::new("over", [from, to]);
+
+    let mut kb = KnowledgeBase::new();
+    kb.add_clause(Clause::fact(over(Term::atom("a"), Term::atom("b"))));
+    kb.add_clause(Clause::fact(over(Term::atom("b"), Term::atom("c"))));
+    kb.add_clause(Clause::rule(over(Term::variable(0), Term::variable(1)), [
+        Goal { predicate: over(Term::variable(0), Term::variable(2)) },
+        Goal { predicate: over(Term::variable(2), Term::variable(1)) },
+    ]));
+
+    let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+
+    let mut solver = Solver::new(&kb);
+    let recorder = events.clone();
+    solver.set_tracer(move |event: &TraceEvent| {
+        recorder.lock().unwrap().push(event.to_string());
+    });
+
+    let mut goal_state = solver.create_goal_state(Goal {
+        predicate: over(Term::atom("a"), Term::variable(0)),
+    });
+    while solver.pull_next_goal(&mut goal_state).unwrap().is_some() {}
+
+    drop(solver);
+
+    // the strands of `over(c, ?0)`, `over(b, ?0)`, and `over(a, ?0)` end up
+    // waiting on each other, which completes the whole cycle
+    assert_eq!(std::mem::take(&mut *events.lock().unwrap()), [
+        "new table over(a, ?0)",
+        "answer over(a, ?0): {?0 = b}",
+        "fork over(a, ?0)",
+        "new table over(b, ?0)",
+        "answer over(b, ?0): {?0 = c}",
+        "fork over(a, ?0)",
+        "answer over(a, ?0): {?0 = c}",
+        "fork over(a, ?0)",
+        "new table over(c, ?0)",
+        "fork over(b, ?0)",
+        "delay over(c, ?0) on #3",
+        "delay over(b, ?0) on #2",
+        "delay over(a, ?0) on #1",
+    ]);
+}
+
+#[test]
+fn traced_solver_runs_on_another_thread() {
+    let kb = family_knowledge_base();
+    let (sender, receiver) = std::sync::mpsc::channel();
+
+    let mut solver = Solver::new(&kb);
+    solver.set_tracer(move |event: &TraceEvent| {
+        sender.send(event.to_string()).unwrap();
+    });
+
+    let answers = std::thread::scope(|scope| {
+        scope
+            .spawn(move || {
+                let mut goal_state = solver
+                    .create_goal_state(parse_goal("ancestor(bob, X)").unwrap());
+
+                let mut answers = 0;
+                while solver.pull_next_goal(&mut goal_state).unwrap().is_some()
+                {
+                    answers += 1;
+                }
+
+                answers
+            })
+            .join()
+            .unwrap()
+    });
+
+    // dan, eve, and grace
+    assert_eq!(answers, 3);
+    assert_eq!(
+        receiver.iter().next().as_deref(),
+        Some("new table ancestor(bob, ?0)")
+    );
+}
+
+#[test]
+fn canonical_mapping_of_query_with_sparse_variables() {
+    let kb: KnowledgeBase = "p(b, a, c, b).".parse().unwrap();
+
+    // p(?5, a, ?7, ?5)
+    let query = Goal::new("p", [
+        Term::variable(5),
+        Term::atom("a"),
+        Term::variable(7),
+        Term::variable(5),
+    ]);
+
+    let mut solver = Solver::new(&kb);
+    let mut goal_state = solver.create_goal_state(query);
+
+    assert_eq!(
+        solver.canonical_goal(&goal_state).to_string(),
+        "p(?0, a, ?1, ?0)"
+    );
+    assert_eq!(
+        goal_state.canonical_mapping(),
+        &[(0, 5), (1, 7)].into_iter().collect()
+    );
+
+    // the stored answer is translated back through the mapping
+    let canonical_answer = Substitution {
+        mapping: [(0, Term::atom("b")), (1, Term::atom("c"))]
+            .into_iter()
+            .collect(),
+    };
+    let expected = uncanonicalize_substitution(
+        &canonical_answer,
+        goal_state.canonical_mapping(),
+    );
+
+    assert_eq!(expected.to_string(), "{?5 = b, ?7 = c}");
+    assert_eq!(solver.pull_next_goal(&mut goal_state).unwrap(), Some(expected));
+}
+
+#[test]
+fn later_query_reuses_tables_of_earlier_query() {
+    let kb = family_knowledge_base();
+    let mut solver = Solver::new(&kb);
+
+    let enumerate = |solver: &mut Solver, root: &str| {
+        let mut goal_state = solver.create_goal_state(Goal::new("ancestor", [
+            Term::atom(root),
+            Term::variable(0),
+        ]));
+
+        let mut descendants = Vec::new();
+        while let Some(solution) =
+            solver.pull_next_goal(&mut goal_state).unwrap()
+        {
+            descendants.push(solution.mapping[&0].to_string());
+        }
+        descendants.sort();
+
+        descendants
+    };
+
+    assert_eq!(enumerate(&mut solver, "adam"), [
+        "bob", "carol", "dan", "eve", "frank", "grace"
+    ]);
+
+    let table_count = solver.table_count();
+    let steps = solver.steps();
+
+    // `ancestor(bob, ?0)` and the `parent` tables it depends on are complete
+    assert_eq!(enumerate(&mut solver, "bob"), ["dan", "eve", "grace"]);
+
+    assert_eq!(solver.table_count(), table_count);
+    assert_eq!(solver.steps(), steps);
+}
+
+#[test]
+fn long_dependency_chain_does_not_overflow_stack() {
+    // far deeper than the stack of a test thread could hold if every table
+    // in the chain was evaluated by a nested call
+    const LENGTH: usize = 50_000;
+
+    // edge(n0, n1). edge(n1, n2). ... edge(n49999, n50000).
+    // path(X, Y) :- edge(X, Y).
+    // path(X, Y) :- edge(X, Z), path(Z, Y).
+    let mut kb = KnowledgeBase::new();
+    for i in 0..LENGTH {
+        kb.add_clause(Clause::fact(Predicate::new("edge", [
+            Term::atom(format!("n{i}")),
+            Term::atom(format!("n{}", i + 1)),
+        ])));
+    }
+    kb.add_clause(Clause::rule(
+        Predicate::new("path", [Term::variable(0), Term::variable(1)]),
+        [Goal::new("edge", [Term::variable(0), Term::variable(1)])],
+    ));
+    kb.add_clause(Clause::rule(
+        Predicate::new("path", [Term::variable(0), Term::variable(1)]),
+        [
+            Goal::new("edge", [Term::variable(0), Term::variable(2)]),
+            Goal::new("path", [Term::variable(2), Term::variable(1)]),
+        ],
+    ));
+
+    // every `path(nI, n50000)` table waits on `path(nI+1, n50000)`
+    let mut solver = Solver::new(&kb);
+    let mut goal_state = solver.create_goal_state(Goal::new("path", [
+        Term::atom("n0"),
+        Term::atom(format!("n{LENGTH}")),
+    ]));
+
+    assert!(solver.pull_next_goal(&mut goal_state).unwrap().is_some());
+    assert!(solver.pull_next_goal(&mut goal_state).unwrap().is_none());
+}
+
+#[test]
+fn long_chain_of_distinct_predicates_does_not_overflow_stack() {
+    const LENGTH: usize = 20_000;
+
+    // p0(X) :- p1(X). p1(X) :- p2(X). ... p19999(X) :- p20000(X).
+    // p20000(a).
+    let mut kb = KnowledgeBase::new();
+    for i in 0..LENGTH {
+        kb.add_clause(Clause::rule(
+            Predicate::new(format!("p{i}"), [Term::variable(0)]),
+            [Goal::new(format!("p{}", i + 1), [Term::variable(0)])],
+        ));
+    }
+    kb.add_clause(Clause::fact(Predicate::new(format!("p{LENGTH}"), [
+        Term::atom("a"),
+    ])));
+
+    // every table is created by the strand of the one before it
+    let mut solver = Solver::new(&kb);
+    let answers =
+        solver.solve_all(Goal::new("p0", [Term::variable(0)])).unwrap();
+
+    assert_eq!(answers.len(), 1);
+    assert_eq!(answers[0].mapping[&0], Term::atom("a"));
+    assert_eq!(solver.table_count(), LENGTH + 1);
+}
+
+#[test]
+fn reset_solver_answers_many_queries() {
+    let kb = family_knowledge_base();
+    let mut solver = Solver::new(&kb);
+
+    let roots = [
+        ("adam", &["bob", "carol", "dan", "eve", "frank", "grace"][..]),
+        ("bob", &["dan", "eve", "grace"][..]),
+        ("carol", &["frank"][..]),
+        ("frank", &[][..]),
+    ];
+
+    for iteration in 0..1000 {
+        solver.reset();
+        assert_eq!(solver.table_count(), 0);
+        assert_eq!(solver.steps(), 0);
+
+        let (root, expected) = roots[iteration % roots.len()];
+        let mut goal_state = solver.create_goal_state(Goal::new("ancestor", [
+            Term::atom(root),
+            Term::variable(0),
+        ]));
+
+        let mut descendants = Vec::new();
+        while let Some(solution) =
+            solver.pull_next_goal(&mut goal_state).unwrap()
+        {
+            descendants.push(solution.mapping[&0].to_string());
+        }
+        descendants.sort();
+
+        assert_eq!(descendants, expected);
+    }
+
+    // the memoized answers are discarded while the allocations carry over
+    let solver = Solver::new_reusing(solver, &kb);
+    assert_eq!(solver.table_count(), 0);
+}
+
+#[test]
+fn anonymous_variables_are_left_out_of_answers() {
+    let kb = family_knowledge_base();
+    let mut solver = Solver::new(&kb);
+
+    let mut goal_state =
+        solver.create_goal_state(parse_goal("parent(X, _)").unwrap());
+
+    let mut parents = Vec::new();
+    while let Some(solution) = solver.pull_next_goal(&mut goal_state).unwrap() {
+        assert_eq!(solution.mapping.keys().collect::<Vec<_>>(), [&0]);
+        parents.push(solution.mapping[&0].to_string());
+    }
+    parents.sort();
+
+    // `adam` and `bob` have two children each
+    assert_eq!(parents, ["adam", "adam", "bob", "bob", "carol", "dan"]);
+}
+
+#[test]
+fn asserted_fact_invalidates_dependent_tables() {
+    let kb = family_knowledge_base();
+    let mut solver = Solver::new(&kb);
+
+    let descendants = |solver: &mut Solver| {
+        let mut goal_state =
+            solver.create_goal_state(parse_goal("ancestor(dan, X)").unwrap());
+
+        let mut descendants = Vec::new();
+        while let Some(solution) =
+            solver.pull_next_goal(&mut goal_state).unwrap()
+        {
+            descendants.push(solution.mapping[&0].to_string());
+        }
+        descendants.sort();
+
+        descendants
+    };
+
+    assert_eq!(descendants(&mut solver), ["grace"]);
+
+    let henry = parse_goal("parent(grace, henry)").unwrap().predicate;
+
+    assert_eq!(
+        solver.assertz(henry.clone()),
+        Err(AssertError::NotDynamic(("parent", 2).into()))
+    );
+
+    solver.declare_dynamic(("parent", 2));
+
+    let anyone = parse_goal("parent(grace, X)").unwrap().predicate;
+    assert_eq!(
+        solver.assertz(anyone.clone()),
+        Err(AssertError::NotGround(anyone))
+    );
+
+    // the complete `ancestor(dan, ?0)` table is evaluated again
+    solver.assertz(henry).unwrap();
+
+    assert_eq!(descendants(&mut solver), ["grace", "henry"]);
+}
+
+#[test]
+fn added_clause_invalidates_dependent_tables() {
+    let mut solver = Solver::owning(family_knowledge_base());
+
+    let descendants = |solver: &mut Solver| {
+        let mut descendants = solver
+            .solve_all(parse_goal("ancestor(bob, X)").unwrap())
+            .unwrap()
+            .into_iter()
+            .map(|solution| solution.mapping[&0].to_string())
+            .collect::<Vec<_>>();
+        descendants.sort();
+
+        descendants
+    };
+
+    assert_eq!(descendants(&mut solver), ["dan", "eve", "grace"]);
+
+    // `parent/2` isn't dynamic, the clause goes to the owned knowledge base
+    solver.add_clause(Clause::fact(
+        parse_goal("parent(eve, henry)").unwrap().predicate,
+    ));
+
+    assert_eq!(descendants(&mut solver), ["dan", "eve", "grace", "henry"]);
+    assert_eq!(solver.knowledge_base().clause_count("parent"), 7);
+
+    // a table not depending on `parent/2` is kept
+    let kb = family_knowledge_base();
+    let mut borrowing = Solver::new(&kb);
+    let mut goal_state =
+        borrowing.create_goal_state(parse_goal("ancestor(bob, X)").unwrap());
+    let table_count = borrowing.table_count();
+    borrowing.add_clause(Clause::fact(
+        parse_goal("unrelated(henry)").unwrap().predicate,
+    ));
+
+    assert_eq!(borrowing.table_count(), table_count);
+    assert!(borrowing.pull_next_goal(&mut goal_state).unwrap().is_some());
+
+    // the borrowed knowledge base is left untouched
+    assert_eq!(kb.clause_count("unrelated"), 0);
+    assert_eq!(borrowing.knowledge_base().clause_count("unrelated"), 1);
+}
+
+#[test]
+fn project_answers_onto_sparse_query_variables() {
+    let kb = family_knowledge_base();
+    let mut solver = Solver::new(&kb);
+
+    let mut goal_state = solver.create_goal_state(Goal::new("parent", [
+        Term::variable(7),
+        Term::variable(42),
+    ]));
+
+    let mut pairs = Vec::new();
+    while let Some(solution) = solver.pull_next_goal(&mut goal_state).unwrap() {
+        let mut variables =
+            solution.mapping.keys().copied().collect::<Vec<_>>();
+        variables.sort_unstable();
+        assert_eq!(variables, [7, 42]);
+
+        pairs.push(format!(
+            "{}-{}",
+            solution.mapping[&7], solution.mapping[&42]
+        ));
+    }
+    pairs.sort();
+
+    assert_eq!(pairs, [
+        "adam-bob",
+        "adam-carol",
+        "bob-dan",
+        "bob-eve",
+        "carol-frank",
+        "dan-grace"
+    ]);
+
+    // bindings of variables outside the canonical goal are dropped
+    let mut answer = Substitution::default();
+    answer.mapping.insert(0, Term::atom("adam"));
+    answer.mapping.insert(1, Term::atom("bob"));
+    answer.mapping.insert(2, Term::atom("carol"));
+
+    assert_eq!(
+        goal_state.project(&answer).to_string(),
+        "{?7 = adam, ?42 = bob}"
+    );
+}
+
+#[test]
+fn has_solution_stops_at_first_answer() {
+    let kb: KnowledgeBase = "
+        parent(alice, bob).
+        parent(bob, carol).
+        ancestor(X, Y) :- parent(X, Y).
+        ancestor(X, Y) :- parent(X, Z), ancestor(Z, Y).
+    "
+    .parse()
+    .unwrap();
+    let mut solver = Solver::new(&kb);
+
+    assert!(
+        solver.has_solution(parse_goal("parent(alice, bob)").unwrap()).unwrap()
+    );
+    assert!(
+        !solver
+            .has_solution(parse_goal("parent(bob, alice)").unwrap())
+            .unwrap()
+    );
+
+    // `ancestor(alice, ?0)` has two answers, only the first one is searched for
+    assert!(
+        solver.has_solution(parse_goal("ancestor(alice, X)").unwrap()).unwrap()
+    );
+    assert_eq!(
+        solver.answers_for(&parse_goal("ancestor(alice, X)").unwrap()),
+        Some(1)
+    );
+}
+
+#[test]
+fn ordered_answers_of_transitive_query() {
+    let kb: KnowledgeBase = "
+        over(a, d).
+        over(c, e).
+        over(a, b).
+        over(b, c).
+        over(X, Y) :- over(X, Z), over(Z, Y).
+    "
+    .parse()
+    .unwrap();
+
+    let answers = |mut solver: Solver| {
+        let mut goal_state =
+            solver.create_goal_state(parse_goal("over(a, X)").unwrap());
+
+        let mut answers = Vec::new();
+        while let Some(solution) =
+            solver.pull_next_goal(&mut goal_state).unwrap()
+        {
+            answers.push(solution.to_string());
+        }
+
+        answers
+    };
+
+    // found in the order of the facts first
+    assert_eq!(answers(Solver::new(&kb))[..2], ["{?0 = d}", "{?0 = b}"]);
+
+    assert_eq!(answers(Solver::with_ordered_answers(&kb)), [
+        "{?0 = b}", "{?0 = c}", "{?0 = d}", "{?0 = e}"
+    ]);
+}
+
+#[test]
+fn unification_built_ins() {
+    let kb: KnowledgeBase = "
+        wrap(X, Y) :- Y = box(X).
+        item(a). item(b).
+        distinct(X, Y) :- item(X), item(Y), X \\= Y.
+    "
+    .parse()
+    .unwrap();
+    let mut solver = Solver::new(&kb);
+
+    let mut answers = |query: &str| {
+        let mut goal_state =
+            solver.create_goal_state(parse_goal(query).unwrap());
+
+        let mut answers = Vec::new();
+        while let Some(solution) =
+            solver.pull_next_goal(&mut goal_state).unwrap()
+        {
+            answers.push(solution.to_string());
+        }
+        answers.sort();
+
+        answers
+    };
+
+    // ground arguments
+    assert_eq!(answers("a = a"), ["{}"]);
+    assert!(answers("a = b").is_empty());
+    assert_eq!(answers("a \\= b"), ["{}"]);
+    assert!(answers("f(a) \\= f(a)").is_empty());
+
+    // partially bound arguments bind like a clause head would
+    assert_eq!(answers("X = foo(Y), Y = bar"), ["{?0 = foo(bar), ?1 = bar}"]);
+    assert_eq!(answers("wrap(1, W)"), ["{?0 = box(1)}"]);
+    assert!(answers("X = f(X)").is_empty());
+
+    // `\=` fails if the arguments unify and never binds anything
+    assert!(answers("f(X, b) \\= f(a, Y)").is_empty());
+    assert_eq!(answers("f(X, b) \\= f(a, c)"), ["{}"]);
+    assert_eq!(answers("distinct(a, Y)"), ["{?0 = b}"]);
+}
+
+#[test]
+fn non_tabled_predicates_yield_same_answers() {
+    let mut kb: KnowledgeBase = "
+        edge(a, b). edge(a, c). edge(b, d). edge(c, d). edge(d, e).
+        path(X, Y) :- edge(X, Y).
+        path(X, Z) :- edge(X, Y), path(Y, Z).
+    "
+    .parse()
+    .unwrap();
+
+    let answers = |kb: &KnowledgeBase| {
+        let mut solver = Solver::new(kb);
+        let mut goal_state =
+            solver.create_goal_state(parse_goal("path(a, X)").unwrap());
+
+        let mut answers = Vec::new();
+        while let Some(solution) =
+            solver.pull_next_goal(&mut goal_state).unwrap()
+        {
+            answers.push(solution.to_string());
+        }
+        answers.sort();
+
+        (answers, solver.table_count())
+    };
+
+    let (tabled, tabled_count) = answers(&kb);
+
+    kb.set_tabled(("edge", 2), false);
+    kb.set_tabled(("path", 2), false);
+    assert!(!kb.is_tabled(("path", 2)));
+
+    let (untabled, untabled_count) = answers(&kb);
+
+    assert_eq!(tabled, ["{?0 = b}", "{?0 = c}", "{?0 = d}", "{?0 = e}"]);
+    assert_eq!(untabled, tabled);
+
+    // only the query itself gets a table
+    assert!(tabled_count > 1);
+    assert_eq!(untabled_count, 1);
+}
+
+#[test]
+fn non_tabled_recursive_predicate_yields_same_answers() {
+    let mut kb: KnowledgeBase = "
+        app([], L, L).
+        app([H | T], L, [H | R]) :- app(T, L, R).
+        split(X, Y) :- app(X, Y, [1, 2, 3]).
+    "
+    .parse()
+    .unwrap();
+
+    let answers = |kb: &KnowledgeBase| {
+        let mut solver = Solver::new(kb);
+        let mut answers = solver
+            .solve_all(parse_goal("split(X, Y)").unwrap())
+            .unwrap()
+            .into_iter()
+            .map(|answer| answer.to_string())
+            .collect::<Vec<_>>();
+        answers.sort();
+
+        (answers, solver.table_count())
+    };
+
+    let (tabled, tabled_count) = answers(&kb);
+
+    kb.set_tabled(("app", 3), false);
+
+    // `app/3` recurses once per element, depth-first without a table
+    let (untabled, untabled_count) = answers(&kb);
+
+    assert_eq!(tabled, [
+        "{?0 = [1, 2, 3], ?1 = []}",
+        "{?0 = [1, 2], ?1 = [3]}",
+        "{?0 = [1], ?1 = [2, 3]}",
+        "{?0 = [], ?1 = [1, 2, 3]}",
+    ]);
+    assert_eq!(untabled, tabled);
+
+    assert_eq!(tabled_count, 5);
+    assert_eq!(untabled_count, 1);
+}
+
+#[test]
+fn non_tabled_loop_stops_at_the_limits() {
+    let mut kb: KnowledgeBase = "
+        lp(X) :- lp(X).
+        loops(X) :- lp(X).
+    "
+    .parse()
+    .unwrap();
+    kb.set_tabled(("lp", 1), false);
+
+    // the query gets a table, but every call to `lp/1` in its body unfolds
+    // into another one
+    let mut solver = Solver::with_max_steps(&kb, 100);
+    let mut goal_state =
+        solver.create_goal_state(parse_goal("loops(X)").unwrap());
+
+    assert_eq!(
+        solver.pull_next_goal(&mut goal_state),
+        Err(SolveError::StepLimitExceeded)
+    );
+    assert_eq!(solver.steps(), 100);
+
+    // resuming picks the loop up again, still within the limits
+    assert_eq!(
+        solver.pull_next_goal(&mut goal_state),
+        Err(SolveError::StepLimitExceeded)
+    );
+
+    let mut solver = Solver::with_deadline(&kb, Instant::now());
+    let mut goal_state =
+        solver.create_goal_state(parse_goal("loops(X)").unwrap());
+
+    assert_eq!(
+        solver.pull_next_goal(&mut goal_state),
+        Err(SolveError::Timeout)
+    );
+}
+
+#[test]
+fn typed_solutions_of_grandparent_query() {
+    let kb: KnowledgeBase = "
+        parent(alice, bob).
+        parent(bob, dave).
+        parent(bob, erin).
+        grandparent(X, Y) :- parent(X, Z), parent(Z, Y).
+    "
+    .parse()
+    .unwrap();
+    let mut solver = Solver::new(&kb);
+
+    let mut goal_state =
+        solver.create_goal_state(parse_goal("grandparent(G, C)").unwrap());
+
+    let mut grandchildren = Vec::new();
+    while let Some(solution) =
+        solver.next_solution_as::<Solution>(&mut goal_state).unwrap()
+    {
+        assert_eq!(solution.get_atom(0), Some("alice"));
+        assert_eq!(solution.get_term(0), Some(&Term::atom("alice")));
+
+        // the variable doesn't occur in the query
+        assert_eq!(solution.get_term(2), None);
+
+        grandchildren.push(solution.get_atom(1).unwrap().to_owned());
+    }
+    grandchildren.sort();
+
+    assert_eq!(grandchildren, ["dave", "erin"]);
+
+    // non-atom bindings aren't atoms
+    let kb: KnowledgeBase = "box(f(a)).".parse().unwrap();
+    let mut solver = Solver::new(&kb);
+    let mut goal_state =
+        solver.create_goal_state(parse_goal("box(X)").unwrap());
+
+    let solution =
+        solver.next_solution_as::<Solution>(&mut goal_state).unwrap().unwrap();
+
+    assert_eq!(solution.get_atom(0), None);
+    assert_eq!(
+        solution.get_term(0),
+        Some(&Term::component("f", [Term::atom("a")]))
+    );
+}
+
+#[test]
+fn negation_as_failure() {
+    let kb: KnowledgeBase = "
+        man(adam). man(bob). man(carl).
+        married(bob).
+        bachelor(X) :- man(X), \\+ married(X).
+        edge(a, b). edge(b, a). edge(b, c).
+        path(X, Y) :- edge(X, Y).
+        path(X, Z) :- path(X, Y), edge(Y, Z).
+        unreachable(X, Y) :- man(Y), \\+ path(X, Y).
+    "
+    .parse()
+    .unwrap();
+    let mut solver = Solver::new(&kb);
+
+    let mut answers = |query: &str| {
+        let mut goal_state =
+            solver.create_goal_state(parse_goal(query).unwrap());
+
+        let mut answers = Vec::new();
+        while let Some(solution) =
+            solver.pull_next_goal(&mut goal_state).unwrap()
+        {
+            answers.push(solution.to_string());
+        }
+        answers.sort();
+
+        answers
+    };
+
+    assert_eq!(answers("bachelor(X)"), ["{?0 = adam}", "{?0 = carl}"]);
+    assert!(answers("\\+ man(bob)").is_empty());
+    assert_eq!(answers("\\+ married(adam)"), ["{}"]);
+
+    // the negated goal is recursive but doesn't depend on the negation
+    assert_eq!(answers("unreachable(a, Y)"), [
+        "{?0 = adam}",
+        "{?0 = bob}",
+        "{?0 = carl}"
+    ]);
+}
+
+#[test]
+fn unstratified_negation_is_reported() {
+    let kb: KnowledgeBase = "
+        p :- \\+ p.
+        q :- r.
+        r :- s, \\+ q.
+        s.
+    "
+    .parse()
+    .unwrap();
+    let mut solver = Solver::new(&kb);
+
+    let mut goal_state = solver.create_goal_state(parse_goal("p").unwrap());
+
+    assert_eq!(
+        solver.pull_next_goal(&mut goal_state),
+        Err(SolveError::UnstratifiedNegation)
+    );
+
+    // the cycle may go through other predicates
+    solver.reset();
+    let mut goal_state = solver.create_goal_state(parse_goal("q").unwrap());
+
+    assert_eq!(
+        solver.pull_next_goal(&mut goal_state),
+        Err(SolveError::UnstratifiedNegation)
+    );
+}
+
+#[test]
+fn pull_next_goal_ok_conflates_errors_with_exhaustion() {
+    let kb: KnowledgeBase = "
+        p :- \\+ p.
+        nat(zero).
+        nat(s(X)) :- nat(X).
+    "
+    .parse()
+    .unwrap();
+
+    let mut solver = Solver::with_max_steps(&kb, 10);
+
+    let mut negation = solver.create_goal_state(parse_goal("p").unwrap());
+    assert_eq!(
+        solver.pull_next_goal(&mut negation),
+        Err(SolveError::UnstratifiedNegation)
+    );
+    assert_eq!(solver.pull_next_goal_ok(&mut negation), None);
+
+    solver.reset();
+    let mut nat = solver.create_goal_state(parse_goal("nat(X)").unwrap());
+
+    let mut answers = 0;
+    while solver.pull_next_goal_ok(&mut nat).is_some() {
+        answers += 1;
+    }
+
+    // the enumeration ended because of the step limit, not for lack of
+    // answers
+    assert!(answers > 0);
+    assert_eq!(
+        solver.pull_next_goal(&mut nat),
+        Err(SolveError::StepLimitExceeded)
+    );
+}
+
+#[test]
+fn between_enumerates_range_lazily() {
+    let kb: KnowledgeBase = "
+        pair(X, Y) :- between(1, 3, X), between(X, 3, Y).
+        first(X) :- between(1, 10, X), !.
+    "
+    .parse()
+    .unwrap();
+    let mut solver = Solver::new(&kb);
+
+    let mut goal_state =
+        solver.create_goal_state(parse_goal("between(1, 4, X)").unwrap());
+
+    // the integers are only generated as they're pulled
+    let first = solver.pull_next_goal(&mut goal_state).unwrap().unwrap();
+    assert_eq!(first.mapping[&0], Term::integer(1));
+    assert_eq!(solver.table(&goal_state).answers().len(), 1);
+
+    let mut answers = vec![first.mapping[&0].to_string()];
+    while let Some(answer) = solver.pull_next_goal(&mut goal_state).unwrap() {
+        answers.push(answer.mapping[&0].to_string());
+    }
+
+    assert_eq!(answers, ["1", "2", "3", "4"]);
+
+    let solve = |solver: &mut Solver, goal: &str| {
+        solver.solve_all(parse_goal(goal).unwrap()).unwrap().len()
+    };
+
+    assert_eq!(solve(&mut solver, "between(1, 4, 3)"), 1);
+    assert_eq!(solve(&mut solver, "between(1, 4, 7)"), 0);
+    assert_eq!(solve(&mut solver, "between(4, 1, X)"), 0);
+    assert_eq!(solve(&mut solver, "between(a, 4, X)"), 0);
+    assert_eq!(solve(&mut solver, "pair(X, Y)"), 6);
+
+    // the cut discards the rest of the range
+    assert_eq!(solver.solve_all(parse_goal("first(X)").unwrap()).unwrap(), [
+        Substitution { mapping: [(0, Term::integer(1))].into_iter().collect() }
+    ]);
+}
+
+#[test]
+fn copy_term_renames_variables_apart() {
+    let kb: KnowledgeBase = "
+        copied(S, C) :- S = f(X, g(Y), X), copy_term(S, C).
+    "
+    .parse()
+    .unwrap();
+    let mut solver = Solver::new(&kb);
+
+    let answers =
+        solver.solve_all(parse_goal("copied(S, C)").unwrap()).unwrap();
+    assert_eq!(answers.len(), 1);
+
+    let (Term::Compound(_, source), Term::Compound(_, copy)) =
+        (&answers[0].mapping[&0], &answers[0].mapping[&1])
+    else {
+        panic!("both terms should be compounds");
+    };
+
+    let variables = |arguments: &[Term]| {
+        let Term::Compound(_, inner) = &arguments[1] else { unreachable!() };
+
+        [arguments[0].clone(), inner[0].clone(), arguments[2].clone()].map(
+            |term| match term {
+                Term::Variable(variable) => variable,
+                term => panic!("`{term}` should be a variable"),
+            },
+        )
+    };
+
+    // the structure is preserved, `X` occurring twice in both
+    let [x, y, x_again] = variables(source);
+    let [copied_x, copied_y, copied_x_again] = variables(copy);
+
+    assert_eq!(x, x_again);
+    assert_eq!(copied_x, copied_x_again);
+    assert_ne!(x, y);
+    assert_ne!(copied_x, copied_y);
+
+    // but no variable is shared
+    assert!(![x, y].contains(&copied_x));
+    assert!(![x, y].contains(&copied_y));
+
+    // copying doesn't bind the source
+    let answers = solver
+        .solve_all(parse_goal("copy_term(f(X, b), f(a, Y))").unwrap())
+        .unwrap();
+
+    assert_eq!(answers, [Substitution {
+        mapping: [(1, Term::atom("b"))].into_iter().collect()
+    }]);
+}
+
+#[test]
+fn length_measures_and_generates_lists() {
+    let kb = KnowledgeBase::new();
+    let mut solver = Solver::new(&kb);
+
+    let mut solve =
+        |goal: &str| solver.solve_all(parse_goal(goal).unwrap()).unwrap();
+
+    // a proper list has a single length
+    assert_eq!(solve("length([a, b, c], N)"), [Substitution {
+        mapping: [(0, Term::integer(3))].into_iter().collect()
+    }]);
+    assert_eq!(solve("length([a, X], 2)").len(), 1);
+    assert!(solve("length([a, b], 3)").is_empty());
+    assert!(solve("length(foo, N)").is_empty());
+    assert!(solve("length([a|b], N)").is_empty());
+    assert!(solve("length(L, -1)").is_empty());
+    assert!(solve("length([a, b], two)").is_empty());
+
+    // the list of a given length is made of distinct fresh variables
+    let items = |term: &Term| {
+        let mut items = Vec::new();
+        let mut term = term;
+        while let Some([item, tail]) = term.as_compound(".", 2) {
+            items.push(item.as_variable().unwrap());
+            term = tail;
+        }
+        assert_eq!(term.as_atom(), Some("[]"));
+
+        items
+    };
+
+    let answers = solve("length(L, 3)");
+    assert_eq!(answers.len(), 1);
+
+    let generated = items(&answers[0].mapping[&0]);
+    assert_eq!(generated.len(), 3);
+    assert!(generated[0] != generated[1] && generated[1] != generated[2]);
+
+    let answers = solve("length([a|T], 3)");
+    assert_eq!(items(&answers[0].mapping[&0]).len(), 2);
+
+    // a partial list grows one item at a time as the answers are pulled
+    let mut goal_state =
+        solver.create_goal_state(parse_goal("length([a|T], N)").unwrap());
+
+    let mut lengths = Vec::new();
+    for pulled in 1..=3 {
+        let answer = solver.pull_next_goal(&mut goal_state).unwrap().unwrap();
+        assert_eq!(solver.table(&goal_state).answers().len(), pulled);
+
+        assert_eq!(items(&answer.mapping[&0]).len(), pulled - 1);
+        lengths.push(answer.mapping[&1].clone());
+    }
+
+    assert_eq!(lengths, [Term::integer(1), Term::integer(2), Term::integer(3)]);
+}
+
+#[test]
+fn solve_all_limited_stops_early() {
+    let kb: KnowledgeBase = "
+        nat(zero).
+        nat(s(X)) :- nat(X).
+    "
+    .parse()
+    .unwrap();
+    let mut solver = Solver::new(&kb);
+
+    // `nat/1` has infinitely many answers
+    let answers =
+        solver.solve_all_limited(parse_goal("nat(X)").unwrap(), 3).unwrap();
+
+    assert_eq!(answers.iter().map(ToString::to_string).collect::<Vec<_>>(), [
+        "{?0 = zero}",
+        "{?0 = s(zero)}",
+        "{?0 = s(s(zero))}"
+    ]);
+
+    // fewer answers than the limit
+    assert_eq!(
+        solver.solve_all_limited(parse_goal("nat(s(zero))").unwrap(), 3),
+        Ok(vec![Substitution::default()])
+    );
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn resume_enumeration_from_serialized_goal_state() {
+    let kb = family_knowledge_base();
+    let query = parse_goal("relative(adam, X)").unwrap();
+
+    let expected = Solver::new(&kb).solve_all(query.clone()).unwrap();
+    assert!(expected.len() > 2);
+
+    let mut solver = Solver::new(&kb);
+    let mut goal_state = solver.create_goal_state(query);
+
+    let mut answers =
+        vec![solver.pull_next_goal(&mut goal_state).unwrap().unwrap()];
+
+    let checkpoint = serde_json::to_string(&goal_state).unwrap();
+    drop(goal_state);
+
+    let mut goal_state: crate::solver::GoalState =
+        serde_json::from_str(&checkpoint).unwrap();
+    while let Some(solution) = solver.pull_next_goal(&mut goal_state).unwrap() {
+        answers.push(solution);
+    }
+
+    assert_eq!(answers, expected);
+}
+
+#[test]
+fn depth_first_scheduling_finds_first_answer_sooner() {
+    let kb: KnowledgeBase = "
+        task(X) :- a(X), b(X), c(X).
+        task(X) :- b(X), c(X), a(X).
+        task(X) :- c(X), a(X), b(X).
+        a(1). a(2). a(3).
+        b(1). b(2). b(3).
+        c(1). c(2). c(3).
+    "
+    .parse()
+    .unwrap();
+
+    let run = |policy| {
+        let mut solver = Solver::new(&kb);
+        solver.set_scheduling_policy(policy);
+
+        let mut goal_state =
+            solver.create_goal_state(parse_goal("task(X)").unwrap());
+
+        let first = solver.pull_next_goal(&mut goal_state).unwrap().unwrap();
+        let first_answer_steps = solver.steps();
+
+        let mut answers = vec![first.to_string()];
+        while let Some(solution) =
+            solver.pull_next_goal(&mut goal_state).unwrap()
+        {
+            answers.push(solution.to_string());
+        }
+        answers.sort();
+
+        (first_answer_steps, answers)
+    };
+
+    let (fifo_steps, fifo_answers) = run(SchedulingPolicy::Fifo);
+    let (lifo_steps, lifo_answers) = run(SchedulingPolicy::Lifo);
+
+    assert!(lifo_steps < fifo_steps, "{lifo_steps} >= {fifo_steps}");
+
+    // the policy doesn't change the answers
+    assert_eq!(fifo_answers, ["{?0 = 1}", "{?0 = 2}", "{?0 = 3}"]);
+    assert_eq!(lifo_answers, fifo_answers);
+}
+
+#[test]
+fn shortest_first_scheduling_orders_answers_by_cost() {
+    // `aa` comes first alphabetically but is the farthest from `a`
+    let mut kb = graph_reachability_knowledge_base();
+    kb.add_clause(parse_clause("boat(f, aa).").unwrap());
+
+    let mut solver = Solver::with_ordered_answers(&kb);
+    solver.set_scheduling_policy(SchedulingPolicy::ShortestFirst);
+
+    let mut goal_state =
+        solver.create_goal_state(parse_goal("reachable(a, Y)").unwrap());
+
+    let mut destinations = Vec::new();
+    while let Some(solution) =
+        solver.next_solution_as::<Solution>(&mut goal_state).unwrap()
+    {
+        destinations.push(solution.get_atom(0).unwrap().to_owned());
+    }
+
+    // `b` is a single hop away, `f` five hops
+    assert_eq!(destinations, ["b", "c", "d", "e", "f", "aa"]);
+
+    // every hop resolves a `reachable/2`, a `connected/2`, and an edge clause
+    let table = solver.table(&goal_state);
+    let costs = table
+        .answers()
+        .iter()
+        .zip(table.answer_costs())
+        .map(|(answer, cost)| (answer.to_string(), *cost))
+        .collect::<HashMap<_, _>>();
+
+    assert_eq!(costs["{?0 = b}"], 3);
+    assert_eq!(costs["{?0 = f}"], 15);
+}
+
+#[test]
+fn switching_the_scheduling_policy_reorders_pending_strands() {
+    let kb = graph_reachability_knowledge_base();
+    let goal = parse_goal("reachable(a, Y)").unwrap();
+
+    let mut expected = Solver::new(&kb).solve_all(goal.clone()).unwrap();
+    expected.sort();
+
+    let mut solver = Solver::new(&kb);
+    solver.set_scheduling_policy(SchedulingPolicy::Lifo);
+
+    let mut goal_state = solver.create_goal_state(goal);
+    let mut answers = Vec::new();
+    for _ in 0..2 {
+        answers.extend(solver.pull_next_goal(&mut goal_state).unwrap());
+    }
+
+    let costs = |solver: &Solver| {
+        solver
+            .table(&goal_state)
+            .strands()
+            .map(|strand| strand.cost())
+            .collect::<Vec<_>>()
+    };
+
+    // `Lifo` advances the strand forked last, the longest derivation
+    assert_eq!(costs(&solver), [3, 1]);
+
+    // the pending strands now come out cheapest first
+    solver.set_scheduling_policy(SchedulingPolicy::ShortestFirst);
+    assert_eq!(costs(&solver), [1, 3]);
+
+    answers.extend(solver.pull_next_goal(&mut goal_state).unwrap());
+
+    solver.set_scheduling_policy(SchedulingPolicy::Fifo);
+    while let Some(answer) = solver.pull_next_goal(&mut goal_state).unwrap() {
+        answers.push(answer);
+    }
+
+    // the policy doesn't change the answers
+    answers.sort();
+    assert_eq!(answers, expected);
+}
+
+#[test]
+fn bind_variables_nested_in_query_compound() {
+    let program = "
+        p(pair(a, b)).
+        p(pair(c, f(d))).
+        p(pair(e, g(Z))).
+
+        s(A, B) :- p(pair(A, B)), B = g(h).
+    ";
+
+    let mut kb = KnowledgeBase::new();
+    for clause in parse_program(program).unwrap() {
+        kb.add_clause(clause);
+    }
+
+    let mut solver = Solver::new(&kb);
+
+    let solve = |solver: &mut Solver, goal: Goal| {
+        let mut goal_state = solver.create_goal_state(goal.clone());
+
+        let mut results = Vec::new();
+        while let Some(solution) =
+            solver.pull_next_goal(&mut goal_state).unwrap()
+        {
+            let mut query = goal.to_term();
+            solution.apply_term(&mut query);
+
+            results.push(query.to_string());
+        }
+        results.sort();
+
+        results
+    };
+
+    // p(pair(?3, ?9)), the variable of the last fact is renamed apart from
+    // the query variables
+    let goal = Goal::new("p", [Term::component("pair", [
+        Term::variable(3),
+        Term::variable(9),
+    ])]);
+
+    assert_eq!(solve(&mut solver, goal), [
+        "p(pair(a, b))",
+        "p(pair(c, f(d)))",
+        "p(pair(e, g(?10)))"
+    ]);
+
+    // p(pair(?0, ?2)), whose canonical goal `p(pair(?0, ?1))` has the answer
+    // `{?0 = e, ?1 = g(?2)}`
+    let goal = Goal::new("p", [Term::component("pair", [
+        Term::variable(0),
+        Term::variable(2),
+    ])]);
+
+    assert_eq!(solve(&mut solver, goal)[2], "p(pair(e, g(?3)))");
+
+    // the answer is combined with the bindings of the strand of `s/2`, whose
+    // variables mustn't be confused with the one of the answer either
+    assert_eq!(solve(&mut solver, parse_goal("s(X, Y)").unwrap()), [
+        "s(e, g(h))"
+    ]);
+}
+
+#[test]
+fn inspect_pending_strands_mid_enumeration() {
+    let kb = family_knowledge_base();
+    let mut solver = Solver::new(&kb);
+
+    let mut goal_state =
+        solver.create_goal_state(parse_goal("ancestor(adam, X)").unwrap());
+
+    assert_eq!(
+        solver.pull_next_goal(&mut goal_state).unwrap().unwrap().to_string(),
+        "{?0 = bob}"
+    );
+
+    // both clauses of `ancestor/2` still consume the answers of `parent/2`,
+    // the second one has yet to prove `ancestor/2` recursively
+    let table = solver.table(&goal_state);
+
+    assert_eq!(table.canonical_goal().to_string(), "ancestor(adam, ?0)");
+    assert_eq!(table.answers().len(), 1);
+    assert_eq!(table.work_list_len(), 2);
+
+    let mut strands = table
+        .strands()
+        .map(|strand| {
+            assert_eq!(
+                strand.selected_subgoal().to_string(),
+                "parent(adam, ?0)"
+            );
+            assert_eq!(strand.substitution().mapping[&1], Term::atom("adam"));
+
+            strand
+                .rest_subgoals()
+                .iter()
+                .map(|goal| goal.predicate.name.to_string())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    strands.sort();
+
+    assert_eq!(strands, [vec![], vec!["ancestor".to_string()]]);
+}
+
+#[test]
+fn unbound_input_argument_is_rejected() {
+    let mut kb = family_knowledge_base();
+    kb.declare_mode("parent", [Mode::In, Mode::Out]);
+
+    let violation = SolveError::ModeViolation(Functor::new("parent", 2), 0);
+    let mut solver = Solver::new(&kb);
+
+    // the first argument of the query itself is unbound
+    assert_eq!(
+        solver.solve_all(parse_goal("parent(X, bob)").unwrap()),
+        Err(violation)
+    );
+
+    // calling in the declared direction works as usual, including the
+    // recursive calls of `ancestor/2` whose first argument gets bound first
+    assert_eq!(
+        solver.solve_all(parse_goal("parent(adam, X)").unwrap()).unwrap().len(),
+        2
+    );
+    assert_eq!(
+        solver
+            .solve_all(parse_goal("ancestor(adam, X)").unwrap())
+            .unwrap()
+            .len(),
+        6
+    );
+
+    // `sibling/2` calls `parent(Z, X)` with `Z` unbound, the offending call
+    // is dropped so pulling again finds no answer
+    let mut goal_state =
+        solver.create_goal_state(parse_goal("sibling(bob, X)").unwrap());
+
+    assert_eq!(solver.pull_next_goal(&mut goal_state), Err(violation));
+    assert_eq!(solver.pull_next_goal(&mut goal_state), Ok(None));
+
+    // goals nested in `findall/3` are checked as well
+    assert_eq!(
+        solver.solve_all(parse_goal("findall(X, parent(X, Y), L)").unwrap()),
+        Err(violation)
+    );
+}
+
+#[test]
+fn unbound_input_argument_is_rejected_through_an_alias() {
+    let mut kb = family_knowledge_base();
+    kb.declare_mode("parent", [Mode::In, Mode::Out]);
+    kb.alias_predicate("parent", "father_or_mother");
+    kb.add_clause(
+        "parent_of(X, Y) :- father_or_mother(Y, X)."
+            .parse::<KnowledgeBase>()
+            .unwrap()
+            .clauses_iter()
+            .next()
+            .unwrap()
+            .clone(),
+    );
+
+    assert_eq!(
+        kb.modes(("father_or_mother", 2)),
+        Some(&[Mode::In, Mode::Out][..])
+    );
+
+    let mut solver = Solver::new(&kb);
+
+    // the query calls the alias, the violation names the predicate called
+    assert_eq!(
+        solver.solve_all(parse_goal("father_or_mother(X, bob)").unwrap()),
+        Err(SolveError::ModeViolation(Functor::new("father_or_mother", 2), 0))
+    );
+    assert_eq!(
+        solver
+            .solve_all(parse_goal("father_or_mother(adam, X)").unwrap())
+            .unwrap()
+            .len(),
+        2
+    );
+
+    // so does a subgoal calling the alias with its input unbound
+    assert_eq!(
+        solver.solve_all(parse_goal("parent_of(bob, X)").unwrap()),
+        Err(SolveError::ModeViolation(Functor::new("father_or_mother", 2), 0))
+    );
+    drop(solver);
+
+    // the other per-predicate settings resolve the alias too
+    kb.set_tabled(("father_or_mother", 2), false);
+
+    assert!(!kb.is_tabled(("parent", 2)));
+    assert_eq!(kb.clause_count("father_or_mother"), 6);
+}
+
+#[test]
+fn group_children_by_parent() {
+    let kb = family_knowledge_base();
+    let mut solver = Solver::new(&kb);
+
+    // parent(P, C), grouped by P
+    let goal = parse_goal("parent(P, C)").unwrap();
+    let groups = solver.solve_grouped(goal, 0).unwrap();
+
+    let mut children = groups
+        .iter()
+        .map(|(parent, answers)| {
+            let mut children = answers
+                .iter()
+                .map(|answer| answer.mapping[&1].to_string())
+                .collect::<Vec<_>>();
+            children.sort();
+
+            (parent.to_string(), children)
+        })
+        .collect::<Vec<_>>();
+    children.sort();
+
+    assert_eq!(children, [
+        ("adam".to_string(), vec!["bob".to_string(), "carol".to_string()]),
+        ("bob".to_string(), vec!["dan".to_string(), "eve".to_string()]),
+        ("carol".to_string(), vec!["frank".to_string()]),
+        ("dan".to_string(), vec!["grace".to_string()]),
+    ]);
+
+    // every answer binds the key to the parent it's grouped under
+    for (parent, answers) in &groups {
+        assert!(answers.iter().all(|answer| answer.mapping[&0] == *parent));
+    }
+}
+
+#[test]
+fn seeded_query_only_returns_pinned_answers() {
+    let kb = "
+        parent(alice, bob).
+        parent(alice, carol).
+        parent(bob, dave).
+        parent(carol, erin).
+        parent(frank, gina).
+        parent(gina, hank).
+        grandparent(X, Z) :- parent(X, Y), parent(Y, Z).
+    "
+    .parse::<KnowledgeBase>()
+    .unwrap();
+    let mut solver = Solver::new(&kb);
+
+    // grandparent(X, Y) seeded with X = alice
+    let mut seed = Substitution::default();
+    seed.mapping.insert(0, Term::atom("alice"));
+
+    let mut goal_state = solver
+        .create_goal_state_with(parse_goal("grandparent(X, Y)").unwrap(), seed);
+
+    let mut grandchildren = Vec::new();
+    while let Some(answer) = solver.pull_next_goal(&mut goal_state).unwrap() {
+        assert!(!answer.mapping.contains_key(&0));
+        grandchildren.push(answer.mapping[&1].to_string());
+    }
+    grandchildren.sort();
+
+    assert_eq!(grandchildren, ["dave", "erin"]);
+
+    // the seeded goal shares the table of the goal written out
+    assert_eq!(
+        solver.canonical_goal(&goal_state).to_string(),
+        "grandparent(alice, ?0)"
+    );
+
+    // chained bindings of the seed are resolved
+    let mut seed = Substitution::default();
+    seed.mapping.insert(0, Term::variable(5));
+    seed.mapping.insert(5, Term::atom("frank"));
+
+    let mut goal_state = solver
+        .create_goal_state_with(parse_goal("grandparent(X, Y)").unwrap(), seed);
+    let answer = solver.pull_next_goal(&mut goal_state).unwrap().unwrap();
+
+    assert_eq!(answer.mapping[&1], Term::atom("hank"));
+    assert_eq!(solver.pull_next_goal(&mut goal_state), Ok(None));
+}
+
+#[test]
+fn peek_then_pull_yields_each_answer_once() {
+    let kb = family_knowledge_base();
+    let mut solver = Solver::new(&kb);
+
+    let mut goal_state =
+        solver.create_goal_state(parse_goal("parent(adam, X)").unwrap());
+
+    let peeked = solver.peek_next_goal(&mut goal_state).unwrap().cloned();
+    assert!(peeked.is_some());
+
+    // peeking again doesn't move past the buffered answer
+    assert_eq!(
+        solver.peek_next_goal(&mut goal_state).unwrap().cloned(),
+        peeked
+    );
+    assert_eq!(solver.pull_next_goal(&mut goal_state).unwrap(), peeked);
+
+    let second = solver.pull_next_goal(&mut goal_state).unwrap().unwrap();
+    assert_ne!(Some(&second), peeked.as_ref());
+
+    let mut children = [&peeked.unwrap(), &second]
+        .map(|answer| answer.mapping[&0].to_string());
+    children.sort();
+    assert_eq!(children, ["bob", "carol"]);
+
+    // peeking past the last answer buffers nothing
+    assert_eq!(solver.peek_next_goal(&mut goal_state), Ok(None));
+    assert_eq!(solver.pull_next_goal(&mut goal_state), Ok(None));
+}
+
+#[test]
+fn failed_guard_creates_no_table_for_later_subgoals() {
+    let kb = "
+        expensive(X, Y) :- expensive(Y, X).
+        expensive(1, 2).
+        check(X, Y) :- X > 10, X =< 20, expensive(X, Y).
+        check(X, Y) :- X \\= 3, Y = small.
+    "
+    .parse::<KnowledgeBase>()
+    .unwrap();
+    let mut solver = Solver::new(&kb);
+
+    // the first clause fails its guard, the second one its unification
+    assert_eq!(
+        solver.solve_all(parse_goal("check(3, Y)").unwrap()),
+        Ok(vec![])
+    );
+    assert_eq!(solver.table_count(), 1);
+    assert_eq!(
+        solver.answers_for(&parse_goal("expensive(3, Y)").unwrap()),
+        None
+    );
+
+    // once the guard holds, the subgoal gets its table, and so does the
+    // recursive call of the subgoal
+    assert_eq!(
+        solver.solve_all(parse_goal("check(15, Y)").unwrap()).unwrap().len(),
+        1
+    );
+    assert_eq!(solver.table_count(), 4);
+    assert_eq!(
+        solver.answers_for(&parse_goal("expensive(15, Y)").unwrap()),
+        Some(0)
+    );
+}
+
+#[test]
+fn parent_answers_as_two_column_table() {
+    let kb = family_knowledge_base();
+    let mut solver = Solver::new(&kb);
+
+    // parent(P, C) with the columns P and C
+    let goal = parse_goal("parent(P, C)").unwrap();
+    let mut rows = solver
+        .solve_table(goal, &[0, 1])
+        .unwrap()
+        .into_iter()
+        .map(|row| {
+            row.iter().map(ToString::to_string).collect::<Vec<_>>().join(",")
+        })
+        .collect::<Vec<_>>();
+    rows.sort();
+
+    assert_eq!(rows, [
+        "adam,bob",
+        "adam,carol",
+        "bob,dan",
+        "bob,eve",
+        "carol,frank",
+        "dan,grace",
+    ]);
+
+    // columns may repeat or be reordered, and unbound variables stay free
+    let kb = "pair(a, X).".parse::<KnowledgeBase>().unwrap();
+    let mut solver = Solver::new(&kb);
+
+    let rows = solver
+        .solve_table(parse_goal("pair(A, B)").unwrap(), &[1, 0, 0])
+        .unwrap();
+
+    assert_eq!(rows.len(), 1);
+    assert!(rows[0][0].as_variable().is_some());
+    assert_eq!(rows[0][1..], [Term::atom("a"), Term::atom("a")]);
+}
+
+#[test]
+fn alias_resolves_against_the_aliased_predicate() {
+    let mut kb = family_knowledge_base();
+    kb.alias_predicate("parent", "father_or_mother");
+
+    let mut solver = Solver::new(&kb);
+
+    let mut parents = solver
+        .solve_table(parse_goal("father_or_mother(P, C)").unwrap(), &[0, 1])
+        .unwrap();
+    let mut expected = solver
+        .solve_table(parse_goal("parent(P, C)").unwrap(), &[0, 1])
+        .unwrap();
+    parents.sort();
+    expected.sort();
+
+    assert_eq!(parents.len(), 6);
+    assert_eq!(parents, expected);
+    drop(solver);
+
+    // rules calling the alias resolve it too, and aliasing back is ignored
+    kb.add_clause(
+        "grand(X, Z) :- father_or_mother(X, Y), parent(Y, Z)."
+            .parse::<KnowledgeBase>()
+            .unwrap()
+            .clauses_iter()
+            .next()
+            .unwrap()
+            .clone(),
+    );
+    kb.alias_predicate("father_or_mother", "parent");
+
+    let mut solver = Solver::new(&kb);
+    let answers =
+        solver.solve_all(parse_goal("grand(adam, Z)").unwrap()).unwrap();
+
+    assert_eq!(answers.len(), 3);
+    assert_eq!(kb.get_clauses(("parent", 2)).unwrap().len(), 6);
+}
+
+#[test]
+fn clauses_added_through_an_alias_go_to_the_aliased_predicate() {
+    let mut kb = family_knowledge_base();
+    kb.alias_predicate("parent", "father_or_mother");
+    kb.add_clause(
+        "father_or_mother(grace, ian)."
+            .parse::<KnowledgeBase>()
+            .unwrap()
+            .clauses_iter()
+            .next()
+            .unwrap()
+            .clone(),
+    );
+
+    assert_eq!(kb.get_clauses(("parent", 2)).unwrap().len(), 7);
+    assert!(
+        kb.clauses_iter()
+            .all(|clause| clause.head.name.as_str() != "father_or_mother")
+    );
+
+    let mut solver = Solver::owning(kb);
+    let descendants = |solver: &mut Solver| {
+        let mut descendants = solver
+            .solve_all(parse_goal("ancestor(dan, X)").unwrap())
+            .unwrap()
+            .into_iter()
+            .map(|solution| solution.mapping[&0].to_string())
+            .collect::<Vec<_>>();
+        descendants.sort();
+
+        descendants
+    };
+
+    assert_eq!(descendants(&mut solver), ["grace", "ian"]);
+
+    // declaring the alias dynamic declares `parent/2`, and the asserted fact
+    // invalidates the `ancestor(dan, ?0)` table calling `parent/2`
+    solver.declare_dynamic(("father_or_mother", 2));
+    solver
+        .assertz(
+            parse_goal("father_or_mother(grace, henry)").unwrap().predicate,
+        )
+        .unwrap();
+
+    assert_eq!(descendants(&mut solver), ["grace", "henry", "ian"]);
+    assert_eq!(
+        solver
+            .solve_all(parse_goal("parent(grace, henry)").unwrap())
+            .unwrap()
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn bulk_loaded_edges_are_reachable() {
+    let mut kb = "
+        path(X, Y) :- edge(X, Y).
+        path(X, Y) :- path(X, Z), edge(Z, Y).
+    "
+    .parse::<KnowledgeBase>()
+    .unwrap();
+
+    // a chain n0 -> n1 -> ... -> n1000
+    kb.add_facts(
+        "edge",
+        (0..1000).map(|i| {
+            vec![Term::atom(format!("n{i}")), Term::atom(format!("n{}", i + 1))]
+        }),
+    );
+
+    assert_eq!(kb.get_clauses(("edge", 2)).unwrap().len(), 1000);
+
+    let mut solver = Solver::new(&kb);
+
+    let answers = solver.solve_all(parse_goal("path(n0, X)").unwrap()).unwrap();
+    assert_eq!(answers.len(), 1000);
+
+    let answers =
+        solver.solve_all(parse_goal("path(n500, n1000)").unwrap()).unwrap();
+    assert_eq!(answers.len(), 1);
+
+    let answers =
+        solver.solve_all(parse_goal("path(n1000, X)").unwrap()).unwrap();
+    assert!(answers.is_empty());
+}
+
+#[test]
+fn project_answers_to_swapped_pairs() {
+    let kb = family_knowledge_base();
+    let mut solver = Solver::new(&kb);
+
+    // parent(P, C) projected to pair(C, P)
+    let goal = parse_goal("parent(P, C)").unwrap();
+    let template =
+        Term::component("pair", [Term::variable(1), Term::variable(0)]);
+
+    let mut pairs = solver
+        .solve_project(goal, template)
+        .unwrap()
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    pairs.sort();
+
+    assert_eq!(pairs, [
+        "pair(bob, adam)",
+        "pair(carol, adam)",
+        "pair(dan, bob)",
+        "pair(eve, bob)",
+        "pair(frank, carol)",
+        "pair(grace, dan)",
+    ]);
+
+    // variables the answers leave unbound stay in the projected terms
+    let kb = "pair(a, X).".parse::<KnowledgeBase>().unwrap();
+    let mut solver = Solver::new(&kb);
+
+    let template = Term::list([Term::variable(1), Term::variable(0)]);
+    let projected = solver
+        .solve_project(parse_goal("pair(A, B)").unwrap(), template)
+        .unwrap();
+
+    assert_eq!(projected.len(), 1);
+    let Term::Compound(_, items) = &projected[0] else {
+        panic!("expected a list, got {}", projected[0]);
+    };
+    assert!(items[0].as_variable().is_some());
+}
+
+#[test]
+fn gc_drops_complete_tables_no_goal_state_reads() {
+    let kb = family_knowledge_base();
+    let mut solver = Solver::new(&kb);
+
+    // keep a query halfway through, the tables it reads from must survive
+    let mut live =
+        solver.create_goal_state(parse_goal("ancestor(adam, X)").unwrap());
+    let first = solver.pull_next_goal(&mut live).unwrap().unwrap();
+
+    for person in ["adam", "bob", "carol", "dan", "eve", "frank", "grace"] {
+        for query in ["sibling", "cousin", "relative"] {
+            let goal = parse_goal(&format!("{query}({person}, X)")).unwrap();
+            solver.solve_all(goal).unwrap();
+        }
+    }
+
+    let before = solver.table_count();
+    let dropped = solver.gc([&live]);
+
+    assert!(dropped > 0);
+    assert_eq!(solver.table_count(), before - dropped);
+
+    // the live query carries on from where it stopped
+    let mut answers = vec![first];
+    while let Some(answer) = solver.pull_next_goal(&mut live).unwrap() {
+        answers.push(answer);
+    }
+    assert_eq!(answers.len(), 6);
+
+    // the dropped goals are evaluated again with the same answers
+    let mut fresh = Solver::new(&kb);
+    for query in ["sibling(bob, X)", "cousin(dan, X)", "relative(eve, X)"] {
+        let goal = parse_goal(query).unwrap();
+
+        assert_eq!(
+            solver.solve_all(goal.clone()).unwrap().len(),
+            fresh.solve_all(goal).unwrap().len()
+        );
+    }
+
+    // nothing is left to read from once every query is done
+    solver.gc([]);
+    assert_eq!(solver.table_count(), 0);
+}
+
+#[test]
+fn goal_state_left_out_of_gc_is_stale() {
+    let kb = family_knowledge_base();
+    let mut solver = Solver::new(&kb);
+
+    let mut dropped =
+        solver.create_goal_state(parse_goal("ancestor(bob, X)").unwrap());
+    while solver.pull_next_goal(&mut dropped).unwrap().is_some() {}
+
+    let mut kept =
+        solver.create_goal_state(parse_goal("sibling(bob, X)").unwrap());
+    while solver.pull_next_goal(&mut kept).unwrap().is_some() {}
+
+    solver.gc([&kept]);
+
+    // the same goal gets a table of its own, the stale state can't read it
+    let mut fresh =
+        solver.create_goal_state(parse_goal("ancestor(bob, X)").unwrap());
+    assert_ne!(solver.table(&fresh), solver.table(&kept));
+    assert_eq!(
+        solver.pull_next_goal(&mut dropped),
+        Err(SolveError::StaleGoalState)
+    );
+    assert!(solver.pull_next_goal(&mut fresh).unwrap().is_some());
+
+    // the kept state is still there, done
+    assert_eq!(solver.pull_next_goal(&mut kept), Ok(None));
+}
+
+#[test]
+fn succ_computes_either_argument() {
+    let kb = KnowledgeBase::new();
+    let mut solver = Solver::new(&kb);
+
+    let solve = |solver: &mut Solver, query: &str| {
+        solver
+            .solve_all(parse_goal(query).unwrap())
+            .unwrap()
+            .into_iter()
+            .map(|answer| answer.mapping.get(&0).cloned())
+            .collect::<Vec<_>>()
+    };
+
+    // forward, backward, and checking
+    assert_eq!(solve(&mut solver, "succ(3, Y)"), [Some(Term::integer(4))]);
+    assert_eq!(solve(&mut solver, "succ(X, 4)"), [Some(Term::integer(3))]);
+    assert_eq!(solve(&mut solver, "succ(0, 1)"), [None]);
+    assert!(solve(&mut solver, "succ(1, 3)").is_empty());
+
+    // no natural number precedes 0, and negative numbers have no successor
+    assert!(solve(&mut solver, "succ(X, 0)").is_empty());
+    assert!(solve(&mut solver, "succ(-1, Y)").is_empty());
+    assert!(solve(&mut solver, "succ(X, -3)").is_empty());
+
+    // at least one argument must be an integer
+    assert!(solve(&mut solver, "succ(X, Y)").is_empty());
+    assert!(solve(&mut solver, "succ(a, Y)").is_empty());
+}
+
+#[test]
+fn var_and_ground_inspect_the_current_bindings() {
+    let kb: KnowledgeBase = "
+        var_then_bind(X) :- var(X), X = a.
+        bind_then_var(X) :- X = a, var(X).
+        closed(X) :- X = f(a), ground(X).
+        open(X) :- X = f(_), ground(X).
+    "
+    .parse()
+    .unwrap();
+
+    let mut solver = Solver::new(&kb);
+    let mut solve = |query: &str| {
+        solver
+            .solve_all(parse_goal(query).unwrap())
+            .unwrap()
+            .into_iter()
+            .map(|answer| answer.to_string())
+            .collect::<Vec<_>>()
+    };
+
+    // `var/1` succeeds before the binding only
+    assert_eq!(solve("var_then_bind(X)"), ["{?0 = a}"]);
+    assert!(solve("bind_then_var(X)").is_empty());
+    assert!(solve("var_then_bind(b)").is_empty());
+
+    assert_eq!(solve("closed(X)"), ["{?0 = f(a)}"]);
+    assert!(solve("open(X)").is_empty());
+
+    assert_eq!(solve("var(X)"), ["{}"]);
+    assert!(solve("var(f(X))").is_empty());
+    assert_eq!(solve("ground(f(a, [1, \"s\"]))"), ["{}"]);
+    assert!(solve("ground(f(a, [1, X]))").is_empty());
+}
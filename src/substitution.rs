@@ -1,8 +1,17 @@
-use std::collections::HashMap;
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    fmt,
+};
 
-use crate::{clause::Predicate, term::Term};
+use crate::{
+    clause::{Clause, Functor, Goal, Predicate},
+    term::Term,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Substitution {
     pub mapping: HashMap<usize, Term>,
 }
@@ -11,8 +20,14 @@ impl Substitution {
     /// Applies a substitution to a term, replacing any [`Term::Variable`] with
     /// the corresponding term from the substitution mapping.
     pub fn apply_term(&self, term: &mut Term) {
+        // a ground term is left as is, keeping its cached variables, see
+        // `Term::is_known_ground`
+        if term.is_known_ground() {
+            return;
+        }
+
         match term {
-            Term::Atom(_) => {}
+            Term::Atom(_) | Term::Integer(_) | Term::Str(_) => {}
 
             Term::Variable(variable) => {
                 if let Some(replacement) = self.mapping.get(variable) {
@@ -33,6 +48,10 @@ impl Substitution {
         variable: usize,
         term_to_insert: &Term,
     ) {
+        if term.is_known_ground() {
+            return;
+        }
+
         match term {
             Term::Variable(v) if *v == variable => {
                 *term = term_to_insert.clone();
@@ -50,6 +69,12 @@ impl Substitution {
         }
     }
 
+    /// Binds the variable to the term, substituting it within the existing
+    /// bindings.
+    ///
+    /// No check is performed: if the term contains the variable itself, or a
+    /// variable bound in the substitution, the bindings may end up cyclic. See
+    /// [`Self::compose`] for the checked alternative.
     pub fn insert_mapping(&mut self, variable: usize, term: Term) {
         // compose the existing mapping with the new term
         for value in self.mapping.values_mut() {
@@ -65,54 +90,186 @@ impl Substitution {
         }
     }
 
-    pub fn unify_terms(
-        mut self,
+    /// Applies the substitution to every argument of the goal's predicate.
+    pub fn apply_goal(&self, goal: &mut Goal) {
+        self.apply_predicate(&mut goal.predicate);
+    }
+
+    /// Applies the substitution to the head and every goal in the body of
+    /// the clause.
+    pub fn apply_clause(&self, clause: &mut Clause) {
+        self.apply_predicate(&mut clause.head);
+
+        for goal in &mut clause.body {
+            self.apply_goal(goal);
+        }
+    }
+
+    pub fn unify_terms(self, lhs: &Term, rhs: &Term) -> Option<Substitution> {
+        self.unify_terms_internal(lhs, rhs, true).ok()
+    }
+
+    /// Same as [`Self::unify_terms`] but tells why the unification failed.
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`UnifyError`] describing the first pair of subterms that
+    /// can't be unified, with the bindings made so far applied to them.
+    pub fn unify_terms_explained(
+        self,
+        lhs: &Term,
+        rhs: &Term,
+    ) -> Result<Substitution, UnifyError> {
+        self.unify_terms_internal(lhs, rhs, true)
+    }
+
+    /// Same as [`Self::unify_terms`] but skips the occurs check, matching the
+    /// default behavior of mainstream Prolog systems.
+    ///
+    /// The occurs check walks the whole term on every variable binding, which
+    /// is wasted work for most well-formed programs. Skipping it is unsound
+    /// however: unifying `X` with `f(X)` succeeds and binds `X` to a term
+    /// containing itself instead of failing.
+    pub fn unify_terms_unchecked(
+        self,
         lhs: &Term,
         rhs: &Term,
     ) -> Option<Substitution> {
-        let mut lhs = lhs.clone();
-        let mut rhs = rhs.clone();
+        self.unify_terms_internal(lhs, rhs, false).ok()
+    }
 
-        self.apply_term(&mut lhs);
-        self.apply_term(&mut rhs);
+    fn unify_terms_internal(
+        mut self,
+        lhs: &Term,
+        rhs: &Term,
+        check_occurs: bool,
+    ) -> Result<Substitution, UnifyError> {
+        // only the terms at the top are resolved, the arguments of compounds
+        // are resolved once the recursion reaches them instead of copying the
+        // whole terms at every level
+        let lhs = self.resolve(lhs);
+        let rhs = self.resolve(rhs);
 
-        match (&lhs, &rhs) {
-            (Term::Variable(v1), Term::Variable(v2)) if v1 == v2 => Some(self),
+        match (lhs.as_ref(), rhs.as_ref()) {
+            (Term::Variable(v1), Term::Variable(v2)) if v1 == v2 => Ok(self),
             (Term::Variable(v), t) | (t, Term::Variable(v)) => {
-                if occurs_check(v, t) {
-                    None
+                let mut t = t.clone();
+                self.apply_term(&mut t);
+
+                if check_occurs && t.contains_variable(*v) {
+                    Err(UnifyError::OccursCheck(*v, t))
                 } else {
-                    self.insert_mapping(*v, t.clone());
-                    Some(self)
+                    self.insert_mapping(*v, t);
+                    Ok(self)
                 }
             }
-            (Term::Atom(a1), Term::Atom(a2)) if a1 == a2 => Some(self),
+            (Term::Atom(a1), Term::Atom(a2)) if a1 == a2 => Ok(self),
+            (Term::Integer(i1), Term::Integer(i2)) if i1 == i2 => Ok(self),
+            (Term::Str(s1), Term::Str(s2)) if s1 == s2 => Ok(self),
             (Term::Compound(f1, args1), Term::Compound(f2, args2))
                 if f1 == f2 && args1.len() == args2.len() =>
             {
                 let mut current_sub = self;
 
                 for (arg1, arg2) in args1.iter().zip(args2.iter()) {
-                    current_sub = current_sub.unify_terms(arg1, arg2)?;
+                    current_sub = current_sub.unify_terms_internal(
+                        arg1,
+                        arg2,
+                        check_occurs,
+                    )?;
                 }
 
-                Some(current_sub)
+                Ok(current_sub)
+            }
+            (Term::Compound(..), _) | (_, Term::Compound(..)) => {
+                let (lhs, rhs) =
+                    self.applied(lhs.into_owned(), rhs.into_owned());
+                let functor = |term: &Term| match term {
+                    Term::Atom(name) => Some(Functor::new(*name, 0)),
+                    Term::Compound(name, arguments) => {
+                        Some(Functor::new(*name, arguments.len()))
+                    }
+                    _ => None,
+                };
+
+                match (functor(&lhs), functor(&rhs)) {
+                    (Some(expected), Some(found))
+                        if expected.name == found.name =>
+                    {
+                        Err(UnifyError::ArityMismatch {
+                            lhs,
+                            rhs,
+                            expected: expected.arity,
+                            found: found.arity,
+                        })
+                    }
+                    (expected, found) => Err(UnifyError::FunctorMismatch {
+                        lhs,
+                        rhs,
+                        expected,
+                        found,
+                    }),
+                }
+            }
+            _ => {
+                let (lhs, rhs) =
+                    self.applied(lhs.into_owned(), rhs.into_owned());
+                Err(UnifyError::AtomMismatch(lhs, rhs))
             }
-            _ => None,
         }
     }
 
+    /// Returns the term the variable is bound to if the term is a bound
+    /// variable, the term itself otherwise.
+    fn resolve<'t>(&self, term: &'t Term) -> Cow<'t, Term> {
+        match term {
+            Term::Variable(variable) => self
+                .mapping
+                .get(variable)
+                .map_or(Cow::Borrowed(term), |bound| Cow::Owned(bound.clone())),
+            term => Cow::Borrowed(term),
+        }
+    }
+
+    /// Applies the substitution to both terms, e.g. to report them in an
+    /// [`UnifyError`].
+    fn applied(&self, mut lhs: Term, mut rhs: Term) -> (Term, Term) {
+        self.apply_term(&mut lhs);
+        self.apply_term(&mut rhs);
+
+        (lhs, rhs)
+    }
+
     pub fn unify_predicate(
+        self,
+        lhs: &Predicate,
+        rhs: &Predicate,
+    ) -> Option<Substitution> {
+        self.unify_predicate_internal(lhs, rhs, true)
+    }
+
+    /// Same as [`Self::unify_predicate`] but skips the occurs check, see
+    /// [`Self::unify_terms_unchecked`] for the tradeoff.
+    pub fn unify_predicate_unchecked(
+        self,
+        lhs: &Predicate,
+        rhs: &Predicate,
+    ) -> Option<Substitution> {
+        self.unify_predicate_internal(lhs, rhs, false)
+    }
+
+    fn unify_predicate_internal(
         mut self,
         lhs: &Predicate,
         rhs: &Predicate,
+        check_occurs: bool,
     ) -> Option<Substitution> {
         if lhs.name != rhs.name || lhs.arguments.len() != rhs.arguments.len() {
             return None;
         }
 
         for (arg1, arg2) in lhs.arguments.iter().zip(rhs.arguments.iter()) {
-            self = self.unify_terms(arg1, arg2)?;
+            self = self.unify_terms_internal(arg1, arg2, check_occurs).ok()?;
         }
 
         Some(self)
@@ -123,19 +280,331 @@ impl Substitution {
     /// Given the `other` substitution and `self` substitution, after applying
     /// composition, the `self` substitution will be equivalent of
     /// `other(self(x))`
-    pub fn compose(&mut self, other: Substitution) {
-        for (var, term) in other.mapping {
+    ///
+    /// The bindings of `other` may refer to each other, e.g. `{?0 = ?1, ?1 =
+    /// a}`; they're resolved against `other` first so that the result doesn't
+    /// depend on the order they're composed in.
+    ///
+    /// Returns `None` if a binding of `other` would make a term contain
+    /// itself, e.g. composing `{?0 = g(?1)}` into `{?1 = f(?0)}` would bind
+    /// `?1` to `f(g(?1))`. Like a unification failing the occurs check, such a
+    /// composition has no finite solution.
+    #[must_use]
+    pub fn compose(mut self, other: Substitution) -> Option<Substitution> {
+        for (var, mut term) in other.resolved() {
+            // resolve the variables bound by `self` first so that a cycle
+            // going through them shows up as a self-reference
+            self.apply_term(&mut term);
+
+            if term == Term::Variable(var) {
+                continue;
+            }
+
+            if term.contains_variable(var) {
+                return None;
+            }
+
             self.insert_mapping(var, term);
         }
+
+        Some(self)
+    }
+
+    /// Same as [`Self::compose`] but skips the occurs check, see
+    /// [`Self::unify_terms_unchecked`] for the tradeoff.
+    #[must_use]
+    pub fn compose_unchecked(mut self, other: Substitution) -> Substitution {
+        for (var, term) in other.resolved() {
+            self.insert_mapping(var, term);
+        }
+
+        self
+    }
+
+    /// Returns the bindings of `self` that `other` doesn't imply, roughly the
+    /// inverse of [`Self::compose`]: composing the result into `other` gives
+    /// back a substitution equivalent to `self`.
+    ///
+    /// A binding `?n = t` is implied if `other` resolves `?n` and `t` to the
+    /// same term, e.g. the difference between `{?0 = f(?1), ?1 = a}` and
+    /// `{?0 = f(a)}` is `{?1 = a}` whereas `{?0 = f(?1)}` is implied. Taking
+    /// the difference between the substitutions before and after a solver
+    /// step shows exactly which bindings the step contributed, which helps
+    /// with incremental reasoning and debugging.
+    #[must_use]
+    pub fn diff(&self, other: &Substitution) -> Substitution {
+        let resolve = |term: &Term| {
+            let mut term = term.clone();
+            other.apply_term_fully(&mut term, &mut Vec::new());
+
+            term
+        };
+
+        let mapping = self
+            .mapping
+            .iter()
+            .filter(|(variable, term)| {
+                resolve(&Term::Variable(**variable)) != resolve(term)
+            })
+            .map(|(variable, term)| (*variable, term.clone()))
+            .collect();
+
+        Substitution { mapping }
+    }
+
+    /// Returns the bindings with the substitution applied to them until none
+    /// of their variables are bound anymore, see [`Self::apply_term_fully`].
+    fn resolved(&self) -> Vec<(usize, Term)> {
+        self.mapping
+            .iter()
+            .map(|(variable, term)| {
+                let mut term = term.clone();
+                self.apply_term_fully(&mut term, &mut vec![*variable]);
+
+                (*variable, term)
+            })
+            .collect()
+    }
+
+    /// Restricts the substitution to the given variables, dropping the
+    /// bindings of every other variable.
+    ///
+    /// References to a dropped variable within the kept bindings are replaced
+    /// by the term it was bound to, so that no information is lost; only the
+    /// references to unbound variables remain. For example, restricting
+    /// `{?0 = f(?1), ?1 = g(?2)}` to `?0` yields `{?0 = f(g(?2))}`.
+    #[must_use]
+    pub fn restrict(&self, variables: &HashSet<usize>) -> Substitution {
+        let mapping = self
+            .mapping
+            .iter()
+            .filter(|(variable, _)| variables.contains(variable))
+            .map(|(variable, term)| {
+                let mut term = term.clone();
+                self.apply_term_fully(&mut term, &mut vec![*variable]);
+
+                (*variable, term)
+            })
+            .collect();
+
+        Substitution { mapping }
+    }
+
+    /// Applies the substitution to the term until none of its variables are
+    /// bound anymore.
+    ///
+    /// The `expanding` variables are being substituted at the moment; they're
+    /// left as is to guard against cyclic bindings created without the
+    /// occurs check.
+    fn apply_term_fully(&self, term: &mut Term, expanding: &mut Vec<usize>) {
+        if term.is_known_ground() {
+            return;
+        }
+
+        match term {
+            Term::Atom(_) | Term::Integer(_) | Term::Str(_) => {}
+
+            Term::Variable(variable) => {
+                let variable = *variable;
+                if expanding.contains(&variable) {
+                    return;
+                }
+
+                if let Some(replacement) = self.mapping.get(&variable) {
+                    *term = replacement.clone();
+
+                    expanding.push(variable);
+                    self.apply_term_fully(term, expanding);
+                    expanding.pop();
+                }
+            }
+
+            Term::Compound(_, terms) => {
+                for subterm in terms {
+                    self.apply_term_fully(subterm, expanding);
+                }
+            }
+        }
+    }
+
+    /// Checks whether every variable is bound to a ground term, see
+    /// [`Term::is_ground`], i.e. whether no variable is left in the answer.
+    #[must_use]
+    pub fn is_ground(&self) -> bool {
+        self.mapping.values().all(Term::is_ground)
+    }
+
+    /// Checks whether `self` is an instance of `other`, i.e. whether binding
+    /// the variables occurring in `other` can turn it into `self`.
+    ///
+    /// Only the variables in the domain of either substitution are compared;
+    /// a variable outside both domains is left unconstrained. For example,
+    /// `{?0 = f(a)}` is an instance of `{?0 = f(?1)}` and of `{}`, but not the
+    /// other way around.
+    #[must_use]
+    pub fn is_instance_of(&self, other: &Substitution) -> bool {
+        let mut bindings = HashMap::new();
+
+        self.mapping.keys().chain(other.mapping.keys()).all(|variable| {
+            let resolve = |substitution: &Substitution| {
+                substitution
+                    .mapping
+                    .get(variable)
+                    .cloned()
+                    .unwrap_or(Term::Variable(*variable))
+            };
+
+            match_term(&resolve(other), &resolve(self), &mut bindings)
+        })
+    }
+}
+
+/// Substitutions are ordered by their bindings sorted by variable, so that
+/// the order doesn't depend on the iteration order of the mapping. For
+/// example, `{?0 = a, ?1 = c}` < `{?0 = b}` < `{?0 = b, ?1 = a}`.
+impl PartialOrd for Substitution {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Substitution {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sorted_bindings().cmp(&other.sorted_bindings())
+    }
+}
+
+impl Substitution {
+    fn sorted_bindings(&self) -> Vec<(&usize, &Term)> {
+        let mut bindings = self.mapping.iter().collect::<Vec<_>>();
+        bindings.sort_by_key(|(variable, _)| **variable);
+
+        bindings
+    }
+}
+
+/// Prints the bindings sorted by variable, e.g. `{?0 = dave, ?1 = carol}`.
+impl fmt::Display for Substitution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        for (i, (variable, term)) in
+            self.sorted_bindings().into_iter().enumerate()
+        {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "?{variable} = {term}")?;
+        }
+        write!(f, "}}")
     }
 }
 
-fn occurs_check(variable: &usize, term: &Term) -> bool {
-    match term {
-        Term::Atom(_) => false,
-        Term::Variable(v) => v == variable,
-        Term::Compound(_, terms) => {
-            terms.iter().any(|t| occurs_check(variable, t))
+/// Matches the `pattern` against the `term` by binding only the variables of
+/// the `pattern`, the variables of the `term` are treated as constants.
+fn match_term(
+    pattern: &Term,
+    term: &Term,
+    bindings: &mut HashMap<usize, Term>,
+) -> bool {
+    match (pattern, term) {
+        (Term::Variable(variable), term) => match bindings.get(variable) {
+            Some(bound) => bound == term,
+            None => {
+                bindings.insert(*variable, term.clone());
+                true
+            }
+        },
+
+        (
+            Term::Compound(lhs_name, lhs_terms),
+            Term::Compound(rhs_name, rhs_terms),
+        ) if lhs_name == rhs_name && lhs_terms.len() == rhs_terms.len() => {
+            lhs_terms
+                .iter()
+                .zip(rhs_terms)
+                .all(|(lhs, rhs)| match_term(lhs, rhs, bindings))
         }
+
+        (Term::Atom(lhs), Term::Atom(rhs)) => lhs == rhs,
+        (Term::Integer(lhs), Term::Integer(rhs)) => lhs == rhs,
+        (Term::Str(lhs), Term::Str(rhs)) => lhs == rhs,
+
+        _ => false,
     }
 }
+
+/// Describes why two terms don't unify, see
+/// [`Substitution::unify_terms_explained`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnifyError {
+    /// Two different atomic terms, i.e. atoms, integers, or strings, e.g. `a`
+    /// and `b` or `1` and `"1"`.
+    AtomMismatch(Term, Term),
+
+    /// Two compound terms with the same name but a different number of
+    /// arguments, e.g. `f(a)` and `f(a, b)`. An atom counts as a compound
+    /// without arguments, e.g. `f` and `f(a)`.
+    ArityMismatch {
+        lhs: Term,
+        rhs: Term,
+
+        /// The number of arguments of `lhs`.
+        expected: usize,
+
+        /// The number of arguments of `rhs`.
+        found: usize,
+    },
+
+    /// A compound term and a term with a different name, e.g. `f(a)` and
+    /// `g(a)` or `f(a)` and `1`.
+    FunctorMismatch {
+        lhs: Term,
+        rhs: Term,
+
+        /// The functor of `lhs`, `None` if it's neither an atom nor a
+        /// compound term.
+        expected: Option<Functor>,
+
+        /// The functor of `rhs`, `None` if it's neither an atom nor a
+        /// compound term.
+        found: Option<Functor>,
+    },
+
+    /// The variable would be bound to a term containing itself, e.g. `?0` and
+    /// `f(?0)`.
+    OccursCheck(usize, Term),
+}
+
+impl fmt::Display for UnifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnifyError::AtomMismatch(lhs, rhs) => {
+                write!(f, "`{lhs}` and `{rhs}` are different")
+            }
+            UnifyError::ArityMismatch { lhs, rhs, expected, found } => {
+                write!(
+                    f,
+                    "`{lhs}` and `{rhs}` have a different arity, expected \
+                     {expected}, found {found}"
+                )
+            }
+            UnifyError::FunctorMismatch { lhs, rhs, expected, found } => {
+                write!(f, "`{lhs}` and `{rhs}` have a different functor")?;
+
+                if let (Some(expected), Some(found)) = (expected, found) {
+                    write!(f, ", expected {expected}, found {found}")?;
+                }
+
+                Ok(())
+            }
+            UnifyError::OccursCheck(variable, term) => {
+                write!(f, "`?{variable}` occurs in `{term}`")
+            }
+        }
+    }
+}
+
+impl std::error::Error for UnifyError {}
+
+#[cfg(test)]
+mod test;
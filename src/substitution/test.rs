@@ -0,0 +1,499 @@
+use crate::{
+    clause::{Clause, Functor, Goal, Predicate},
+    parser::parse_term,
+    substitution::{Substitution, UnifyError},
+    term::Term,
+};
+
+#[test]
+fn display_substitution() {
+    let substitution = Substitution {
+        mapping: [
+            (1, Term::atom("carol")),
+            (0, Term::atom("dave")),
+            (2, Term::component("f", [Term::variable(3), Term::atom("a")])),
+        ]
+        .into_iter()
+        .collect(),
+    };
+
+    assert_eq!(
+        substitution.to_string(),
+        "{?0 = dave, ?1 = carol, ?2 = f(?3, a)}"
+    );
+    assert_eq!(Substitution::default().to_string(), "{}");
+}
+
+#[test]
+fn apply_clause_across_body() {
+    // grandparent(?0, ?1) :- parent(?0, ?2), parent(?2, f(?1, ?3)).
+    let mut clause = Clause {
+        head: Predicate {
+            name: "grandparent".into(),
+            arguments: vec![Term::variable(0), Term::variable(1)],
+        },
+        body: vec![
+            Goal {
+                predicate: Predicate {
+                    name: "parent".into(),
+                    arguments: vec![Term::variable(0), Term::variable(2)],
+                },
+            },
+            Goal {
+                predicate: Predicate {
+                    name: "parent".into(),
+                    arguments: vec![
+                        Term::variable(2),
+                        Term::component("f", [
+                            Term::variable(1),
+                            Term::variable(3),
+                        ]),
+                    ],
+                },
+            },
+        ],
+    };
+
+    let substitution = Substitution {
+        mapping: [
+            (0, Term::atom("alice")),
+            (2, Term::atom("bob")),
+            (3, Term::component("g", [Term::atom("carol")])),
+        ]
+        .into_iter()
+        .collect(),
+    };
+
+    substitution.apply_clause(&mut clause);
+
+    assert_eq!(
+        clause.to_string(),
+        "grandparent(alice, ?1) :- parent(alice, bob), parent(bob, f(?1, \
+         g(carol)))."
+    );
+
+    let mut goal = clause.body[0].clone();
+    Substitution::default().apply_goal(&mut goal);
+
+    assert_eq!(goal, clause.body[0]);
+}
+
+#[test]
+fn unchecked_unification_skips_occurs_check() {
+    // X = f(X)
+    let variable = Term::variable(0);
+    let compound = Term::component("f", [Term::variable(0)]);
+
+    assert!(
+        Substitution::default().unify_terms(&variable, &compound).is_none()
+    );
+
+    let substitution = Substitution::default()
+        .unify_terms_unchecked(&variable, &compound)
+        .unwrap();
+
+    assert_eq!(substitution.mapping.get(&0), Some(&compound));
+}
+
+#[test]
+fn unify_long_lists_element_wise() {
+    // [?0, ..., ?199] = [f(?200), ..., f(?399)]
+    let variables = Term::list((0..200).map(Term::variable));
+    let compounds = Term::list(
+        (0..200).map(|i| Term::component("f", [Term::variable(200 + i)])),
+    );
+
+    let substitution =
+        Substitution::default().unify_terms(&variables, &compounds).unwrap();
+
+    assert_eq!(substitution.mapping.len(), 200);
+    assert_eq!(
+        substitution.mapping[&199],
+        Term::component("f", [Term::variable(399)])
+    );
+
+    // [?0, ..., ?199] = [?1, ..., ?199, f(?0)] binds every variable to the
+    // next one, the last binding closes a cycle
+    let shifted = Term::list(
+        (1..200)
+            .map(Term::variable)
+            .chain([Term::component("f", [Term::variable(0)])]),
+    );
+
+    assert!(
+        Substitution::default().unify_terms(&variables, &shifted).is_none()
+    );
+    assert!(
+        Substitution::default()
+            .unify_terms_unchecked(&variables, &shifted)
+            .is_some()
+    );
+}
+
+#[test]
+fn instance_of_more_general_substitution() {
+    let substitution = |bindings: Vec<(usize, Term)>| Substitution {
+        mapping: bindings.into_iter().collect(),
+    };
+
+    let specific = substitution(vec![
+        (0, Term::component("f", [Term::atom("a"), Term::atom("a")])),
+        (1, Term::atom("b")),
+    ]);
+    let general = substitution(vec![(
+        0,
+        Term::component("f", [Term::variable(2), Term::variable(2)]),
+    )]);
+    let mismatched = substitution(vec![(
+        0,
+        Term::component("f", [Term::atom("a"), Term::atom("c")]),
+    )]);
+
+    assert!(specific.is_instance_of(&general));
+    assert!(specific.is_instance_of(&Substitution::default()));
+    assert!(specific.is_instance_of(&specific));
+
+    assert!(!general.is_instance_of(&specific));
+    assert!(!mismatched.is_instance_of(&general));
+
+    // the variables of the instance are never bound
+    let variable = substitution(vec![(0, Term::variable(1))]);
+    assert!(!variable.is_instance_of(&specific));
+}
+
+#[test]
+fn restrict_resolves_dropped_variables() {
+    // {?0 = f(?1, g(?2)), ?1 = h(?3), ?2 = a, ?4 = b}
+    let substitution = Substitution {
+        mapping: [
+            (
+                0,
+                Term::component("f", [
+                    Term::variable(1),
+                    Term::component("g", [Term::variable(2)]),
+                ]),
+            ),
+            (1, Term::component("h", [Term::variable(3)])),
+            (2, Term::atom("a")),
+            (4, Term::atom("b")),
+        ]
+        .into_iter()
+        .collect(),
+    };
+
+    let restricted = substitution.restrict(&[0, 3].into_iter().collect());
+
+    // `?3` is unbound, hence the reference to it stays
+    assert_eq!(restricted.to_string(), "{?0 = f(h(?3), g(a))}");
+
+    let restricted = substitution.restrict(&[1, 4].into_iter().collect());
+    assert_eq!(restricted.to_string(), "{?1 = h(?3), ?4 = b}");
+
+    assert_eq!(
+        substitution.restrict(&[5].into_iter().collect()).mapping.len(),
+        0
+    );
+}
+
+#[test]
+fn restrict_with_cyclic_bindings() {
+    // {?0 = f(?1), ?1 = g(?0)}, only possible without the occurs check
+    let substitution = Substitution {
+        mapping: [
+            (0, Term::component("f", [Term::variable(1)])),
+            (1, Term::component("g", [Term::variable(0)])),
+        ]
+        .into_iter()
+        .collect(),
+    };
+
+    let restricted = substitution.restrict(&[0].into_iter().collect());
+
+    assert_eq!(restricted.to_string(), "{?0 = f(g(?0))}");
+}
+
+#[test]
+fn ground_terms() {
+    assert!(Term::atom("a").is_ground());
+    assert!(Term::integer(1).is_ground());
+    assert!(!Term::variable(0).is_ground());
+
+    assert!(
+        Term::component("f", [
+            Term::atom("a"),
+            Term::component("g", [Term::integer(1)])
+        ])
+        .is_ground()
+    );
+    assert!(
+        !Term::component("f", [
+            Term::atom("a"),
+            Term::component("g", [Term::variable(0)])
+        ])
+        .is_ground()
+    );
+}
+
+#[test]
+fn ground_substitution() {
+    let mut substitution = Substitution::default();
+    assert!(substitution.is_ground());
+
+    substitution.mapping.insert(0, Term::component("f", [Term::atom("a")]));
+    assert!(substitution.is_ground());
+
+    substitution
+        .mapping
+        .insert(1, Term::component("g", [Term::list([Term::variable(2)])]));
+    assert!(!substitution.is_ground());
+}
+
+#[test]
+fn compose_rejects_cyclic_bindings() {
+    // {?1 = f(?0)} then {?0 = g(?1)} would bind ?1 to f(g(?1))
+    let mut substitution = Substitution::default();
+    substitution.mapping.insert(1, Term::component("f", [Term::variable(0)]));
+
+    let mut other = Substitution::default();
+    other.mapping.insert(0, Term::component("g", [Term::variable(1)]));
+
+    assert!(substitution.clone().compose(other.clone()).is_none());
+
+    // {?0 = g(?2)} has no cycle, ?1 sees the new binding
+    let mut acyclic = Substitution::default();
+    acyclic.mapping.insert(0, Term::component("g", [Term::variable(2)]));
+
+    let composed = substitution.clone().compose(acyclic).unwrap();
+    assert_eq!(composed.to_string(), "{?0 = g(?2), ?1 = f(g(?2))}");
+
+    // binding a variable to itself is a no-op
+    let mut identity = Substitution::default();
+    identity.mapping.insert(0, Term::variable(0));
+
+    assert_eq!(substitution.clone().compose(identity), Some(substitution));
+}
+
+#[test]
+fn compose_chained_bindings_in_any_order() {
+    // `other` is {X = Y, Y = a}, whose bindings are iterated in an order
+    // depending on the variable indices, try many of them so that both
+    // orders show up
+    for x in 1..16 {
+        for y in (1..16).filter(|&y| y != x) {
+            let mut substitution = Substitution::default();
+            substitution
+                .mapping
+                .insert(0, Term::component("f", [Term::variable(x)]));
+
+            let mut other = Substitution::default();
+            other.mapping.insert(x, Term::variable(y));
+            other.mapping.insert(y, Term::atom("a"));
+
+            let mut expected = Substitution::default();
+            expected.mapping.insert(0, Term::component("f", [Term::atom("a")]));
+            expected.mapping.insert(x, Term::atom("a"));
+            expected.mapping.insert(y, Term::atom("a"));
+
+            assert_eq!(
+                substitution.clone().compose(other.clone()).as_ref(),
+                Some(&expected)
+            );
+            assert_eq!(substitution.compose_unchecked(other), expected);
+        }
+    }
+}
+
+#[test]
+fn diff_keeps_bindings_not_implied() {
+    let substitution = |bindings: &[(usize, Term)]| Substitution {
+        mapping: bindings.iter().cloned().collect(),
+    };
+
+    let before =
+        substitution(&[(0, Term::component("f", [Term::variable(1)]))]);
+    let after = substitution(&[
+        (0, Term::component("f", [Term::variable(1)])),
+        (1, Term::atom("a")),
+    ]);
+
+    assert_eq!(after.diff(&before), substitution(&[(1, Term::atom("a"))]));
+    assert_eq!(before.diff(&after), substitution(&[]));
+    assert_eq!(after.diff(&after), substitution(&[]));
+    assert_eq!(after.diff(&Substitution::default()), after);
+
+    // `{?0 = f(?1)}` is implied by `{?0 = f(a), ?1 = a}` once resolved
+    let resolved = substitution(&[
+        (0, Term::component("f", [Term::atom("a")])),
+        (1, Term::atom("a")),
+    ]);
+    assert_eq!(after.diff(&resolved), substitution(&[]));
+
+    // conflicting bindings are never implied
+    let conflicting = substitution(&[(1, Term::atom("b"))]);
+    assert_eq!(after.diff(&conflicting), after);
+}
+
+#[test]
+fn compose_diff_back() {
+    let before = Substitution {
+        mapping: [(1, Term::variable(2))].into_iter().collect(),
+    };
+    let after = Substitution {
+        mapping: [
+            (0, Term::component("g", [Term::variable(1)])),
+            (1, Term::variable(2)),
+            (2, Term::atom("b")),
+        ]
+        .into_iter()
+        .collect(),
+    };
+
+    let diff = after.diff(&before);
+    assert_eq!(diff.mapping.len(), 2);
+    assert!(!diff.mapping.contains_key(&1));
+
+    let variables = [0, 1, 2].into_iter().collect();
+    assert_eq!(
+        before.compose(diff).unwrap().restrict(&variables),
+        after.restrict(&variables)
+    );
+}
+
+#[test]
+fn strings_are_distinct_from_atoms() {
+    let unify = |lhs: &Term, rhs: &Term| {
+        Substitution::default().unify_terms(lhs, rhs).is_some()
+    };
+
+    assert!(!unify(&Term::string("x"), &Term::atom("x")));
+    assert!(unify(&Term::string("x"), &Term::string("x")));
+    assert!(!unify(&Term::string("x"), &Term::string("y")));
+
+    // strings bind variables like any other term
+    let substitution = Substitution::default()
+        .unify_terms(
+            &Term::component("f", [Term::variable(0), Term::string("b")]),
+            &Term::component("f", [Term::string("a"), Term::variable(1)]),
+        )
+        .unwrap();
+
+    assert_eq!(substitution.to_string(), r#"{?0 = "a", ?1 = "b"}"#);
+}
+
+#[test]
+fn explain_unification_failures() {
+    let explain = |lhs: &Term, rhs: &Term| {
+        Substitution::default().unify_terms_explained(lhs, rhs).unwrap_err()
+    };
+    let f = |arguments: Vec<Term>| Term::component("f", arguments);
+
+    assert_eq!(
+        explain(&Term::atom("a"), &Term::atom("b")),
+        UnifyError::AtomMismatch(Term::atom("a"), Term::atom("b"))
+    );
+    assert_eq!(
+        explain(&Term::integer(1), &Term::string("1")),
+        UnifyError::AtomMismatch(Term::integer(1), Term::string("1"))
+    );
+
+    assert_eq!(
+        explain(&f(vec![Term::atom("a")]), &f(vec![])),
+        UnifyError::ArityMismatch {
+            lhs: f(vec![Term::atom("a")]),
+            rhs: f(vec![]),
+            expected: 1,
+            found: 0,
+        }
+    );
+    assert_eq!(
+        explain(&Term::atom("f"), &f(vec![Term::atom("a")])),
+        UnifyError::ArityMismatch {
+            lhs: Term::atom("f"),
+            rhs: f(vec![Term::atom("a")]),
+            expected: 0,
+            found: 1,
+        }
+    );
+
+    assert_eq!(
+        explain(&f(vec![]), &Term::component("g", [])),
+        UnifyError::FunctorMismatch {
+            lhs: f(vec![]),
+            rhs: Term::component("g", []),
+            expected: Some(Functor::new("f", 0)),
+            found: Some(Functor::new("g", 0)),
+        }
+    );
+    assert_eq!(
+        explain(&f(vec![]), &Term::integer(1)),
+        UnifyError::FunctorMismatch {
+            lhs: f(vec![]),
+            rhs: Term::integer(1),
+            expected: Some(Functor::new("f", 0)),
+            found: None,
+        }
+    );
+
+    assert_eq!(
+        explain(&Term::variable(0), &f(vec![Term::variable(0)])),
+        UnifyError::OccursCheck(0, f(vec![Term::variable(0)]))
+    );
+
+    // the offending subterms are reported with the bindings made so far
+    assert_eq!(
+        explain(
+            &f(vec![Term::variable(0), Term::variable(0)]),
+            &f(vec![Term::atom("a"), Term::atom("b")]),
+        ),
+        UnifyError::AtomMismatch(Term::atom("a"), Term::atom("b"))
+    );
+
+    assert_eq!(
+        Substitution::default()
+            .unify_terms_explained(&Term::variable(0), &Term::atom("a"))
+            .unwrap()
+            .to_string(),
+        "{?0 = a}"
+    );
+}
+
+#[test]
+fn report_arity_and_functor_of_mismatches() {
+    let explain = |lhs: &str, rhs: &str| {
+        Substitution::default()
+            .unify_terms_explained(
+                &parse_term(lhs).unwrap(),
+                &parse_term(rhs).unwrap(),
+            )
+            .unwrap_err()
+    };
+
+    let arity = explain("f(a, b)", "f(a, b, c)");
+    let UnifyError::ArityMismatch { expected, found, .. } = arity else {
+        panic!("expected an arity mismatch, got {arity:?}");
+    };
+    assert_eq!((expected, found), (2, 3));
+    assert_eq!(
+        arity.to_string(),
+        "`f(a, b)` and `f(a, b, c)` have a different arity, expected 2, found \
+         3"
+    );
+
+    let functor = explain("f(a, b)", "g(a, b)");
+    let UnifyError::FunctorMismatch { expected, found, .. } = functor else {
+        panic!("expected a functor mismatch, got {functor:?}");
+    };
+    assert_eq!(expected, Some(Functor::new("f", 2)));
+    assert_eq!(found, Some(Functor::new("g", 2)));
+    assert_eq!(
+        functor.to_string(),
+        "`f(a, b)` and `g(a, b)` have a different functor, expected f/2, \
+         found g/2"
+    );
+
+    // a term without functor leaves the detail out
+    assert_eq!(
+        explain("f(a, b)", "42").to_string(),
+        "`f(a, b)` and `42` have a different functor"
+    );
+}
@@ -0,0 +1,284 @@
+//! Contains the definition of [`Symbol`] and the process-wide interner
+//! backing it.
+//!
+//! Predicate names and atoms are compared, hashed, and cloned constantly
+//! during canonicalization and unification. Interning them turns these
+//! operations into cheap integer operations.
+
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fmt,
+    sync::{LazyLock, RwLock},
+};
+
+/// An interned string that is cheap to copy, hash, and compare for equality.
+///
+/// Symbols are interned in a single process-wide interner, therefore, two
+/// symbols created from the same string are always equal.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Symbol(u32);
+
+/// The names the [`Interner`] interns upfront, in this order, so that the
+/// associated constants on [`Symbol`] are valid without any lookup.
+const WELL_KNOWN_NAMES: [&str; 19] = [
+    "!",
+    "<",
+    ">",
+    ">=",
+    "=<",
+    "[]",
+    ".",
+    "findall",
+    ";",
+    ",",
+    "=",
+    "\\=",
+    "\\+",
+    "between",
+    "copy_term",
+    "length",
+    "succ",
+    "ground",
+    "var",
+];
+
+impl Symbol {
+    /// The symbol of the cut goal `!`.
+    pub const CUT: Self = Self(0);
+
+    /// The symbol of the `</2` comparison.
+    pub const LESS: Self = Self(1);
+
+    /// The symbol of the `>/2` comparison.
+    pub const GREATER: Self = Self(2);
+
+    /// The symbol of the `>=/2` comparison.
+    pub const GREATER_OR_EQUAL: Self = Self(3);
+
+    /// The symbol of the `=</2` comparison.
+    pub const LESS_OR_EQUAL: Self = Self(4);
+
+    /// The symbol of the empty list atom `[]`.
+    pub const NIL: Self = Self(5);
+
+    /// The functor `./2` of a non-empty list cell.
+    pub const CONS: Self = Self(6);
+
+    /// The symbol of the `findall/3` meta-predicate.
+    pub const FINDALL: Self = Self(7);
+
+    /// The functor `;` of a disjunction.
+    pub const DISJUNCTION: Self = Self(8);
+
+    /// The functor `,` of a conjunction.
+    pub const CONJUNCTION: Self = Self(9);
+
+    /// The symbol of the `=/2` unification.
+    pub const UNIFY: Self = Self(10);
+
+    /// The symbol of the `\=/2` negated unification.
+    pub const NOT_UNIFIABLE: Self = Self(11);
+
+    /// The symbol of the `\+/1` negation as failure.
+    pub const NEGATION: Self = Self(12);
+
+    /// The symbol of the `between/3` generator.
+    pub const BETWEEN: Self = Self(13);
+
+    /// The symbol of the `copy_term/2` built-in.
+    pub const COPY_TERM: Self = Self(14);
+
+    /// The symbol of the `length/2` built-in.
+    pub const LENGTH: Self = Self(15);
+
+    /// The symbol of the `succ/2` built-in.
+    pub const SUCC: Self = Self(16);
+
+    /// The symbol of the `ground/1` type check.
+    pub const GROUND: Self = Self(17);
+
+    /// The symbol of the `var/1` type check.
+    pub const VAR: Self = Self(18);
+}
+
+/// The process-wide interner used by [`Symbol::new`] and [`Symbol::as_str`].
+static GLOBAL_INTERNER: LazyLock<RwLock<Interner>> =
+    LazyLock::new(|| RwLock::new(Interner::new()));
+
+impl Symbol {
+    /// Interns the given string in the process-wide interner and returns its
+    /// [`Symbol`].
+    ///
+    /// The first time a string is interned, a copy of it is leaked so that
+    /// [`Symbol::as_str`] can hand out a `&'static str` without holding the
+    /// lock of the interner: every distinct name stays allocated until the
+    /// process exits. Interning names built from unbounded input, e.g. a
+    /// counter, therefore grows the memory without bound.
+    #[must_use]
+    pub fn new(name: &str) -> Self {
+        if let Some(symbol) = Self::get(name) {
+            return symbol;
+        }
+
+        GLOBAL_INTERNER.write().unwrap().intern(name)
+    }
+
+    /// Returns the [`Symbol`] of the given string if it has been interned
+    /// before, without interning it.
+    #[must_use]
+    pub fn get(name: &str) -> Option<Self> {
+        GLOBAL_INTERNER.read().unwrap().get(name)
+    }
+
+    /// Returns the string this [`Symbol`] was interned from.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        GLOBAL_INTERNER.read().unwrap().resolve(self)
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Symbols are ordered by their string contents rather than by the order in
+/// which they were interned, so that sorting is independent of interning
+/// order.
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Symbol {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if self == other {
+            return Ordering::Equal;
+        }
+
+        let interner = GLOBAL_INTERNER.read().unwrap();
+
+        interner.resolve(*self).cmp(interner.resolve(*other))
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(name: &str) -> Self { Self::new(name) }
+}
+
+impl From<&String> for Symbol {
+    fn from(name: &String) -> Self { Self::new(name) }
+}
+
+impl From<String> for Symbol {
+    fn from(name: String) -> Self { Self::new(&name) }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool { self.as_str() == other }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool { self.as_str() == *other }
+}
+
+/// Symbols serialize as their string contents since the interned indices are
+/// only meaningful within a single process.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Symbol {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Symbol {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        let name = <String as serde::Deserialize>::deserialize(deserializer)?;
+
+        Ok(Self::new(&name))
+    }
+}
+
+/// Maps strings to [`Symbol`]s and back.
+///
+/// Only the process-wide instance behind [`Symbol::new`] hands out symbols,
+/// a symbol is an index into it. Interned strings are leaked so that they can
+/// be resolved to a `&'static str`, see [`Symbol::new`].
+#[derive(Debug, Clone)]
+struct Interner {
+    symbols_by_name: HashMap<&'static str, Symbol>,
+    names: Vec<&'static str>,
+}
+
+impl Default for Interner {
+    fn default() -> Self { Self::new() }
+}
+
+impl Interner {
+    /// Creates a new [`Interner`] containing only the well-known symbols such
+    /// as [`Symbol::CUT`].
+    #[must_use]
+    pub fn new() -> Self {
+        let mut interner =
+            Self { symbols_by_name: HashMap::new(), names: Vec::new() };
+
+        for name in WELL_KNOWN_NAMES {
+            interner.intern(name);
+        }
+
+        interner
+    }
+
+    /// Interns the given string, returning the existing [`Symbol`] if the
+    /// string has been interned before.
+    pub fn intern(&mut self, name: &str) -> Symbol {
+        if let Some(symbol) = self.symbols_by_name.get(name) {
+            return *symbol;
+        }
+
+        let symbol = Symbol(
+            u32::try_from(self.names.len())
+                .expect("too many symbols have been interned"),
+        );
+        let name: &'static str = Box::leak(name.to_owned().into_boxed_str());
+
+        self.names.push(name);
+        self.symbols_by_name.insert(name, symbol);
+
+        symbol
+    }
+
+    /// Returns the [`Symbol`] of the given string if it has been interned.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<Symbol> {
+        self.symbols_by_name.get(name).copied()
+    }
+
+    /// Returns the string the given [`Symbol`] was interned from.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the [`Symbol`] wasn't created by this [`Interner`].
+    #[must_use]
+    pub fn resolve(&self, symbol: Symbol) -> &'static str {
+        self.names[symbol.0 as usize]
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,83 @@
+use crate::{
+    substitution::Substitution,
+    symbol::{Interner, Symbol, WELL_KNOWN_NAMES},
+    term::Term,
+};
+
+#[test]
+fn interner_round_trip() {
+    let mut interner = Interner::new();
+
+    let parent = interner.intern("parent");
+    let child = interner.intern("child");
+
+    assert_ne!(parent, child);
+    assert_eq!(interner.intern("parent"), parent);
+    assert_eq!(interner.get("child"), Some(child));
+    assert_eq!(interner.get("sibling"), None);
+
+    assert_eq!(interner.resolve(parent), "parent");
+    assert_eq!(interner.resolve(child), "child");
+}
+
+#[test]
+fn well_known_symbols_match_their_names() {
+    let symbols = [
+        (Symbol::CUT, "!"),
+        (Symbol::LESS, "<"),
+        (Symbol::GREATER, ">"),
+        (Symbol::GREATER_OR_EQUAL, ">="),
+        (Symbol::LESS_OR_EQUAL, "=<"),
+        (Symbol::NIL, "[]"),
+        (Symbol::CONS, "."),
+        (Symbol::FINDALL, "findall"),
+        (Symbol::DISJUNCTION, ";"),
+        (Symbol::CONJUNCTION, ","),
+        (Symbol::UNIFY, "="),
+        (Symbol::NOT_UNIFIABLE, "\\="),
+        (Symbol::NEGATION, "\\+"),
+        (Symbol::BETWEEN, "between"),
+        (Symbol::COPY_TERM, "copy_term"),
+        (Symbol::LENGTH, "length"),
+        (Symbol::SUCC, "succ"),
+        (Symbol::GROUND, "ground"),
+        (Symbol::VAR, "var"),
+    ];
+
+    // every name interned upfront has its constant
+    assert_eq!(symbols.len(), WELL_KNOWN_NAMES.len());
+
+    for (symbol, name) in symbols {
+        assert_eq!(symbol.as_str(), name);
+        assert_eq!(Symbol::new(name), symbol);
+    }
+}
+
+#[test]
+fn symbols_order_by_contents() {
+    // intern in reverse lexicographic order
+    let zebra = Symbol::new("zebra_ordering_test");
+    let apple = Symbol::new("apple_ordering_test");
+
+    assert!(apple < zebra);
+    assert_eq!(apple.to_string(), "apple_ordering_test");
+}
+
+#[test]
+fn identical_atoms_unify_by_symbol_equality() {
+    let lhs = Term::atom("alice");
+    let rhs = Term::atom(String::from("alice"));
+
+    let (Term::Atom(lhs_symbol), Term::Atom(rhs_symbol)) = (&lhs, &rhs) else {
+        unreachable!()
+    };
+
+    assert_eq!(lhs_symbol, rhs_symbol);
+
+    let substitution = Substitution::default().unify_terms(&lhs, &rhs).unwrap();
+
+    assert!(substitution.mapping.is_empty());
+    assert!(
+        Substitution::default().unify_terms(&lhs, &Term::atom("bob")).is_none()
+    );
+}
@@ -1,34 +1,699 @@
-use std::fmt;
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    ops::{ControlFlow, Deref, DerefMut},
+    sync::atomic::{self, AtomicU32},
+};
+
+use crate::symbol::Symbol;
 
 // Term representation
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Term {
-    Atom(String),
+    Atom(Symbol),
+    Integer(i64),
     Variable(usize),
-    Compound(String, Vec<Term>),
+    Compound(Symbol, Arguments),
+
+    /// A double-quoted string, e.g. `"abc"`, which never unifies with the
+    /// atom `abc`.
+    Str(String),
 }
 
 impl Term {
+    /// The index of the first anonymous variable, see [`Term::anonymous`].
+    ///
+    /// Anonymous variables take the upper half of the indices so that they
+    /// never clash with the variables of a program or query.
+    pub const FIRST_ANONYMOUS_VARIABLE: usize = usize::MAX / 2 + 1;
+
     #[must_use]
-    pub fn atom(name: impl Into<String>) -> Self { Term::Atom(name.into()) }
+    pub fn atom(name: impl Into<Symbol>) -> Self { Term::Atom(name.into()) }
+
+    #[must_use]
+    pub fn integer(value: i64) -> Self { Term::Integer(value) }
 
     #[must_use]
     pub fn variable(id: usize) -> Self { Term::Variable(id) }
 
+    #[must_use]
+    pub fn string(value: impl Into<String>) -> Self { Term::Str(value.into()) }
+
+    /// Creates a proper list of the given items, i.e. nested `'.'(Head,
+    /// Tail)` cells ending in the empty list atom `[]`.
+    #[must_use]
+    pub fn list(items: impl IntoIterator<Item = Term>) -> Self {
+        Self::list_with_tail(items, Term::Atom(Symbol::NIL))
+    }
+
+    /// Creates a list of the given items ending in the given tail, e.g.
+    /// `[a, b|T]`.
+    #[must_use]
+    pub fn list_with_tail(
+        items: impl IntoIterator<Item = Term>,
+        tail: Term,
+    ) -> Self {
+        let items = items.into_iter().collect::<Vec<_>>();
+
+        items.into_iter().rev().fold(tail, |tail, head| {
+            Term::Compound(Symbol::CONS, vec![head, tail].into())
+        })
+    }
+
     #[must_use]
     pub fn component(
-        name: impl Into<String>,
+        name: impl Into<Symbol>,
         args: impl IntoIterator<Item = Term>,
     ) -> Self {
         Term::Compound(name.into(), args.into_iter().collect())
     }
+
+    /// Creates the `nth` anonymous variable, written `_` in Prolog.
+    ///
+    /// Every occurrence of `_` is a distinct variable whose binding nobody
+    /// cares about, therefore, the solver leaves anonymous variables out of
+    /// the answers, e.g. `parent(X, _)` is answered with bindings of `X`
+    /// only.
+    #[must_use]
+    pub fn anonymous(nth: usize) -> Self {
+        Term::Variable(Self::FIRST_ANONYMOUS_VARIABLE + nth)
+    }
+
+    /// Checks whether the variable index belongs to an anonymous variable,
+    /// see [`Term::anonymous`].
+    #[must_use]
+    pub fn is_anonymous(variable: usize) -> bool {
+        variable >= Self::FIRST_ANONYMOUS_VARIABLE
+    }
+
+    /// Checks whether the term contains no variables anywhere, e.g. `f(a, 1)`
+    /// is ground whereas `f(a, g(?0))` isn't.
+    ///
+    /// This is a lookup of the variables cached by the [`Arguments`] once
+    /// they've been computed.
+    #[must_use]
+    pub fn is_ground(&self) -> bool { self.variable_bits() == 0 }
+
+    /// Checks whether the variable occurs anywhere in the term, stopping at
+    /// its first occurrence.
+    ///
+    /// The variables cached by the [`Arguments`] rule most terms out without
+    /// walking them, only the terms that may contain the variable are walked.
+    #[must_use]
+    pub fn contains_variable(&self, variable: usize) -> bool {
+        if self.variable_bits() & variable_bit(variable) == 0 {
+            return false;
+        }
+
+        self.try_visit(&mut |term| match term {
+            Term::Variable(other) if *other == variable => {
+                ControlFlow::Break(())
+            }
+            _ => ControlFlow::Continue(()),
+        })
+        .is_break()
+    }
+
+    /// Returns the arguments of the term if it's a compound of the given name
+    /// and arity, e.g. `point(1, 2)` matches `("point", 2)` but neither
+    /// `("point", 3)` nor `("line", 2)`.
+    ///
+    /// ```
+    /// use slg_prolog_solver::term::Term;
+    ///
+    /// let point = Term::component("point", [Term::integer(1), Term::integer(2)]);
+    ///
+    /// let Some([Term::Integer(x), Term::Integer(y)]) =
+    ///     point.as_compound("point", 2)
+    /// else {
+    ///     panic!("not a point");
+    /// };
+    ///
+    /// assert_eq!((x, y), (&1, &2));
+    /// ```
+    #[must_use]
+    pub fn as_compound(&self, name: &str, arity: usize) -> Option<&[Term]> {
+        match self {
+            Term::Compound(symbol, arguments)
+                if arguments.len() == arity && *symbol == name =>
+            {
+                Some(arguments)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the term if it's an atom.
+    #[must_use]
+    pub fn as_atom(&self) -> Option<&'static str> {
+        match self {
+            Term::Atom(symbol) => Some(symbol.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Returns the index of the term if it's a variable.
+    #[must_use]
+    pub fn as_variable(&self) -> Option<usize> {
+        match self {
+            Term::Variable(variable) => Some(*variable),
+            _ => None,
+        }
+    }
+
+    /// Calls `f` on the term and then on each of its subterms, depth-first
+    /// and left to right, e.g. `f(a, g(?0))` visits `f(a, g(?0))`, `a`,
+    /// `g(?0)`, and `?0` in this order.
+    ///
+    /// ```
+    /// use slg_prolog_solver::term::Term;
+    ///
+    /// let term = Term::component("f", [Term::variable(0), Term::atom("a")]);
+    ///
+    /// let mut variables = Vec::new();
+    /// term.visit(&mut |term| {
+    ///     if let Term::Variable(variable) = term {
+    ///         variables.push(*variable);
+    ///     }
+    /// });
+    ///
+    /// assert_eq!(variables, [0]);
+    /// ```
+    pub fn visit(&self, f: &mut impl FnMut(&Term)) {
+        let _ = self.try_visit(&mut |term| {
+            f(term);
+            ControlFlow::<()>::Continue(())
+        });
+    }
+
+    /// Like [`Term::visit`] but stops as soon as `f` breaks, returning what
+    /// it broke with, e.g. to look for a subterm.
+    ///
+    /// The term is traversed with an explicit stack, see [`Term::depth`].
+    ///
+    /// ```
+    /// use std::ops::ControlFlow;
+    ///
+    /// use slg_prolog_solver::term::Term;
+    ///
+    /// let term = Term::component("f", [Term::variable(0), Term::atom("a")]);
+    ///
+    /// let first_atom = term.try_visit(&mut |term| match term {
+    ///     Term::Atom(name) => ControlFlow::Break(name.as_str()),
+    ///     _ => ControlFlow::Continue(()),
+    /// });
+    ///
+    /// assert_eq!(first_atom, ControlFlow::Break("a"));
+    /// ```
+    pub fn try_visit<B>(
+        &self,
+        f: &mut impl FnMut(&Term) -> ControlFlow<B>,
+    ) -> ControlFlow<B> {
+        f(self)?;
+
+        // atoms and variables, the most common terms, don't allocate a stack
+        let Term::Compound(_, arguments) = self else {
+            return ControlFlow::Continue(());
+        };
+        let mut stack = arguments.iter().rev().collect::<Vec<_>>();
+
+        while let Some(term) = stack.pop() {
+            f(term)?;
+
+            if let Term::Compound(_, arguments) = term {
+                stack.extend(arguments.iter().rev());
+            }
+        }
+
+        ControlFlow::Continue(())
+    }
+
+    /// Like [`Term::visit`] but allows rewriting the terms in place.
+    ///
+    /// The subterms are visited after `f` returns, so a term replaced by `f`
+    /// has the subterms of its replacement visited. The term is traversed
+    /// with an explicit stack, see [`Term::depth`].
+    pub fn visit_mut(&mut self, f: &mut impl FnMut(&mut Term)) {
+        let mut stack = vec![self];
+
+        while let Some(term) = stack.pop() {
+            f(term);
+
+            if let Term::Compound(_, arguments) = term {
+                stack.extend(arguments.iter_mut().rev());
+            }
+        }
+    }
+
+    /// Renames every atom of the term, including the ones nested in
+    /// compounds, e.g. mapping `f(a, [b])` with an uppercasing function
+    /// yields `f(A, [B])`.
+    ///
+    /// Only atoms are renamed, the names of compound terms are kept. Note
+    /// that the empty list `[]` is an atom as well.
+    #[must_use]
+    pub fn map_atoms(mut self, f: impl Fn(&str) -> String) -> Term {
+        self.visit_mut(&mut |term| {
+            if let Term::Atom(name) = term {
+                *name = Symbol::new(&f(name.as_str()));
+            }
+        });
+
+        self
+    }
+
+    /// Adds `by` to the index of every variable of the term, e.g. shifting
+    /// `f(?0, g(?1))` by `2` yields `f(?2, g(?3))`.
+    ///
+    /// Variables are plain indices shared by every term, so two terms built
+    /// separately, e.g. from different sources, may use the same index for
+    /// unrelated variables. Shifting one of them past the named variables of
+    /// the other, see [`Term::max_variable_index`], keeps them apart before
+    /// combining them.
+    ///
+    /// Anonymous variables, see [`Term::anonymous`], are shifted within their
+    /// own range, the `nth` one becomes the `nth + by` one: `p(_)` and `q(_)`
+    /// built separately share their anonymous variable as well.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a shifted variable leaves its range, i.e. if a named variable
+    /// would become anonymous or an anonymous one would overflow, which would
+    /// change the meaning of the term.
+    #[must_use]
+    pub fn shift_variables(mut self, by: usize) -> Term {
+        self.visit_mut(&mut |term| {
+            if let Term::Variable(variable) = term {
+                *variable = if Term::is_anonymous(*variable) {
+                    variable.checked_add(by).expect(
+                        "the shifted anonymous variable must not overflow",
+                    )
+                } else {
+                    variable
+                        .checked_add(by)
+                        .filter(|variable| !Term::is_anonymous(*variable))
+                        .expect(
+                            "the shifted named variable must stay out of the \
+                             anonymous range",
+                        )
+                };
+            }
+        });
+
+        self
+    }
+
+    /// Returns the number of terms on the longest path from the term down to
+    /// one of its subterms, e.g. `1` for an atom and `3` for `f(a, g(b))`.
+    ///
+    /// The term is traversed with an explicit stack, so arbitrarily deep
+    /// terms don't overflow the call stack.
+    #[must_use]
+    pub fn depth(&self) -> usize {
+        let mut depth = 0;
+        let mut stack = vec![(self, 1)];
+
+        while let Some((term, term_depth)) = stack.pop() {
+            depth = depth.max(term_depth);
+
+            if let Term::Compound(_, arguments) = term {
+                stack.extend(
+                    arguments.iter().map(|argument| (argument, term_depth + 1)),
+                );
+            }
+        }
+
+        depth
+    }
+
+    /// Returns the number of terms the term consists of, counting itself and
+    /// every subterm, e.g. `1` for an atom and `4` for `f(a, g(b))`.
+    ///
+    /// Like [`Term::depth`], this doesn't recurse on the call stack.
+    #[must_use]
+    pub fn size(&self) -> usize {
+        let mut size = 0;
+        let mut stack = vec![self];
+
+        while let Some(term) = stack.pop() {
+            size += 1;
+
+            if let Term::Compound(_, arguments) = term {
+                stack.extend(arguments);
+            }
+        }
+
+        size
+    }
+}
+
+impl Term {
+    /// Returns the set of the variables of the term, see [`Arguments`].
+    fn variable_bits(&self) -> u32 {
+        match self {
+            Term::Atom(_) | Term::Integer(_) | Term::Str(_) => 0,
+            Term::Variable(variable) => variable_bit(*variable),
+            Term::Compound(_, arguments) => arguments.variable_bits(),
+        }
+    }
+
+    /// Checks whether the term is known to be ground without computing
+    /// anything, i.e. it has no variable or it's a compound whose variables
+    /// have been cached already and are none.
+    pub(crate) fn is_known_ground(&self) -> bool {
+        match self {
+            Term::Atom(_) | Term::Integer(_) | Term::Str(_) => true,
+            Term::Variable(_) => false,
+            Term::Compound(_, arguments) => {
+                arguments.cached_variable_bits() == Some(0)
+            }
+        }
+    }
+}
+
+/// Returns the bit standing for the variable in a set of variables, see
+/// [`Arguments`].
+fn variable_bit(variable: usize) -> u32 {
+    1 << (variable % Arguments::VARIABLE_BITS)
+}
+
+/// The arguments of a [`Term::Compound`], along with a cache of the
+/// variables occurring in them.
+///
+/// It dereferences to the slice of the arguments. The variables are cached
+/// the first time [`Term::is_ground`] or [`Term::contains_variable`] needs
+/// them, and dropped whenever the arguments are borrowed mutably, so that
+/// terms that aren't rewritten in between, e.g. the ground data a program
+/// passes around, are only walked once.
+///
+/// The cache is a set of 31 bits, `?n` setting the bit `n % 31`: it's exact
+/// for telling ground terms apart, and rules out most of the variables that
+/// don't occur. Unlike a set of the variables themselves, it takes the same
+/// space in every compound, e.g. the list cells of a long list of variables
+/// don't cache every variable after them.
+pub struct Arguments {
+    terms: Box<[Term]>,
+
+    /// The bits of the variables, with [`Self::COMPUTED`] set once computed.
+    variables: AtomicU32,
+}
+
+impl Arguments {
+    /// The number of bits the variables are spread over.
+    const VARIABLE_BITS: usize = 31;
+
+    /// Marks the cache as computed.
+    const COMPUTED: u32 = 1 << Self::VARIABLE_BITS;
+
+    /// Returns the cached set of variables, if computed.
+    fn cached_variable_bits(&self) -> Option<u32> {
+        let bits = self.variables.load(atomic::Ordering::Relaxed);
+
+        (bits & Self::COMPUTED != 0).then_some(bits & !Self::COMPUTED)
+    }
+
+    /// Returns the set of variables, computing the missing caches of the
+    /// nested arguments on the way.
+    fn variable_bits(&self) -> u32 {
+        if let Some(bits) = self.cached_variable_bits() {
+            return bits;
+        }
+
+        // the innermost arguments are computed first so that every set is
+        // made of the ones of its arguments, without recursing
+        let mut pending = vec![self];
+        let mut uncached = Vec::new();
+
+        while let Some(arguments) = pending.pop() {
+            uncached.push(arguments);
+
+            for term in &arguments.terms {
+                if let Term::Compound(_, nested) = term
+                    && nested.cached_variable_bits().is_none()
+                {
+                    pending.push(nested);
+                }
+            }
+        }
+
+        for arguments in uncached.into_iter().rev() {
+            let bits = arguments
+                .terms
+                .iter()
+                .fold(0, |bits, term| bits | term.variable_bits());
+
+            arguments
+                .variables
+                .store(bits | Self::COMPUTED, atomic::Ordering::Relaxed);
+        }
+
+        self.cached_variable_bits().unwrap()
+    }
+}
+
+impl Deref for Arguments {
+    type Target = [Term];
+
+    fn deref(&self) -> &[Term] { &self.terms }
+}
+
+/// Drops the cached variables, the arguments may be rewritten.
+impl DerefMut for Arguments {
+    fn deref_mut(&mut self) -> &mut [Term] {
+        *self.variables.get_mut() = 0;
+
+        &mut self.terms
+    }
+}
+
+impl Clone for Arguments {
+    fn clone(&self) -> Self {
+        Self {
+            terms: self.terms.clone(),
+            variables: AtomicU32::new(
+                self.variables.load(atomic::Ordering::Relaxed),
+            ),
+        }
+    }
+}
+
+impl Default for Arguments {
+    fn default() -> Self { Vec::new().into() }
+}
+
+impl fmt::Debug for Arguments {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.terms, f)
+    }
+}
+
+impl PartialEq for Arguments {
+    fn eq(&self, other: &Self) -> bool { self.terms == other.terms }
+}
+
+impl Eq for Arguments {}
+
+impl Hash for Arguments {
+    fn hash<H: Hasher>(&self, state: &mut H) { self.terms.hash(state); }
+}
+
+impl PartialOrd for Arguments {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Arguments {
+    fn cmp(&self, other: &Self) -> Ordering { self.terms.cmp(&other.terms) }
+}
+
+impl From<Vec<Term>> for Arguments {
+    fn from(terms: Vec<Term>) -> Self {
+        Self { terms: terms.into_boxed_slice(), variables: AtomicU32::new(0) }
+    }
+}
+
+impl From<Arguments> for Vec<Term> {
+    fn from(arguments: Arguments) -> Self { arguments.terms.into_vec() }
+}
+
+impl FromIterator<Term> for Arguments {
+    fn from_iter<I: IntoIterator<Item = Term>>(iter: I) -> Self {
+        iter.into_iter().collect::<Vec<_>>().into()
+    }
+}
+
+impl IntoIterator for Arguments {
+    type IntoIter = std::vec::IntoIter<Term>;
+    type Item = Term;
+
+    fn into_iter(self) -> Self::IntoIter { self.terms.into_vec().into_iter() }
+}
+
+impl<'a> IntoIterator for &'a Arguments {
+    type IntoIter = std::slice::Iter<'a, Term>;
+    type Item = &'a Term;
+
+    fn into_iter(self) -> Self::IntoIter { self.terms.iter() }
+}
+
+impl<'a> IntoIterator for &'a mut Arguments {
+    type IntoIter = std::slice::IterMut<'a, Term>;
+    type Item = &'a mut Term;
+
+    fn into_iter(self) -> Self::IntoIter { self.deref_mut().iter_mut() }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Arguments {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        self.terms.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Arguments {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        Vec::<Term>::deserialize(deserializer).map(Self::from)
+    }
+}
+
+impl From<i64> for Term {
+    fn from(value: i64) -> Self { Term::Integer(value) }
+}
+
+/// Converts to an atom rather than a [`Term::Str`], like [`Term::atom`] and
+/// the unquoted names of the parser do; strings are only created explicitly
+/// with [`Term::string`].
+impl From<&str> for Term {
+    fn from(name: &str) -> Self { Term::atom(name) }
+}
+
+/// Converts to an atom, see the conversion from `&str`.
+impl From<String> for Term {
+    fn from(name: String) -> Self { Term::atom(name) }
+}
+
+/// Builds a [`Term`] written in Prolog syntax, see [`crate::parser`].
+///
+/// Variables are numbered from zero in the order of their first appearance,
+/// like in a parsed clause.
+///
+/// ```
+/// use slg_prolog_solver::{term, term::Term};
+///
+/// assert_eq!(
+///     term!(point(1, X, [a | X])),
+///     Term::component("point", [
+///         Term::integer(1),
+///         Term::variable(0),
+///         Term::list_with_tail([Term::atom("a")], Term::variable(0)),
+///     ])
+/// );
+/// ```
+///
+/// # Panics
+///
+/// Panics if the tokens aren't a valid term.
+#[macro_export]
+macro_rules! term {
+    ($($term:tt)+) => {
+        $crate::parser::parse_term(stringify!($($term)+))
+            .expect(concat!("`", stringify!($($term)+), "` is not a term"))
+    };
+}
+
+/// Terms are ordered by kind first: variables < atoms < integers < strings <
+/// compounds. Within a kind, variables are ordered by index, atoms and
+/// strings alphabetically, integers numerically, and compounds by arity,
+/// then by name, then by their arguments from left to right.
+///
+/// For example, `?1` < `?2` < `b` < `c` < `-3` < `10` < `"a"` < `g(z)` <
+/// `f(a, b)` < `f(b, a)`.
+///
+/// The order doesn't depend on the order in which the atoms have been
+/// interned, so sorting terms, e.g. the answers of
+/// [`crate::solver::Solver::set_ordered_answers`], gives the same result in
+/// every run.
+impl PartialOrd for Term {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Term {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Term::Variable(lhs), Term::Variable(rhs)) => lhs.cmp(rhs),
+            (Term::Atom(lhs), Term::Atom(rhs)) => lhs.cmp(rhs),
+            (Term::Integer(lhs), Term::Integer(rhs)) => lhs.cmp(rhs),
+            (Term::Str(lhs), Term::Str(rhs)) => lhs.cmp(rhs),
+            (
+                Term::Compound(lhs_name, lhs_arguments),
+                Term::Compound(rhs_name, rhs_arguments),
+            ) => lhs_arguments
+                .len()
+                .cmp(&rhs_arguments.len())
+                .then_with(|| lhs_name.cmp(rhs_name))
+                .then_with(|| lhs_arguments.cmp(rhs_arguments)),
+            _ => self.kind_rank().cmp(&other.kind_rank()),
+        }
+    }
+}
+
+impl Term {
+    /// Returns the position of the kind of the term in the order of terms,
+    /// see the [`Ord`] implementation of [`Term`].
+    fn kind_rank(&self) -> u8 {
+        match self {
+            Term::Variable(_) => 0,
+            Term::Atom(_) => 1,
+            Term::Integer(_) => 2,
+            Term::Str(_) => 3,
+            Term::Compound(..) => 4,
+        }
+    }
 }
 
 impl fmt::Display for Term {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Term::Atom(name) => write!(f, "{name}"),
-            Term::Variable(name) => write!(f, "{name}"),
+            Term::Integer(value) => write!(f, "{value}"),
+            Term::Str(value) => write!(f, "\"{}\"", value.replace('"', "\"\"")),
+            Term::Variable(id) if Term::is_anonymous(*id) => write!(f, "_"),
+            Term::Variable(id) => write!(f, "?{id}"),
+            Term::Compound(name, args)
+                if *name == Symbol::CONS && args.len() == 2 =>
+            {
+                write!(f, "[{}", args[0])?;
+
+                let mut tail = &args[1];
+                loop {
+                    match tail {
+                        Term::Compound(name, args)
+                            if *name == Symbol::CONS && args.len() == 2 =>
+                        {
+                            write!(f, ", {}", args[0])?;
+                            tail = &args[1];
+                        }
+                        Term::Atom(name) if *name == Symbol::NIL => break,
+                        tail => {
+                            write!(f, "|{tail}")?;
+                            break;
+                        }
+                    }
+                }
+
+                write!(f, "]")
+            }
             Term::Compound(name, args) => {
                 write!(f, "{name}(")?;
                 for (i, arg) in args.iter().enumerate() {
@@ -42,3 +707,6 @@ impl fmt::Display for Term {
         }
     }
 }
+
+#[cfg(test)]
+mod test;
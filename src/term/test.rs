@@ -0,0 +1,383 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    ops::ControlFlow,
+};
+
+use crate::{parser::parse_term, term::Term};
+
+#[test]
+fn collect_variables_of_nested_compound() {
+    // f(?0, g(a, h(?3, [?1, ?0])), k(l(m(?7))), 42)
+    let term = Term::component("f", [
+        Term::variable(0),
+        Term::component("g", [
+            Term::atom("a"),
+            Term::component("h", [
+                Term::variable(3),
+                Term::list([Term::variable(1), Term::variable(0)]),
+            ]),
+        ]),
+        Term::component("k", [Term::component("l", [Term::component("m", [
+            Term::variable(7),
+        ])])]),
+        Term::integer(42),
+    ]);
+
+    let mut variables = BTreeSet::new();
+    let mut visited = 0;
+    term.visit(&mut |term| {
+        visited += 1;
+
+        if let Term::Variable(variable) = term {
+            variables.insert(*variable);
+        }
+    });
+
+    assert_eq!(variables, BTreeSet::from([0, 1, 3, 7]));
+
+    // every subterm is visited, including the list cells and `[]`
+    assert_eq!(visited, 16);
+    assert_eq!(term.max_variable_index(), Some(7));
+}
+
+#[test]
+fn rename_atoms() {
+    let term = Term::component("f", [
+        Term::atom("a"),
+        Term::component("g", [Term::atom("b"), Term::variable(0)]),
+        Term::integer(1),
+    ]);
+
+    let renamed = term.map_atoms(|name| format!("{name}_renamed"));
+
+    assert_eq!(
+        renamed,
+        Term::component("f", [
+            Term::atom("a_renamed"),
+            Term::component("g", [Term::atom("b_renamed"), Term::variable(0)]),
+            Term::integer(1),
+        ])
+    );
+}
+
+#[test]
+fn rewrite_deep_terms() {
+    // s(s(...s(X)...))
+    let mut deep = Term::variable(0);
+    for _ in 0..10_000 {
+        deep = Term::component("s", [deep]);
+    }
+
+    // a small stack overflows if the rewrites recurse, the term is dropped
+    // back on the test thread
+    let renamed = std::thread::Builder::new()
+        .stack_size(64 * 1024)
+        .spawn(move || {
+            deep.shift_variables(1).map_atoms(|name| format!("{name}_renamed"))
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+
+    assert_eq!(renamed.max_variable_index(), Some(1));
+    assert_eq!(renamed.depth(), 10_001);
+}
+
+#[test]
+fn canonicalize_terms_consistently() {
+    let terms = || {
+        [
+            Term::component("f", [Term::variable(4), Term::variable(9)]),
+            Term::component("g", [Term::variable(9), Term::variable(2)]),
+        ]
+    };
+
+    // a shared mapping keeps `?9` the same variable across both terms
+    let [mut lhs, mut rhs] = terms();
+    let (mut counter, mut mapping) = (3, HashMap::new());
+    lhs.canonicalize_with_mapping(&mut counter, &mut mapping);
+    rhs.canonicalize_with_mapping(&mut counter, &mut mapping);
+
+    assert_eq!(lhs.to_string(), "f(?3, ?4)");
+    assert_eq!(rhs.to_string(), "g(?4, ?5)");
+    assert_eq!(counter, 6);
+    assert_eq!(mapping, HashMap::from([(4, 3), (9, 4), (2, 5)]));
+
+    // whereas each call to `canonicalize_with_counter` starts afresh
+    let [mut lhs, mut rhs] = terms();
+    let counter = lhs.canonicalize_with_counter(3);
+    rhs.canonicalize_with_counter(counter);
+
+    assert_eq!(lhs.to_string(), "f(?3, ?4)");
+    assert_eq!(rhs.to_string(), "g(?5, ?6)");
+}
+
+#[test]
+fn depth_and_size_of_terms() {
+    for atomic in [
+        Term::atom("a"),
+        Term::integer(1),
+        Term::variable(0),
+        Term::string("abc"),
+    ] {
+        assert_eq!(atomic.depth(), 1);
+        assert_eq!(atomic.size(), 1);
+    }
+
+    // f(a, g(b)) and [a, b], i.e. '.'(a, '.'(b, []))
+    let shallow = Term::component("f", [
+        Term::atom("a"),
+        Term::component("g", [Term::atom("b")]),
+    ]);
+    let list = Term::list([Term::atom("a"), Term::atom("b")]);
+
+    assert_eq!((shallow.depth(), shallow.size()), (3, 4));
+    assert_eq!((list.depth(), list.size()), (3, 5));
+
+    // s(s(...s(zero)...))
+    let mut deep = Term::atom("zero");
+    for _ in 0..10_000 {
+        deep = Term::component("s", [deep]);
+    }
+
+    assert_eq!(deep.depth(), 10_001);
+    assert_eq!(deep.size(), 10_001);
+}
+
+#[test]
+fn destructure_point() {
+    // point(1, 2)
+    let point = Term::component("point", [Term::integer(1), Term::integer(2)]);
+
+    assert_eq!(
+        point.as_compound("point", 2),
+        Some([Term::integer(1), Term::integer(2)].as_slice())
+    );
+    assert_eq!(point.as_compound("point", 3), None);
+    assert_eq!(point.as_compound("line", 2), None);
+    assert_eq!(point.as_atom(), None);
+    assert_eq!(point.as_variable(), None);
+
+    // point(X, origin)
+    let point =
+        Term::component("point", [Term::variable(3), Term::atom("origin")]);
+    let [x, label] = point.as_compound("point", 2).unwrap() else {
+        unreachable!()
+    };
+
+    assert_eq!(x.as_variable(), Some(3));
+    assert_eq!(label.as_atom(), Some("origin"));
+    assert_eq!(label.as_variable(), None);
+
+    // an atom isn't a compound of arity zero
+    assert_eq!(Term::atom("point").as_compound("point", 0), None);
+}
+
+#[test]
+fn convert_primitives_to_terms() {
+    assert_eq!(Term::from(42), Term::Integer(42));
+    assert_eq!(Term::from(-7), Term::Integer(-7));
+
+    // names become atoms, never strings
+    assert_eq!(Term::from("alice"), Term::atom("alice"));
+    assert_eq!(Term::from("alice".to_string()), Term::atom("alice"));
+    assert_ne!(Term::from("alice"), Term::string("alice"));
+}
+
+#[test]
+fn build_terms_with_macro() {
+    assert_eq!(term!(alice), Term::atom("alice"));
+    assert_eq!(term!(-7), Term::integer(-7));
+    assert_eq!(term!("alice"), Term::string("alice"));
+
+    assert_eq!(
+        term!(point(1, X)),
+        Term::component("point", [Term::integer(1), Term::variable(0)])
+    );
+
+    // variables are shared by name, numbered by first appearance
+    assert_eq!(
+        term!(edge(Y, f(X, g(Y)), [1, 2 | T])),
+        Term::component("edge", [
+            Term::variable(0),
+            Term::component("f", [
+                Term::variable(1),
+                Term::component("g", [Term::variable(0)]),
+            ]),
+            Term::list_with_tail(
+                [Term::integer(1), Term::integer(2)],
+                Term::variable(2)
+            ),
+        ])
+    );
+}
+
+#[test]
+fn shift_variables_past_another_term() {
+    let term = term!(f(X, g(Y, X), a));
+    let other = term!(h(X, Y, Z));
+
+    let shifted = term.shift_variables(other.max_variable_index().unwrap() + 1);
+
+    assert_eq!(shifted.to_string(), "f(?3, g(?4, ?3), a)");
+    assert_eq!(
+        Term::component("p", [Term::variable(1), Term::anonymous(0)])
+            .shift_variables(5),
+        Term::component("p", [Term::variable(6), Term::anonymous(5)])
+    );
+    assert_eq!(term!(a).shift_variables(5), term!(a));
+}
+
+#[test]
+fn shift_variables_past_anonymous_variables() {
+    // p(X, _, _)
+    let term = Term::component("p", [
+        Term::variable(0),
+        Term::anonymous(0),
+        Term::anonymous(1),
+    ]);
+    assert_eq!(
+        term.max_variable_index(),
+        Some(Term::FIRST_ANONYMOUS_VARIABLE + 1)
+    );
+    assert_eq!(term.max_variable_offset(), Some(1));
+
+    // the named variable stays out of the anonymous range
+    let shifted =
+        term!(q(X)).shift_variables(term.max_variable_offset().unwrap() + 1);
+    assert_eq!(shifted, Term::component("q", [Term::variable(2)]));
+    assert!(!Term::is_anonymous(2));
+
+    // the anonymous variables move past the ones of the other term
+    let shifted = Term::component("r", [Term::anonymous(0)])
+        .shift_variables(term.max_variable_offset().unwrap() + 1);
+    assert_eq!(shifted, Term::component("r", [Term::anonymous(2)]));
+}
+
+#[test]
+#[should_panic = "the shifted named variable must stay out of the anonymous \
+                  range"]
+fn shift_variables_into_the_anonymous_range() {
+    let _ = term!(q(X)).shift_variables(Term::FIRST_ANONYMOUS_VARIABLE);
+}
+
+#[test]
+fn try_visit_stops_at_the_first_break() {
+    // f(a, g(X, h(Y)), X)
+    let term = term!(f(a, g(X, h(Y)), X));
+
+    let mut visited = Vec::new();
+    let found = term.try_visit(&mut |term| {
+        visited.push(term.to_string());
+
+        match term {
+            Term::Variable(variable) => ControlFlow::Break(*variable),
+            _ => ControlFlow::Continue(()),
+        }
+    });
+
+    assert_eq!(found, ControlFlow::Break(0));
+    assert_eq!(visited, ["f(a, g(?0, h(?1)), ?0)", "a", "g(?0, h(?1))", "?0"]);
+}
+
+#[test]
+fn contains_variable_after_rewrites() {
+    use crate::substitution::Substitution;
+
+    // f(X, g(Y, [Z]))
+    let mut term = term!(f(X, g(Y, [Z])));
+
+    assert!((0..3).all(|variable| term.contains_variable(variable)));
+    assert!(!term.contains_variable(3));
+
+    // {X = h(W), Z = a} rewrites the term to f(h(W), g(Y, [a]))
+    let substitution = Substitution {
+        mapping: [
+            (0, Term::component("h", [Term::variable(3)])),
+            (2, Term::atom("a")),
+        ]
+        .into_iter()
+        .collect(),
+    };
+    substitution.apply_term(&mut term);
+
+    assert!(!term.contains_variable(0));
+    assert!(term.contains_variable(1));
+    assert!(!term.contains_variable(2));
+    assert!(term.contains_variable(3));
+    assert!(!term.is_ground());
+
+    // binding the rest leaves no variable
+    let substitution = Substitution {
+        mapping: [(1, Term::integer(1)), (3, Term::integer(3))]
+            .into_iter()
+            .collect(),
+    };
+    substitution.apply_term(&mut term);
+
+    assert!((0..4).all(|variable| !term.contains_variable(variable)));
+    assert!(term.is_ground());
+}
+
+#[test]
+fn cached_variables_follow_mutations() {
+    assert_eq!(std::mem::size_of::<Term>(), 32);
+
+    // f(a, g(b, [c]))
+    let mut term = term!(f(a, g(b, [c])));
+    assert!(term.is_ground());
+
+    // rewriting a nested argument drops the caches on the way down
+    term.visit_mut(&mut |term| {
+        if *term == Term::atom("b") {
+            *term = Term::variable(70);
+        }
+    });
+    assert!(!term.is_ground());
+    assert!(term.contains_variable(70));
+
+    // `?39` shares the bit of `?70` but doesn't occur
+    assert!(!term.contains_variable(39));
+
+    let Term::Compound(_, arguments) = &mut term else { unreachable!() };
+    arguments[1] = Term::atom("b");
+    assert!(term.is_ground());
+
+    // a clone keeps the cache of the original, and is rewritten on its own
+    let mut clone = term.clone();
+    let Term::Compound(_, arguments) = &mut clone else { unreachable!() };
+    arguments[0] = Term::variable(0);
+
+    assert!(term.is_ground());
+    assert!(clone.contains_variable(0));
+    assert_eq!(clone.to_string(), "f(?0, b)");
+}
+
+#[test]
+fn sort_mixed_terms_by_kind_then_content() {
+    let mut terms = [
+        "f(b, a)", "\"a\"", "10", "g(z)", "c", "-3", "f(a, b)", "[a]", "b",
+        "g(1)", "h",
+    ]
+    .map(|source| parse_term(source).unwrap())
+    .to_vec();
+    terms.extend([Term::variable(2), Term::variable(1)]);
+
+    terms.sort();
+
+    assert_eq!(terms, [
+        Term::variable(1),
+        Term::variable(2),
+        Term::atom("b"),
+        Term::atom("c"),
+        Term::atom("h"),
+        Term::integer(-3),
+        Term::integer(10),
+        Term::string("a"),
+        Term::component("g", [Term::atom("z")]),
+        Term::component("g", [Term::integer(1)]),
+        Term::list([Term::atom("a")]),
+        Term::component("f", [Term::atom("a"), Term::atom("b")]),
+        Term::component("f", [Term::atom("b"), Term::atom("a")]),
+    ]);
+}